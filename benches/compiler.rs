@@ -0,0 +1,71 @@
+//! Benchmarks of the compiler phases on generated programs, see [`fortytwolang::bench_support`].
+
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use fortytwolang::{bench_support, dump::Dumper, semantic_analyzer::CheckConfig};
+
+/// Generates a program of the given size.
+type Generator = fn(usize) -> String;
+
+/// Generators of the benchmarked programs and the sizes they are benchmarked with.
+const PROGRAMS: [(&str, Generator, [usize; 2]); 3] = [
+	("many_functions", bench_support::many_functions, [100, 1000]),
+	("deep_expression", bench_support::deep_expression, [16, 128]),
+	("long_file", bench_support::long_file, [100, 1000]),
+];
+
+fn lex(c: &mut Criterion) {
+	let mut group = c.benchmark_group("lex");
+	for (name, generate, sizes) in PROGRAMS {
+		for size in sizes {
+			let source = bench_support::source(generate(size));
+			group.bench_with_input(BenchmarkId::new(name, size), &source, |b, source| {
+				b.iter(|| fortytwolang::lex_source(source.clone(), &Dumper::default()).unwrap())
+			});
+		}
+	}
+	group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+	let mut group = c.benchmark_group("parse");
+	for (name, generate, sizes) in PROGRAMS {
+		for size in sizes {
+			let tokens = fortytwolang::lex_source(bench_support::source(generate(size)), &Dumper::default()).unwrap();
+			group.bench_with_input(BenchmarkId::new(name, size), &tokens, |b, tokens| {
+				b.iter_batched(
+					|| tokens.clone(),
+					|tokens| fortytwolang::parse_tokens(Path::new("bench.ftl"), tokens, &Dumper::default()).unwrap(),
+					BatchSize::SmallInput,
+				)
+			});
+		}
+	}
+	group.finish();
+}
+
+fn analyze(c: &mut Criterion) {
+	let mut group = c.benchmark_group("analyze");
+	for (name, generate, sizes) in PROGRAMS {
+		for size in sizes {
+			let ast_nodes =
+				fortytwolang::parse_source(bench_support::source(generate(size)), &Dumper::default()).unwrap();
+			group.bench_with_input(BenchmarkId::new(name, size), &ast_nodes, |b, ast_nodes| {
+				b.iter(|| {
+					fortytwolang::analyze(
+						Path::new("bench.ftl"),
+						ast_nodes.iter(),
+						&CheckConfig::default(),
+						&Dumper::default(),
+					)
+					.unwrap()
+				})
+			});
+		}
+	}
+	group.finish();
+}
+
+criterion_group!(benches, lex, parse, analyze);
+criterion_main!(benches);
@@ -0,0 +1,497 @@
+//! Constructing ASTs in code, e.g. to generate FTL, C or Python code with the [emitters](crate::emitter).
+//!
+//! All built nodes get the same [synthetic position](SourcePositionRange::synthetic):
+//!
+//! ```
+//! use fortytwolang::ast::{self, build::*, expression::BinaryOperator};
+//!
+//! let add = function("add").arg("a", int()).arg("b", int()).returns(int()).body([ret(binary(
+//! 	var("a"),
+//! 	BinaryOperator::Add,
+//! 	var("b"),
+//! ))]);
+//! assert_eq!(ast::to_source([add.into()]), "def add(a: int, b: int): int {\n\treturn a + b\n}\n");
+//! ```
+//!
+//! Parsed ASTs compare equal to built ones after [clearing their positions](ClearPositions).
+
+use crate::{
+	ast::{
+		expression::{BinaryExpression, BinaryOperator, FieldAccess, FunctionCall, NumberKind, TryExpression},
+		statement::{
+			BasicDataType, DataType, FieldAssignment, FunctionArgument, VariableAssignment, VariableDeclaration,
+		},
+		struct_::Field,
+		Block, Expression, FunctionDefinition, FunctionPrototype, IfElse, Instruction, Node, Statement, Struct,
+		WhileLoop,
+	},
+	source::{SourcePositionRange, Spanned},
+};
+
+/// The position of all built nodes, a [synthetic position](SourcePositionRange::synthetic) without origin.
+pub fn position() -> SourcePositionRange {
+	SourcePositionRange::synthetic(None)
+}
+
+fn spanned<T>(value: T) -> Spanned<T> {
+	Spanned::new(value, position())
+}
+
+pub fn int() -> DataType {
+	DataType::Basic(BasicDataType::Int)
+}
+
+pub fn float() -> DataType {
+	DataType::Basic(BasicDataType::Float)
+}
+
+pub fn str() -> DataType {
+	DataType::Basic(BasicDataType::Str)
+}
+
+/// A pointer to the `pointee`, i.e. `ptr pointee`.
+pub fn pointer(pointee: DataType) -> DataType {
+	DataType::Pointer(Box::new(spanned(pointee)))
+}
+
+/// An optional `inner` value, i.e. `opt inner`.
+pub fn optional(inner: DataType) -> DataType {
+	DataType::Optional(Box::new(spanned(inner)))
+}
+
+/// The `inner` value or `error`, i.e. `inner or error`, the return type of a function that can fail.
+pub fn fallible(inner: DataType) -> DataType {
+	DataType::Fallible(Box::new(spanned(inner)))
+}
+
+/// The struct called `name`.
+pub fn struct_type(name: impl Into<String>) -> DataType {
+	DataType::Struct(name.into())
+}
+
+pub fn int_value(value: i64) -> Expression {
+	Expression::Number(spanned(NumberKind::Int(value)))
+}
+
+pub fn float_value(value: f64) -> Expression {
+	Expression::Number(spanned(NumberKind::Float(value)))
+}
+
+/// A string literal with the `value`, which is escaped by the emitters.
+pub fn str_value(value: impl Into<String>) -> Expression {
+	Expression::String(spanned(value.into()))
+}
+
+pub fn null() -> Expression {
+	Expression::Null(position())
+}
+
+pub fn error() -> Expression {
+	Expression::Error(position())
+}
+
+/// Placeholder for an expression that could not be parsed.
+pub fn invalid() -> Expression {
+	Expression::Invalid(position())
+}
+
+/// The result of the call `value`, or `fallback` if it failed, i.e. `try value else fallback`.
+pub fn try_(value: Expression, fallback: Expression) -> Expression {
+	Expression::Try(TryExpression { position: position(), value: Box::new(value), fallback: Box::new(fallback) })
+}
+
+pub fn var(name: impl Into<String>) -> Expression {
+	Expression::Variable(spanned(name.into()))
+}
+
+pub fn binary(lhs: Expression, operator: BinaryOperator, rhs: Expression) -> Expression {
+	Expression::BinaryExpression(BinaryExpression {
+		lhs: Box::new(lhs),
+		operator: spanned(operator),
+		rhs: Box::new(rhs),
+	})
+}
+
+pub fn call(name: impl Into<String>, params: impl IntoIterator<Item = Expression>) -> Expression {
+	Expression::FunctionCall(FunctionCall { name: spanned(name.into()), params: params.into_iter().collect() })
+}
+
+/// Access of the field `name` of the struct `base`, i.e. `base.name`.
+pub fn field(base: Expression, name: impl Into<String>) -> Expression {
+	Expression::FieldAccess(field_access(base, name))
+}
+
+fn field_access(base: Expression, name: impl Into<String>) -> FieldAccess {
+	FieldAccess { base: Box::new(base), field: spanned(name.into()) }
+}
+
+/// An expression as instruction, e.g. a call whose return value is unused.
+pub fn expr(expression: Expression) -> Instruction {
+	Instruction::Expression(expression)
+}
+
+/// Declaration of the variable `name`, i.e. `var name: data_type = value`.
+pub fn declare(name: impl Into<String>, data_type: DataType, value: Expression) -> Instruction {
+	let declaration = VariableDeclaration { name: spanned(name.into()), data_type: spanned(data_type), value };
+	Instruction::Statement(Statement::VariableDeclaration(declaration))
+}
+
+pub fn assign(name: impl Into<String>, value: Expression) -> Instruction {
+	Instruction::Statement(Statement::VariableAssignment(VariableAssignment { name: spanned(name.into()), value }))
+}
+
+/// Assignment of the field `name` of the struct `base`, i.e. `base.name = value`.
+pub fn assign_field(base: Expression, name: impl Into<String>, value: Expression) -> Instruction {
+	let assignment = FieldAssignment { target: field_access(base, name), value };
+	Instruction::Statement(Statement::FieldAssignment(assignment))
+}
+
+/// A `return` with an optional value, e.g. `ret(int_value(0))` or `ret(None)`.
+pub fn ret(value: impl Into<Option<Expression>>) -> Instruction {
+	Instruction::Statement(Statement::Return(spanned(value.into())))
+}
+
+pub fn if_else(
+	condition: Expression,
+	if_true: impl IntoIterator<Item = Instruction>,
+	if_false: impl IntoIterator<Item = Instruction>,
+) -> Instruction {
+	let if_else =
+		IfElse { condition, if_true: if_true.into_iter().collect(), if_false: if_false.into_iter().collect() };
+	Instruction::IfElse(Box::new(if_else))
+}
+
+pub fn while_loop(condition: Expression, body: impl IntoIterator<Item = Instruction>) -> Instruction {
+	Instruction::WhileLoop(Box::new(WhileLoop { condition, body: body.into_iter().collect() }))
+}
+
+/// Placeholder for an instruction that could not be parsed.
+pub fn invalid_instr() -> Instruction {
+	Instruction::Invalid(position())
+}
+
+/// Placeholder for a function or struct that could not be parsed.
+pub fn invalid_node() -> Node {
+	Node::Invalid(position())
+}
+
+/// A comment between functions and structs.
+pub fn comment(text: impl Into<String>) -> Node {
+	Node::Comment(spanned(text.into()))
+}
+
+/// Starts building the function `name`, without arguments, return type and instructions.
+pub fn function(name: impl Into<String>) -> FunctionBuilder {
+	FunctionBuilder {
+		prototype: FunctionPrototype {
+			doc: None,
+			name: spanned(name.into()),
+			args: Vec::new(),
+			return_type: None,
+			exported: false,
+		},
+		body: Vec::new(),
+		annotations: Vec::new(),
+	}
+}
+
+/// Builder of a function definition or, without body, of an extern function declaration. Created by [`function`].
+#[derive(Debug, Clone)]
+pub struct FunctionBuilder {
+	prototype: FunctionPrototype,
+	body: Block,
+	annotations: Vec<Spanned<String>>,
+}
+
+impl FunctionBuilder {
+	/// Appends an argument.
+	pub fn arg(mut self, name: impl Into<String>, data_type: DataType) -> Self {
+		self.prototype.args.push(FunctionArgument { name: spanned(name.into()), data_type: spanned(data_type) });
+		self
+	}
+
+	pub fn returns(mut self, data_type: DataType) -> Self {
+		self.prototype.return_type = Some(spanned(data_type));
+		self
+	}
+
+	/// Marks the function with `export`.
+	pub fn exported(mut self) -> Self {
+		self.prototype.exported = true;
+		self
+	}
+
+	/// Appends an annotation like `bench`.
+	pub fn annotation(mut self, name: impl Into<String>) -> Self {
+		self.annotations.push(spanned(name.into()));
+		self
+	}
+
+	/// Appends the `instructions` to the body.
+	pub fn body(mut self, instructions: impl IntoIterator<Item = Instruction>) -> Self {
+		self.body.extend(instructions);
+		self
+	}
+
+	pub fn build(self) -> FunctionDefinition {
+		FunctionDefinition { prototype: self.prototype, body: self.body, annotations: self.annotations }
+	}
+
+	/// Builds an extern declaration, ignoring the body and annotations.
+	pub fn build_extern(self) -> FunctionPrototype {
+		self.prototype
+	}
+}
+
+impl From<FunctionBuilder> for Node {
+	fn from(builder: FunctionBuilder) -> Self {
+		Node::Function(builder.build())
+	}
+}
+
+/// Starts building the struct `name`, without fields.
+pub fn struct_(name: impl Into<String>) -> StructBuilder {
+	StructBuilder(Struct { doc: None, name: spanned(name.into()), fields: Vec::new(), exported: false })
+}
+
+/// Builder of a struct definition. Created by [`struct_`].
+#[derive(Debug, Clone)]
+pub struct StructBuilder(Struct);
+
+impl StructBuilder {
+	/// Appends a field without default value.
+	pub fn field(mut self, name: impl Into<String>, data_type: DataType) -> Self {
+		self.0.fields.push(Field {
+			doc: None,
+			name: spanned(name.into()),
+			data_type: spanned(data_type),
+			default: None,
+		});
+		self
+	}
+
+	/// Appends a field with a default value.
+	pub fn field_with_default(mut self, name: impl Into<String>, data_type: DataType, default: Expression) -> Self {
+		let (name, data_type) = (spanned(name.into()), spanned(data_type));
+		self.0.fields.push(Field { doc: None, name, data_type, default: Some(default) });
+		self
+	}
+
+	/// Attaches the `doc` comment to the last field.
+	pub fn doc(mut self, doc: impl Into<String>) -> Self {
+		self.0.fields.last_mut().expect("a field to document").doc = Some(spanned(doc.into()));
+		self
+	}
+
+	/// Marks the struct with `export`.
+	pub fn exported(mut self) -> Self {
+		self.0.exported = true;
+		self
+	}
+
+	pub fn build(self) -> Struct {
+		self.0
+	}
+}
+
+impl From<StructBuilder> for Node {
+	fn from(builder: StructBuilder) -> Self {
+		Node::Struct(builder.build())
+	}
+}
+
+/// Replaces all positions in an AST with the synthesized [`position`] of built nodes.
+pub trait ClearPositions {
+	/// Replaces the positions of this node and of all nodes nested in it.
+	fn clear_positions(&mut self);
+}
+
+impl<T: ClearPositions> ClearPositions for Spanned<T> {
+	fn clear_positions(&mut self) {
+		self.position = position();
+		self.value.clear_positions();
+	}
+}
+
+impl<T: ClearPositions> ClearPositions for Vec<T> {
+	fn clear_positions(&mut self) {
+		self.iter_mut().for_each(ClearPositions::clear_positions);
+	}
+}
+
+impl<T: ClearPositions> ClearPositions for Option<T> {
+	fn clear_positions(&mut self) {
+		self.iter_mut().for_each(ClearPositions::clear_positions);
+	}
+}
+
+impl<T: ClearPositions> ClearPositions for Box<T> {
+	fn clear_positions(&mut self) {
+		(**self).clear_positions();
+	}
+}
+
+impl ClearPositions for String {
+	fn clear_positions(&mut self) {}
+}
+
+impl ClearPositions for NumberKind {
+	fn clear_positions(&mut self) {}
+}
+
+impl ClearPositions for BinaryOperator {
+	fn clear_positions(&mut self) {}
+}
+
+impl ClearPositions for DataType {
+	fn clear_positions(&mut self) {
+		if let DataType::Pointer(inner) | DataType::Optional(inner) | DataType::Fallible(inner) = self {
+			inner.clear_positions();
+		}
+	}
+}
+
+impl ClearPositions for Node {
+	fn clear_positions(&mut self) {
+		match self {
+			Node::FunctionPrototype(prototype) => prototype.clear_positions(),
+			Node::Function(function) => {
+				function.prototype.clear_positions();
+				function.body.clear_positions();
+				function.annotations.clear_positions();
+			},
+			Node::Struct(struct_) => {
+				struct_.doc.clear_positions();
+				struct_.name.clear_positions();
+				for field in &mut struct_.fields {
+					field.doc.clear_positions();
+					field.name.clear_positions();
+					field.data_type.clear_positions();
+					field.default.clear_positions();
+				}
+			},
+			Node::Comment(comment) => comment.clear_positions(),
+			Node::Invalid(position) => *position = self::position(),
+		}
+	}
+}
+
+impl ClearPositions for FunctionPrototype {
+	fn clear_positions(&mut self) {
+		self.doc.clear_positions();
+		self.name.clear_positions();
+		for arg in &mut self.args {
+			arg.name.clear_positions();
+			arg.data_type.clear_positions();
+		}
+		self.return_type.clear_positions();
+	}
+}
+
+impl ClearPositions for Instruction {
+	fn clear_positions(&mut self) {
+		match self {
+			Instruction::Expression(expression) => expression.clear_positions(),
+			Instruction::Statement(Statement::VariableDeclaration(declaration)) => {
+				declaration.name.clear_positions();
+				declaration.data_type.clear_positions();
+				declaration.value.clear_positions();
+			},
+			Instruction::Statement(Statement::VariableAssignment(assignment)) => {
+				assignment.name.clear_positions();
+				assignment.value.clear_positions();
+			},
+			Instruction::Statement(Statement::FieldAssignment(assignment)) => {
+				assignment.target.clear_positions();
+				assignment.value.clear_positions();
+			},
+			Instruction::Statement(Statement::Return(value)) => value.clear_positions(),
+			Instruction::IfElse(if_else) => {
+				if_else.condition.clear_positions();
+				if_else.if_true.clear_positions();
+				if_else.if_false.clear_positions();
+			},
+			Instruction::WhileLoop(while_loop) => {
+				while_loop.condition.clear_positions();
+				while_loop.body.clear_positions();
+			},
+			Instruction::Invalid(position) => *position = self::position(),
+		}
+	}
+}
+
+impl ClearPositions for FieldAccess {
+	fn clear_positions(&mut self) {
+		self.base.clear_positions();
+		self.field.clear_positions();
+	}
+}
+
+impl ClearPositions for Expression {
+	fn clear_positions(&mut self) {
+		match self {
+			Expression::BinaryExpression(binary_expression) => {
+				binary_expression.lhs.clear_positions();
+				binary_expression.operator.clear_positions();
+				binary_expression.rhs.clear_positions();
+			},
+			Expression::FieldAccess(field_access) => field_access.clear_positions(),
+			Expression::FunctionCall(function_call) => {
+				function_call.name.clear_positions();
+				function_call.params.clear_positions();
+			},
+			Expression::Number(number) => number.clear_positions(),
+			Expression::String(string) => string.clear_positions(),
+			Expression::Variable(variable) => variable.clear_positions(),
+			Expression::Null(position) | Expression::Error(position) | Expression::Invalid(position) => {
+				*position = self::position()
+			},
+			Expression::Try(try_expression) => {
+				try_expression.position = self::position();
+				try_expression.value.clear_positions();
+				try_expression.fallback.clear_positions();
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		ast::expression::BinaryOperator::{Add, Less},
+		dump::Dumper,
+		interpreter::Interpreter,
+		semantic_analyzer::{self, CheckConfig},
+		source::Source,
+	};
+
+	/// Builds a program, checks and runs it, and compares it with the result of parsing the emitted FTL code.
+	#[test]
+	fn test_round_trip() {
+		let count = || field(var("counter"), "count");
+		let count_to = function("count_to").arg("limit", int()).returns(int()).body([
+			declare("counter", struct_type("Counter"), call("Counter", [])),
+			while_loop(
+				binary(count(), Less, var("limit")),
+				[assign_field(var("counter"), "count", binary(count(), Add, int_value(1)))],
+			),
+			ret(count()),
+		]);
+		let ast_nodes = vec![
+			comment("Generated"),
+			struct_("Counter").field_with_default("count", int(), int_value(0)).into(),
+			count_to.into(),
+			function("main").returns(int()).body([ret(call("count_to", [int_value(3)]))]).into(),
+		];
+		semantic_analyzer::analyze(ast_nodes.iter(), &CheckConfig::default()).unwrap();
+		assert_eq!(Interpreter::new(ast_nodes.iter()).run(), Ok(3));
+
+		let source = Arc::new(Source::new("generated.ftl".to_owned(), crate::ast::to_source(ast_nodes.clone())));
+		let mut parsed = crate::parse_source(source, &Dumper::default()).unwrap();
+		parsed.clear_positions();
+		assert_eq!(parsed, ast_nodes);
+	}
+}
@@ -1,7 +1,7 @@
 use super::BinaryOperator;
 use crate::{
 	ast::Expression,
-	source::{PositionContainer, SourcePositionRange},
+	source::{SourcePositionRange, Spanned},
 };
 
 /// A binary expression of the form `lhs op rhs` like `40 + 2`.
@@ -10,7 +10,7 @@ pub struct BinaryExpression {
 	/// The left hand side.
 	pub lhs: Box<Expression>,
 	/// The operator connecting `lhs` and `rhs`.
-	pub operator: PositionContainer<BinaryOperator>,
+	pub operator: Spanned<BinaryOperator>,
 	/// The right hand side.
 	pub rhs: Box<Expression>,
 }
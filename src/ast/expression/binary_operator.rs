@@ -1,7 +1,5 @@
 use std::{cmp::Ordering, collections::HashMap};
 
-// TODO: Implement Copy for BinaryOperator? See parser::Parser::parse_binary_operation_rhs() at `If the next binary
-//  operator binds stronger with rhs than with current, let it go with rhs`
 /// A binary operator connecting a lhs and a rhs.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum BinaryOperator {
@@ -9,16 +7,46 @@ pub enum BinaryOperator {
 	Less,
 	/// Comparison if lhs is bigger/greater than rhs (`>`).
 	Greater,
+	/// Comparison if lhs is less than or equal to rhs (`<=`).
+	LessEqual,
+	/// Comparison if lhs is greater than or equal to rhs (`>=`).
+	GreaterEqual,
 	/// Addition (`+`).
 	Add,
 	/// Subtraction (`-`).
 	Subtract,
 	/// Multiplication (`*`)
 	Multiply,
-	/// Division (`/`)
+	/// Division (`/`), which truncates towards zero for ints.
 	Divide,
+	/// Remainder of the division of ints (`mod`), which is never negative, e.g. `(0 - 7) mod 3` is `2`.
+	Modulus,
 	Equal,
 	NotEqual,
+	/// Logical and (`and`), which only evaluates rhs if lhs is true, i.e. not zero.
+	LogicalAnd,
+	/// Logical or (`or`), which only evaluates rhs if lhs is false, i.e. zero.
+	LogicalOr,
+}
+
+impl BinaryOperator {
+	/// Whether the operator compares lhs and rhs, i.e. evaluates to `1` or `0`.
+	pub fn is_comparison(&self) -> bool {
+		matches!(
+			self,
+			BinaryOperator::Less
+				| BinaryOperator::Greater
+				| BinaryOperator::LessEqual
+				| BinaryOperator::GreaterEqual
+				| BinaryOperator::Equal
+				| BinaryOperator::NotEqual
+		)
+	}
+
+	/// Whether the operator connects two conditions, i.e. evaluates to `1` or `0` and short-circuits.
+	pub fn is_logical(&self) -> bool {
+		matches!(self, BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr)
+	}
 }
 
 impl PartialOrd for BinaryOperator {
@@ -29,12 +57,17 @@ impl PartialOrd for BinaryOperator {
 		let mut precedence = HashMap::new();
 		precedence.insert(BinaryOperator::Less, 10);
 		precedence.insert(BinaryOperator::Greater, 10);
+		precedence.insert(BinaryOperator::LessEqual, 10);
+		precedence.insert(BinaryOperator::GreaterEqual, 10);
 		precedence.insert(BinaryOperator::Add, 20);
 		precedence.insert(BinaryOperator::Subtract, 20);
 		precedence.insert(BinaryOperator::Multiply, 30);
 		precedence.insert(BinaryOperator::Divide, 30);
+		precedence.insert(BinaryOperator::Modulus, 30);
 		precedence.insert(BinaryOperator::Equal, 5);
 		precedence.insert(BinaryOperator::NotEqual, 5);
+		precedence.insert(BinaryOperator::LogicalAnd, 3);
+		precedence.insert(BinaryOperator::LogicalOr, 2);
 
 		precedence[self].partial_cmp(&precedence[other])
 	}
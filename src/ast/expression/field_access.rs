@@ -0,0 +1,21 @@
+use crate::{
+	ast::Expression,
+	source::{SourcePositionRange, Spanned},
+};
+
+/// Access of a struct field in the form `base.field` like `point.x`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldAccess {
+	/// The expression evaluating to the struct whose field is accessed.
+	pub base: Box<Expression>,
+	/// The name of the accessed field.
+	pub field: Spanned<String>,
+}
+
+impl FieldAccess {
+	pub fn source_position(&self) -> SourcePositionRange {
+		let mut position = self.base.source_position();
+		position.position.end = self.field.position.position.end;
+		position
+	}
+}
@@ -1,13 +1,13 @@
 use std::{fmt, ops::Deref};
 
 use super::Expression;
-use crate::source::PositionContainer;
+use crate::source::Spanned;
 
 /// A function call, i.e. the execution of a [`FunctionDefinition`](crate::ast::FunctionDefinition) with concrete parameters.
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionCall {
 	/// The name of the function to be called.
-	pub name: PositionContainer<String>,
+	pub name: Spanned<String>,
 	/// The parameters to invoke the called function with.
 	pub params: Vec<Expression>,
 }
@@ -1,36 +1,58 @@
 mod binary_expression;
 mod binary_operator;
+mod field_access;
 mod function_call;
+mod try_expression;
 
 pub use binary_expression::BinaryExpression;
 pub use binary_operator::BinaryOperator;
+pub use field_access::FieldAccess;
 pub use function_call::FunctionCall;
+pub use try_expression::TryExpression;
 
-use crate::source::{PositionContainer, SourcePositionRange};
+use crate::source::{SourcePositionRange, Spanned};
 
-pub type Variable = PositionContainer<String>;
+pub type Variable = Spanned<String>;
 
 /// An expression produces a value.
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
 	BinaryExpression(BinaryExpression),
+	FieldAccess(FieldAccess),
 	FunctionCall(FunctionCall),
 	Number(Number),
-	Variable(PositionContainer<String>),
+	String(StringLiteral),
+	Variable(Spanned<String>),
+	/// `null`, the value of an optional type without a value.
+	Null(SourcePositionRange),
+	/// `try value else fallback`, checking the result of a function that can fail.
+	Try(TryExpression),
+	/// `error`, the result of a function that failed.
+	Error(SourcePositionRange),
+	/// Code that could not be parsed, standing in for the expression in a partial AST.
+	Invalid(SourcePositionRange),
 }
 
 impl Expression {
 	pub fn source_position(&self) -> SourcePositionRange {
 		match self {
 			Expression::BinaryExpression(binary_expression) => binary_expression.source_position(),
+			Expression::FieldAccess(field_access) => field_access.source_position(),
 			Expression::FunctionCall(function_call) => function_call.name.position.clone(),
 			Expression::Number(number) => number.position.clone(),
+			Expression::String(string) => string.position.clone(),
 			Expression::Variable(variable) => variable.position.clone(),
+			Expression::Null(position) => position.clone(),
+			Expression::Try(try_expression) => try_expression.source_position(),
+			Expression::Error(position) | Expression::Invalid(position) => position.clone(),
 		}
 	}
 }
 
-pub type Number = PositionContainer<NumberKind>;
+pub type Number = Spanned<NumberKind>;
+
+/// A string literal like `"Hello"`, already without quotes and with escape sequences resolved.
+pub type StringLiteral = Spanned<String>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum NumberKind {
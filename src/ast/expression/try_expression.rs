@@ -0,0 +1,24 @@
+use super::Expression;
+use crate::source::SourcePositionRange;
+
+/// A `try value else fallback` expression, which evaluates to the result of the call `value` of a function that can
+/// fail, or to `fallback` if it failed.
+///
+/// A fallback of `error` passes the failure on to the caller of the enclosing function.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TryExpression {
+	/// Position of the `try` keyword.
+	pub position: SourcePositionRange,
+	/// The call that may fail.
+	pub value: Box<Expression>,
+	/// Evaluated only if `value` failed.
+	pub fallback: Box<Expression>,
+}
+
+impl TryExpression {
+	pub fn source_position(&self) -> SourcePositionRange {
+		let mut position = self.position.clone();
+		position.position.end = self.fallback.source_position().position.end;
+		position
+	}
+}
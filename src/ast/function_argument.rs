@@ -1,10 +1,10 @@
-use crate::{ast::statement::DataType, source::PositionContainer};
+use crate::{ast::statement::DataType, source::Spanned};
 
 /// Name and a type that specify an argument of a function in its function prototype.
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 pub struct FunctionArgument {
 	/// The name of the function argument.
-	pub name: PositionContainer<String>,
+	pub name: Spanned<String>,
 	/// The type of the argument, e.g. a int, a struct or a pointer.
-	pub data_type: PositionContainer<DataType>,
+	pub data_type: Spanned<DataType>,
 }
@@ -1,4 +1,7 @@
-use crate::ast::{function_prototype::FunctionPrototype, Block};
+use crate::{
+	ast::{function_prototype::FunctionPrototype, Block},
+	source::Spanned,
+};
 
 /// Name, arguments and body define a function.
 #[derive(Debug, PartialEq, Clone)]
@@ -7,4 +10,6 @@ pub struct FunctionDefinition {
 	pub prototype: FunctionPrototype,
 	/// The body of the function.
 	pub body: Block,
+	/// Names of the annotations in front of the function, e.g. `bench` for `@bench`.
+	pub annotations: Vec<Spanned<String>>,
 }
@@ -1,15 +1,19 @@
 use crate::{
 	ast::{function_argument::FunctionArgument, statement::DataType},
-	source::PositionContainer,
+	source::Spanned,
 };
 
 /// The header of the function i.e. function name and arguments, but not the body.
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 pub struct FunctionPrototype {
+	/// The [doc comment](crate::token::TokenKind::DocComment) preceding the function.
+	pub doc: Option<Spanned<String>>,
 	/// The name of the function.
-	pub name: PositionContainer<String>,
+	pub name: Spanned<String>,
 	/// The arguments for the function.
 	pub args: Vec<FunctionArgument>,
 	/// Return type is what this function returns.
-	pub return_type: Option<PositionContainer<DataType>>,
+	pub return_type: Option<Spanned<DataType>>,
+	/// Whether the function is marked with `export`, i.e. is part of the C header of a library.
+	pub exported: bool,
 }
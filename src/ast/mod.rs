@@ -2,6 +2,7 @@
 //!
 //! The AST is a tree representation of the source code, which is used for [semantic analysis](crate::semantic_analyzer) and [code generation](crate::emitter).
 
+pub mod build;
 pub mod expression;
 mod function_argument;
 mod function_definition;
@@ -19,6 +20,11 @@ pub use statement::Statement;
 pub use struct_::Struct;
 pub use while_loop::WhileLoop;
 
+use crate::{
+	emitter,
+	source::{SourcePositionRange, Spanned},
+};
+
 /// A "regular" line of code.
 #[derive(Debug, PartialEq, Clone)]
 pub enum Instruction {
@@ -26,15 +32,77 @@ pub enum Instruction {
 	Statement(Statement),
 	IfElse(Box<IfElse>),
 	WhileLoop(Box<WhileLoop>),
+	/// Code that could not be parsed, standing in for the instruction in a partial AST.
+	Invalid(SourcePositionRange),
+}
+
+impl Instruction {
+	/// Position of the instruction. For `if` and `while`, the position of the condition.
+	pub fn source_position(&self) -> SourcePositionRange {
+		match self {
+			Instruction::Expression(expression) => expression.source_position(),
+			Instruction::Statement(statement) => statement.source_position(),
+			Instruction::IfElse(if_else) => if_else.condition.source_position(),
+			Instruction::WhileLoop(while_loop) => while_loop.condition.source_position(),
+			Instruction::Invalid(position) => position.clone(),
+		}
+	}
 }
 
 /// The top-level element of an AST.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Node {
 	FunctionPrototype(FunctionPrototype),
 	Function(FunctionDefinition),
 	Struct(Struct),
+	/// Comment between functions and structs, which is only kept for [formatting](crate::emitter::Ftl).
+	Comment(Spanned<String>),
+	/// Code that could not be parsed, standing in for a function or struct in a partial AST.
+	Invalid(SourcePositionRange),
+}
+
+impl Node {
+	/// The [doc comment](crate::token::TokenKind::DocComment) of a function, extern declaration or struct.
+	pub fn doc(&self) -> Option<&Spanned<String>> {
+		match self {
+			Node::FunctionPrototype(prototype) => prototype.doc.as_ref(),
+			Node::Function(function) => function.prototype.doc.as_ref(),
+			Node::Struct(struct_) => struct_.doc.as_ref(),
+			Node::Comment(_) | Node::Invalid(_) => None,
+		}
+	}
 }
 
 /// A list of instructions.
 pub type Block = Vec<Instruction>;
+
+/// Generates FTL source code for the `ast_nodes` in the default [style](emitter::FmtConfig), e.g. for ASTs that were
+/// [built](build) or transformed in code. Use the [FTL emitter](emitter::Ftl) directly for another style.
+pub fn to_source(ast_nodes: impl IntoIterator<Item = Node>) -> String {
+	emitter::codegen_to_string(&emitter::Ftl::default(), &mut ast_nodes.into_iter())
+		.expect("generating code in memory doesn't fail")
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::{build::ClearPositions, *};
+	use crate::{dump::Dumper, source::Source};
+
+	#[test]
+	fn test_to_source() {
+		let source_code =
+			"struct P {\n\tname: str = \"a \\\"quoted\\\" name\"\n}\n\ndef main(): int {\n\treturn 0\n}\n";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let generated = to_source(ast_nodes.clone());
+		assert_eq!(generated, source_code);
+
+		let source = Arc::new(Source::new("generated.ftl".to_owned(), generated));
+		let (mut reparsed, mut expected) = (crate::parse_source(source, &Dumper::default()).unwrap(), ast_nodes);
+		reparsed.clear_positions();
+		expected.clear_positions();
+		assert_eq!(reparsed, expected);
+	}
+}
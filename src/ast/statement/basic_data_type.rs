@@ -7,6 +7,8 @@ pub enum BasicDataType {
 	Int,
 	/// A floating point number like 4.2
 	Float,
+	/// A string like "Hello"
+	Str,
 }
 
 impl TryFrom<&str> for BasicDataType {
@@ -18,6 +20,7 @@ impl TryFrom<&str> for BasicDataType {
 		match data_type {
 			"int" => Ok(BasicDataType::Int),
 			"float" => Ok(BasicDataType::Float),
+			"str" => Ok(BasicDataType::Str),
 			_ => Err(()), // No basic data type with this name
 		}
 	}
@@ -28,6 +31,7 @@ impl fmt::Display for BasicDataType {
 		match self {
 			BasicDataType::Int => write!(f, "int"),
 			BasicDataType::Float => write!(f, "float"),
+			BasicDataType::Str => write!(f, "str"),
 		}
 	}
 }
@@ -1,9 +1,9 @@
 use std::fmt;
 
 use super::basic_data_type::BasicDataType;
-use crate::source::PositionContainer;
+use crate::source::Spanned;
 
-/// A data type is either basic, a struct, or a pointer to a data type.
+/// A data type is either basic, a struct, a pointer to a data type, or void.
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 pub enum DataType {
 	/// A basic data type like int and float.
@@ -11,7 +11,13 @@ pub enum DataType {
 	/// A user defined struct with custom name.
 	Struct(String),
 	/// A Pointer to a data type.
-	Pointer(Box<PositionContainer<DataType>>),
+	Pointer(Box<Spanned<DataType>>),
+	/// Either a value of the data type or `null`.
+	Optional(Box<Spanned<DataType>>),
+	/// Either a value of the data type or `error`, the return type of a function that can fail.
+	Fallible(Box<Spanned<DataType>>),
+	/// No value, the type of calls to functions without return type.
+	Void,
 }
 
 impl fmt::Display for DataType {
@@ -20,6 +26,9 @@ impl fmt::Display for DataType {
 			DataType::Basic(basic_data_type) => write!(f, "{}", basic_data_type),
 			DataType::Struct(struct_name) => write!(f, "{}", struct_name),
 			DataType::Pointer(pointer) => write!(f, "ptr {}", pointer.value),
+			DataType::Optional(inner) => write!(f, "opt {}", inner.value),
+			DataType::Fallible(inner) => write!(f, "{} or error", inner.value),
+			DataType::Void => write!(f, "void"),
 		}
 	}
 }
@@ -10,12 +10,27 @@ pub use crate::ast::{
 	function_argument::FunctionArgument,
 	function_definition::FunctionDefinition,
 	function_prototype::FunctionPrototype,
-	statement::var_assignment::{VariableAssignment, VariableDeclaration},
+	statement::var_assignment::{FieldAssignment, VariableAssignment, VariableDeclaration},
 };
+use crate::source::{SourcePositionRange, Spanned};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
 	VariableDeclaration(VariableDeclaration),
 	VariableAssignment(VariableAssignment),
-	Return(Expression),
+	FieldAssignment(FieldAssignment),
+	/// A `return` with an optional value, positioned at the `return` keyword.
+	Return(Spanned<Option<Expression>>),
+}
+
+impl Statement {
+	/// Position of the declared or assigned variable or field, or of the `return` keyword.
+	pub fn source_position(&self) -> SourcePositionRange {
+		match self {
+			Statement::VariableDeclaration(declaration) => declaration.name.position.clone(),
+			Statement::VariableAssignment(assignment) => assignment.name.position.clone(),
+			Statement::FieldAssignment(assignment) => assignment.target.source_position(),
+			Statement::Return(value) => value.position.clone(),
+		}
+	}
 }
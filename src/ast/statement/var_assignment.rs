@@ -1,17 +1,25 @@
 use crate::{
-	ast::{statement::DataType, Expression},
-	source::PositionContainer,
+	ast::{expression::FieldAccess, statement::DataType, Expression},
+	source::Spanned,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct VariableDeclaration {
-	pub name: PositionContainer<String>,
-	pub data_type: PositionContainer<DataType>,
+	pub name: Spanned<String>,
+	pub data_type: Spanned<DataType>,
 	pub value: Expression,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct VariableAssignment {
-	pub name: PositionContainer<String>,
+	pub name: Spanned<String>,
+	pub value: Expression,
+}
+
+/// Assignment to a field of a struct variable, e.g. `line.start.x = 1`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldAssignment {
+	/// The assigned field, whose base is a variable or a field access of a variable.
+	pub target: FieldAccess,
 	pub value: Expression,
 }
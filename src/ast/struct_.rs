@@ -1,19 +1,30 @@
-use crate::{ast::statement::DataType, source::PositionContainer};
+use crate::{
+	ast::{statement::DataType, Expression},
+	source::Spanned,
+};
 
 /// Collection of fields.
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Struct {
+	/// The [doc comment](crate::token::TokenKind::DocComment) preceding the struct.
+	pub doc: Option<Spanned<String>>,
 	/// The name of the struct.
-	pub name: PositionContainer<String>,
+	pub name: Spanned<String>,
 	/// The fields of the struct.
 	pub fields: Vec<Field>,
+	/// Whether the struct is marked with `export`, i.e. is part of the C header of a library.
+	pub exported: bool,
 }
 
 /// A struct field consists of a name and a type that specify a field of a struct.
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Field {
+	/// The [doc comment](crate::token::TokenKind::DocComment) preceding the field.
+	pub doc: Option<Spanned<String>>,
 	/// The name of the struct field.
-	pub name: PositionContainer<String>,
+	pub name: Spanned<String>,
 	/// The type of the field, e.g. a int, a struct or a pointer.
-	pub data_type: PositionContainer<DataType>,
+	pub data_type: Spanned<DataType>,
+	/// The value of the field if it is not given explicitly, e.g. `0` in `x: int = 0`.
+	pub default: Option<Expression>,
 }
@@ -1,5 +1,5 @@
 use crate::ast;
-use crate::source::PositionContainer;
+use crate::source::Spanned;
 use std::{io, todo};
 
 /// *Visitor pattern* for visiting each [`Node`] of an AST.
@@ -138,9 +138,9 @@ pub trait Visitor {
 
 	fn data_type(
 		&mut self,
-		data_type: PositionContainer<ast::statement::DataType>,
+		data_type: Spanned<ast::statement::DataType>,
 	) -> Result<(), Self::Err> {
-		match data_type.inner {
+		match data_type.value {
 			ast::statement::DataType::Basic(basic_data_type) => {
 				self.basic_data_type(basic_data_type)
 			}
@@ -162,7 +162,7 @@ pub trait Visitor {
 
 	fn pointer(
 		&mut self,
-		pointer: Box<PositionContainer<ast::statement::DataType>>,
+		pointer: Box<Spanned<ast::statement::DataType>>,
 	) -> Result<(), Self::Err> {
 		self.data_type(*pointer)
 	}
@@ -0,0 +1,119 @@
+//! Benchmarking functions annotated with `@bench` using the [interpreter](crate::interpreter).
+
+use std::{
+	io,
+	time::{Duration, Instant},
+};
+
+use crate::{
+	ast,
+	interpreter::{self, Interpreter},
+	source::Spanned,
+};
+
+/// How often each benchmarked function is called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchOptions {
+	/// Calls before the measurement starts, which are not measured.
+	pub warmup: u32,
+	/// Measured calls.
+	pub iterations: u32,
+}
+
+impl Default for BenchOptions {
+	fn default() -> Self {
+		Self { warmup: 10, iterations: 100 }
+	}
+}
+
+/// Measured wall time of one benchmarked function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchResult {
+	/// Name of the benchmarked function.
+	pub name: String,
+	/// Number of measured calls.
+	pub iterations: u32,
+	/// Wall time of all measured calls together.
+	pub total: Duration,
+}
+
+impl BenchResult {
+	/// Average wall time of a single call in nanoseconds.
+	pub fn nanos_per_op(&self) -> u128 {
+		self.total.as_nanos() / u128::from(self.iterations.max(1))
+	}
+}
+
+/// Runs all functions of the program annotated with `@bench` and measures their wall time.
+///
+/// The output of the benchmarked functions is discarded.
+pub fn run(ast_nodes: &[ast::Node], options: BenchOptions) -> Result<Vec<BenchResult>, interpreter::Error> {
+	let mut interpreter = Interpreter::new(ast_nodes.iter()).with_stdout(Box::new(io::sink()));
+	bench_functions(ast_nodes)
+		.map(|name| {
+			for _ in 0..options.warmup {
+				interpreter.call_function(name)?;
+			}
+			let start = Instant::now();
+			for _ in 0..options.iterations {
+				interpreter.call_function(name)?;
+			}
+			Ok(BenchResult { name: name.value.clone(), iterations: options.iterations, total: start.elapsed() })
+		})
+		.collect()
+}
+
+/// Names of the functions annotated with `@bench`, in the order of their definition.
+fn bench_functions(ast_nodes: &[ast::Node]) -> impl Iterator<Item = &Spanned<String>> {
+	ast_nodes.iter().filter_map(|ast_node| match ast_node {
+		ast::Node::Function(function) if function.annotations.iter().any(|annotation| **annotation == "bench") => {
+			Some(&function.prototype.name)
+		},
+		_ => None,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{dump::Dumper, semantic_analyzer::CheckConfig, source::Source};
+
+	#[test]
+	fn test_bench() {
+		let source_code = "
+			@bench
+			def sum(): int {
+				var i: int = 0
+				while i < 10 { i = i + 1 }
+				return i
+			}
+
+			def helper(): int { return 1 }
+
+			@bench
+			export def greet() { ftl_print(\"discarded\") }";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes =
+			crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+		let results = run(&ast_nodes, BenchOptions { warmup: 1, iterations: 3 }).unwrap();
+		let names: Vec<_> = results.iter().map(|result| result.name.as_str()).collect();
+		assert_eq!(names, ["sum", "greet"]);
+		assert!(results.iter().all(|result| result.iterations == 3));
+	}
+
+	#[test]
+	fn test_invalid_annotation() {
+		for source_code in ["@fast def f() { }", "@bench def f(a: int) { }"] {
+			let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+			assert!(
+				crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).is_err(),
+				"{}",
+				source_code
+			);
+		}
+		let source = Arc::new(Source::new("test.ftl".to_owned(), "@bench struct S { }".to_owned()));
+		assert!(crate::parse_source(source, &Dumper::default()).is_err());
+	}
+}
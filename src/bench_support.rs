@@ -0,0 +1,55 @@
+//! Deterministic FTL source code generators for benchmarking the compiler itself.
+//!
+//! The generated programs are valid, so that every phase of the compiler can be benchmarked with them. They only
+//! depend on their size parameters, which makes measurements comparable between runs.
+
+use std::{fmt::Write, sync::Arc};
+
+use crate::source::Source;
+
+/// Wraps the generated `code` in a [`Source`], which can be passed to [`lex_source`](crate::lex_source) and
+/// [`parse_source`](crate::parse_source).
+pub fn source(code: String) -> Arc<Source> {
+	Arc::new(Source::new("bench.ftl".to_owned(), code))
+}
+
+/// A program with `count` functions, each calling the previous one.
+pub fn many_functions(count: usize) -> String {
+	let mut code = String::from("def f0(a: int): int {\n\treturn a\n}\n");
+	for i in 1..count {
+		let _ = writeln!(code, "def f{}(a: int): int {{\n\treturn f{}(a) + {}\n}}", i, i - 1, i);
+	}
+	let _ = writeln!(code, "def main(): int {{\n\treturn f{}(0)\n}}", count.saturating_sub(1));
+	code
+}
+
+/// A program returning an expression with `depth` nested parentheses.
+pub fn deep_expression(depth: usize) -> String {
+	format!("def main(): int {{\n\treturn {}1{}\n}}\n", "(1 + ".repeat(depth), ")".repeat(depth))
+}
+
+/// A program with a single function of `lines` variable declarations.
+pub fn long_file(lines: usize) -> String {
+	let mut code = String::from("def main(): int {\n\tvar sum: int = 0\n");
+	for i in 0..lines {
+		let _ = writeln!(code, "\tvar v{}: int = ({} * 2)\n\tsum = sum + v{}", i, i, i);
+	}
+	code.push_str("\treturn sum\n}\n");
+	code
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{dump::Dumper, interpreter::Interpreter, semantic_analyzer::CheckConfig};
+
+	#[test]
+	fn test_generated_programs() {
+		for (code, exit_code) in [(many_functions(10), 45), (deep_expression(32), 33), (long_file(10), 90)] {
+			let ast_nodes =
+				crate::compile_source(source(code), &CheckConfig::default(), &Dumper::default(), &mut Vec::new())
+					.unwrap();
+			assert_eq!(Interpreter::new(ast_nodes.iter()).run(), Ok(exit_code));
+		}
+	}
+}
@@ -0,0 +1,179 @@
+//! Building an executable from multiple FTL source files.
+//!
+//! Each source file is compiled to its own C translation unit and object file, which are linked together with the
+//! [runtime] at the end.
+//! The prelude as well as the struct definitions and function prototypes of all units are emitted only once into a
+//! shared header. C files are only rewritten and recompiled if their content changed, so that large projects don't
+//! recompile everything on each build.
+
+use std::{
+	collections::HashSet,
+	fs,
+	path::{Path, PathBuf},
+	process,
+};
+
+use crate::{
+	ast,
+	dump::{Artifact, Dumper},
+	emitter::{self, CAnalysis, CMangling, CSharedHeader, CUnit},
+	runtime,
+	semantic_analyzer::CheckConfig,
+	CompileError,
+};
+
+/// File name of the header shared by all translation units.
+const SHARED_HEADER: &str = "ftl.h";
+
+/// One source file of a build.
+struct Unit {
+	/// Path of the FTL source file.
+	path: PathBuf,
+	/// Name of the generated C and object files, without extension.
+	name: String,
+	ast_nodes: Vec<ast::Node>,
+}
+
+/// Compiles the `source_paths` to object files in `build_directory` and links them to `executable`.
+pub fn build(
+	source_paths: &[PathBuf],
+	build_directory: &Path,
+	executable: &Path,
+	config: &CheckConfig,
+	dumper: &Dumper,
+) -> Result<(), CompileError> {
+	let mut units = Vec::new();
+	let mut names = HashSet::new();
+	for path in source_paths {
+		let ast_nodes = crate::parse_source(crate::read_source(path)?, dumper)?;
+		let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+		if !names.insert(name.clone()) {
+			return Err(CompileError::Invalid(format!(
+				"Multiple source files are named `{}`, but the names of source files must be unique",
+				name
+			)));
+		}
+		units.push(Unit { path: path.clone(), name, ast_nodes });
+	}
+	let Some(first_unit) = units.first() else {
+		return Err(CompileError::Invalid("No source files given".to_owned()));
+	};
+
+	// Analyze all units together, so that units can use the functions and structs of each other
+	let program = crate::analyze(&first_unit.path, units.iter().flat_map(|unit| &unit.ast_nodes), config, dumper)?;
+
+	fs::create_dir_all(build_directory)
+		.map_err(CompileError::io(format!("Creating build directory `{:?}`", build_directory)))?;
+
+	let shared_header =
+		emitter::codegen_to_string(&CSharedHeader, &mut units.iter().flat_map(|unit| unit.ast_nodes.iter().cloned()))
+			.map_err(CompileError::io("Generating the shared header"))?;
+	let shared_header_changed = write_if_changed(&build_directory.join(SHARED_HEADER), &shared_header)?;
+
+	let mangling = CMangling::new(units.iter().flat_map(|unit| &unit.ast_nodes));
+	let analysis = CAnalysis::new(&program);
+	let mut object_paths = vec![runtime::compile(build_directory)?];
+	for unit in units {
+		let unit_emitter =
+			CUnit { shared_header: SHARED_HEADER.to_owned(), mangling: mangling.clone(), analysis: analysis.clone() };
+		let c_code = emitter::codegen_to_string(&unit_emitter, &mut unit.ast_nodes.into_iter())
+			.map_err(CompileError::io(format!("Generating C code for `{:?}`", unit.path)))?;
+		dumper.dump(Artifact::C, &unit.path, || c_code.clone()).map_err(CompileError::io("Dumping C code"))?;
+
+		let c_path = build_directory.join(format!("{}.c", unit.name));
+		let object_path = build_directory.join(format!("{}.o", unit.name));
+		let c_code_changed = write_if_changed(&c_path, &c_code)?;
+		if c_code_changed || shared_header_changed || !object_path.exists() {
+			tracing::info!(path = ?c_path, "compiling unit");
+			run_c_compiler(process::Command::new("cc").arg("-c").arg(&c_path).arg("-o").arg(&object_path))?;
+		}
+		object_paths.push(object_path);
+	}
+
+	tracing::info!(path = ?executable, "linking");
+	run_c_compiler(process::Command::new("cc").args(&object_paths).args(runtime::LINK_ARGS).arg("-o").arg(executable))
+}
+
+/// Writes the `content` to the file at `path`, unless the file already has exactly this content.
+///
+/// Returns whether the file was written.
+pub(crate) fn write_if_changed(path: &Path, content: &str) -> Result<bool, CompileError> {
+	if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+		return Ok(false);
+	}
+	fs::write(path, content).map_err(CompileError::io(format!("Writing `{:?}`", path)))?;
+	Ok(true)
+}
+
+/// Runs the C compiler `command` and fails with its error output if it does not succeed.
+pub(crate) fn run_c_compiler(command: &mut process::Command) -> Result<(), CompileError> {
+	let output = command.output().map_err(CompileError::io("Invoking C compiler"))?;
+	if !output.status.success() {
+		return Err(CompileError::CCompiler { stderr: String::from_utf8_lossy(&output.stderr).into_owned() });
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{differential::Backend, test_support::TempDir};
+
+	#[test]
+	fn test_multiple_units() {
+		if !Backend::C.is_available() {
+			return;
+		}
+		let directory = TempDir::new().unwrap();
+		let main_path = directory.path().join("main.ftl");
+		let square_path = directory.path().join("square.ftl");
+		fs::write(&main_path, "def main(): int {\n\treturn square(6) + 6\n}").unwrap();
+		fs::write(&square_path, "export def square(a: int): int {\n\treturn a * a\n}").unwrap();
+		let build_directory = directory.path().join("build");
+		let executable = directory.path().join("main");
+		let sources = [main_path, square_path];
+
+		build(&sources, &build_directory, &executable, &CheckConfig::default(), &Dumper::default()).unwrap();
+		let status = process::Command::new(&executable).status().unwrap();
+		assert_eq!(status.code(), Some(42));
+
+		// Unchanged units are not recompiled
+		let object_path = build_directory.join("square.o");
+		let modified = fs::metadata(&object_path).unwrap().modified().unwrap();
+		build(&sources, &build_directory, &executable, &CheckConfig::default(), &Dumper::default()).unwrap();
+		assert_eq!(fs::metadata(&object_path).unwrap().modified().unwrap(), modified);
+	}
+
+	#[test]
+	fn test_mutual_recursion_across_units() {
+		if !Backend::C.is_available() {
+			return;
+		}
+		let directory = TempDir::new().unwrap();
+		let even_path = directory.path().join("even.ftl");
+		let odd_path = directory.path().join("odd.ftl");
+		fs::write(
+			&even_path,
+			"def main(): int {\n\treturn is_even(9) + 2\n}\nexport def is_even(n: int): int {\n\tif n < 1 {\n\t\treturn \
+			 1\n\t}\n\treturn is_odd((n - 1))\n}",
+		)
+		.unwrap();
+		fs::write(
+			&odd_path,
+			"export def is_odd(n: int): int {\n\tif n < 1 {\n\t\treturn 0\n\t}\n\treturn is_even((n - 1))\n}",
+		)
+		.unwrap();
+		let executable = directory.path().join("even");
+
+		build(
+			&[even_path, odd_path],
+			&directory.path().join("build"),
+			&executable,
+			&CheckConfig::default(),
+			&Dumper::default(),
+		)
+		.unwrap();
+		let status = process::Command::new(&executable).status().unwrap();
+		assert_eq!(status.code(), Some(2));
+	}
+}
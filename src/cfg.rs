@@ -0,0 +1,141 @@
+//! Control flow graph of a function, the basis of the [data-flow analyses](crate::dataflow).
+
+use crate::ast::{self, Expression, FunctionDefinition};
+
+/// Identifies a [`Block`] of a [`Cfg`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub usize);
+
+/// Something executed as a whole, without any branching.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step<'a> {
+	/// A statement or an expression evaluated for its side effects. Never an if-else or a loop.
+	Instruction(&'a ast::Instruction),
+	/// The condition of an if-else or a while loop. It ends its block, which branches depending on its value.
+	Condition(&'a Expression),
+}
+
+/// Steps that are always executed one after another, i.e. a basic block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block<'a> {
+	pub steps: Vec<Step<'a>>,
+	/// Blocks that may be executed next.
+	pub successors: Vec<BlockId>,
+	/// Blocks that may have been executed before.
+	pub predecessors: Vec<BlockId>,
+}
+
+/// Control flow graph of a function body.
+///
+/// Control enters at the [entry](Self::ENTRY) block and leaves through the [exit](Self::EXIT) block, which contains
+/// no steps. Blocks following a `return` are unreachable, i.e. have no predecessors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cfg<'a> {
+	pub blocks: Vec<Block<'a>>,
+}
+
+impl<'a> Cfg<'a> {
+	/// The block control enters the function at.
+	pub const ENTRY: BlockId = BlockId(0);
+	/// The block control leaves the function through, by a `return` or the end of the body.
+	pub const EXIT: BlockId = BlockId(1);
+
+	/// Builds the control flow graph of the body of the `function`.
+	pub fn new(function: &'a FunctionDefinition) -> Self {
+		let mut cfg = Cfg { blocks: vec![Block::default(), Block::default()] };
+		let end = cfg.instructions(Self::ENTRY, &function.body);
+		cfg.add_edge(end, Self::EXIT);
+		cfg
+	}
+
+	/// The block with the `id`.
+	pub fn block(&self, id: BlockId) -> &Block<'a> {
+		&self.blocks[id.0]
+	}
+
+	/// Ids of all blocks, in the order of the source code.
+	pub fn block_ids(&self) -> impl Iterator<Item = BlockId> {
+		(0..self.blocks.len()).map(BlockId)
+	}
+
+	/// Adds the `instructions` to the `block` and returns the block executed after them.
+	fn instructions(&mut self, mut block: BlockId, instructions: &'a [ast::Instruction]) -> BlockId {
+		for instruction in instructions {
+			match instruction {
+				ast::Instruction::Expression(_) | ast::Instruction::Statement(_) | ast::Instruction::Invalid(_) => {
+					self.blocks[block.0].steps.push(Step::Instruction(instruction));
+				},
+				ast::Instruction::IfElse(if_else) => {
+					self.blocks[block.0].steps.push(Step::Condition(&if_else.condition));
+					let join = self.add_block();
+					for branch in [&if_else.if_true, &if_else.if_false] {
+						let start = self.add_block();
+						self.add_edge(block, start);
+						let end = self.instructions(start, branch);
+						self.add_edge(end, join);
+					}
+					block = join;
+				},
+				ast::Instruction::WhileLoop(while_loop) => {
+					let header = self.add_block();
+					self.add_edge(block, header);
+					self.blocks[header.0].steps.push(Step::Condition(&while_loop.condition));
+					let body = self.add_block();
+					self.add_edge(header, body);
+					let end = self.instructions(body, &while_loop.body);
+					self.add_edge(end, header);
+					block = self.add_block();
+					self.add_edge(header, block);
+				},
+			}
+			if let ast::Instruction::Statement(ast::Statement::Return(_)) = instruction {
+				self.add_edge(block, Self::EXIT);
+				// Instructions after a return are unreachable
+				block = self.add_block();
+			}
+		}
+		block
+	}
+
+	/// Adds an empty block without edges.
+	fn add_block(&mut self) -> BlockId {
+		self.blocks.push(Block::default());
+		BlockId(self.blocks.len() - 1)
+	}
+
+	/// Adds an edge from the block `from` to the block `to`.
+	fn add_edge(&mut self, from: BlockId, to: BlockId) {
+		self.blocks[from.0].successors.push(to);
+		self.blocks[to.0].predecessors.push(from);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::dataflow::tests::parse_function;
+
+	#[test]
+	fn test_cfg() {
+		let function = parse_function(
+			"def f(n: int): int {\n\tvar i: int = 0\n\twhile i < n {\n\t\tif i = 3 {\n\t\t\treturn i\n\t\t}\n\t\ti = i + \
+			 1\n\t}\n\treturn 0\n}",
+		);
+		let cfg = Cfg::new(&function);
+
+		let successors = |id: usize| cfg.block(BlockId(id)).successors.iter().map(|id| id.0).collect::<Vec<_>>();
+		let steps = |id: usize| cfg.block(BlockId(id)).steps.len();
+		// Entry -> loop header -> if-else in the body -> return in the if or back to the header
+		assert_eq!((steps(0), successors(0)), (1, vec![2]));
+		assert_eq!((steps(2), successors(2)), (1, vec![3, 8]));
+		assert_eq!((steps(3), successors(3)), (1, vec![5, 7]));
+		assert_eq!((steps(5), successors(5)), (1, vec![1]));
+		assert_eq!((steps(7), successors(7)), (0, vec![4]));
+		assert_eq!((steps(4), successors(4)), (1, vec![2]));
+		// After the loop
+		assert_eq!((steps(8), successors(8)), (1, vec![1]));
+		// After the returns
+		assert!(cfg.block(BlockId(6)).predecessors.is_empty());
+		assert!(cfg.block(BlockId(9)).predecessors.is_empty());
+	}
+}
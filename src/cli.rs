@@ -4,6 +4,79 @@
 pub struct Args {
 	#[clap(subcommand)]
 	pub command: Command,
+
+	/// How errors are printed.
+	#[clap(long, value_enum, global = true, default_value_t = DiagnosticStyle::Auto)]
+	pub diagnostics: DiagnosticStyle,
+
+	/// Number of source code lines shown before and after the code an error refers to.
+	#[clap(long, global = true, default_value_t = 2)]
+	pub context_lines: usize,
+
+	/// Enable all optional checks: deny warnings, require comparisons as conditions and forbid variables named like
+	/// functions.
+	#[clap(long, global = true)]
+	pub strict: bool,
+
+	/// Write intermediate artifacts of the compiler pipeline to files, e.g. `--dump tokens,ast,symbols,typed-ast,c`.
+	#[clap(long, global = true, value_delimiter = ',')]
+	pub dump: Vec<fortytwolang::dump::Artifact>,
+
+	/// Allow instructions outside of functions, which form the `main` function. Always enabled for `ftl run`.
+	#[clap(long, global = true)]
+	pub script: bool,
+
+	/// Directory for the files written by `--dump`.
+	#[clap(long, global = true, default_value = "ftl-dump")]
+	pub dump_dir: std::path::PathBuf,
+}
+
+/// Renderer for errors and warnings.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticStyle {
+	/// Fancy reports on terminals (if compiled with the `fancy` feature), plain text otherwise.
+	Auto,
+	/// Plain text with the affected code underlined.
+	Plain,
+	/// Colored reports with multiple labels, notes and help.
+	#[cfg(feature = "fancy")]
+	Fancy,
+}
+
+/// Options of `ftl fmt`, see [`FmtConfig`](fortytwolang::emitter::FmtConfig).
+#[derive(clap::Args, Debug)]
+pub struct FmtStyle {
+	/// Number of spaces of one level of indentation.
+	#[clap(long)]
+	pub indent_width: Option<usize>,
+
+	/// Whether to indent with tabs instead of spaces.
+	#[clap(long)]
+	pub use_tabs: Option<bool>,
+
+	/// Lines longer than this get the parameters of a call on separate lines.
+	#[clap(long)]
+	pub max_line_length: Option<usize>,
+
+	/// Placement of opening braces: `same-line` or `next-line`.
+	#[clap(long)]
+	pub brace_style: Option<fortytwolang::emitter::BraceStyle>,
+
+	/// Whether each struct field is followed by a comma.
+	#[clap(long)]
+	pub trailing_commas: Option<bool>,
+}
+
+impl From<FmtStyle> for fortytwolang::driver::FmtStyle {
+	fn from(style: FmtStyle) -> Self {
+		Self {
+			indent_width: style.indent_width,
+			use_tabs: style.use_tabs,
+			max_line_length: style.max_line_length,
+			brace_style: style.brace_style,
+			trailing_commas: style.trailing_commas,
+		}
+	}
 }
 
 #[derive(clap::Parser, Debug)]
@@ -12,12 +85,171 @@ pub enum Command {
 	Fmt {
 		/// The file to format. Note that this file will be overwritten.
 		file: std::path::PathBuf,
+
+		/// Style options overriding those of the nearest `ftlfmt.toml`.
+		#[clap(flatten)]
+		style: FmtStyle,
+
+		/// Format whatever can be parsed of a file with syntax errors, e.g. while it is being edited, instead of
+		/// failing. The errors are reported, and invalid lines in functions are kept as they are. Other top-level nodes
+		/// containing errors are left out.
+		#[clap(long)]
+		recover_best_effort: bool,
+
+		/// Check that the formatted code consists of the same tokens as the original, apart from `;` and
+		/// parentheses, and is the same program. Fails without printing the code otherwise.
+		#[clap(long, conflicts_with = "recover_best_effort")]
+		verify: bool,
 	},
 
 	/// Compile to an executable.
 	Compile {
 		/// The file to compile.
 		file: std::path::PathBuf,
+
+		/// Name of the emitter generating the target code. Only `c` produces an executable, other emitters just
+		/// write the generated code next to the file.
+		#[clap(long, default_value = "c")]
+		emit: String,
+
+		/// Compile to an object file instead of an executable and generate a C header containing everything
+		/// marked with `export`.
+		#[clap(long)]
+		lib: bool,
+
+		/// Check the compiled program at runtime with sanitizers of the C compiler, e.g. `--sanitize
+		/// address,undefined`. Their reports refer to the lines of the FTL source.
+		#[clap(long, value_delimiter = ',')]
+		sanitize: Vec<fortytwolang::sanitizer::Sanitizer>,
+	},
+
+	/// Compile multiple files separately and link them to an executable.
+	Build {
+		/// The files to compile.
+		#[clap(required = true)]
+		files: Vec<std::path::PathBuf>,
+
+		/// Path of the executable. Defaults to the first file without extension.
+		#[clap(short, long)]
+		output: Option<std::path::PathBuf>,
+
+		/// Directory for the generated C code and object files.
+		#[clap(long, default_value = "ftl-build")]
+		build_dir: std::path::PathBuf,
+	},
+
+	/// Check the files for code that compiles, but probably contains a mistake, like infinite recursion.
+	Lint {
+		/// The files to check together.
+		#[clap(required = true)]
+		files: Vec<std::path::PathBuf>,
+	},
+
+	/// Print where a function or struct is defined and used in the files. The symbols of the files are cached in an
+	/// index, so that only changed files are parsed again.
+	Refs {
+		/// Name of the function or struct.
+		name: String,
+
+		/// The files to search.
+		#[clap(required = true)]
+		files: Vec<std::path::PathBuf>,
+
+		/// Path of the index file.
+		#[clap(long, default_value = "ftl-build/symbols.index")]
+		index: std::path::PathBuf,
+	},
+
+	/// Print which files use functions and structs of which other files, and the cycles of files depending on each
+	/// other. Exits with 1 if there are cycles.
+	Deps {
+		/// The files of the project.
+		#[clap(required = true)]
+		files: Vec<std::path::PathBuf>,
+
+		/// `text` for a line per dependency, `dot` for a graph in the DOT language of Graphviz.
+		#[clap(long, default_value_t = fortytwolang::deps::Format::Text)]
+		format: fortytwolang::deps::Format,
+
+		/// Path of the index file.
+		#[clap(long, default_value = "ftl-build/symbols.index")]
+		index: std::path::PathBuf,
+	},
+
+	/// Execute with the interpreter, without compiling.
+	Eval {
+		/// The file to execute.
+		file: std::path::PathBuf,
+
+		/// How deeply functions may call each other before the execution fails with a stack overflow.
+		#[clap(long, default_value_t = fortytwolang::interpreter::DEFAULT_MAX_CALL_DEPTH)]
+		max_call_depth: usize,
+
+		/// Log every function call, return and assignment with its position and values.
+		#[clap(long)]
+		trace: bool,
+
+		/// Write the traced events to this file, one per line, to step through the execution afterwards.
+		#[clap(long)]
+		trace_log: Option<std::path::PathBuf>,
+
+		/// Count the executions of each line and write the annotated source to `<file>.ftlcov`.
+		#[clap(long)]
+		coverage: bool,
+	},
+
+	/// Execute FTL source code with the interpreter and report the calls and time of each function.
+	Profile {
+		/// The file to execute.
+		file: std::path::PathBuf,
+	},
+
+	/// Evaluate code interactively. Inputs may span multiple lines, e.g. function definitions.
+	Repl,
+
+	/// Run the functions annotated with `@bench` with the interpreter and report their wall time.
+	Bench {
+		/// The file containing the benchmarks.
+		file: std::path::PathBuf,
+
+		/// Calls of each function before measuring.
+		#[clap(long, default_value_t = 10)]
+		warmup: u32,
+
+		/// Measured calls of each function.
+		#[clap(long, default_value_t = 100)]
+		iterations: u32,
+	},
+
+	/// Compile the `ftl` code blocks in the comments of the files, and optionally run them.
+	Test {
+		/// The files containing the code blocks.
+		#[clap(required = true)]
+		files: Vec<std::path::PathBuf>,
+
+		/// Also execute code blocks defining `main` with the interpreter, which have to exit with `0`.
+		#[clap(long)]
+		run: bool,
+	},
+
+	/// Print the code with syntax highlighting, e.g. for documentation.
+	Highlight {
+		/// The file to highlight.
+		file: std::path::PathBuf,
+
+		/// `html` for a standalone HTML document, `ansi` for terminals.
+		#[clap(long, default_value_t = fortytwolang::highlight::Format::Html)]
+		format: fortytwolang::highlight::Format,
+	},
+
+	/// Print the tokens that differ between two files, regardless of their positions, e.g. when changing the lexer.
+	/// Exits with 1 if there are differences.
+	DiffTokens {
+		/// The file with the old tokens.
+		old: std::path::PathBuf,
+
+		/// The file with the new tokens.
+		new: std::path::PathBuf,
 	},
 
 	/// Compile and execute.
@@ -25,4 +257,58 @@ pub enum Command {
 		/// The file to run.
 		file: std::path::PathBuf,
 	},
+
+	/// List the bundled example programs, or run one of them with the interpreter.
+	Examples {
+		/// The example to run, e.g. `fib`.
+		name: Option<String>,
+	},
+
+	/// Create a new project with an `ftl.toml`, a hello-world program in `src/main.ftl` and a `.gitignore`.
+	New {
+		/// The directory of the project, which must not exist yet. The project is named after it.
+		directory: std::path::PathBuf,
+	},
+
+	/// Print a script completing the commands and options in the shell, e.g. `source <(ftl completions bash)`.
+	Completions {
+		/// The shell to complete in.
+		shell: clap_complete::Shell,
+	},
+
+	/// Print the man page, e.g. `ftl man > ftl.1`.
+	Man,
+}
+
+impl From<Command> for fortytwolang::driver::Command {
+	fn from(command: Command) -> Self {
+		match command {
+			Command::Fmt { file, style, recover_best_effort, verify } => {
+				Self::Fmt { file, style: style.into(), recover_best_effort, verify }
+			},
+			Command::Compile { file, emit, lib, sanitize } => Self::Compile { file, emit, lib, sanitize },
+			Command::Build { files, output, build_dir } => Self::Build { files, output, build_dir },
+			Command::Lint { files } => Self::Lint { files },
+			Command::Refs { name, files, index } => Self::Refs { name, files, index },
+			Command::Deps { files, format, index } => Self::Deps { files, format, index },
+			Command::Eval { file, max_call_depth, trace, trace_log, coverage } => {
+				Self::Eval { file, max_call_depth, trace, trace_log, coverage }
+			},
+			Command::Profile { file } => Self::Profile { file },
+			Command::Repl => Self::Repl,
+			Command::Bench { file, warmup, iterations } => {
+				Self::Bench { file, options: fortytwolang::bench::BenchOptions { warmup, iterations } }
+			},
+			Command::Test { files, run } => Self::Test { files, run },
+			Command::Highlight { file, format } => Self::Highlight { file, format },
+			Command::DiffTokens { old, new } => Self::DiffTokens { old, new },
+			Command::Run { file } => Self::Run { file },
+			Command::Examples { name } => Self::Examples { name },
+			Command::New { directory } => Self::New { directory },
+			Command::Completions { shell } => {
+				Self::Completions { shell, cli: Box::new(<Args as clap::CommandFactory>::command()) }
+			},
+			Command::Man => Self::Man { cli: Box::new(<Args as clap::CommandFactory>::command()) },
+		}
+	}
 }
@@ -0,0 +1,149 @@
+//! Line coverage of programs executed by the [interpreter](crate::interpreter).
+//!
+//! With [coverage](crate::interpreter::Interpreter::with_coverage) enabled, the interpreter [counts](Counts) how
+//! often each instruction and function is executed. A [`Report`] maps these counts to the lines of the source code
+//! and renders the source annotated with the counts, similar to `gcov`:
+//!
+//! ```text
+//!         3:    1:def square(a: int): int {
+//!         3:    2:	return a * a
+//!         -:    3:}
+//!     #####:    4:	return 0
+//! ```
+//!
+//! Lines without instructions are marked with `-`, lines that were never executed with `#####`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+	ast::{self, Block},
+	source::Position,
+};
+
+/// Number of executions of instructions and function calls, by the position the instruction or the name of the
+/// function starts at.
+pub type Counts = HashMap<Position, u64>;
+
+/// Executions of each line of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+	/// Lines containing instructions or function definitions, with the number of their executions.
+	lines: BTreeMap<u32, u64>,
+}
+
+impl Report {
+	/// Maps the `counts` of the execution of the `ast_nodes` to lines. A line with multiple instructions counts as
+	/// often as the instruction executed most often.
+	pub fn new(ast_nodes: &[ast::Node], counts: &Counts) -> Self {
+		let mut report = Self { lines: BTreeMap::new() };
+		for ast_node in ast_nodes {
+			if let ast::Node::Function(function) = ast_node {
+				report.add(function.prototype.name.position.position.start, counts);
+				report.add_block(&function.body, counts);
+			}
+		}
+		report
+	}
+
+	/// Adds the instructions of the `block` and of the blocks nested in it.
+	fn add_block(&mut self, block: &Block, counts: &Counts) {
+		for instruction in block {
+			self.add(instruction.source_position().position.start, counts);
+			match instruction {
+				ast::Instruction::IfElse(if_else) => {
+					self.add_block(&if_else.if_true, counts);
+					self.add_block(&if_else.if_false, counts);
+				},
+				ast::Instruction::WhileLoop(while_loop) => self.add_block(&while_loop.body, counts),
+				ast::Instruction::Expression(_) | ast::Instruction::Statement(_) | ast::Instruction::Invalid(_) => (),
+			}
+		}
+	}
+
+	/// Adds the line of the instruction or function starting at `position`.
+	fn add(&mut self, position: Position, counts: &Counts) {
+		let count = counts.get(&position).copied().unwrap_or_default();
+		let line = self.lines.entry(position.line).or_default();
+		*line = count.max(*line);
+	}
+
+	/// Number of lines containing instructions or function definitions.
+	pub fn executable_lines(&self) -> usize {
+		self.lines.len()
+	}
+
+	/// Number of executable lines that were executed at least once.
+	pub fn executed_lines(&self) -> usize {
+		self.lines.values().filter(|&&count| count > 0).count()
+	}
+
+	/// The executable lines that were never executed.
+	pub fn unexecuted_lines(&self) -> impl Iterator<Item = u32> + '_ {
+		self.lines.iter().filter(|(_, &count)| count == 0).map(|(&line, _)| line)
+	}
+
+	/// Renders the source `text` with the number of executions in front of each line.
+	pub fn render(&self, text: &str) -> String {
+		let mut output = String::new();
+		for (i, line) in text.lines().enumerate() {
+			let count = match self.lines.get(&(i as u32 + 1)) {
+				Some(0) => "#####".to_owned(),
+				Some(count) => count.to_string(),
+				None => "-".to_owned(),
+			};
+			output.push_str(&format!("{:>9}:{:>5}:{}\n", count, i + 1, line));
+		}
+		output
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{dump::Dumper, interpreter::Interpreter, source::Source};
+
+	#[test]
+	fn test_report() {
+		let source_code = "# Unused
+def square(a: int): int { return a * a }
+def main(): int {
+	var i: int = 0
+	while i < 3 {
+		i = i + square(i)
+		i = i + 1
+	}
+	if i = 0 {
+		return 1
+	}
+	return 0
+}
+";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let mut interpreter = Interpreter::new(ast_nodes.iter()).with_coverage();
+		assert_eq!(interpreter.run(), Ok(0));
+		let report = Report::new(&ast_nodes, interpreter.coverage().unwrap());
+
+		assert_eq!((report.executed_lines(), report.executable_lines()), (8, 9));
+		assert_eq!(report.unexecuted_lines().collect::<Vec<_>>(), [10]);
+		assert_eq!(
+			report.render(source_code),
+			"        -:    1:# Unused
+        2:    2:def square(a: int): int { return a * a }
+        1:    3:def main(): int {
+        1:    4:	var i: int = 0
+        1:    5:	while i < 3 {
+        2:    6:		i = i + square(i)
+        2:    7:		i = i + 1
+        -:    8:	}
+        1:    9:	if i = 0 {
+    #####:   10:		return 1
+        -:   11:	}
+        1:   12:	return 0
+        -:   13:}
+"
+		);
+	}
+}
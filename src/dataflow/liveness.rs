@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+
+use super::{definition, uses, Analysis, Direction};
+use crate::cfg::Step;
+
+/// Backward analysis of which variables are live, i.e. may be read before they are assigned again.
+///
+/// Variables are identified by their names, which are unique among the variables in scope.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Liveness;
+
+impl<'a> Analysis<'a> for Liveness {
+	type Domain = HashSet<String>;
+
+	const DIRECTION: Direction = Direction::Backward;
+
+	fn bottom(&self) -> Self::Domain {
+		HashSet::new()
+	}
+
+	fn boundary(&self) -> Self::Domain {
+		HashSet::new()
+	}
+
+	fn transfer(&self, step: &Step<'a>, facts: &mut Self::Domain) {
+		if let Some(name) = definition(step) {
+			facts.remove(&name.value);
+		}
+		facts.extend(uses(step).into_iter().map(|name| name.value.clone()));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		cfg::{BlockId, Cfg},
+		dataflow::{solve, tests::parse_function},
+	};
+
+	/// Sorted names of the live variables.
+	fn sorted(facts: &HashSet<String>) -> Vec<&str> {
+		let mut names: Vec<_> = facts.iter().map(String::as_str).collect();
+		names.sort();
+		names
+	}
+
+	#[test]
+	fn test_liveness() {
+		let function = parse_function(
+			"def f(n: int): int {\n\tvar x: int = 0\n\tif n < 1 {\n\t\tx = 1\n\t} else {\n\t\tn = 2\n\t}\n\treturn x + \
+			 n\n}",
+		);
+		let cfg = Cfg::new(&function);
+		let results = solve(&Liveness, &cfg);
+		assert_eq!(sorted(results.entry(Cfg::ENTRY)), ["n"]);
+		assert_eq!(sorted(results.exit(Cfg::ENTRY)), ["n", "x"]);
+		// Each branch assigns one of the variables
+		assert_eq!(sorted(results.entry(BlockId(3))), ["n"]);
+		assert_eq!(sorted(results.entry(BlockId(4))), ["x"]);
+	}
+
+	#[test]
+	fn test_liveness_in_loop() {
+		let function = parse_function(
+			"def f(n: int): int {\n\tvar i: int = 0\n\twhile i < n {\n\t\ti = i + 1\n\t}\n\treturn 0\n}",
+		);
+		let cfg = Cfg::new(&function);
+		let results = solve(&Liveness, &cfg);
+		assert_eq!(sorted(results.entry(Cfg::ENTRY)), ["n"]);
+		// Loop header, body and the block after the loop
+		assert_eq!(sorted(results.entry(BlockId(2))), ["i", "n"]);
+		assert_eq!(sorted(results.entry(BlockId(3))), ["i", "n"]);
+		assert!(results.entry(BlockId(4)).is_empty());
+	}
+}
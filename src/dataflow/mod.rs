@@ -0,0 +1,204 @@
+//! Data-flow analyses over the [control flow graph](Cfg) of a function.
+//!
+//! An [`Analysis`] describes facts about the program, e.g. which variables are live, and how each [`Step`] changes
+//! them. [`solve`] computes the facts at the start and end of each block by propagating them along the edges of the
+//! graph until nothing changes anymore.
+
+mod liveness;
+mod reaching_definitions;
+
+use std::{
+	collections::{HashSet, VecDeque},
+	hash::Hash,
+};
+
+pub use liveness::Liveness;
+pub use reaching_definitions::{Definition, ReachingDefinitions};
+
+use crate::{
+	ast::{self, Expression},
+	cfg::{BlockId, Cfg, Step},
+	source::Spanned,
+};
+
+/// Facts that can be combined where control flow merges, i.e. a join-semilattice.
+pub trait Lattice: Clone + PartialEq {
+	/// Combines the `other` facts into these, e.g. by a union of sets.
+	fn join(&mut self, other: &Self);
+}
+
+impl<T: Clone + Eq + Hash> Lattice for HashSet<T> {
+	fn join(&mut self, other: &Self) {
+		self.extend(other.iter().cloned());
+	}
+}
+
+/// In which direction facts are propagated through the control flow graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+	/// From the entry to the exit, e.g. for facts about what happened before a step.
+	Forward,
+	/// From the exit to the entry, e.g. for facts about what happens after a step.
+	Backward,
+}
+
+/// A data-flow analysis, defined by its facts and transfer function.
+pub trait Analysis<'a> {
+	/// The facts at a point of the program.
+	type Domain: Lattice;
+
+	/// The direction in which the facts flow.
+	const DIRECTION: Direction;
+
+	/// The facts nothing is known about yet, which don't change anything when [joined](Lattice::join).
+	fn bottom(&self) -> Self::Domain;
+
+	/// The facts at the [entry](Cfg::ENTRY) of forward analyses, or at the [exit](Cfg::EXIT) of backward analyses.
+	fn boundary(&self) -> Self::Domain;
+
+	/// Applies the effect of the `step` to the `facts`. Backward analyses turn the facts after the step into those
+	/// before it.
+	fn transfer(&self, step: &Step<'a>, facts: &mut Self::Domain);
+}
+
+/// Facts at the start and the end of each block, computed by [`solve`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Results<D> {
+	entry: Vec<D>,
+	exit: Vec<D>,
+}
+
+impl<D> Results<D> {
+	/// The facts before the first step of the `block`.
+	pub fn entry(&self, block: BlockId) -> &D {
+		&self.entry[block.0]
+	}
+
+	/// The facts after the last step of the `block`.
+	pub fn exit(&self, block: BlockId) -> &D {
+		&self.exit[block.0]
+	}
+}
+
+/// Computes the facts of the `analysis` at the start and end of each block of the `cfg`.
+pub fn solve<'a, A: Analysis<'a>>(analysis: &A, cfg: &Cfg<'a>) -> Results<A::Domain> {
+	let block_count = cfg.blocks.len();
+	let mut results =
+		Results { entry: vec![analysis.bottom(); block_count], exit: vec![analysis.bottom(); block_count] };
+	let boundary_block = match A::DIRECTION {
+		Direction::Forward => Cfg::ENTRY,
+		Direction::Backward => Cfg::EXIT,
+	};
+
+	// Every block is analyzed at least once, later only those whose inputs changed
+	let mut worklist: VecDeque<BlockId> = cfg.block_ids().collect();
+	let mut queued = vec![true; block_count];
+	while let Some(id) = worklist.pop_front() {
+		queued[id.0] = false;
+		let block = cfg.block(id);
+		let (inputs, outputs, flow_in, flow_out) = match A::DIRECTION {
+			Direction::Forward => (&block.predecessors, &block.successors, &mut results.entry, &mut results.exit),
+			Direction::Backward => (&block.successors, &block.predecessors, &mut results.exit, &mut results.entry),
+		};
+
+		let mut facts = if id == boundary_block { analysis.boundary() } else { analysis.bottom() };
+		for input in inputs {
+			facts.join(&flow_out[input.0]);
+		}
+		flow_in[id.0] = facts.clone();
+		match A::DIRECTION {
+			Direction::Forward => block.steps.iter().for_each(|step| analysis.transfer(step, &mut facts)),
+			Direction::Backward => block.steps.iter().rev().for_each(|step| analysis.transfer(step, &mut facts)),
+		}
+
+		if facts != flow_out[id.0] {
+			flow_out[id.0] = facts;
+			for output in outputs {
+				if !queued[output.0] {
+					queued[output.0] = true;
+					worklist.push_back(*output);
+				}
+			}
+		}
+	}
+	results
+}
+
+/// The variable the `step` assigns a value to, if any. Assignments to fields don't count, since the variable keeps
+/// the values of the other fields.
+fn definition<'a>(step: &Step<'a>) -> Option<&'a Spanned<String>> {
+	match step {
+		Step::Instruction(ast::Instruction::Statement(ast::Statement::VariableDeclaration(declaration))) => {
+			Some(&declaration.name)
+		},
+		Step::Instruction(ast::Instruction::Statement(ast::Statement::VariableAssignment(assignment))) => {
+			Some(&assignment.name)
+		},
+		Step::Instruction(_) | Step::Condition(_) => None,
+	}
+}
+
+/// The variables whose values the `step` reads.
+fn uses<'a>(step: &Step<'a>) -> Vec<&'a Spanned<String>> {
+	let mut variables = Vec::new();
+	match step {
+		Step::Condition(condition) => expression_variables(condition, &mut variables),
+		Step::Instruction(ast::Instruction::Expression(expression)) => expression_variables(expression, &mut variables),
+		Step::Instruction(ast::Instruction::Statement(statement)) => match statement {
+			ast::Statement::VariableDeclaration(declaration) => {
+				expression_variables(&declaration.value, &mut variables)
+			},
+			ast::Statement::VariableAssignment(assignment) => expression_variables(&assignment.value, &mut variables),
+			ast::Statement::FieldAssignment(assignment) => {
+				expression_variables(&assignment.target.base, &mut variables);
+				expression_variables(&assignment.value, &mut variables);
+			},
+			ast::Statement::Return(value) => value.iter().for_each(|value| expression_variables(value, &mut variables)),
+		},
+		Step::Instruction(
+			ast::Instruction::IfElse(_) | ast::Instruction::WhileLoop(_) | ast::Instruction::Invalid(_),
+		) => (),
+	}
+	variables
+}
+
+/// Collects all variables read in the `expression`.
+fn expression_variables<'a>(expression: &'a Expression, variables: &mut Vec<&'a Spanned<String>>) {
+	match expression {
+		Expression::BinaryExpression(binary_expression) => {
+			expression_variables(&binary_expression.lhs, variables);
+			expression_variables(&binary_expression.rhs, variables);
+		},
+		Expression::FieldAccess(field_access) => expression_variables(&field_access.base, variables),
+		Expression::FunctionCall(function_call) => {
+			for param in &function_call.params {
+				expression_variables(param, variables);
+			}
+		},
+		Expression::Try(try_expression) => {
+			expression_variables(&try_expression.value, variables);
+			expression_variables(&try_expression.fallback, variables);
+		},
+		Expression::Variable(variable) => variables.push(variable),
+		Expression::Number(_)
+		| Expression::String(_)
+		| Expression::Null(_)
+		| Expression::Error(_)
+		| Expression::Invalid(_) => (),
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use std::sync::Arc;
+
+	use crate::{ast, dump::Dumper, source::Source};
+
+	/// Parses the source code of a single function.
+	pub(crate) fn parse_function(source_code: &str) -> ast::FunctionDefinition {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let Some(ast::Node::Function(function)) = ast_nodes.into_iter().next() else { panic!("expected function") };
+		function
+	}
+}
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+
+use super::{definition, Analysis, Direction};
+use crate::{ast::FunctionDefinition, cfg::Step, source::SourcePositionRange};
+
+/// A place where a variable gets a value: an argument, a declaration or an assignment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Definition {
+	pub variable: String,
+	/// Position of the name of the variable in the definition.
+	pub position: SourcePositionRange,
+}
+
+/// Forward analysis of which [definitions](Definition) may have given the variables their current values.
+#[derive(Debug, Clone, Copy)]
+pub struct ReachingDefinitions<'a> {
+	function: &'a FunctionDefinition,
+}
+
+impl<'a> ReachingDefinitions<'a> {
+	/// Creates the analysis of the `function`, whose arguments are defined on entry.
+	pub fn new(function: &'a FunctionDefinition) -> Self {
+		Self { function }
+	}
+}
+
+impl<'a> Analysis<'a> for ReachingDefinitions<'a> {
+	type Domain = HashSet<Definition>;
+
+	const DIRECTION: Direction = Direction::Forward;
+
+	fn bottom(&self) -> Self::Domain {
+		HashSet::new()
+	}
+
+	fn boundary(&self) -> Self::Domain {
+		self.function
+			.prototype
+			.args
+			.iter()
+			.map(|arg| Definition { variable: arg.name.value.clone(), position: arg.name.position.clone() })
+			.collect()
+	}
+
+	fn transfer(&self, step: &Step<'a>, facts: &mut Self::Domain) {
+		let Some(name) = definition(step) else {
+			return;
+		};
+		// The new value replaces all previous ones
+		facts.retain(|definition| definition.variable != name.value);
+		facts.insert(Definition { variable: name.value.clone(), position: name.position.clone() });
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		cfg::{BlockId, Cfg},
+		dataflow::{solve, tests::parse_function},
+	};
+
+	#[test]
+	fn test_reaching_definitions() {
+		let function = parse_function(
+			"def f(n: int): int {\n\tvar x: int = 0\n\tif n < 1 {\n\t\tx = 1\n\t} else {\n\t\tn = 2\n\t}\n\treturn x + \
+			 n\n}",
+		);
+		let cfg = Cfg::new(&function);
+		let results = solve(&ReachingDefinitions::new(&function), &cfg);
+
+		// The definitions of both branches reach the return after the if-else
+		let lines = |variable: &str| {
+			let mut lines: Vec<_> = results
+				.entry(BlockId(2))
+				.iter()
+				.filter(|definition| definition.variable == variable)
+				.map(|definition| definition.position.position.start.line)
+				.collect();
+			lines.sort();
+			lines
+		};
+		assert_eq!(lines("x"), [2, 4]);
+		assert_eq!(lines("n"), [1, 6]);
+		// Only the declaration reaches the else branch
+		assert_eq!(results.entry(BlockId(4)).len(), 2);
+	}
+}
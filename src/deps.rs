@@ -0,0 +1,266 @@
+//! Dependency graph between the files of a project, for untangling larger code bases with `ftl deps`.
+//!
+//! A file depends on another one if it uses a function or struct defined there, which is looked up in the
+//! [symbol index](crate::index). Files that depend on each other, directly or through other files, form a cycle.
+
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	fmt::{self, Write},
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+use crate::index::{SymbolIndex, SymbolKind};
+
+/// Output format of [`DependencyGraph::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// A line per dependency with the used symbols, followed by the cycles.
+	Text,
+	/// Graph in the DOT language of Graphviz, with the edges of cycles in red.
+	Dot,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"text" => Ok(Format::Text),
+			"dot" => Ok(Format::Dot),
+			other => Err(format!("unknown format `{}`, expected one of text, dot", other)),
+		}
+	}
+}
+
+impl fmt::Display for Format {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Format::Text => write!(f, "text"),
+			Format::Dot => write!(f, "dot"),
+		}
+	}
+}
+
+/// Which files use functions and structs of which other files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+	/// All files, also those without dependencies.
+	pub files: BTreeSet<PathBuf>,
+	/// The symbols that a file uses of each of its dependencies, by the using file.
+	pub edges: BTreeMap<PathBuf, BTreeMap<PathBuf, BTreeSet<String>>>,
+}
+
+impl DependencyGraph {
+	/// Connects the uses of functions and structs in the indexed files with the files defining them. Functions
+	/// declared with `extern` are defined outside of FTL, so they are no dependency.
+	pub fn new(index: &SymbolIndex) -> Self {
+		let mut definitions: HashMap<&str, Vec<&Path>> = HashMap::new();
+		for (path, symbols) in index.files() {
+			for definition in symbols.definitions.iter().filter(|definition| definition.kind != SymbolKind::Extern) {
+				definitions.entry(&definition.name).or_default().push(path);
+			}
+		}
+
+		let mut graph = Self::default();
+		for (path, symbols) in index.files() {
+			graph.files.insert(path.to_owned());
+			for reference in &symbols.references {
+				let dependencies = definitions.get(reference.name.as_str()).into_iter().flatten();
+				for dependency in dependencies.filter(|dependency| **dependency != path) {
+					let edge = graph.edges.entry(path.to_owned()).or_default();
+					edge.entry(dependency.to_path_buf()).or_default().insert(reference.name.clone());
+				}
+			}
+		}
+		graph
+	}
+
+	/// The groups of files that depend on each other, each sorted by path.
+	pub fn cycles(&self) -> Vec<Vec<&Path>> {
+		let files: Vec<&Path> = self.files.iter().map(PathBuf::as_path).collect();
+		let position = |file: &Path| files.binary_search(&file).expect("dependencies are indexed files");
+		let successors = files
+			.iter()
+			.map(|file| {
+				self.edges.get(*file).into_iter().flat_map(BTreeMap::keys).map(|dependency| position(dependency))
+			})
+			.map(Iterator::collect)
+			.collect();
+		let mut components = StronglyConnectedComponents::new(successors);
+		for file in 0..files.len() {
+			components.visit(file);
+		}
+		components
+			.components
+			.into_iter()
+			.filter(|component| component.len() > 1)
+			.map(|mut component| {
+				component.sort();
+				component.into_iter().map(|file| files[file]).collect()
+			})
+			.collect()
+	}
+
+	/// Renders the graph in the `format`.
+	pub fn render(&self, format: Format) -> String {
+		let cycles = self.cycles();
+		let in_cycle = |from: &Path, to: &Path| cycles.iter().any(|cycle| cycle.contains(&from) && cycle.contains(&to));
+		let mut output = String::new();
+		match format {
+			Format::Text => {
+				for (from, to, symbols) in self.dependencies() {
+					let _ = writeln!(output, "{} -> {}: {}", from.display(), to.display(), symbols);
+				}
+				for cycle in &cycles {
+					let files: Vec<_> = cycle.iter().map(|file| file.display().to_string()).collect();
+					let _ = writeln!(output, "cycle: {}", files.join(", "));
+				}
+			},
+			Format::Dot => {
+				output.push_str("digraph dependencies {\n");
+				for file in &self.files {
+					let _ = writeln!(output, "\t{};", quote(file));
+				}
+				for (from, to, symbols) in self.dependencies() {
+					let color = if in_cycle(from, to) { ", color=red" } else { "" };
+					let _ = writeln!(output, "\t{} -> {} [label={}{}];", quote(from), quote(to), quote(symbols), color);
+				}
+				output.push_str("}\n");
+			},
+		}
+		output
+	}
+
+	/// The edges of the graph with the used symbols separated by commas.
+	fn dependencies(&self) -> impl Iterator<Item = (&Path, &Path, String)> {
+		self.edges.iter().flat_map(|(from, dependencies)| {
+			dependencies.iter().map(move |(to, symbols)| {
+				(from.as_path(), to.as_path(), symbols.iter().map(String::as_str).collect::<Vec<_>>().join(", "))
+			})
+		})
+	}
+}
+
+/// Encloses the `text` in quotes for DOT.
+fn quote(text: impl AsRef<Path>) -> String {
+	format!("\"{}\"", text.as_ref().display().to_string().replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Tarjan's algorithm, which finds the strongly connected components of a graph, i.e. the groups of nodes that can all
+/// reach each other.
+struct StronglyConnectedComponents {
+	/// The nodes that each node has an edge to.
+	successors: Vec<Vec<usize>>,
+	/// Order in which the nodes were visited, if they were.
+	order: Vec<Option<usize>>,
+	visited: usize,
+	/// Lowest order of a node on the stack that is reachable from each node.
+	lowest: Vec<usize>,
+	/// Visited nodes whose component is not complete yet.
+	stack: Vec<usize>,
+	on_stack: Vec<bool>,
+	components: Vec<Vec<usize>>,
+}
+
+impl StronglyConnectedComponents {
+	fn new(successors: Vec<Vec<usize>>) -> Self {
+		let nodes = successors.len();
+		Self {
+			successors,
+			order: vec![None; nodes],
+			visited: 0,
+			lowest: vec![0; nodes],
+			stack: Vec::new(),
+			on_stack: vec![false; nodes],
+			components: Vec::new(),
+		}
+	}
+
+	/// Visits the `node` and the nodes reachable from it, unless it was visited before.
+	fn visit(&mut self, node: usize) {
+		if self.order[node].is_some() {
+			return;
+		}
+		let order = self.visited;
+		self.visited += 1;
+		self.order[node] = Some(order);
+		self.lowest[node] = order;
+		self.stack.push(node);
+		self.on_stack[node] = true;
+
+		for successor in self.successors[node].clone() {
+			match self.order[successor] {
+				None => {
+					self.visit(successor);
+					self.lowest[node] = self.lowest[node].min(self.lowest[successor]);
+				},
+				Some(successor_order) if self.on_stack[successor] => {
+					self.lowest[node] = self.lowest[node].min(successor_order)
+				},
+				Some(_) => (),
+			}
+		}
+
+		// The node is the first visited one of its component, which consists of the nodes visited since
+		if self.lowest[node] == order {
+			let mut component = Vec::new();
+			while let Some(member) = self.stack.pop() {
+				self.on_stack[member] = false;
+				component.push(member);
+				if member == node {
+					break;
+				}
+			}
+			self.components.push(component);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use super::*;
+	use crate::test_support::TempDir;
+
+	#[test]
+	fn test_dependency_graph() {
+		let directory = TempDir::new().unwrap();
+		let files = [
+			("main.ftl", "extern puts(s: str): int\ndef main(): int {\n\tputs(\"\")\n\treturn a() + square(2)\n}"),
+			("a.ftl", "export def a(): int {\n\treturn b()\n}"),
+			("b.ftl", "export struct P { x: int }\nexport def b(): int {\n\treturn a() - P().x\n}"),
+			("math.ftl", "export def square(x: int): int {\n\treturn x * x\n}\ndef unused(p: P) {\n}"),
+			("lonely.ftl", "def f() {\n}"),
+		];
+		let paths: Vec<_> = files
+			.iter()
+			.map(|(name, code)| {
+				let path = directory.path().join(name);
+				fs::write(&path, code).unwrap();
+				path
+			})
+			.collect();
+		let mut index = SymbolIndex::default();
+		index.update(&paths).unwrap();
+		let graph = DependencyGraph::new(&index);
+
+		let name = |path: &Path| path.file_name().unwrap().to_str().unwrap().to_owned();
+		let cycles: Vec<Vec<_>> =
+			graph.cycles().iter().map(|cycle| cycle.iter().map(|file| name(file)).collect()).collect();
+		assert_eq!(cycles, [["a.ftl", "b.ftl"]]);
+
+		let text = graph.render(Format::Text).replace(&format!("{}/", directory.path().display()), "");
+		assert_eq!(
+			text,
+			"a.ftl -> b.ftl: b\nb.ftl -> a.ftl: a\nmain.ftl -> a.ftl: a\nmain.ftl -> math.ftl: square\n\
+			 math.ftl -> b.ftl: P\ncycle: a.ftl, b.ftl\n"
+		);
+		let dot = graph.render(Format::Dot).replace(&format!("{}/", directory.path().display()), "");
+		assert!(dot.starts_with("digraph dependencies {\n\t\"a.ftl\";\n"), "{}", dot);
+		assert!(dot.contains("\t\"lonely.ftl\";\n"), "{}", dot);
+		assert!(dot.contains("\t\"a.ftl\" -> \"b.ftl\" [label=\"b\", color=red];\n"), "{}", dot);
+		assert!(dot.contains("\t\"main.ftl\" -> \"a.ftl\" [label=\"a\"];\n"), "{}", dot);
+	}
+}
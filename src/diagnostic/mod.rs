@@ -0,0 +1,107 @@
+//! Compiler-independent representation of errors and warnings, which can be rendered for the user.
+//!
+//! Errors of the [lexer](crate::lexer::Error), [parser](crate::parser::Error) and
+//! [semantic analyzer](crate::semantic_analyzer::Error) can be converted into a [`Diagnostic`],
+//! which is then printed by one of the [renderers](render). The compiler pipeline reports diagnostics to a
+//! [`sink::DiagnosticSink`].
+
+pub mod render;
+pub mod sink;
+
+use std::fmt;
+
+use crate::source::SourcePositionRange;
+
+/// How severe a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+	/// The program cannot be compiled.
+	Error,
+	/// The program can be compiled, but probably contains a mistake.
+	Warning,
+}
+
+impl fmt::Display for Severity {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Severity::Error => write!(f, "error"),
+			Severity::Warning => write!(f, "warning"),
+		}
+	}
+}
+
+/// A position in the source code with a message describing what is located there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+	/// The position this label points to.
+	pub position: SourcePositionRange,
+	/// Explains what is wrong at [`Self::position`].
+	pub message: String,
+	/// Whether this label marks the position the [`Diagnostic`] is about. Secondary labels give additional context.
+	pub primary: bool,
+}
+
+/// An error or warning with all information required to present it to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+	/// Whether this is an error or a warning.
+	pub severity: Severity,
+	/// Short name of the kind of problem, e.g. `TypeMismatch`.
+	pub code: &'static str,
+	/// Summary of the problem.
+	pub message: String,
+	/// Positions in the source code related to the problem.
+	pub labels: Vec<Label>,
+	/// Additional information about the problem.
+	pub notes: Vec<String>,
+	/// A suggestion on how to fix the problem.
+	pub help: Option<String>,
+}
+
+impl Diagnostic {
+	/// Creates an error [`Diagnostic`] without any labels, notes or help.
+	pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+		Self {
+			severity: Severity::Error,
+			code,
+			message: message.into(),
+			labels: Vec::new(),
+			notes: Vec::new(),
+			help: None,
+		}
+	}
+
+	/// Creates a warning [`Diagnostic`] without any labels, notes or help.
+	pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+		Self { severity: Severity::Warning, ..Self::error(code, message) }
+	}
+
+	/// Adds a primary label, i.e. the position the diagnostic is about.
+	pub fn with_primary_label(mut self, position: SourcePositionRange, message: impl Into<String>) -> Self {
+		self.labels.push(Label { position, message: message.into(), primary: true });
+		self
+	}
+
+	/// Adds a secondary label giving additional context.
+	pub fn with_secondary_label(mut self, position: SourcePositionRange, message: impl Into<String>) -> Self {
+		self.labels.push(Label { position, message: message.into(), primary: false });
+		self
+	}
+
+	/// Adds a note with additional information.
+	pub fn with_note(mut self, note: impl Into<String>) -> Self {
+		self.notes.push(note.into());
+		self
+	}
+
+	/// Sets a suggestion on how to fix the problem.
+	pub fn with_help(mut self, help: impl Into<String>) -> Self {
+		self.help = Some(help.into());
+		self
+	}
+
+	/// Returns the first primary label, if any.
+	pub fn primary_label(&self) -> Option<&Label> {
+		self.labels.iter().find(|label| label.primary)
+	}
+}
@@ -0,0 +1,73 @@
+//! Renders [`Diagnostic`]s as colored multi-span reports using [`ariadne`].
+
+use std::io;
+
+use ariadne::{Color, Config, IndexType, Label, Report, ReportKind};
+
+use crate::{
+	diagnostic::{Diagnostic, Severity},
+	source::SourcePositionRange,
+};
+
+/// A span as understood by ariadne, i.e. the name of the source and a byte range.
+type Span = (String, std::ops::Range<usize>);
+
+/// Writes the `diagnostic` as colored report to the `writer`.
+pub fn render(diagnostic: &Diagnostic, writer: &mut dyn io::Write) -> io::Result<()> {
+	let kind = match diagnostic.severity {
+		Severity::Error => ReportKind::Error,
+		Severity::Warning => ReportKind::Warning,
+	};
+	let report_span: Span = diagnostic
+		.primary_label()
+		.or(diagnostic.labels.first())
+		.and_then(|label| shown(&label.position))
+		.map(span)
+		.unwrap_or_else(|| (String::new(), 0..0));
+
+	let mut report = Report::build(kind, report_span)
+		.with_config(Config::default().with_color(true).with_index_type(IndexType::Byte))
+		.with_code(diagnostic.code)
+		.with_message(&diagnostic.message);
+	for label in &diagnostic.labels {
+		let color = if label.primary { Color::Red } else { Color::Blue };
+		match shown(&label.position) {
+			Some(position) => {
+				let message = match label.position.is_synthetic() {
+					true => format!("in generated code: {}", label.message),
+					false => label.message.clone(),
+				};
+				report = report.with_label(Label::new(span(position)).with_message(message).with_color(color));
+			},
+			None => report = report.with_note(format!("in {}: {}", label.position, label.message)),
+		}
+	}
+	for note in &diagnostic.notes {
+		report = report.with_note(note);
+	}
+	if let Some(help) = &diagnostic.help {
+		report = report.with_help(help);
+	}
+
+	let sources = diagnostic
+		.labels
+		.iter()
+		.filter_map(|label| shown(&label.position))
+		.map(|position| (position.source.name.clone(), position.source.text.to_string()));
+	report.finish().write(ariadne::sources(sources), writer)
+}
+
+/// The position whose code is shown for a label at `position`, which is the code a
+/// [synthetic](SourcePositionRange::synthetic) position was generated from. [`None`] for synthetic positions without
+/// origin.
+fn shown(position: &SourcePositionRange) -> Option<&SourcePositionRange> {
+	match position.is_synthetic() {
+		true => position.generated_from(),
+		false => Some(position),
+	}
+}
+
+/// Converts a [`SourcePositionRange`](crate::source::SourcePositionRange) into an ariadne [`Span`].
+fn span(position: &SourcePositionRange) -> Span {
+	(position.source.name.clone(), position.byte_range())
+}
@@ -0,0 +1,61 @@
+//! Printing [`Diagnostic`]s for the user.
+//!
+//! The [plain](plain) renderer works everywhere. With the `fancy` feature enabled, the [fancy](fancy) renderer
+//! produces colored multi-span reports for terminals that support it.
+
+#[cfg(feature = "fancy")]
+pub mod fancy;
+pub mod plain;
+
+use std::io;
+
+use super::Diagnostic;
+
+/// Which renderer to use for printing [`Diagnostic`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Style {
+	/// Hand-rolled caret printer, see [`plain`].
+	#[default]
+	Plain,
+	/// Colored multi-span reports, see [`fancy`].
+	#[cfg(feature = "fancy")]
+	Fancy,
+}
+
+impl Style {
+	/// Returns the best style for the given output, i.e. [`Style::Fancy`] for terminals if the `fancy` feature is
+	/// enabled and [`Style::Plain`] otherwise.
+	pub fn detect(output: &impl io::IsTerminal) -> Self {
+		#[cfg(feature = "fancy")]
+		if output.is_terminal() {
+			return Style::Fancy;
+		}
+		let _ = output;
+		Style::Plain
+	}
+}
+
+/// How [`Diagnostic`]s are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+	/// Which renderer to use.
+	pub style: Style,
+	/// Number of source code lines shown before and after the affected lines. Only used by the [plain](plain)
+	/// renderer.
+	pub context_lines: usize,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self { style: Style::default(), context_lines: 2 }
+	}
+}
+
+/// Renders the `diagnostic` with the given `options` to the `writer`.
+pub fn render(diagnostic: &Diagnostic, options: &Options, writer: &mut dyn io::Write) -> io::Result<()> {
+	match options.style {
+		Style::Plain => plain::render(diagnostic, options.context_lines, writer),
+		#[cfg(feature = "fancy")]
+		Style::Fancy => fancy::render(diagnostic, writer),
+	}
+}
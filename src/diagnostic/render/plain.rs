@@ -0,0 +1,180 @@
+//! Renders [`Diagnostic`]s as plain text, underlining the affected code with carets.
+
+use std::io;
+
+use crate::{diagnostic::Diagnostic, source::SourcePositionRange};
+
+/// Width of a tab when printing source code.
+const TAB_WIDTH: usize = 4;
+
+/// Writes the `diagnostic` as plain text to the `writer`, showing `context_lines` lines of source code before and
+/// after each label.
+pub fn render(diagnostic: &Diagnostic, context_lines: usize, writer: &mut dyn io::Write) -> io::Result<()> {
+	writeln!(writer, "{}[{}]: {}", diagnostic.severity, diagnostic.code, diagnostic.message)?;
+	for label in &diagnostic.labels {
+		write_snippet(writer, &label.position, &label.message, context_lines)?;
+	}
+	for note in &diagnostic.notes {
+		writeln!(writer, "note: {}", note)?;
+	}
+	if let Some(help) = &diagnostic.help {
+		writeln!(writer, "help: {}", help)?;
+	}
+	Ok(())
+}
+
+/// Writes the source code lines around the `position` with a line number gutter, underlines the affected code with
+/// carets and labels it with the `message`.
+///
+/// For [synthetic](SourcePositionRange::synthetic) positions, the code they were generated from is shown instead.
+fn write_snippet(
+	writer: &mut dyn io::Write,
+	position: &SourcePositionRange,
+	message: &str,
+	context_lines: usize,
+) -> io::Result<()> {
+	let shown = if position.is_synthetic() { position.generated_from() } else { Some(position) };
+	let Some(shown) = shown else {
+		writeln!(writer, "--> {}", position)?;
+		if !message.is_empty() {
+			writeln!(writer, "  = {}", message)?;
+		}
+		return Ok(());
+	};
+	let lines: Vec<&str> = shown.source.text.lines().collect();
+	let (start, end) = (shown.position.start, shown.position.end);
+	let (start_line, end_line) = (start.line as usize, end.line as usize);
+	let first_line = start_line.saturating_sub(context_lines).max(1);
+	let last_line = (end_line + context_lines).min(lines.len()).max(end_line);
+	let gutter_width = last_line.to_string().len();
+
+	writeln!(writer, "{:width$}--> {}", "", position, width = gutter_width)?;
+	writeln!(writer, "{:width$} |", "", width = gutter_width)?;
+	for line_number in first_line..=last_line {
+		let line = lines.get(line_number - 1).copied().unwrap_or_default();
+		writeln!(
+			writer,
+			"{}",
+			format!("{:>width$} | {}", line_number, expand_tabs(line), width = gutter_width).trim_end()
+		)?;
+		if !(start_line..=end_line).contains(&line_number) {
+			continue;
+		}
+
+		// Underline the affected part of this line. Columns start at 1 and are inclusive.
+		let from = if line_number == start_line { start.column as usize } else { 1 };
+		let to = if line_number == end_line { end.column as usize } else { line.chars().count() };
+		let indent = display_width(line.chars().take(from - 1));
+		let width = display_width(line.chars().skip(from - 1).take((to + 1).saturating_sub(from))).max(1);
+		write!(writer, "{:width$} | {}{}", "", " ".repeat(indent), "^".repeat(width), width = gutter_width)?;
+		if line_number == end_line && !message.is_empty() {
+			write!(writer, " {}", message)?;
+		}
+		writeln!(writer)?;
+	}
+	Ok(())
+}
+
+/// Replaces tabs by spaces, so that the underline can be aligned.
+fn expand_tabs(line: &str) -> String {
+	line.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+/// Number of columns the `chars` occupy when printed with [`expand_tabs`].
+fn display_width(chars: impl Iterator<Item = char>) -> usize {
+	chars.map(|char| if char == '\t' { TAB_WIDTH } else { 1 }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::source::{Position, PositionRange, Source};
+
+	#[test]
+	fn test_render() {
+		let position = SourcePositionRange {
+			source: Arc::new(Source::new("file.ftl".to_owned(), "def main() {\n\tvar x: int = 4.2\n}".to_owned())),
+			position: PositionRange {
+				start: Position { line: 2, column: 6, offset: 18 },
+				end: Position { line: 2, column: 6, offset: 18 },
+			},
+		};
+		let diagnostic = Diagnostic::error("TypeMismatch", "expected int, got float")
+			.with_primary_label(position, "declared as int")
+			.with_help("use an int literal");
+
+		let mut output = Vec::new();
+		render(&diagnostic, 1, &mut output).unwrap();
+		let expected = "\
+error[TypeMismatch]: expected int, got float
+ --> file.ftl:2:6
+  |
+1 | def main() {
+2 |     var x: int = 4.2
+  |         ^ declared as int
+3 | }
+help: use an int literal
+";
+		assert_eq!(String::from_utf8(output).unwrap(), expected);
+	}
+
+	#[test]
+	fn test_multiline_span() {
+		let source_code = (1..=11).map(|line| format!("line {}", line)).collect::<Vec<_>>().join("\n");
+		let position = SourcePositionRange {
+			source: Arc::new(Source::new("file.ftl".to_owned(), source_code)),
+			position: PositionRange {
+				start: Position { line: 9, column: 6, offset: 62 },
+				end: Position { line: 10, column: 4, offset: 70 },
+			},
+		};
+		let diagnostic = Diagnostic::error("Code", "message").with_primary_label(position, "");
+
+		let mut output = Vec::new();
+		render(&diagnostic, 3, &mut output).unwrap();
+		let expected = "\
+error[Code]: message
+  --> file.ftl:9:6
+   |
+ 6 | line 6
+ 7 | line 7
+ 8 | line 8
+ 9 | line 9
+   |      ^
+10 | line 10
+   | ^^^^
+11 | line 11
+";
+		assert_eq!(String::from_utf8(output).unwrap(), expected);
+	}
+
+	#[test]
+	fn test_synthetic_position() {
+		let source = Arc::new(Source::new("file.ftl".to_owned(), "def main() {\n\treturn\n}".to_owned()));
+		let origin = SourcePositionRange {
+			source,
+			position: PositionRange {
+				start: Position { line: 2, column: 2, offset: 14 },
+				end: Position { line: 2, column: 7, offset: 19 },
+			},
+		};
+		let diagnostic = Diagnostic::error("Code", "message")
+			.with_primary_label(SourcePositionRange::synthetic(Some(&origin)), "in the desugared return")
+			.with_secondary_label(SourcePositionRange::synthetic(None), "built in code");
+
+		let mut output = Vec::new();
+		render(&diagnostic, 0, &mut output).unwrap();
+		let expected = "\
+error[Code]: message
+ --> generated code (from file.ftl:2:2)
+  |
+2 |     return
+  |     ^^^^^^ in the desugared return
+--> generated code
+  = built in code
+";
+		assert_eq!(String::from_utf8(output).unwrap(), expected);
+	}
+}
@@ -0,0 +1,94 @@
+//! Receivers of the [`Diagnostic`]s found by the compiler pipeline, e.g. [`compile_source`](crate::compile_source).
+//!
+//! The pipeline reports the diagnostics that don't stop it, like the warnings of the lints, to a [`DiagnosticSink`] as
+//! soon as they are found. Errors that stop the pipeline are returned as [`CompileError`](crate::CompileError)
+//! instead. Embedders choose what happens with the diagnostics:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use fortytwolang::{
+//! 	diagnostic::Diagnostic, dump::Dumper, semantic_analyzer::CheckConfig, source::Source,
+//! };
+//!
+//! let source = Arc::new(Source::new(
+//! 	"main.ftl".to_owned(),
+//! 	"def main(): int {\n\treturn main()\n}".to_owned(),
+//! ));
+//! let mut warnings: Vec<Diagnostic> = Vec::new();
+//! fortytwolang::compile_source(
+//! 	source,
+//! 	&CheckConfig::default(),
+//! 	&Dumper::default(),
+//! 	&mut warnings,
+//! )
+//! .unwrap();
+//! assert_eq!(warnings[0].code, "UnconditionalRecursion");
+//! ```
+
+use std::io;
+
+use super::{render, Diagnostic};
+
+/// Receives [`Diagnostic`]s as they are found, see the [module documentation](self).
+pub trait DiagnosticSink {
+	/// Handles the `diagnostic`.
+	fn emit(&mut self, diagnostic: Diagnostic);
+}
+
+/// Collects the diagnostics.
+impl DiagnosticSink for Vec<Diagnostic> {
+	fn emit(&mut self, diagnostic: Diagnostic) {
+		self.push(diagnostic);
+	}
+}
+
+/// Forwards the diagnostics to a function, e.g. to send them to an editor.
+impl<F> DiagnosticSink for F
+where
+	F: FnMut(Diagnostic),
+{
+	fn emit(&mut self, diagnostic: Diagnostic) {
+		self(diagnostic)
+	}
+}
+
+/// [Renders](render::render) the diagnostics immediately. Errors while writing them are ignored.
+pub struct Printer<W> {
+	writer: W,
+	options: render::Options,
+}
+
+impl<W: io::Write> Printer<W> {
+	/// Creates a printer rendering to the `writer` with the `options`.
+	pub fn new(writer: W, options: render::Options) -> Self {
+		Self { writer, options }
+	}
+}
+
+impl<W: io::Write> DiagnosticSink for Printer<W> {
+	fn emit(&mut self, diagnostic: Diagnostic) {
+		let _ = render::render(&diagnostic, &self.options, &mut self.writer);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sinks() {
+		let warning = || Diagnostic::warning("Test", "a warning");
+		let mut collected = Vec::new();
+		collected.emit(warning());
+		assert_eq!(collected, [warning()]);
+
+		let mut codes = Vec::new();
+		(|diagnostic: Diagnostic| codes.push(diagnostic.code)).emit(warning());
+		assert_eq!(codes, ["Test"]);
+
+		let mut output = Vec::new();
+		Printer::new(&mut output, render::Options::default()).emit(warning());
+		assert!(String::from_utf8(output).unwrap().starts_with("warning[Test]: a warning"));
+	}
+}
@@ -0,0 +1,221 @@
+//! Differential testing: running the same FTL program with every available backend and comparing the results.
+//!
+//! Diverging exit codes or outputs indicate that an emitter (or the interpreter) implements the semantics of FTL
+//! differently than the others.
+
+use std::{fmt, fs, io, process, sync::Arc};
+
+use crate::{
+	build,
+	dump::Dumper,
+	emitter::{self, Emitter, SharedBuffer},
+	interpreter::{self, Interpreter},
+	runtime,
+	semantic_analyzer::CheckConfig,
+	source::Source,
+	test_support::TempDir,
+	CompileError,
+};
+
+/// A way to execute FTL programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+	/// The [interpreter](crate::interpreter).
+	Interpreter,
+	/// The [C emitter](crate::emitter::C), compiled with `cc`.
+	C,
+	/// The [Python emitter](crate::emitter::Py), executed with `python3`.
+	Python,
+}
+
+impl Backend {
+	/// All backends, whether [available](Self::is_available) or not.
+	pub const ALL: [Backend; 3] = [Backend::Interpreter, Backend::C, Backend::Python];
+
+	/// Checks whether the tools required by this backend are installed.
+	pub fn is_available(&self) -> bool {
+		let tool = match self {
+			Backend::Interpreter => return true,
+			Backend::C => "cc",
+			Backend::Python => "python3",
+		};
+		process::Command::new(tool).arg("--version").output().is_ok_and(|output| output.status.success())
+	}
+
+	/// Compiles and runs the `source_code` with this backend.
+	pub fn run(&self, source_code: &str) -> Result<Outcome, Error> {
+		let source = Arc::new(Source::new("differential.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new())?;
+
+		match self {
+			Backend::Interpreter => {
+				let stdout = SharedBuffer::default();
+				let exit_code = Interpreter::new(ast_nodes.iter()).with_stdout(Box::new(stdout.clone())).run()?;
+				// Like the operating system, only keep the lowest byte of the exit code
+				Ok(Outcome {
+					exit_code: (exit_code & 0xff) as i32,
+					stdout: String::from_utf8_lossy(&stdout.0.take()).into_owned(),
+				})
+			},
+			Backend::C => {
+				let directory = TempDir::new().map_err(CompileError::io("Creating temporary directory"))?;
+				let c_path = directory.path().join("program.c");
+				let executable_path = directory.path().join("program");
+				let c_file = fs::File::create(&c_path).map_err(CompileError::io("Creating C file"))?;
+				emitter::C::default()
+					.codegen(&mut ast_nodes.into_iter(), Box::new(c_file))
+					.map_err(CompileError::io("Generating C code"))?;
+				let runtime_path = runtime::compile(directory.path())?;
+				build::run_c_compiler(
+					process::Command::new("cc")
+						.arg(&c_path)
+						.arg(&runtime_path)
+						.args(runtime::LINK_ARGS)
+						.arg("-o")
+						.arg(&executable_path),
+				)?;
+				Outcome::from_command(process::Command::new(&executable_path))
+			},
+			Backend::Python => {
+				let directory = TempDir::new().map_err(CompileError::io("Creating temporary directory"))?;
+				let python_path = directory.path().join("program.py");
+				let python_file = fs::File::create(&python_path).map_err(CompileError::io("Creating Python file"))?;
+				emitter::Py
+					.codegen(&mut ast_nodes.into_iter(), Box::new(python_file))
+					.map_err(CompileError::io("Generating Python code"))?;
+				let mut command = process::Command::new("python3");
+				command.arg(&python_path);
+				Outcome::from_command(command)
+			},
+		}
+	}
+}
+
+/// Why a [`Backend`] couldn't run a program.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// The program didn't compile.
+	#[error(transparent)]
+	Compile(#[from] CompileError),
+	/// The interpreter failed at runtime.
+	#[error(transparent)]
+	Interpreter(#[from] interpreter::Error),
+	/// The compiled program couldn't be started.
+	#[error("Running program: {0}")]
+	Execute(io::Error),
+	/// The compiled program was terminated by a signal instead of exiting.
+	#[error("Program was terminated by a signal")]
+	Signal,
+}
+
+impl fmt::Display for Backend {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		<Self as fmt::Debug>::fmt(self, f)
+	}
+}
+
+/// Observable result of running a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+	/// Exit code of the program.
+	pub exit_code: i32,
+	/// Everything the program printed to stdout.
+	pub stdout: String,
+}
+
+impl Outcome {
+	/// Runs the `command` and captures its exit code and stdout.
+	fn from_command(mut command: process::Command) -> Result<Self, Error> {
+		let output = command.output().map_err(Error::Execute)?;
+		Ok(Outcome {
+			exit_code: output.status.code().ok_or(Error::Signal)?,
+			stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+		})
+	}
+}
+
+/// Runs the `source_code` with every [available](Backend::is_available) backend.
+pub fn run_all(source_code: &str) -> Vec<(Backend, Result<Outcome, Error>)> {
+	Backend::ALL.into_iter().filter(Backend::is_available).map(|backend| (backend, backend.run(source_code))).collect()
+}
+
+/// Runs the `source_code` with every available backend and panics if any backend fails or the outcomes differ.
+///
+/// Returns the common outcome.
+pub fn assert_consistent(source_code: &str) -> Outcome {
+	let results = run_all(source_code);
+	let mut outcomes = Vec::new();
+	for (backend, result) in results {
+		match result {
+			Ok(outcome) => outcomes.push((backend, outcome)),
+			Err(err) => panic!("Backend {} failed: {:#}", backend, err),
+		}
+	}
+
+	let (reference_backend, reference) = outcomes.first().expect("interpreter is always available").clone();
+	for (backend, outcome) in &outcomes {
+		assert_eq!(
+			outcome, &reference,
+			"Backend {} diverges from {} for program:\n{}",
+			backend, reference_backend, source_code
+		);
+	}
+	reference
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_arithmetic() {
+		assert_consistent("def main(): int { return (20 * 2 + 2) }");
+	}
+
+	#[test]
+	fn test_truncating_division() {
+		assert_eq!(assert_consistent("def main(): int { return (0 - 7) / 2 + 10 }").exit_code, 7);
+	}
+
+	#[test]
+	fn test_loop() {
+		assert_consistent(
+			"def main(): int {
+				var i: int = 0
+				var sum: int = 0
+				while i < 10 {
+					i = i + 1
+					sum = sum + i
+				}
+				return sum
+			}",
+		);
+	}
+
+	#[test]
+	fn test_recursion() {
+		assert_consistent(
+			"def fib(n: int): int {
+				if n < 2 {
+					return n
+				}
+				return fib((n - 1)) + fib((n - 2))
+			}
+			def main(): int {
+				return fib(10)
+			}",
+		);
+	}
+
+	#[test]
+	fn test_multiple_parameters() {
+		assert_consistent(
+			"def sub(a: int, b: int): int {
+				return a - b
+			}
+			def main(): int {
+				return sub(50, 8) + pow(2, 3) - max(4, 9) + min(1, 2)
+			}",
+		);
+	}
+}
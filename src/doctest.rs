@@ -0,0 +1,220 @@
+//! Testing the code examples in comments, so that they don't become stale.
+//!
+//! Fenced code blocks marked with `ftl` are [extracted](extract) from the comments of a file:
+//!
+//! ````text
+//! # Returns the square of `a`:
+//! # ```ftl
+//! # def main(): int {
+//! #     return square(3) - 9
+//! # }
+//! # ```
+//! ````
+//!
+//! Each snippet is compiled on its own. If it is [run](Mode::Run) and defines `main`, it is also executed with the
+//! [interpreter](crate::interpreter) and has to exit with `0`. Blocks marked with `ftl,no_run` are only compiled,
+//! those marked with `ftl,ignore` are skipped.
+
+use std::{io, sync::Arc};
+
+use crate::{
+	ast,
+	diagnostic::Diagnostic,
+	dump::Dumper,
+	interpreter::{self, Interpreter},
+	semantic_analyzer::CheckConfig,
+	source::Source,
+	CompileError,
+};
+
+/// How a snippet is tested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+	/// Compile and, if requested and the snippet defines `main`, run the snippet.
+	Run,
+	/// Only compile the snippet (`ftl,no_run`).
+	NoRun,
+	/// Skip the snippet (`ftl,ignore`).
+	Ignore,
+}
+
+/// Code block of a comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+	/// Line of the first line of code in the file.
+	pub line: u32,
+	/// The code without the leading `#` of the comment.
+	pub code: String,
+	/// How the snippet is tested, selected after the `ftl` of the code block.
+	pub mode: Mode,
+}
+
+/// Result of testing a [`Snippet`].
+#[derive(Debug)]
+pub enum Outcome {
+	/// The snippet compiled and, if it was run, exited with `0`.
+	Passed,
+	/// The snippet didn't compile, or its execution failed.
+	Failed(Failure),
+	/// The snippet is marked with `ftl,ignore`.
+	Ignored,
+}
+
+/// Why a [`Snippet`] failed.
+#[derive(Debug, thiserror::Error)]
+pub enum Failure {
+	/// The snippet didn't compile.
+	#[error(transparent)]
+	Compile(#[from] CompileError),
+	/// The interpreter failed while executing the snippet.
+	#[error(transparent)]
+	Interpreter(#[from] interpreter::Error),
+	/// The stack for the interpreter couldn't be reserved.
+	#[error("Reserving the stack of the interpreter: {0}")]
+	Stack(io::Error),
+	/// The snippet exited with a code other than `0`.
+	#[error("Exited with code {0}")]
+	ExitCode(i64),
+}
+
+impl Failure {
+	/// The diagnostic of failures caused by the code of the snippet.
+	pub fn diagnostic(&self) -> Option<Diagnostic> {
+		match self {
+			Failure::Compile(err) => err.diagnostic(),
+			Failure::Interpreter(err) => Some(Diagnostic::from(err)),
+			Failure::Stack(_) | Failure::ExitCode(_) => None,
+		}
+	}
+}
+
+/// Extracts the code blocks marked with `ftl` from the comments and doc comments of the file.
+pub fn extract(ast_nodes: &[ast::Node]) -> Vec<Snippet> {
+	let mut snippets = Vec::new();
+	for ast_node in ast_nodes {
+		if let ast::Node::Comment(comment) = ast_node {
+			// Following lines of the comment start with `#`
+			let lines = comment.lines().enumerate().map(|(i, line)| {
+				let line = if i == 0 { line } else { line.strip_prefix('#').unwrap_or(line) };
+				line.strip_prefix(' ').unwrap_or(line)
+			});
+			extract_from_lines(comment.position.position.start.line, lines, &mut snippets);
+		} else if let Some(doc) = ast_node.doc() {
+			extract_from_lines(doc.position.position.start.line, doc.lines(), &mut snippets);
+		}
+	}
+	snippets
+}
+
+/// Extracts the code blocks of the `lines` of a comment starting at line `first_line` into `snippets`.
+fn extract_from_lines<'a>(first_line: u32, lines: impl Iterator<Item = &'a str>, snippets: &mut Vec<Snippet>) {
+	// The current code block with its mode, first line and code
+	let mut block: Option<(Mode, u32, String)> = None;
+	for (i, line) in lines.enumerate() {
+		let line_number = first_line + i as u32;
+		match (&mut block, line.trim().strip_prefix("```")) {
+			(None, Some(info)) => {
+				block = match info.trim() {
+					"ftl" => Some((Mode::Run, line_number + 1, String::new())),
+					"ftl,no_run" => Some((Mode::NoRun, line_number + 1, String::new())),
+					"ftl,ignore" => Some((Mode::Ignore, line_number + 1, String::new())),
+					// Code blocks of other languages are skipped like ignored ones
+					_ => Some((Mode::Ignore, 0, String::new())),
+				}
+			},
+			(Some(_), Some("")) => {
+				let (mode, line, code) = block.take().unwrap();
+				if line != 0 {
+					snippets.push(Snippet { line, code, mode });
+				}
+			},
+			(Some((_, _, code)), _) => {
+				code.push_str(line);
+				code.push('\n');
+			},
+			(None, None) => (),
+		}
+	}
+}
+
+/// Compiles the `snippet` of the file `path` and, if `run` is set, executes it.
+pub fn test(snippet: &Snippet, path: &str, run: bool, config: &CheckConfig) -> Outcome {
+	if snippet.mode == Mode::Ignore {
+		return Outcome::Ignored;
+	}
+	// Empty lines before the code let errors refer to the line in the file
+	let code = "\n".repeat(snippet.line as usize - 1) + &snippet.code;
+	let source = Arc::new(Source::new(path.to_owned(), code));
+	let ast_nodes = match crate::compile_source(source, config, &Dumper::default(), &mut Vec::new()) {
+		Ok(ast_nodes) => ast_nodes,
+		Err(err) => return Outcome::Failed(err.into()),
+	};
+	let has_main = ast_nodes.iter().any(
+		|ast_node| matches!(ast_node, ast::Node::Function(function) if function.prototype.name.as_str() == "main"),
+	);
+	if !run || snippet.mode == Mode::NoRun || !has_main {
+		return Outcome::Passed;
+	}
+	let result = interpreter::with_stack_for(interpreter::DEFAULT_MAX_CALL_DEPTH, || {
+		Interpreter::new(ast_nodes.iter()).with_stdout(Box::new(io::sink())).run()
+	});
+	match result {
+		Ok(Ok(0)) => Outcome::Passed,
+		Ok(Ok(exit_code)) => Outcome::Failed(Failure::ExitCode(exit_code)),
+		Ok(Err(err)) => Outcome::Failed(err.into()),
+		Err(err) => Outcome::Failed(Failure::Stack(err)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_doctest() {
+		let source_code = "## Squares `a`:
+## ```ftl
+## def main(): int {
+##     return 3 * 3 - 9
+## }
+## ```
+##
+## ```ftl,no_run
+## def main(): int { return 1 }
+## ```
+## ```sh
+## ftl run
+## ```
+def square(a: int): int { return a * a }
+
+# ```ftl
+# def main(): int { return 1 }
+# ```
+# ```ftl,ignore
+# not ftl
+# ```
+# ```ftl
+# def f() { undeclared() }
+# ```";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let snippets = extract(&ast_nodes);
+		let summary: Vec<_> = snippets.iter().map(|snippet| (snippet.line, snippet.mode)).collect();
+		assert_eq!(summary, [(3, Mode::Run), (9, Mode::NoRun), (17, Mode::Run), (20, Mode::Ignore), (23, Mode::Run)]);
+		assert_eq!(snippets[0].code, "def main(): int {\n    return 3 * 3 - 9\n}\n");
+
+		let config = CheckConfig::default();
+		let outcomes: Vec<_> = snippets.iter().map(|snippet| test(snippet, "test.ftl", true, &config)).collect();
+		assert!(matches!(
+			outcomes[..],
+			[
+				Outcome::Passed,
+				Outcome::Passed,
+				Outcome::Failed(Failure::ExitCode(1)),
+				Outcome::Ignored,
+				Outcome::Failed(Failure::Compile(CompileError::Semantic(_)))
+			]
+		));
+		assert!(matches!(test(&snippets[2], "test.ftl", false, &config), Outcome::Passed));
+	}
+}
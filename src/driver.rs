@@ -0,0 +1,946 @@
+//! The commands of the `ftl` command line interface, independent of the process they run in.
+//!
+//! A [`Driver`] executes a [`Command`] and writes its output and errors to writers, which are the standard streams of
+//! the process by default. Tests, the playground or a language server can pass their own:
+//!
+//! ```no_run
+//! use fortytwolang::driver::{Command, Driver, Options};
+//!
+//! let mut output = Vec::new();
+//! let exit_code = Driver::new(Options::default()).with_stdout(&mut output).run(Command::Eval {
+//! 	file: "main.ftl".into(),
+//! 	max_call_depth: 1000,
+//! 	trace: false,
+//! 	trace_log: None,
+//! 	coverage: false,
+//! });
+//! ```
+
+use std::{
+	fs,
+	io::{self, BufRead, Write},
+	mem,
+	path::{Path, PathBuf},
+	process,
+	sync::Arc,
+};
+
+use anyhow::Context;
+
+use crate::{
+	ast::{self, build::ClearPositions},
+	bench::{self, BenchOptions},
+	coverage,
+	deps::{self, DependencyGraph},
+	diagnostic::{
+		render,
+		sink::{DiagnosticSink, Printer},
+		Diagnostic,
+	},
+	doctest,
+	dump::{Artifact, Dumper},
+	emitter::{self, codegen_to_string, BraceStyle, FmtConfig, SharedBuffer},
+	examples, highlight,
+	index::SymbolIndex,
+	interpreter::{self, Interpreter},
+	lexer::{self, Lexer},
+	profile::Profile,
+	project, read_source, repl, runtime,
+	sanitizer::{self, Sanitizer},
+	semantic_analyzer::CheckConfig,
+	source::Source,
+	token::{diff::TokenDiff, Token, TokenKind},
+	CompileError,
+};
+
+/// Options shared by all commands.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+	/// Optional checks of the semantic analysis.
+	pub config: CheckConfig,
+	/// Which intermediate artifacts are written to files.
+	pub dumper: Dumper,
+	/// How errors and warnings are printed.
+	pub render: render::Options,
+	/// Whether instructions outside of functions are allowed, see
+	/// [`Parser::with_script`](crate::parser::Parser::with_script).
+	pub script: bool,
+}
+
+/// Style options of `ftl fmt` overriding those of the nearest `ftlfmt.toml`, if set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FmtStyle {
+	pub indent_width: Option<usize>,
+	pub use_tabs: Option<bool>,
+	pub max_line_length: Option<usize>,
+	pub brace_style: Option<BraceStyle>,
+	pub trailing_commas: Option<bool>,
+}
+
+impl FmtStyle {
+	/// Overrides the options of the `config` that are set.
+	pub fn apply(&self, config: &mut FmtConfig) {
+		config.indent_width = self.indent_width.unwrap_or(config.indent_width);
+		config.use_tabs = self.use_tabs.unwrap_or(config.use_tabs);
+		config.max_line_length = self.max_line_length.unwrap_or(config.max_line_length);
+		config.brace_style = self.brace_style.unwrap_or(config.brace_style);
+		config.trailing_commas = self.trailing_commas.unwrap_or(config.trailing_commas);
+	}
+}
+
+/// A command of the `ftl` command line interface. See `ftl help <command>` for details.
+#[derive(Debug, Clone)]
+pub enum Command {
+	/// Print the formatted code, or whatever can be parsed of it with `recover_best_effort`. With `verify`, the
+	/// formatted code has to be the same program as the original.
+	Fmt { file: PathBuf, style: FmtStyle, recover_best_effort: bool, verify: bool },
+	/// Generate code with the emitter named `emit`, and compile it to an executable or, with `lib`, to an object
+	/// file and a header for the C emitter.
+	Compile { file: PathBuf, emit: String, lib: bool, sanitize: Vec<Sanitizer> },
+	/// Compile the files separately and link them to the executable `output`.
+	Build { files: Vec<PathBuf>, output: Option<PathBuf>, build_dir: PathBuf },
+	/// Print the warnings of all lints for the files.
+	Lint { files: Vec<PathBuf> },
+	/// Print where the function or struct `name` is defined and used in the files, whose symbols are cached in the
+	/// [`index`](crate::index) file. Exits with 1 if there are none.
+	Refs { name: String, files: Vec<PathBuf>, index: PathBuf },
+	/// Print which files use functions and structs of which other files in the `format`, with the symbols cached in
+	/// the [`index`](crate::index) file. Exits with 1 if files depend on each other in a cycle.
+	Deps { files: Vec<PathBuf>, format: deps::Format, index: PathBuf },
+	/// Execute with the interpreter. The exit code is that of the program.
+	Eval { file: PathBuf, max_call_depth: usize, trace: bool, trace_log: Option<PathBuf>, coverage: bool },
+	/// Execute with the interpreter and print the calls and time of each function.
+	Profile { file: PathBuf },
+	/// Evaluate the input line by line.
+	Repl,
+	/// Run the functions annotated with `@bench` and print the time per call.
+	Bench { file: PathBuf, options: BenchOptions },
+	/// Compile and optionally `run` the code blocks in the comments of the files.
+	Test { files: Vec<PathBuf>, run: bool },
+	/// Print the code with syntax highlighting.
+	Highlight { file: PathBuf, format: highlight::Format },
+	/// Print the differences between the token streams of the files `old` and `new`. Exits with 1 if they differ.
+	DiffTokens { old: PathBuf, new: PathBuf },
+	/// Compile and execute. The exit code is that of the program.
+	Run { file: PathBuf },
+	/// List the bundled [examples](crate::examples), or run the one called `name` with the interpreter.
+	Examples { name: Option<String> },
+	/// Create a project in the new `directory`.
+	New { directory: PathBuf },
+	/// Print the script completing the commands and options of the command line interface `cli` in the `shell`.
+	Completions { shell: clap_complete::Shell, cli: Box<clap::Command> },
+	/// Print the man page of the command line interface `cli`.
+	Man { cli: Box<clap::Command> },
+}
+
+/// Executes [`Command`]s, writing to the given streams.
+pub struct Driver<'a> {
+	options: Options,
+	stdin: Box<dyn BufRead + 'a>,
+	stdout: Box<dyn Write + 'a>,
+	stderr: Box<dyn Write + 'a>,
+}
+
+impl<'a> Driver<'a> {
+	/// Creates a driver using the standard streams of the process.
+	pub fn new(options: Options) -> Self {
+		Self {
+			options,
+			stdin: Box::new(io::stdin().lock()),
+			stdout: Box::new(io::stdout()),
+			stderr: Box::new(io::stderr()),
+		}
+	}
+
+	/// Reads the input of the REPL from `stdin`.
+	pub fn with_stdin(mut self, stdin: impl BufRead + 'a) -> Self {
+		self.stdin = Box::new(stdin);
+		self
+	}
+
+	/// Writes the output of the commands to `stdout`.
+	pub fn with_stdout(mut self, stdout: impl Write + 'a) -> Self {
+		self.stdout = Box::new(stdout);
+		self
+	}
+
+	/// Writes errors, warnings and reports to `stderr`.
+	pub fn with_stderr(mut self, stderr: impl Write + 'a) -> Self {
+		self.stderr = Box::new(stderr);
+		self
+	}
+
+	/// Executes the `command` and returns the exit code for the process. Errors are printed to the
+	/// [stderr](Self::with_stderr) and result in the exit code `1`.
+	pub fn run(&mut self, command: Command) -> i32 {
+		let result = match command {
+			Command::Fmt { file, style, recover_best_effort: false, verify } => {
+				self.format(&file, &style, verify).map(|()| 0)
+			},
+			Command::Fmt { file, style, recover_best_effort: true, .. } => self.format_lenient(&file, &style),
+			Command::Compile { file, emit, lib, sanitize } => self.compile(&file, &emit, lib, &sanitize).map(|()| 0),
+			Command::Build { files, output, build_dir } => self.build(&files, output, &build_dir).map(|()| 0),
+			Command::Lint { files } => self.lint(&files).map(|()| 0),
+			Command::Refs { name, files, index } => self.refs(&name, &files, &index),
+			Command::Deps { files, format, index } => self.deps(&files, format, &index),
+			Command::Eval { file, max_call_depth, trace, trace_log, coverage } => {
+				self.eval(&file, max_call_depth, trace, trace_log.as_deref(), coverage)
+			},
+			Command::Profile { file } => self.profile(&file).map(|()| 0),
+			Command::Repl => self.repl().map(|()| 0),
+			Command::Bench { file, options } => self.bench(&file, options).map(|()| 0),
+			Command::Test { files, run } => self.test(&files, run).map(|()| 0),
+			Command::Highlight { file, format } => self.highlight(&file, format).map(|()| 0),
+			Command::DiffTokens { old, new } => self.diff_tokens(&old, &new),
+			Command::Run { file } => self.run_executable(&file),
+			Command::Examples { name: None } => self.list_examples().map(|()| 0),
+			Command::Examples { name: Some(name) } => self.run_example(&name).map(|()| 0),
+			Command::New { directory } => project::create(&directory).map(|()| 0).map_err(anyhow::Error::from),
+			Command::Completions { shell, cli } => self.completions(shell, *cli).map(|()| 0),
+			Command::Man { cli } => self.man(*cli).map(|()| 0),
+		};
+		result.unwrap_or_else(|err| {
+			self.print_error(err);
+			1
+		})
+	}
+
+	/// Runs the [compiler pipeline](crate::compiler_pipeline), in [script mode](crate::script_pipeline) if enabled,
+	/// and prints its warnings.
+	fn pipeline(&mut self, path: &Path) -> Result<Vec<ast::Node>, CompileError> {
+		let mut printer = Printer::new(&mut self.stderr, self.options.render);
+		if self.options.script {
+			crate::script_pipeline(path, &self.options.config, &self.options.dumper, &mut printer)
+		} else {
+			crate::compiler_pipeline(path, &self.options.config, &self.options.dumper, &mut printer)
+		}
+	}
+
+	/// Formats FTL source code using the FTL emitter, with the style of the nearest `ftlfmt.toml` overridden by
+	/// `style`. With `verify`, the formatted code is only printed if it is the [same program](Self::verify_format).
+	fn format(&mut self, path: &Path, style: &FmtStyle, verify: bool) -> anyhow::Result<()> {
+		let mut fmt_config = FmtConfig::load(path)?;
+		style.apply(&mut fmt_config);
+		let ast_nodes = crate::compiler_pipeline(path, &self.options.config, &self.options.dumper, &mut Vec::new())?;
+
+		let source = read_source(path)?;
+		let code = codegen_to_string(&ftl_emitter(fmt_config, source.clone()), &mut ast_nodes.clone().into_iter())?;
+		if verify {
+			Self::verify_format(source, ast_nodes, code.clone())?;
+		}
+		write!(self.stdout, "{}", code)?;
+		Ok(())
+	}
+
+	/// Checks that the `formatted` code has the same tokens as the `original` and parses to the same `ast_nodes`.
+	///
+	/// Semicolons, parentheses and the commas between fields are not compared, since the formatter omits the `;` of
+	/// `return;`, encloses binary expressions in parentheses wherever they are nested and adds or removes trailing
+	/// commas. Their effect on the program is covered by comparing the ASTs.
+	fn verify_format(original: Arc<Source>, mut ast_nodes: Vec<ast::Node>, formatted: String) -> anyhow::Result<()> {
+		let formatted = Arc::new(Source::new(original.name.clone(), formatted));
+		let significant_tokens = |source| -> anyhow::Result<Vec<Token>> {
+			let mut tokens = crate::lex_source(source, &Dumper::default())?;
+			// The formatter adds or removes the commas after the fields of structs, whose bodies contain no other commas
+			let (mut struct_, mut struct_body) = (false, false);
+			tokens.retain(|token| match token.value {
+				TokenKind::Semicolon | TokenKind::OpeningParentheses | TokenKind::ClosingParentheses => false,
+				TokenKind::Struct => {
+					struct_ = true;
+					true
+				},
+				TokenKind::OpeningCurlyBraces => {
+					(struct_body, struct_) = (struct_, false);
+					true
+				},
+				TokenKind::ClosingCurlyBraces => {
+					struct_body = false;
+					true
+				},
+				TokenKind::Comma => !struct_body,
+				_ => true,
+			});
+			Ok(tokens)
+		};
+		let (original_tokens, formatted_tokens) =
+			(significant_tokens(original)?, significant_tokens(formatted.clone())?);
+		let diff = TokenDiff::new(&original_tokens, &formatted_tokens);
+		if !diff.is_empty() {
+			anyhow::bail!("The formatter changed the tokens of the code:\n{}", diff);
+		}
+
+		let mut formatted_nodes = crate::parse_source(formatted, &Dumper::default())?;
+		formatted_nodes.clear_positions();
+		ast_nodes.clear_positions();
+		if formatted_nodes != ast_nodes {
+			anyhow::bail!("The formatter changed the structure of the code, e.g. the grouping of operators");
+		}
+		Ok(())
+	}
+
+	/// Like [`Self::format`], but formats only what can be parsed and reports the syntax errors instead of failing
+	/// on them. Code that could not be parsed is kept as it is. Returns the exit code, 1 if there were syntax errors.
+	fn format_lenient(&mut self, path: &Path, style: &FmtStyle) -> anyhow::Result<i32> {
+		let mut fmt_config = FmtConfig::load(path)?;
+		style.apply(&mut fmt_config);
+		let source = read_source(path)?;
+		let (ast_nodes, diagnostics) = crate::parse_lenient(source.clone());
+		let mut printer = Printer::new(&mut self.stderr, self.options.render);
+		let recovered = !diagnostics.is_empty();
+		diagnostics.into_iter().for_each(|diagnostic| printer.emit(diagnostic));
+
+		let code = codegen_to_string(&ftl_emitter(fmt_config, source), &mut ast_nodes.into_iter())?;
+		write!(self.stdout, "{}", code)?;
+		Ok(i32::from(recovered))
+	}
+
+	/// Tests the code blocks in the comments of the files and prints the outcome of each one.
+	fn test(&mut self, paths: &[PathBuf], run: bool) -> anyhow::Result<()> {
+		let (mut passed, mut failed, mut ignored) = (0, 0, 0);
+		for path in paths {
+			let source = read_source(path)?;
+			let name = source.name.clone();
+			let ast_nodes = crate::parse_source(source, &Dumper::default())?;
+			for snippet in doctest::extract(&ast_nodes) {
+				match doctest::test(&snippet, &name, run, &self.options.config) {
+					doctest::Outcome::Passed => {
+						writeln!(self.stdout, "test {}:{} ... ok", name, snippet.line)?;
+						passed += 1;
+					},
+					doctest::Outcome::Failed(err) => {
+						writeln!(self.stdout, "test {}:{} ... FAILED", name, snippet.line)?;
+						self.print_error(err.into());
+						failed += 1;
+					},
+					doctest::Outcome::Ignored => {
+						writeln!(self.stdout, "test {}:{} ... ignored", name, snippet.line)?;
+						ignored += 1;
+					},
+				}
+			}
+		}
+		writeln!(self.stdout, "test result: {} passed; {} failed; {} ignored", passed, failed, ignored)?;
+		if failed > 0 {
+			anyhow::bail!("{} code blocks failed", failed);
+		}
+		Ok(())
+	}
+
+	/// Prints the FTL source code with syntax highlighting in the given `format`.
+	fn highlight(&mut self, path: &Path, format: highlight::Format) -> anyhow::Result<()> {
+		write!(self.stdout, "{}", highlight::highlight(read_source(path)?, format)?)?;
+		Ok(())
+	}
+
+	/// Prints the [differences](TokenDiff) between the tokens of the files `old` and `new`.
+	fn diff_tokens(&mut self, old: &Path, new: &Path) -> anyhow::Result<i32> {
+		let old_tokens = crate::lex_source(read_source(old)?, &self.options.dumper)?;
+		let new_tokens = crate::lex_source(read_source(new)?, &self.options.dumper)?;
+		let diff = TokenDiff::new(&old_tokens, &new_tokens);
+		write!(self.stdout, "{}", diff)?;
+		Ok(if diff.is_empty() { 0 } else { 1 })
+	}
+
+	/// Prints the definitions and uses of `name` in the `files`, after updating the symbol index at `index_path`.
+	fn refs(&mut self, name: &str, files: &[PathBuf], index_path: &Path) -> anyhow::Result<i32> {
+		let index = Self::update_index(files, index_path)?;
+		let mut found = false;
+		for (path, definition) in index.definitions(name) {
+			let position = &definition.position.start;
+			writeln!(self.stdout, "{}:{}: definition of {} `{}`", path.display(), position, definition.kind, name)?;
+			found = true;
+		}
+		for (path, reference) in index.references(name) {
+			writeln!(self.stdout, "{}:{}: use of `{}`", path.display(), reference.position.start, name)?;
+			found = true;
+		}
+		Ok(if found { 0 } else { 1 })
+	}
+
+	/// Prints the [dependency graph](DependencyGraph) of the files in the `format`.
+	fn deps(&mut self, files: &[PathBuf], format: deps::Format, index_path: &Path) -> anyhow::Result<i32> {
+		let graph = DependencyGraph::new(&Self::update_index(files, index_path)?);
+		write!(self.stdout, "{}", graph.render(format))?;
+		Ok(if graph.cycles().is_empty() { 0 } else { 1 })
+	}
+
+	/// Loads the [`SymbolIndex`] at `index_path`, updates it with the changed `files` and saves it.
+	fn update_index(files: &[PathBuf], index_path: &Path) -> Result<SymbolIndex, CompileError> {
+		let mut index = SymbolIndex::load(index_path);
+		let parsed = index.update(files)?;
+		tracing::info!(parsed, "updated symbol index");
+		index.save(index_path)?;
+		Ok(index)
+	}
+
+	/// Compiles FTL source code using the emitter named `emit`. For the C emitter, the generated code is compiled to
+	/// an executable, or to an object file and a header if `lib` is set. The executable is checked at runtime by the
+	/// `sanitizers`.
+	fn compile(&mut self, path: &Path, emit: &str, lib: bool, sanitizers: &[Sanitizer]) -> anyhow::Result<()> {
+		let mut registry = emitter::Registry::with_builtins();
+		if !sanitizers.is_empty() {
+			if emit != "c" {
+				anyhow::bail!("Sanitizers are only supported by the C emitter");
+			}
+			// Let the reports of the sanitizers refer to the FTL source
+			registry.register("c", Box::new(emitter::C { line_directives: true }));
+		}
+		let Some(target_emitter) = registry.get(emit) else {
+			anyhow::bail!(
+				"Unknown emitter `{}`, available emitters are: {}",
+				emit,
+				registry.names().collect::<Vec<_>>().join(", ")
+			);
+		};
+
+		let ast_nodes = self.pipeline(path)?;
+
+		if lib {
+			let header_path = path.with_extension("h");
+			let header = codegen_to_string(&emitter::CHeader, &mut ast_nodes.clone().into_iter())?;
+			fs::write(&header_path, header).context(format!("Creating header file `{:?}`", header_path))?;
+		}
+
+		// Generate target code
+		let mut output_path = path.with_extension(target_emitter.file_extension());
+		if output_path == path {
+			// Don't overwrite the source file, e.g. when emitting FTL
+			output_path = path.with_extension(format!("out.{}", target_emitter.file_extension()));
+		}
+		let code = codegen_to_string(target_emitter, &mut ast_nodes.into_iter())?;
+		fs::write(&output_path, &code).context(format!("Creating output file `{:?}`", output_path))?;
+		if emit != "c" {
+			return Ok(());
+		}
+
+		let c_code_output_path = output_path;
+		self.options.dumper.dump(Artifact::C, path, || code)?;
+
+		// The runtime is compiled next to the generated code. Libraries have to be linked with it by the user.
+		let runtime_directory = c_code_output_path.parent().unwrap_or(Path::new(""));
+		let runtime_path = runtime::compile(runtime_directory)?;
+
+		// Compile to executable or object file
+		let mut c_compile = process::Command::new("cc");
+		c_compile.args(sanitizer::compiler_args(sanitizers));
+		if lib {
+			c_compile.args(["-c", "-o"]).arg(path.with_extension("o"));
+		} else {
+			c_compile.arg("-o").arg(path.with_extension("")).arg(&runtime_path).args(runtime::LINK_ARGS);
+		}
+		let c_compile = c_compile.arg(&c_code_output_path).output().context("Invoking C compiler")?;
+		if !c_compile.status.success() {
+			self.stdout.write_all(&c_compile.stdout)?;
+			self.stderr.write_all(&c_compile.stderr)?;
+		}
+
+		Ok(())
+	}
+
+	/// Builds the files into the executable `output`, which is named after the first file by default.
+	fn build(&mut self, files: &[PathBuf], output: Option<PathBuf>, build_dir: &Path) -> anyhow::Result<()> {
+		let Some(first) = files.first() else {
+			anyhow::bail!("No files to build");
+		};
+		let output = output.unwrap_or_else(|| first.with_extension(""));
+		crate::build::build(files, build_dir, &output, &self.options.config, &self.options.dumper)?;
+		Ok(())
+	}
+
+	/// Analyzes the files together and prints the warnings of all lints.
+	fn lint(&mut self, paths: &[PathBuf]) -> anyhow::Result<()> {
+		let mut ast_nodes = Vec::new();
+		for path in paths {
+			ast_nodes.extend(crate::parse_source(read_source(path)?, &self.options.dumper)?);
+		}
+		let program = crate::analyze(&paths[0], ast_nodes.iter(), &self.options.config, &self.options.dumper)?;
+		let mut printer = Printer::new(&mut self.stderr, self.options.render);
+		program.diagnostics.into_iter().for_each(|diagnostic| printer.emit(diagnostic));
+		Ok(())
+	}
+
+	/// Executes FTL source code with the interpreter and returns the exit code of the program.
+	///
+	/// With `trace`, each function call, return and assignment is logged. The events are written to `trace_log`,
+	/// even if the execution fails. With `coverage`, the source annotated with the executions of each line is
+	/// written to `<file>.ftlcov`.
+	fn eval(
+		&mut self,
+		path: &Path,
+		max_call_depth: usize,
+		trace: bool,
+		trace_log: Option<&Path>,
+		coverage: bool,
+	) -> anyhow::Result<i32> {
+		let ast_nodes = self.pipeline(path)?;
+		let (result, events, counts) = interpreter::with_stack_for(max_call_depth, || {
+			let mut interpreter = Interpreter::new(ast_nodes.iter()).with_max_call_depth(max_call_depth);
+			if trace || trace_log.is_some() {
+				interpreter = interpreter.with_trace();
+			}
+			if coverage {
+				interpreter = interpreter.with_coverage();
+			}
+			let result = interpreter.run();
+			// Events are formatted here, because their values can't leave the thread of the interpreter
+			let events: String = interpreter.events().iter().map(|event| format!("{}\n", event)).collect();
+			(result, events, interpreter.coverage().cloned())
+		})
+		.context("Reserving the stack of the interpreter")?;
+		if let Some(trace_log) = trace_log {
+			fs::write(trace_log, events).context(format!("Writing trace log `{:?}`", trace_log))?;
+		}
+		if let Some(counts) = counts {
+			let report = coverage::Report::new(&ast_nodes, &counts);
+			let text = fs::read_to_string(path).context(format!("Reading FTL source file `{:?}`", path))?;
+			let report_path = path.with_extension("ftlcov");
+			fs::write(&report_path, report.render(&text))
+				.context(format!("Writing coverage report `{:?}`", report_path))?;
+			let (executed, executable) = (report.executed_lines(), report.executable_lines());
+			let percentage = if executable == 0 { 100.0 } else { executed as f64 * 100.0 / executable as f64 };
+			writeln!(
+				self.stderr,
+				"Lines executed: {:.1}% of {}, see {}",
+				percentage,
+				executable,
+				report_path.display()
+			)?;
+			let unexecuted: Vec<_> = report.unexecuted_lines().map(|line| line.to_string()).collect();
+			if !unexecuted.is_empty() {
+				writeln!(self.stderr, "Lines not executed: {}", unexecuted.join(", "))?;
+			}
+		}
+		Ok(result? as i32)
+	}
+
+	/// Executes FTL source code with the interpreter and prints the calls and time of each function, the hottest
+	/// first.
+	fn profile(&mut self, path: &Path) -> anyhow::Result<()> {
+		let ast_nodes = self.pipeline(path)?;
+		let (result, report) = interpreter::with_stack_for(interpreter::DEFAULT_MAX_CALL_DEPTH, || {
+			let mut interpreter = Interpreter::new(ast_nodes.iter()).with_profile();
+			let result = interpreter.run();
+			(result, interpreter.profile().map(Profile::report))
+		})
+		.context("Reserving the stack of the interpreter")?;
+		let exit_code = result?;
+		write!(self.stdout, "{}", report.unwrap_or_default())?;
+		writeln!(self.stdout, "Exited with code {}", exit_code)?;
+		Ok(())
+	}
+
+	/// Reads code from the stdin and evaluates it input by input, until the end of the input.
+	fn repl(&mut self) -> anyhow::Result<()> {
+		let mut session = repl::Session::new();
+		let mut input = String::new();
+		loop {
+			// Ask for continuation lines while the input is incomplete
+			write!(self.stdout, "{}", if input.is_empty() { "ftl> " } else { "...> " })?;
+			self.stdout.flush()?;
+			if self.stdin.read_line(&mut input)? == 0 {
+				return Ok(());
+			}
+			match session.eval(&input) {
+				Ok(repl::Evaluation::Incomplete) => continue,
+				Ok(repl::Evaluation::Complete(Some(value))) => writeln!(self.stdout, "{}", value)?,
+				Ok(repl::Evaluation::Complete(None)) => (),
+				Err(err) => self.print_error(err.into()),
+			}
+			input.clear();
+		}
+	}
+
+	/// Runs the benchmarks in the FTL source code and prints the average time per call.
+	fn bench(&mut self, path: &Path, options: BenchOptions) -> anyhow::Result<()> {
+		let ast_nodes = self.pipeline(path)?;
+		let results = bench::run(&ast_nodes, options)?;
+		if results.is_empty() {
+			writeln!(self.stdout, "No functions annotated with @bench")?;
+		}
+		for result in results {
+			writeln!(
+				self.stdout,
+				"{:<24} {:>12} ns/op ({} iterations)",
+				result.name,
+				result.nanos_per_op(),
+				result.iterations
+			)?;
+		}
+		Ok(())
+	}
+
+	/// Compiles and runs the executable, forwarding its output, and returns its exit code.
+	///
+	/// The file is always compiled in script mode, which only makes a difference for files without `main`.
+	fn run_executable(&mut self, path: &Path) -> anyhow::Result<i32> {
+		let script = mem::replace(&mut self.options.script, true);
+		let compiled = self.compile(path, "c", false, &[]);
+		self.options.script = script;
+		compiled?;
+
+		let executable = Path::new(".").join(path.with_extension(""));
+		let output = process::Command::new(&executable)
+			.stdin(process::Stdio::inherit())
+			.output()
+			.context(format!("Running executable `{:?}`", executable))?;
+		self.stdout.write_all(&output.stdout)?;
+		self.stderr.write_all(&output.stderr)?;
+		// Programs killed by a signal have no exit code
+		Ok(output.status.code().unwrap_or(1))
+	}
+
+	/// Prints the name and description of each example.
+	fn list_examples(&mut self) -> anyhow::Result<()> {
+		for example in examples::ALL {
+			writeln!(self.stdout, "{:<10} {}", example.name, example.description())?;
+		}
+		Ok(())
+	}
+
+	/// Runs the example called `name` with the interpreter and prints its output and exit code.
+	fn run_example(&mut self, name: &str) -> anyhow::Result<()> {
+		let Some(example) = examples::get(name) else {
+			anyhow::bail!(
+				"Unknown example `{}`, available examples are: {}",
+				name,
+				examples::ALL.iter().map(|example| example.name).collect::<Vec<_>>().join(", ")
+			);
+		};
+		let source = Arc::new(Source::new(format!("{}.ftl", example.name), example.source.to_owned()));
+		let mut printer = Printer::new(&mut self.stderr, self.options.render);
+		let ast_nodes = crate::compile_source(source, &self.options.config, &self.options.dumper, &mut printer)?;
+		let (result, output) = interpreter::with_stack_for(interpreter::DEFAULT_MAX_CALL_DEPTH, || {
+			let output = SharedBuffer::default();
+			let result = Interpreter::new(ast_nodes.iter()).with_stdout(Box::new(output.clone())).run();
+			(result, output.0.take())
+		})
+		.context("Reserving the stack of the interpreter")?;
+		self.stdout.write_all(&output)?;
+		writeln!(self.stdout, "Exited with code {}", result?)?;
+		Ok(())
+	}
+
+	/// Prints the completion script of the `cli` for the `shell`. Unlike [`clap_complete::generate`], this fails instead
+	/// of panicking if stdout is closed, e.g. by a pipe.
+	fn completions(&mut self, shell: clap_complete::Shell, mut cli: clap::Command) -> anyhow::Result<()> {
+		let name = cli.get_name().to_owned();
+		cli.set_bin_name(name);
+		cli.build();
+		clap_complete::Generator::try_generate(&shell, &cli, &mut self.stdout)?;
+		Ok(())
+	}
+
+	/// Prints the man page of the `cli` in the roff format.
+	fn man(&mut self, cli: clap::Command) -> anyhow::Result<()> {
+		clap_mangen::Man::new(cli).render(&mut self.stdout)?;
+		Ok(())
+	}
+
+	/// Renders the error as diagnostic if it refers to the source code, or prints it as text otherwise.
+	fn print_error(&mut self, err: anyhow::Error) {
+		let diagnostic = if let Some(err) = err.downcast_ref::<CompileError>() {
+			err.diagnostic()
+		} else if let Some(err) = err.downcast_ref::<interpreter::Error>() {
+			Some(Diagnostic::from(err))
+		} else if let Some(err) = err.downcast_ref::<repl::Error>() {
+			err.diagnostic()
+		} else if let Some(err) = err.downcast_ref::<doctest::Failure>() {
+			err.diagnostic()
+		} else {
+			None
+		};
+		let _ = match diagnostic {
+			Some(diagnostic) => render::render(&diagnostic, &self.options.render, &mut self.stderr),
+			None => writeln!(self.stderr, "{:#}", err),
+		};
+	}
+}
+
+/// FTL emitter in the style of the `config` that keeps the empty lines and the comments in blocks of the `source`.
+fn ftl_emitter(config: FmtConfig, source: Arc<Source>) -> emitter::Ftl {
+	let tokens: Vec<_> = Lexer::new(source.iter()).with_trivia().filter_map(Result::ok).collect();
+	emitter::Ftl {
+		config,
+		empty_lines: Some(lexer::lines_after_empty_line(&tokens)),
+		comments: Some(lexer::BlockComments::new(&tokens)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{parser::DEFAULT_MAX_NESTING, test_support::TempDir};
+
+	/// Runs the `command` with `stdin` and returns the exit code and what was written to stdout and stderr.
+	fn run(command: Command, stdin: &str) -> (i32, String, String) {
+		let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+		let exit_code = Driver::new(Options::default())
+			.with_stdin(stdin.as_bytes())
+			.with_stdout(&mut stdout)
+			.with_stderr(&mut stderr)
+			.run(command);
+		(exit_code, String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+	}
+
+	#[test]
+	fn test_fmt() {
+		let directory = TempDir::new().unwrap();
+		let file = directory.path().join("main.ftl");
+		fs::write(&file, "def main(): int { return 0 }").unwrap();
+
+		let (exit_code, stdout, stderr) =
+			run(Command::Fmt { file, style: FmtStyle::default(), recover_best_effort: false, verify: false }, "");
+		assert_eq!((exit_code, stdout.as_str(), stderr.as_str()), (0, "def main(): int {\n\treturn 0\n}\n", ""));
+	}
+
+	#[test]
+	fn test_fmt_verify() {
+		let directory = TempDir::new().unwrap();
+		let file = directory.path().join("main.ftl");
+		let source_code = "def f() { return; }\ndef main(): int { return 2 * 3 + (4 - 1) }";
+		fs::write(&file, source_code).unwrap();
+		let command =
+			Command::Fmt { file: file.clone(), style: FmtStyle::default(), recover_best_effort: false, verify: true };
+		assert_eq!(run(command.clone(), "").0, 0);
+		// The formatter removes the commas between fields and keeps the comment after the last one
+		fs::write(&file, "struct P { x: int = 1, # x\n## The y\ny: int,\n# last\n}").unwrap();
+		assert_eq!(
+			run(command, ""),
+			(0, "struct P {\n\tx: int = 1 # x\n\t## The y\n\ty: int\n\t# last\n}\n".to_owned(), String::new())
+		);
+
+		let source = Arc::new(Source::new("main.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source.clone(), &Dumper::default()).unwrap();
+		let verify = |formatted: &str| Driver::verify_format(source.clone(), ast_nodes.clone(), formatted.to_owned());
+		assert!(verify("def f() {\n\treturn\n}\n\ndef main(): int {\n\treturn (2 * 3) + (4 - 1)\n}\n").is_ok());
+		let err = verify("def f() {\n\treturn\n}\n\ndef main(): int {\n\treturn 2 * 3 + 4 - 1\n}\n").unwrap_err();
+		assert!(err.to_string().contains("structure"), "{}", err);
+		let err = verify("def f() {\n\treturn\n}\n\ndef main(): int {\n\treturn 2 * 3 + (4 - 2)\n}\n").unwrap_err();
+		assert!(err.to_string().contains("- INT(1)"), "{}", err);
+	}
+
+	#[test]
+	fn test_fmt_recover_best_effort() {
+		let directory = TempDir::new().unwrap();
+		let file = directory.path().join("main.ftl");
+		let source_code = "struct P { x: int,  y: }\n# P\ndef main(): int {\nvar x: int =  (1 +)  \nreturn   (x *)\n}";
+		fs::write(&file, source_code).unwrap();
+		let command = Command::Fmt { file, style: FmtStyle::default(), recover_best_effort: true, verify: false };
+		// Invalid code is kept as it is, the rest is formatted
+		let (exit_code, stdout, stderr) = run(command, "");
+		assert_eq!(
+			(exit_code, stdout.as_str()),
+			(1, "struct P { x: int,  y: }\n# P\ndef main(): int {\n\tvar x: int =  (1 +)\n\treturn   (x *)\n}\n")
+		);
+		assert!(stderr.contains("error"), "{}", stderr);
+	}
+
+	#[test]
+	fn test_diff_tokens() {
+		let directory = TempDir::new().unwrap();
+		let (old, new) = (directory.path().join("old.ftl"), directory.path().join("new.ftl"));
+		fs::write(&old, "def main(): int { return 0 }").unwrap();
+		fs::write(&new, "def main(): int {\n\treturn 0\n}\n").unwrap();
+		let diff_tokens = || run(Command::DiffTokens { old: old.clone(), new: new.clone() }, "");
+		assert_eq!(diff_tokens(), (0, String::new(), String::new()));
+
+		fs::write(&new, "def main(): int {\n\treturn 1\n}\n").unwrap();
+		let (exit_code, stdout, _) = diff_tokens();
+		assert_eq!(exit_code, 1);
+		assert!(stdout.contains("- INT(0)@1:26-1:26\n+ INT(1)@2:9-2:9\n"), "{}", stdout);
+	}
+
+	#[test]
+	fn test_eval() {
+		let directory = TempDir::new().unwrap();
+		let file = directory.path().join("main.ftl");
+		fs::write(&file, "def main(): int {\n\treturn 6 * 7\n}").unwrap();
+
+		let command = Command::Eval { file, max_call_depth: 1000, trace: false, trace_log: None, coverage: false };
+		assert_eq!(run(command, "").0, 42);
+
+		// Warnings are printed, but don't stop the program
+		let file = directory.path().join("warning.ftl");
+		fs::write(&file, "def f(): int {\n\treturn f()\n}\n\ndef main(): int {\n\treturn 1\n}").unwrap();
+		let command = Command::Eval { file, max_call_depth: 1000, trace: false, trace_log: None, coverage: false };
+		let (exit_code, _, stderr) = run(command, "");
+		assert_eq!(exit_code, 1);
+		assert!(stderr.contains("warning[UnconditionalRecursion]"), "{}", stderr);
+	}
+
+	#[test]
+	fn test_deep_nesting() {
+		let directory = TempDir::new().unwrap();
+		let file = directory.path().join("main.ftl");
+		let eval = || {
+			let command = Command::Eval {
+				file: file.clone(),
+				max_call_depth: 1000,
+				trace: false,
+				trace_log: None,
+				coverage: false,
+			};
+			run(command, "")
+		};
+		let fmt = || {
+			let command = Command::Fmt {
+				file: file.clone(),
+				style: FmtStyle::default(),
+				recover_best_effort: false,
+				verify: true,
+			};
+			run(command, "")
+		};
+		let compile = |emit: &str| {
+			run(Command::Compile { file: file.clone(), emit: emit.to_owned(), lib: false, sanitize: Vec::new() }, "")
+		};
+
+		// Code nested as deep as the parser allows passes the analyzer, the interpreter and the emitters
+		let depth = DEFAULT_MAX_NESTING - 1;
+		let blocks = format!(
+			"def blocks(): int {{\n{}return 1\n{}return 0\n}}",
+			"if 1 {\n".repeat(depth - 1),
+			"}\n".repeat(depth - 1)
+		);
+		let chain = format!("def chain(): int {{\n\treturn {}\n}}", vec!["1"; depth + 1].join(" + "));
+		let calls = format!("def calls(): int {{\n\treturn {}42{}\n}}", "id(".repeat(depth - 1), ")".repeat(depth - 1));
+		let main =
+			"def id(n: int): int {\n\treturn n\n}\n\ndef main(): int {\n\treturn blocks() + chain() + calls()\n}";
+		fs::write(&file, [blocks, chain, calls, main.to_owned()].join("\n\n")).unwrap();
+		assert_eq!(eval().0, 1 + 128 + 42);
+		assert_eq!(fmt().0, 0);
+		for emit in ["c", "py", "ftl"] {
+			assert_eq!(compile(emit).0, 0, "{}", emit);
+		}
+
+		// Deeper code is rejected before the passes after the parser could overflow the stack
+		let blocks = format!("def main(): int {{\n{}{}return 0\n}}", "if 1 {\n".repeat(5000), "}\n".repeat(5000));
+		let chain = format!("def main(): int {{\n\treturn {}\n}}", vec!["1"; 20_000].join(" + "));
+		for source_code in [blocks, chain] {
+			fs::write(&file, source_code).unwrap();
+			for (exit_code, _, stderr) in [eval(), fmt(), compile("c")] {
+				assert_eq!(exit_code, 1);
+				assert!(stderr.contains("error[TooDeeplyNested]"), "{}", stderr);
+			}
+		}
+	}
+
+	#[test]
+	fn test_error() {
+		let directory = TempDir::new().unwrap();
+		let file = directory.path().join("main.ftl");
+		fs::write(&file, "def main(): int {\n\treturn (\n}").unwrap();
+
+		let (exit_code, stdout, stderr) = run(Command::Lint { files: vec![file] }, "");
+		assert_eq!((exit_code, stdout.as_str()), (1, ""));
+		assert!(stderr.contains("main.ftl:3:1"), "{}", stderr);
+
+		let (exit_code, _, stderr) = run(Command::Lint { files: vec![directory.path().join("missing.ftl")] }, "");
+		assert_eq!(exit_code, 1);
+		assert!(stderr.contains("Reading FTL source file"), "{}", stderr);
+
+		let build = Command::Build { files: Vec::new(), output: None, build_dir: directory.path().to_owned() };
+		let (exit_code, _, stderr) = run(build, "");
+		assert_eq!(exit_code, 1);
+		assert!(stderr.contains("No files to build"), "{}", stderr);
+	}
+
+	#[test]
+	fn test_refs() {
+		let directory = TempDir::new().unwrap();
+		let file = directory.path().join("main.ftl");
+		fs::write(&file, "def square(a: int): int {\n\treturn a * a\n}\n\ndef main(): int {\n\treturn square(2)\n}")
+			.unwrap();
+		let index = directory.path().join("build/symbols.index");
+		let refs = |name: &str| {
+			run(Command::Refs { name: name.to_owned(), files: vec![file.clone()], index: index.clone() }, "")
+		};
+
+		let (exit_code, stdout, _) = refs("square");
+		assert_eq!(exit_code, 0);
+		let path = file.display();
+		assert_eq!(stdout, format!("{}:1:5: definition of function `square`\n{}:6:9: use of `square`\n", path, path));
+		assert!(fs::read_to_string(&index).unwrap().starts_with("ftl-index 1\n"));
+		assert_eq!(refs("missing"), (1, String::new(), String::new()));
+	}
+
+	#[test]
+	fn test_deps() {
+		let directory = TempDir::new().unwrap();
+		let main = directory.path().join("main.ftl");
+		let math = directory.path().join("math.ftl");
+		fs::write(&main, "def main(): int {\n\treturn square(2)\n}").unwrap();
+		fs::write(&math, "export def square(a: int): int {\n\treturn a * a\n}").unwrap();
+		let index = directory.path().join("build/symbols.index");
+		let deps =
+			|format| run(Command::Deps { files: vec![main.clone(), math.clone()], format, index: index.clone() }, "");
+
+		let (exit_code, stdout, _) = deps(deps::Format::Text);
+		assert_eq!((exit_code, stdout), (0, format!("{} -> {}: square\n", main.display(), math.display())));
+		let (_, stdout, _) = deps(deps::Format::Dot);
+		assert!(stdout.starts_with("digraph dependencies {\n"), "{}", stdout);
+
+		// A use of `main` closes a cycle
+		fs::write(&math, "export def square(a: int): int {\n\treturn a * main()\n}").unwrap();
+		let (exit_code, stdout, _) = deps(deps::Format::Text);
+		assert_eq!(exit_code, 1);
+		assert!(stdout.ends_with(&format!("cycle: {}, {}\n", main.display(), math.display())), "{}", stdout);
+	}
+
+	#[test]
+	fn test_examples() {
+		let (exit_code, stdout, _) = run(Command::Examples { name: None }, "");
+		assert_eq!(exit_code, 0);
+		assert!(stdout.starts_with("fib        Computes the 10th Fibonacci number"), "{}", stdout);
+
+		let (exit_code, stdout, _) = run(Command::Examples { name: Some("structs".to_owned()) }, "");
+		assert_eq!((exit_code, stdout.as_str()), (0, "Computing the area\nExited with code 42\n"));
+
+		let (exit_code, _, stderr) = run(Command::Examples { name: Some("missing".to_owned()) }, "");
+		assert_eq!(
+			(exit_code, stderr.as_str()),
+			(1, "Unknown example `missing`, available examples are: fib, primes, structs\n")
+		);
+	}
+
+	#[test]
+	fn test_new() {
+		let directory = TempDir::new().unwrap();
+		let project = directory.path().join("hello");
+		let (exit_code, _, stderr) = run(Command::New { directory: project.clone() }, "");
+		assert_eq!((exit_code, stderr.as_str()), (0, ""));
+		assert!(project.join("src/main.ftl").exists());
+
+		// Existing directories are not overwritten
+		assert_eq!(run(Command::New { directory: project }, "").0, 1);
+	}
+
+	#[test]
+	fn test_completions_and_man() {
+		let cli = clap::Command::new("ftl").about("FTL compiler").subcommand(clap::Command::new("fmt"));
+
+		let command = Command::Completions { shell: clap_complete::Shell::Bash, cli: Box::new(cli.clone()) };
+		let (exit_code, stdout, _) = run(command, "");
+		assert_eq!(exit_code, 0);
+		assert!(stdout.contains("complete -F _ftl"), "{}", stdout);
+		assert!(stdout.contains("fmt"), "{}", stdout);
+		// Writing to a full stdout fails
+		let (mut full, mut stderr) = ([0; 16], Vec::new());
+		let command = Command::Completions { shell: clap_complete::Shell::Bash, cli: Box::new(cli.clone()) };
+		let exit_code =
+			Driver::new(Options::default()).with_stdout(&mut full[..]).with_stderr(&mut stderr).run(command);
+		assert_eq!(exit_code, 1);
+		assert!(!stderr.is_empty());
+
+		let (exit_code, stdout, _) = run(Command::Man { cli: Box::new(cli) }, "");
+		assert_eq!(exit_code, 0);
+		assert!(stdout.contains(".TH ftl 1"), "{}", stdout);
+	}
+
+	#[test]
+	fn test_repl() {
+		let (exit_code, stdout, stderr) = run(Command::Repl, "(1 + 2)\ndef f(): int {\nreturn 4\n}\nf()\n");
+		assert_eq!((exit_code, stderr.as_str()), (0, ""));
+		assert_eq!(stdout, "ftl> 3\nftl> ...> ...> ftl> 4\nftl> ");
+	}
+}
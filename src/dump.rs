@@ -0,0 +1,160 @@
+//! Writing intermediate artifacts of the compiler pipeline to files for debugging.
+//!
+//! Enable dumping via the `--dump` flag of the command line interface, e.g. `--dump tokens,ast`.
+
+use std::{
+	borrow::Cow,
+	fmt, fs, io,
+	path::{Component, Path, PathBuf},
+	str::FromStr,
+};
+
+/// An intermediate artifact of the compiler pipeline that can be dumped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Artifact {
+	/// The [`Token`](crate::token::Token)s produced by the [lexer](crate::lexer).
+	Tokens,
+	/// The [AST nodes](crate::ast::Node) produced by the [parser](crate::parser).
+	Ast,
+	/// The [`SymbolTable`](crate::semantic_analyzer::SymbolTable) of the semantic analysis.
+	Symbols,
+	/// The inferred types of the expressions of the [`TypedProgram`](crate::semantic_analyzer::TypedProgram).
+	TypedAst,
+	/// The generated C code.
+	C,
+}
+
+impl Artifact {
+	/// File extension of the dump file for this artifact.
+	fn extension(&self) -> &'static str {
+		match self {
+			Artifact::Tokens => "tokens.txt",
+			Artifact::Ast => "ast.txt",
+			Artifact::Symbols => "symbols.txt",
+			Artifact::TypedAst => "typed-ast.txt",
+			Artifact::C => "c",
+		}
+	}
+}
+
+impl FromStr for Artifact {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"tokens" => Ok(Artifact::Tokens),
+			"ast" => Ok(Artifact::Ast),
+			"symbols" => Ok(Artifact::Symbols),
+			"typed-ast" => Ok(Artifact::TypedAst),
+			"c" => Ok(Artifact::C),
+			other => Err(format!("unknown artifact `{}`, expected one of tokens, ast, symbols, typed-ast, c", other)),
+		}
+	}
+}
+
+impl fmt::Display for Artifact {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Artifact::Tokens => write!(f, "tokens"),
+			Artifact::Ast => write!(f, "ast"),
+			Artifact::Symbols => write!(f, "symbols"),
+			Artifact::TypedAst => write!(f, "typed-ast"),
+			Artifact::C => write!(f, "c"),
+		}
+	}
+}
+
+/// Writes selected [`Artifact`]s into a dump directory.
+///
+/// The [default](Self::default) dumper does not dump anything.
+#[derive(Debug, Clone, Default)]
+pub struct Dumper {
+	/// Directory in which the dump files are created.
+	directory: PathBuf,
+	/// Which artifacts to dump.
+	artifacts: Vec<Artifact>,
+}
+
+impl Dumper {
+	/// Creates a [`Dumper`] writing the given `artifacts` to files in `directory`.
+	pub fn new(directory: PathBuf, artifacts: Vec<Artifact>) -> Self {
+		Self { directory, artifacts }
+	}
+
+	/// Checks whether the `artifact` should be dumped.
+	pub fn is_enabled(&self, artifact: Artifact) -> bool {
+		self.artifacts.contains(&artifact)
+	}
+
+	/// Writes the `content` of the `artifact` produced for the FTL source file at `source_path` into the dump directory.
+	///
+	/// `content` is only evaluated if the artifact [is enabled](Self::is_enabled).
+	pub fn dump(&self, artifact: Artifact, source_path: &Path, content: impl FnOnce() -> String) -> io::Result<()> {
+		if !self.is_enabled(artifact) {
+			return Ok(());
+		}
+		fs::create_dir_all(&self.directory)?;
+		let dump_path = self.directory.join(format!("{}.{}", dump_name(source_path), artifact.extension()));
+		tracing::info!(%artifact, path = ?dump_path, "dumping artifact");
+		fs::write(dump_path, content())
+	}
+}
+
+/// Name of the dump files of the FTL source file at `source_path`. It is the path relative to the current directory
+/// with `_` instead of separators, so that files of the same name in different directories don't share dump files.
+fn dump_name(source_path: &Path) -> String {
+	let relative =
+		std::env::current_dir().ok().and_then(|dir| source_path.strip_prefix(dir).ok()).unwrap_or(source_path);
+	let components: Vec<_> = relative
+		.components()
+		.filter_map(|component| match component {
+			Component::Normal(name) => Some(name.to_string_lossy()),
+			Component::ParentDir => Some(Cow::Borrowed("..")),
+			Component::Prefix(_) | Component::RootDir | Component::CurDir => None,
+		})
+		.collect();
+	components.join("_")
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{semantic_analyzer::CheckConfig, source::Source, test_support::TempDir};
+
+	#[test]
+	fn test_dump() {
+		let directory = std::env::temp_dir().join(format!("ftl-dump-test-{}", std::process::id()));
+		let dumper = Dumper::new(directory.clone(), vec![Artifact::Ast]);
+
+		dumper.dump(Artifact::Ast, Path::new("examples/main.ftl"), || "ast".to_owned()).unwrap();
+		dumper.dump(Artifact::Tokens, Path::new("examples/main.ftl"), || unreachable!()).unwrap();
+
+		assert_eq!(fs::read_to_string(directory.join("examples_main.ftl.ast.txt")).unwrap(), "ast");
+		assert!(!directory.join("examples_main.ftl.tokens.txt").exists());
+		fs::remove_dir_all(directory).unwrap();
+	}
+
+	#[test]
+	fn test_dump_names() {
+		assert_eq!(dump_name(Path::new("a/main.ftl")), "a_main.ftl");
+		assert_eq!(dump_name(Path::new("b/main.ftl")), "b_main.ftl");
+		assert_eq!(dump_name(Path::new("./main.ftlx")), "main.ftlx");
+		assert_eq!(dump_name(Path::new("../main.ftl")), ".._main.ftl");
+		assert_eq!(dump_name(&std::env::current_dir().unwrap().join("src/main.ftl")), "src_main.ftl");
+	}
+
+	#[test]
+	fn test_dump_typed_ast() {
+		let directory = TempDir::new().unwrap();
+		let dumper = Dumper::new(directory.path().to_owned(), vec![Artifact::TypedAst]);
+		let source =
+			Arc::new(Source::new("main.ftl".to_owned(), "def f(x: float): float {\n\treturn x * 2.0\n}".to_owned()));
+		let ast_nodes = crate::parse_source(source, &dumper).unwrap();
+		crate::analyze(Path::new("main.ftl"), ast_nodes.iter(), &CheckConfig::default(), &dumper).unwrap();
+
+		let dump = fs::read_to_string(directory.path().join("main.ftl.typed-ast.txt")).unwrap();
+		assert_eq!(dump, "main.ftl:2:9: `x * 2.0`: float\nmain.ftl:2:9: `x`: float\nmain.ftl:2:13: `2.0`: float\n");
+	}
+}
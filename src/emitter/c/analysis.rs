@@ -0,0 +1,136 @@
+//! What the C emitter needs to know from the semantic analysis.
+//!
+//! Optional types are represented like their inner type, with a sentinel value for `null`: `NULL` for strings and
+//! pointers, `INT64_MIN` for `int` and `NAN` for `float`. Since `NAN` is not equal to itself, comparisons of floats
+//! with `null` use `isnan()`.
+//!
+//! Strings are concatenated with `ftl_str_concat()` and compared with `strcmp()`.
+//!
+//! Structs passed to `ptr` arguments are passed by reference with `&`, and fields behind pointers are accessed with
+//! `->`.
+//!
+//! Self-recursive tail calls assign the new arguments and jump back to the start of the function with `goto`, so that
+//! deep recursion doesn't overflow the stack, regardless of the optimizations of the C compiler.
+//!
+//! Values of `dump` are written by a function for their type, which needs the exact type including optionals.
+//!
+//! Functions that can fail return a status code, which is non-zero if they failed, and store their result through an
+//! additional pointer argument. `try` declares a variable of the result type for it.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+	ast,
+	ast::statement::{BasicDataType, DataType},
+	semantic_analyzer::{self, CheckConfig, TypedProgram},
+	source::SourcePositionRange,
+	types::{Type, TypeId, TypeTable},
+};
+
+/// Types of expressions whose C code depends on them.
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+	/// The C values of the `null`s, which depend on the optional type that is expected where they are used.
+	null_sentinels: HashMap<SourcePositionRange, &'static str>,
+	/// The result types of the calls of functions that can fail, by the position of the call.
+	result_types: HashMap<SourcePositionRange, DataType>,
+	/// The positions of the structs passed by reference.
+	references: HashSet<SourcePositionRange>,
+	/// The positions of the expressions of pointer types.
+	pointers: HashSet<SourcePositionRange>,
+	/// The positions of the expressions of type `str`.
+	strings: HashSet<SourcePositionRange>,
+	/// The positions of the self-recursive tail calls.
+	tail_calls: HashSet<SourcePositionRange>,
+	/// The types of all expressions, which [`Self::data_type`] looks up.
+	types: TypeTable,
+	expression_types: HashMap<SourcePositionRange, TypeId>,
+}
+
+impl Analysis {
+	/// Collects the types of the expressions of the `program`.
+	pub fn new(program: &TypedProgram) -> Self {
+		let mut analysis = Self {
+			references: program.references.clone(),
+			tail_calls: program.tail_calls.clone(),
+			types: program.types.clone(),
+			expression_types: program.expression_types.clone(),
+			..Self::default()
+		};
+		for (position, type_) in &program.expression_types {
+			if let Some(inner) = program.types.optional_inner(*type_) {
+				let sentinel = match program.types.get(inner) {
+					Type::Basic(BasicDataType::Int) => "INT64_MIN",
+					Type::Basic(BasicDataType::Float) => "NAN",
+					_ => "NULL",
+				};
+				analysis.null_sentinels.insert(position.clone(), sentinel);
+			}
+			if let Some(inner) = program.types.fallible_inner(*type_) {
+				analysis.result_types.insert(position.clone(), program.types.data_type(inner));
+			}
+			if program.types.pointee(*type_).is_some() {
+				analysis.pointers.insert(position.clone());
+			}
+			if *type_ == TypeId::STR {
+				analysis.strings.insert(position.clone());
+			}
+		}
+		analysis
+	}
+
+	/// Analyzes the `ast_nodes` to collect their types. Programs that don't pass the analysis have none.
+	pub fn analyze<'a>(ast_nodes: impl Iterator<Item = &'a ast::Node> + Clone) -> Self {
+		semantic_analyzer::analyze(ast_nodes, &CheckConfig::default())
+			.map_or_else(|_| Self::default(), |p| Self::new(&p))
+	}
+
+	/// The C value of the `null` at the `position`.
+	pub fn null_sentinel(&self, position: &SourcePositionRange) -> &'static str {
+		self.null_sentinels.get(position).copied().unwrap_or("0")
+	}
+
+	/// The type of the result of the call at the `position`, if the called function can fail.
+	pub fn result_type(&self, position: &SourcePositionRange) -> Option<&DataType> {
+		self.result_types.get(position)
+	}
+
+	/// The type of the expression at the `position`.
+	pub fn data_type(&self, position: &SourcePositionRange) -> Option<DataType> {
+		self.expression_types.get(position).map(|type_| self.types.data_type(*type_))
+	}
+
+	/// Whether the struct at the `position` is passed by reference to a `ptr` argument.
+	pub fn is_reference(&self, position: &SourcePositionRange) -> bool {
+		self.references.contains(position)
+	}
+
+	/// Whether the expression at the `position` is a pointer.
+	pub fn is_pointer(&self, position: &SourcePositionRange) -> bool {
+		self.pointers.contains(position)
+	}
+
+	/// Whether the expression at the `position` is a string.
+	pub fn is_string(&self, position: &SourcePositionRange) -> bool {
+		self.strings.contains(position)
+	}
+
+	/// Whether the call at the `position` is a self-recursive tail call.
+	pub fn is_tail_call(&self, position: &SourcePositionRange) -> bool {
+		self.tail_calls.contains(position)
+	}
+
+	/// Whether the `instructions` contain a self-recursive tail call, also in nested blocks.
+	pub fn contains_tail_call(&self, instructions: &[ast::Instruction]) -> bool {
+		instructions.iter().any(|instruction| match instruction {
+			ast::Instruction::Statement(ast::Statement::Return(value)) => {
+				value.value.as_ref().is_some_and(|value| self.is_tail_call(&value.source_position()))
+			},
+			ast::Instruction::IfElse(if_else) => {
+				self.contains_tail_call(&if_else.if_true) || self.contains_tail_call(&if_else.if_false)
+			},
+			ast::Instruction::WhileLoop(while_loop) => self.contains_tail_call(&while_loop.body),
+			ast::Instruction::Expression(_) | ast::Instruction::Statement(_) | ast::Instruction::Invalid(_) => false,
+		})
+	}
+}
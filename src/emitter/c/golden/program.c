@@ -0,0 +1,69 @@
+
+typedef struct Node Node;
+static inline void ftl_dump_ptr_Node(Node* value);
+
+struct Node {
+	int64_t value;
+	Node* next;
+};
+
+static inline Node ftl_default_Node(void) {
+	Node value = {.value = 1, .next = 0};
+	return value;
+}
+
+static inline void ftl_dump_Node(Node value) {
+	ftl_print("Node { value: ");
+	ftl_dump_int(value.value);
+	ftl_print(", next: ");
+	ftl_dump_ptr_Node(value.next);
+	ftl_print(" }");
+}
+
+static inline void ftl_dump_ptr_Node(Node* value) {
+	if (value == NULL) {
+		ftl_print("null");
+		return;
+	}
+	ftl_dump_Node(*value);
+}
+
+static int64_t ftl_fn_countdown(int64_t n);
+static int64_t ftl_fn_sign(double x);
+static void ftl_fn_greet();
+static int64_t ftl_fn_add(int64_t a, int64_t b);
+int main();
+
+static int64_t ftl_fn_countdown(int64_t n) {
+	int64_t steps = 0;
+	while (0 < n) {
+		n = n - 1;
+		steps = steps + 1;
+	}
+	return steps;
+}
+
+static int64_t ftl_fn_sign(double x) {
+	if (x < 0.0) {
+		return 0 - 1;
+	} else {
+		if (x == 0.0) {
+			return 0;
+		}
+	}
+	return 1;
+}
+
+static void ftl_fn_greet() {
+	puts("Hello, \"World\"\n");
+	return;
+}
+
+static int64_t ftl_fn_add(int64_t a, int64_t b) {
+	return a + b;
+}
+
+int main() {
+	ftl_fn_greet();
+	return ftl_fn_add(ftl_fn_countdown(3), ftl_intrinsic_max(1, 2));
+}
@@ -0,0 +1,41 @@
+# Covers the layout of all kinds of nodes
+struct Node {
+	value: int = 1
+	next: ptr Node
+}
+
+extern puts(s: str): int
+
+def countdown(n: int): int {
+	var steps: int = 0
+	while 0 < n {
+		n = n - 1
+		steps = steps + 1
+	}
+	return steps
+}
+
+def sign(x: float): int {
+	if x < 0.0 {
+		return 0 - 1
+	} else {
+		if x = 0.0 {
+			return 0
+		}
+	}
+	return 1
+}
+
+def greet() {
+	puts("Hello, \"World\"\n")
+	return
+}
+
+def add(a: int, b: int): int {
+	return a + b
+}
+
+def main(): int {
+	greet()
+	return add(countdown(3), max(1, 2))
+}
@@ -0,0 +1,137 @@
+//! C header emitter for FTL libraries.
+
+use std::io;
+
+use super::{Analysis, Codegen, Mangling, PRELUDE};
+use crate::{ast, emitter};
+
+/// Emits a C header containing the struct definitions and function prototypes of everything marked with `export`.
+///
+/// This allows C code to call into object files compiled from FTL code. The header starts with the prelude, since the
+/// writers of the structs for `dump` use the runtime, whose include guard allows including it more than once.
+pub struct Emitter;
+
+impl crate::emitter::Emitter for Emitter {
+	fn file_extension(&self) -> &'static str {
+		"h"
+	}
+
+	fn codegen(&self, ast_nodes: &mut dyn Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()> {
+		let ast_nodes: Vec<ast::Node> = ast_nodes.collect();
+		let mut this = Codegen::new(writer, Mangling::new(ast_nodes.iter()), Analysis::analyze(ast_nodes.iter()));
+
+		writeln!(this.writer, "#pragma once")?;
+		write!(this.writer, "{}", PRELUDE)?;
+
+		let mut structs = Vec::new();
+		let mut prototypes = Vec::new();
+		for ast_node in ast_nodes {
+			match ast_node {
+				ast::Node::Struct(struct_) if struct_.exported => structs.push(struct_),
+				ast::Node::Function(function) if function.prototype.exported => prototypes.push(function.prototype),
+				_ => (),
+			}
+		}
+		this.declarations(structs, prototypes.iter())
+	}
+}
+
+/// Emits the header shared by all translation units of a [multi-unit build](crate::build).
+///
+/// It contains the prelude, so that it is only emitted once, as well as the struct definitions of all units and the
+/// prototypes of their functions that aren't `static`, so that each unit can use the exported functions of the
+/// others.
+pub struct SharedEmitter;
+
+impl crate::emitter::Emitter for SharedEmitter {
+	fn file_extension(&self) -> &'static str {
+		"h"
+	}
+
+	fn codegen(&self, ast_nodes: &mut dyn Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()> {
+		let ast_nodes: Vec<ast::Node> = ast_nodes.collect();
+		let mut this = Codegen::new(writer, Mangling::new(ast_nodes.iter()), Analysis::analyze(ast_nodes.iter()));
+
+		writeln!(this.writer, "#pragma once")?;
+		write!(this.writer, "{}", PRELUDE)?;
+
+		let mut structs = Vec::new();
+		let mut prototypes = Vec::new();
+		for ast_node in ast_nodes {
+			match ast_node {
+				ast::Node::Struct(struct_) => structs.push(struct_),
+				// Each unit declares its static functions itself
+				ast::Node::Function(function) if this.mangling.is_static(&function.prototype.name) => (),
+				ast::Node::Function(function) => prototypes.push(function.prototype),
+				ast::Node::FunctionPrototype(_) | ast::Node::Comment(_) => (), // extern function
+				ast::Node::Invalid(position) => return Err(emitter::invalid_code(&position)),
+			}
+		}
+		this.declarations(structs, prototypes.iter())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, process, sync::Arc};
+
+	use super::*;
+	use crate::{
+		build, emitter::codegen_to_string, lexer::Lexer, parser::Parser, runtime, source::Source, test_support::TempDir,
+	};
+
+	const LIBRARY: &str =
+		"export struct Point {\n\tx: int\n}\nstruct Hidden {\n\ty: int\n}\nexport def square(a: int): int \
+	                       {\n\treturn a * a\n}\ndef helper(): int {\n\treturn 1\n}";
+
+	fn parse(source_code: &str) -> Vec<ast::Node> {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap()
+	}
+
+	#[test]
+	fn test_only_exported() {
+		let ast_nodes = parse(LIBRARY);
+		let output = codegen_to_string(&Emitter, &mut ast_nodes.into_iter()).unwrap();
+
+		assert!(output.starts_with("#pragma once\n"));
+		assert!(
+			output.contains(
+				"typedef struct Point Point;\nstatic inline void ftl_dump_ptr_Point(Point* value);\n\nstruct Point {"
+			),
+			"{}",
+			output
+		);
+		assert!(output.contains("int64_t square(int64_t a);"), "{}", output);
+		assert!(!output.contains("Hidden"), "{}", output);
+		assert!(!output.contains("helper"), "{}", output);
+	}
+
+	#[test]
+	fn test_include() {
+		let directory = TempDir::new().unwrap();
+		let ast_nodes = parse(LIBRARY);
+		let header = codegen_to_string(&Emitter, &mut ast_nodes.clone().into_iter()).unwrap();
+		fs::write(directory.path().join("lib.h"), header).unwrap();
+		let library = codegen_to_string(&crate::emitter::C::default(), &mut ast_nodes.into_iter()).unwrap();
+		fs::write(directory.path().join("lib.c"), library).unwrap();
+		let main = "#include \"lib.h\"\n\nint main(void) {\n\tPoint point = ftl_default_Point();\n\tpoint.x = \
+		            square(3);\n\tftl_dump_Point(point);\n\treturn 0;\n}\n";
+		fs::write(directory.path().join("main.c"), main).unwrap();
+
+		let executable = directory.path().join("main");
+		build::run_c_compiler(
+			process::Command::new("cc")
+				.args(["main.c", "lib.c"])
+				.arg(runtime::compile(directory.path()).unwrap())
+				.args(runtime::LINK_ARGS)
+				.arg("-o")
+				.arg(&executable)
+				.current_dir(directory.path()),
+		)
+		.unwrap();
+		let output = process::Command::new(&executable).output().unwrap();
+		assert_eq!(String::from_utf8(output.stdout).unwrap(), "Point { x: 9 }");
+	}
+}
@@ -0,0 +1,352 @@
+//! Names of FTL functions and identifiers in the generated C code.
+//!
+//! Functions defined in FTL get the prefix `ftl_fn_`, so that they can't collide with C keywords, the C standard
+//! library or the runtime. Functions that are called from C keep their name: `main`, functions marked with `export`
+//! and functions annotated with `@no_mangle`. Other identifiers, e.g. variables and fields, only get the prefix
+//! `ftl_id_` if they are C keywords or names of the C standard library that they would collide with, e.g. `NULL`.
+//!
+//! Mangled functions are also `static`, since they can only be called from FTL code of the same file.
+
+use std::{borrow::Cow, collections::HashSet};
+
+use crate::{ast, intrinsic::Intrinsic, runtime};
+
+/// Keywords of C, including those of C23, which can't be used as identifiers.
+const C_KEYWORDS: &[&str] = &[
+	"alignas",
+	"alignof",
+	"auto",
+	"bool",
+	"break",
+	"case",
+	"char",
+	"const",
+	"constexpr",
+	"continue",
+	"default",
+	"do",
+	"double",
+	"else",
+	"enum",
+	"extern",
+	"false",
+	"float",
+	"for",
+	"goto",
+	"if",
+	"inline",
+	"int",
+	"long",
+	"nullptr",
+	"register",
+	"restrict",
+	"return",
+	"short",
+	"signed",
+	"sizeof",
+	"static",
+	"static_assert",
+	"struct",
+	"switch",
+	"thread_local",
+	"true",
+	"typedef",
+	"typeof",
+	"typeof_unqual",
+	"union",
+	"unsigned",
+	"void",
+	"volatile",
+	"while",
+	"_Alignas",
+	"_Alignof",
+	"_Atomic",
+	"_Bool",
+	"_Complex",
+	"_Generic",
+	"_Imaginary",
+	"_Noreturn",
+	"_Static_assert",
+	"_Thread_local",
+];
+
+/// Macros and types of the headers included by the runtime header, which identifiers can't be named like, and the
+/// functions of the C standard library that the generated code calls, which variables must not hide.
+const C_LIBRARY_NAMES: &[&str] = &[
+	"BUFSIZ",
+	"EOF",
+	"EXIT_FAILURE",
+	"EXIT_SUCCESS",
+	"FILENAME_MAX",
+	"FOPEN_MAX",
+	"L_tmpnam",
+	"MB_CUR_MAX",
+	"NULL",
+	"RAND_MAX",
+	"SEEK_CUR",
+	"SEEK_END",
+	"SEEK_SET",
+	"TMP_MAX",
+	"_IOFBF",
+	"_IOLBF",
+	"_IONBF",
+	"stderr",
+	"stdin",
+	"stdout",
+	"CHAR_BIT",
+	"CHAR_MAX",
+	"CHAR_MIN",
+	"INT_MAX",
+	"INT_MIN",
+	"LLONG_MAX",
+	"LLONG_MIN",
+	"LONG_MAX",
+	"LONG_MIN",
+	"MB_LEN_MAX",
+	"SCHAR_MAX",
+	"SCHAR_MIN",
+	"SHRT_MAX",
+	"SHRT_MIN",
+	"UCHAR_MAX",
+	"UINT_MAX",
+	"ULLONG_MAX",
+	"ULONG_MAX",
+	"USHRT_MAX",
+	"INT8_MAX",
+	"INT8_MIN",
+	"INT16_MAX",
+	"INT16_MIN",
+	"INT32_MAX",
+	"INT32_MIN",
+	"INT64_MAX",
+	"INT64_MIN",
+	"INTMAX_MAX",
+	"INTMAX_MIN",
+	"INTPTR_MAX",
+	"INTPTR_MIN",
+	"PTRDIFF_MAX",
+	"PTRDIFF_MIN",
+	"SIZE_MAX",
+	"UINT8_MAX",
+	"UINT16_MAX",
+	"UINT32_MAX",
+	"UINT64_MAX",
+	"UINTMAX_MAX",
+	"UINTPTR_MAX",
+	"FP_INFINITE",
+	"FP_NAN",
+	"FP_NORMAL",
+	"FP_SUBNORMAL",
+	"FP_ZERO",
+	"HUGE_VAL",
+	"HUGE_VALF",
+	"HUGE_VALL",
+	"INFINITY",
+	"MATH_ERREXCEPT",
+	"MATH_ERRNO",
+	"M_1_PI",
+	"M_2_PI",
+	"M_2_SQRTPI",
+	"M_E",
+	"M_LN10",
+	"M_LN2",
+	"M_LOG10E",
+	"M_LOG2E",
+	"M_PI",
+	"M_PI_2",
+	"M_PI_4",
+	"M_SQRT1_2",
+	"M_SQRT2",
+	"NAN",
+	"math_errhandling",
+	"FILE",
+	"div_t",
+	"double_t",
+	"float_t",
+	"fpos_t",
+	"imaxdiv_t",
+	"int8_t",
+	"int16_t",
+	"int32_t",
+	"int64_t",
+	"intmax_t",
+	"intptr_t",
+	"ldiv_t",
+	"lldiv_t",
+	"ptrdiff_t",
+	"size_t",
+	"uint8_t",
+	"uint16_t",
+	"uint32_t",
+	"uint64_t",
+	"uintmax_t",
+	"uintptr_t",
+	"wchar_t",
+	"fabs",
+	"fmax",
+	"fmin",
+	"isinf",
+	"isnan",
+	"pow",
+	"sqrt",
+	"strcmp",
+];
+
+/// Macros that C compilers predefine in their default GNU mode, depending on the platform, e.g. `linux` as `1`.
+const C_PLATFORM_MACROS: &[&str] = &["i386", "linux", "mips", "sparc", "sun", "unix", "vax"];
+
+/// Prefixes of further names of the headers and of those the runtime and the generated code use, e.g. `PRId64` and
+/// `ftl_result`.
+const C_RESERVED_PREFIXES: &[&str] = &["ftl_", "PRI", "SCN"];
+
+/// Knows which functions of a program are mangled.
+///
+/// It has to be created from all AST nodes of the program, since calls refer to functions of other files in a
+/// [multi-unit build](crate::build).
+#[derive(Debug, Clone, Default)]
+pub struct Mangling {
+	/// Names of the functions defined in FTL that get the prefix `ftl_fn_`.
+	mangled_functions: HashSet<String>,
+	/// Names of the structs, which are constructed by calling them like `Point()`.
+	structs: HashSet<String>,
+}
+
+impl Mangling {
+	/// Determines the mangled functions of the program consisting of the `ast_nodes`.
+	pub fn new<'a>(ast_nodes: impl Iterator<Item = &'a ast::Node>) -> Self {
+		let mut mangling = Self::default();
+		for node in ast_nodes {
+			match node {
+				ast::Node::Function(function) if is_mangled(function) => {
+					mangling.mangled_functions.insert(function.prototype.name.value.clone());
+				},
+				ast::Node::Struct(struct_) => {
+					mangling.structs.insert(struct_.name.value.clone());
+				},
+				_ => (),
+			}
+		}
+		mangling
+	}
+
+	/// Whether the function `name` is defined in FTL and mangled, and therefore `static`.
+	pub fn is_static(&self, name: &str) -> bool {
+		self.mangled_functions.contains(name)
+	}
+
+	/// Returns the C name of the function `name`, for its definition as well as for calls.
+	pub fn function<'a>(&self, name: &'a str) -> Cow<'a, str> {
+		if self.mangled_functions.contains(name) {
+			Cow::Owned(format!("ftl_fn_{}", name))
+		} else if self.structs.contains(name) {
+			Cow::Owned(format!("ftl_default_{}", identifier(name)))
+		} else if Intrinsic::from_name(name).is_some() {
+			// The macros in the runtime header select the overload based on the parameter types
+			Cow::Owned(format!("ftl_intrinsic_{}", name))
+		} else {
+			// Runtime or extern function
+			Cow::Borrowed(runtime::c_name(name))
+		}
+	}
+}
+
+/// Whether the function gets a mangled name, i.e. is not meant to be called from C.
+fn is_mangled(function: &ast::FunctionDefinition) -> bool {
+	let no_mangle = function.annotations.iter().any(|annotation| annotation.as_str() == "no_mangle");
+	function.prototype.name.as_str() != "main" && !function.prototype.exported && !no_mangle
+}
+
+/// Returns the C name of a variable, argument, struct or field `name`.
+pub fn identifier(name: &str) -> Cow<'_, str> {
+	let reserved = C_KEYWORDS.contains(&name)
+		|| C_LIBRARY_NAMES.contains(&name)
+		|| C_PLATFORM_MACROS.contains(&name)
+		|| C_RESERVED_PREFIXES.iter().any(|prefix| name.starts_with(prefix));
+	if reserved {
+		Cow::Owned(format!("ftl_id_{}", name))
+	} else {
+		Cow::Borrowed(name)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		differential::{assert_consistent, Outcome},
+		lexer::Lexer,
+		parser::Parser,
+		source::Source,
+	};
+
+	#[test]
+	fn test_function_names() {
+		let source = Arc::new(Source::new(
+			"test.ftl".to_owned(),
+			"def printf(): int {\n\treturn 1\n}\nexport def api(): int {\n\treturn 2\n}\n@no_mangle\ndef callback(): int \
+			 {\n\treturn 3\n}\ndef main(): int {\n\treturn 0\n}\nstruct int { }"
+				.to_owned(),
+		));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let ast_nodes = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let mangling = Mangling::new(ast_nodes.iter());
+
+		assert_eq!(mangling.function("printf"), "ftl_fn_printf");
+		assert_eq!(mangling.function("api"), "api");
+		assert_eq!(mangling.function("callback"), "callback");
+		assert_eq!(mangling.function("main"), "main");
+		assert_eq!(mangling.function("sqrt"), "ftl_intrinsic_sqrt");
+		assert_eq!(mangling.function("open"), "ftl_open");
+		assert_eq!(mangling.function("int"), "ftl_default_ftl_id_int");
+		assert_eq!(identifier("int"), "ftl_id_int");
+		assert_eq!(identifier("NULL"), "ftl_id_NULL");
+		assert_eq!(identifier("linux"), "ftl_id_linux");
+		assert_eq!(identifier("ftl_result"), "ftl_id_ftl_result");
+		assert_eq!(identifier("x"), "x");
+	}
+
+	#[test]
+	fn test_c_names() {
+		let outcome = assert_consistent(
+			"def printf(double: int): int {
+				return double * 2
+			}
+			def abort(): int {
+				return 1
+			}
+			def main(): int {
+				var long: int = printf(abort())
+				return long
+			}",
+		);
+		assert_eq!(outcome.exit_code, 2);
+
+		// Names of macros, types and functions of the C standard library
+		let outcome = assert_consistent(
+			"struct EOF { stdin: int = 1 }
+			def main(): int {
+				var NULL: int = 2
+				var INFINITY: float = 0.5
+				var sqrt: float = 4.0
+				var int64_t: EOF = EOF()
+				var ftl_result: str = \"a\"
+				print(\"{} {} {} {} {}\\n\", NULL, INFINITY, sqrt(sqrt), int64_t.stdin, (ftl_result < \"b\"))
+				return NULL
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: 2, stdout: "2 0.500000 2.000000 1 1\n".to_owned() });
+
+		// Macros predefined by the C compiler
+		let outcome = assert_consistent(
+			"def main(): int {
+				var linux: int = 2
+				var unix: int = 3
+				var i386: int = 4
+				return linux * unix + i386
+			}",
+		);
+		assert_eq!(outcome.exit_code, 10);
+	}
+}
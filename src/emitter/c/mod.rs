@@ -1,6 +1,16 @@
 //! C emitter.
 
-use std::io;
+mod analysis;
+mod header;
+mod mangle;
+mod unit;
+
+use std::{collections::HashMap, io, iter};
+
+pub use analysis::Analysis;
+pub use header::{Emitter as HeaderEmitter, SharedEmitter as SharedHeaderEmitter};
+pub use mangle::Mangling;
+pub use unit::Emitter as UnitEmitter;
 
 use crate::{
 	ast,
@@ -9,228 +19,1328 @@ use crate::{
 		statement::{BasicDataType, DataType},
 		Expression,
 	},
-	source::PositionContainer,
+	emitter::{self, intersperse},
+	intrinsic::Intrinsic,
+	source::{SourcePositionRange, Spanned},
 };
 
+/// Every generated C program starts with the runtime header, which also includes the C standard library headers.
+const PRELUDE: &str = crate::runtime::C_HEADER;
+
 /// Emits C code.
+#[derive(Debug, Clone, Copy, Default)]
 pub struct Emitter {
-	writer: Box<dyn io::Write>,
+	/// Precede functions and instructions with `#line` directives, so that the C compiler, debuggers and sanitizers
+	/// refer to the lines of the FTL source instead of the generated code.
+	pub line_directives: bool,
 }
 
 impl super::Emitter for Emitter {
-	fn codegen(ast_nodes: impl Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()> {
-		let mut this = Self { writer };
+	fn file_extension(&self) -> &'static str {
+		"c"
+	}
+
+	fn codegen(&self, ast_nodes: &mut dyn Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()> {
+		let ast_nodes: Vec<ast::Node> = ast_nodes.collect();
+		let mut this = Codegen::new(writer, Mangling::new(ast_nodes.iter()), Analysis::analyze(ast_nodes.iter()));
+		this.line_directives = self.line_directives;
 
-		// Prelude
-		writeln!(this.writer, "#include <stdio.h>\n#include <stdlib.h>")?;
+		write!(this.writer, "{}", PRELUDE)?;
 
+		// Prototypes of all functions come before the definitions, so that functions can call functions defined later
+		// in the file. They may use structs, which therefore come first. Structs are declared before their definitions,
+		// so that they can point to themselves and to structs defined later.
+		let mut structs = Vec::new();
+		let mut functions = Vec::new();
 		for ast_node in ast_nodes {
-			this.ast_node(ast_node)?;
+			match ast_node {
+				ast::Node::Struct(struct_) => structs.push(struct_),
+				ast::Node::Function(function) => functions.push(function),
+				ast::Node::FunctionPrototype(_) | ast::Node::Comment(_) => (), // extern function
+				ast::Node::Invalid(position) => return Err(emitter::invalid_code(&position)),
+			}
+		}
+		this.declarations(structs, functions.iter().map(|function| &function.prototype))?;
+		for function in functions {
+			writeln!(this.writer)?;
+			this.function(function)?;
 		}
 		Ok(())
 	}
 }
 
+/// Characters of one level of indentation.
+const INDENTATION: &str = "\t";
+
+/// State while emitting C code.
+struct Codegen {
+	writer: Box<dyn io::Write>,
+	mangling: Mangling,
+	analysis: Analysis,
+	/// Whether the function currently being emitted can fail, i.e. returns a status code.
+	fallible: bool,
+	/// Arguments of the function currently being emitted, which its tail calls assign.
+	args: Vec<ast::statement::FunctionArgument>,
+	/// Variables of the current function holding the values of [hoisted](Self::hoist) expressions, by the position of
+	/// their `try` keyword or logical operation.
+	temporaries: HashMap<SourcePositionRange, String>,
+	/// Current level of indentation.
+	indentation: usize,
+	/// See [`Emitter::line_directives`].
+	line_directives: bool,
+}
+
 /// Each of the functions in this impl block is responsible for emitting the corresponding AST node.
-impl Emitter {
-	fn ast_node(&mut self, node: ast::Node) -> io::Result<()> {
-		match node {
-			ast::Node::Function(function) => self.function(function),
-			ast::Node::Struct(struct_) => self.struct_(struct_),
-			ast::Node::FunctionPrototype(_) => Ok(()), // extern function
-			_ => todo!(),
+impl Codegen {
+	fn new(writer: Box<dyn io::Write>, mangling: Mangling, analysis: Analysis) -> Self {
+		Self {
+			writer,
+			mangling,
+			analysis,
+			fallible: false,
+			args: Vec::new(),
+			temporaries: HashMap::new(),
+			indentation: 0,
+			line_directives: false,
+		}
+	}
+
+	/// Emits a `#line` directive attributing the next line to the FTL code at `position`, if enabled.
+	fn line_directive(&mut self, position: &SourcePositionRange) -> io::Result<()> {
+		if !self.line_directives {
+			return Ok(());
 		}
+		let file = position.source.name.replace('\\', "\\\\").replace('"', "\\\"");
+		writeln!(self.writer, "#line {} \"{}\"", position.position.start.line, file)
+	}
+
+	fn indent(&mut self) -> io::Result<()> {
+		write!(self.writer, "{}", INDENTATION.repeat(self.indentation))
 	}
 
 	fn function(&mut self, function: ast::FunctionDefinition) -> io::Result<()> {
-		// Function header
-		// Return type
-		match function.prototype.return_type {
-			Some(return_type) => self.data_type(return_type)?,
-			None => write!(self.writer, "void")?,
+		self.line_directive(&function.prototype.name.position)?;
+		self.fallible = matches!(
+			function.prototype.return_type.as_ref().map(|return_type| &return_type.value),
+			Some(DataType::Fallible(_))
+		);
+		self.args = function.prototype.args.clone();
+		self.temporaries.clear();
+		self.function_prototype(function.prototype)?;
+		write!(self.writer, " ")?;
+		if !self.analysis.contains_tail_call(&function.body) {
+			self.block(function.body)?;
+			return writeln!(self.writer);
 		}
+
+		// Tail calls jump to the label before the body
+		writeln!(self.writer, "{{")?;
+		self.indentation += 1;
+		self.indent()?;
+		writeln!(self.writer, "ftl_tail_call:;")?;
+		self.indent()?;
+		self.block(function.body)?;
+		writeln!(self.writer)?;
+		self.indentation -= 1;
+		writeln!(self.writer, "}}")
+	}
+
+	/// Emits the instructions enclosed in curly braces, without a line break after the closing brace.
+	fn block(&mut self, block: ast::Block) -> io::Result<()> {
+		writeln!(self.writer, "{{")?;
+		self.indentation += 1;
+		for instruction in block {
+			self.instruction(instruction)?;
+		}
+		self.indentation -= 1;
+		self.indent()?;
+		write!(self.writer, "}}")
+	}
+
+	/// Emits the declarations and definitions of the structs, followed by the prototypes of the functions.
+	fn declarations<'a>(
+		&mut self,
+		structs: Vec<ast::Struct>,
+		prototypes: impl Iterator<Item = &'a ast::FunctionPrototype>,
+	) -> io::Result<()> {
+		self.struct_declarations(&structs)?;
+		for struct_ in structs {
+			writeln!(self.writer)?;
+			self.struct_(struct_)?;
+		}
+		self.function_prototypes(prototypes)
+	}
+
+	/// Emits prototypes of the functions, preceded by an empty line. Nothing is emitted if there are no functions.
+	fn function_prototypes<'a>(
+		&mut self,
+		prototypes: impl Iterator<Item = &'a ast::FunctionPrototype>,
+	) -> io::Result<()> {
+		for (i, prototype) in prototypes.enumerate() {
+			if i == 0 {
+				writeln!(self.writer)?;
+			}
+			self.function_prototype(prototype.clone())?;
+			writeln!(self.writer, ";")?;
+		}
+		Ok(())
+	}
+
+	/// Emits return type, name and arguments of a function, without a trailing `;` or body.
+	///
+	/// Functions that can fail return a status code instead, and their result through the last argument `ftl_result`.
+	fn function_prototype(&mut self, prototype: ast::FunctionPrototype) -> io::Result<()> {
+		if self.mangling.is_static(&prototype.name) {
+			write!(self.writer, "static ")?;
+		}
+		// Return type
+		let result_type = match prototype.return_type {
+			Some(Spanned { value: DataType::Fallible(inner), .. }) => {
+				write!(self.writer, "int")?;
+				Some(*inner)
+			},
+			// C requires `main` to return an `int`, the exit code
+			Some(_) if self.mangling.function(&prototype.name) == "main" => {
+				write!(self.writer, "int")?;
+				None
+			},
+			Some(return_type) => {
+				self.data_type(return_type)?;
+				None
+			},
+			None => {
+				write!(self.writer, "void")?;
+				None
+			},
+		};
 		write!(self.writer, " ")?;
 
 		// Function name
-		write!(self.writer, "{}(", *function.prototype.name)?;
+		write!(self.writer, "{}(", self.mangling.function(&prototype.name))?;
 
 		// Function arguments
-		for (i, arg) in function.prototype.args.into_iter().enumerate() {
-			if i != 0 {
+		let has_args = !prototype.args.is_empty();
+		intersperse(self, prototype.args, |this| write!(this.writer, ", "), Self::function_argument)?;
+		if let Some(result_type) = result_type {
+			if has_args {
 				write!(self.writer, ", ")?;
 			}
-			self.function_argument(arg)?;
+			self.data_type(result_type)?;
+			write!(self.writer, "* ftl_result")?;
 		}
-		writeln!(self.writer, ") {{")?;
+		write!(self.writer, ")")
+	}
 
-		// Function body
-		for instruction in function.body {
-			self.instruction(instruction)?;
+	/// Declares the structs as named C structs, preceded by an empty line. This allows pointers to them before their
+	/// [definitions](Self::struct_). Nothing is emitted if there are no structs.
+	fn struct_declarations(&mut self, structs: &[ast::Struct]) -> io::Result<()> {
+		for (i, struct_) in structs.iter().enumerate() {
+			if i == 0 {
+				writeln!(self.writer)?;
+			}
+			writeln!(self.writer, "typedef struct {name} {name};", name = mangle::identifier(&struct_.name))?;
+			// Structs may contain pointers to structs defined later, which their writers for `dump` call
+			writeln!(
+				self.writer,
+				"static inline void ftl_dump_ptr_{name}({name}* value);",
+				name = mangle::identifier(&struct_.name)
+			)?;
 		}
-		writeln!(self.writer)?;
-		writeln!(self.writer, "}}")?;
 		Ok(())
 	}
 
+	/// Emits the struct definition and a constructor `ftl_default_<name>()` that returns the struct with all fields set
+	/// to their default values. Fields without default value are zero, empty or constructed the same way.
+	///
+	/// The [writers](dump_function) `ftl_dump_<name>()` and `ftl_dump_ptr_<name>()` for `dump` follow.
 	fn struct_(&mut self, struct_: ast::Struct) -> io::Result<()> {
-		writeln!(self.writer, "typedef struct {{",)?;
-		for field in struct_.fields {
-			self.data_type(field.data_type)?;
-			write!(self.writer, " {};", *field.name)?;
+		let dumped_fields: Vec<_> =
+			struct_.fields.iter().map(|field| (field.name.value.clone(), field.data_type.value.clone())).collect();
+		let name = mangle::identifier(&struct_.name).into_owned();
+		writeln!(self.writer, "struct {} {{", name)?;
+		// Structs without members are not allowed in ISO C
+		if struct_.fields.is_empty() {
+			writeln!(self.writer, "{}char ftl_empty;", INDENTATION)?;
 		}
-		writeln!(self.writer, "}} {};", *struct_.name)?;
-		Ok(())
+		for field in &struct_.fields {
+			write!(self.writer, "{}", INDENTATION)?;
+			self.data_type(field.data_type.clone())?;
+			writeln!(self.writer, " {};", mangle::identifier(&field.name))?;
+		}
+		writeln!(self.writer, "}};")?;
+
+		writeln!(self.writer)?;
+		writeln!(self.writer, "static inline {name} ftl_default_{name}(void) {{")?;
+		write!(self.writer, "{}{} value = {{", INDENTATION, name)?;
+		if struct_.fields.is_empty() {
+			write!(self.writer, "0")?;
+		}
+		intersperse(
+			self,
+			struct_.fields,
+			|this| write!(this.writer, ", "),
+			|this, field| {
+				write!(this.writer, ".{} = ", mangle::identifier(&field.name))?;
+				match field.default {
+					Some(default) => this.expression(default),
+					None => this.zero_value(field.data_type.value),
+				}
+			},
+		)?;
+		writeln!(self.writer, "}};")?;
+		writeln!(self.writer, "{}return value;", INDENTATION)?;
+		writeln!(self.writer, "}}")?;
+
+		writeln!(self.writer)?;
+		self.struct_dump(&struct_.name, dumped_fields)
+	}
+
+	/// Emits the writers of the struct called `name` for `dump`, which write the struct name and the `fields` like
+	/// `Point { x: 1, y: 2.500000 }`.
+	fn struct_dump(&mut self, name: &str, fields: Vec<(String, DataType)>) -> io::Result<()> {
+		let c_name = mangle::identifier(name).into_owned();
+		writeln!(self.writer, "static inline void ftl_dump_{c_name}({c_name} value) {{")?;
+		if fields.is_empty() {
+			writeln!(self.writer, "{}ftl_print(\"{} {{}}\");", INDENTATION, name)?;
+		}
+		for (i, (field, data_type)) in fields.iter().enumerate() {
+			let prefix = if i == 0 { format!("{} {{ ", name) } else { ", ".to_owned() };
+			writeln!(self.writer, "{}ftl_print(\"{}{}: \");", INDENTATION, prefix, field)?;
+			match dump_function(data_type) {
+				Some(function) => {
+					writeln!(self.writer, "{}{}(value.{});", INDENTATION, function, mangle::identifier(field))?
+				},
+				// The type checker rejects dumping structs with such fields
+				None => writeln!(self.writer, "{}ftl_print(\"?\");", INDENTATION)?,
+			}
+		}
+		if !fields.is_empty() {
+			writeln!(self.writer, "{}ftl_print(\" }}\");", INDENTATION)?;
+		}
+		writeln!(self.writer, "}}")?;
+
+		writeln!(self.writer)?;
+		writeln!(self.writer, "static inline void ftl_dump_ptr_{c_name}({c_name}* value) {{")?;
+		writeln!(self.writer, "{}if (value == NULL) {{", INDENTATION)?;
+		writeln!(self.writer, "{0}{0}ftl_print(\"null\");", INDENTATION)?;
+		writeln!(self.writer, "{0}{0}return;", INDENTATION)?;
+		writeln!(self.writer, "{}}}", INDENTATION)?;
+		writeln!(self.writer, "{}ftl_dump_{}(*value);", INDENTATION, c_name)?;
+		writeln!(self.writer, "}}")
 	}
 
+	/// Emits an instruction on its own, indented line. Instructions are the only place where lines are terminated, so
+	/// that expressions can be nested in any other expression. The lines of [hoisted](Self::hoist) expressions precede
+	/// it.
 	fn instruction(&mut self, instruction: ast::Instruction) -> io::Result<()> {
+		let position = instruction.source_position();
+		self.line_directive(&position)?;
+		if self.hoist_instruction(&instruction)? {
+			// The directive referred to the first hoisted line
+			self.line_directive(&position)?;
+		}
+		self.indent()?;
 		match instruction {
-			ast::Instruction::Expression(expression) => self.expression(expression),
+			ast::Instruction::Expression(expression) => {
+				self.expression(expression)?;
+				writeln!(self.writer, ";")
+			},
 			ast::Instruction::Statement(statement) => self.statement(statement),
 			ast::Instruction::IfElse(if_else) => self.if_else(*if_else),
 			ast::Instruction::WhileLoop(while_loop) => self.while_loop(*while_loop),
+			ast::Instruction::Invalid(position) => Err(emitter::invalid_code(&position)),
 		}
 	}
 
+	/// [Hoists](Self::hoist) the `try` expressions of the instruction, except those in its blocks and in the condition of
+	/// a loop, see [`Self::while_loop`]. Returns whether there were any.
+	fn hoist_instruction(&mut self, instruction: &ast::Instruction) -> io::Result<bool> {
+		let expressions = match instruction {
+			ast::Instruction::Expression(expression) => vec![expression],
+			ast::Instruction::Statement(ast::Statement::VariableDeclaration(declaration)) => vec![&declaration.value],
+			ast::Instruction::Statement(ast::Statement::VariableAssignment(assignment)) => vec![&assignment.value],
+			ast::Instruction::Statement(ast::Statement::FieldAssignment(assignment)) => {
+				vec![&*assignment.target.base, &assignment.value]
+			},
+			ast::Instruction::Statement(ast::Statement::Return(value)) => value.value.iter().collect(),
+			ast::Instruction::IfElse(if_else) => vec![&if_else.condition],
+			ast::Instruction::WhileLoop(_) | ast::Instruction::Invalid(_) => Vec::new(),
+		};
+		let hoisted = expressions.iter().any(|expression| contains_try(expression));
+		expressions.into_iter().try_for_each(|expression| self.hoist(expression))?;
+		Ok(hoisted)
+	}
+
+	/// Emits statements evaluating the `try` expressions in the `expression` into temporary variables, since standard C
+	/// has no expressions containing statements. For `try f(a) else fallback`, these are
+	/// `int64_t ftl_try_0;` and `if (f(a, &ftl_try_0)) { ftl_try_0 = fallback; }`. A fallback of `error` returns the
+	/// failure from the enclosing function instead.
+	///
+	/// Logical operations with a `try` on their right side are evaluated into a temporary variable as a whole, so that
+	/// the right side is only evaluated if the left side doesn't determine the result.
+	fn hoist(&mut self, expression: &Expression) -> io::Result<()> {
+		match expression {
+			Expression::BinaryExpression(binary_expression) => match *binary_expression.operator {
+				BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr if contains_try(&binary_expression.rhs) => {
+					self.hoist(&binary_expression.lhs)?;
+					self.hoist_logical(binary_expression)
+				},
+				_ => self.hoist_sequence(&[&*binary_expression.lhs, &*binary_expression.rhs]),
+			},
+			Expression::FieldAccess(field_access) => self.hoist(&field_access.base),
+			Expression::FunctionCall(function_call) => {
+				self.hoist_sequence(&function_call.params.iter().collect::<Vec<_>>())
+			},
+			Expression::Try(try_expression) => {
+				let Expression::FunctionCall(function_call) = &*try_expression.value else {
+					// Only calls can fail, so the value is always used, see `Self::try_`
+					return self.hoist(&try_expression.value);
+				};
+				self.hoist_sequence(&function_call.params.iter().collect::<Vec<_>>())?;
+				let name = self.temporary(&expression.source_position(), "try");
+				let result_type =
+					self.analysis.result_type(&function_call.name.position).cloned().unwrap_or(DataType::Void);
+				self.indent()?;
+				self.data_type(Spanned::new(result_type, try_expression.position.clone()))?;
+				writeln!(self.writer, " {};", name)?;
+				self.indent()?;
+				write!(self.writer, "if ({}(", self.mangling.function(&function_call.name))?;
+				for param in function_call.params.clone() {
+					self.param(param)?;
+					write!(self.writer, ", ")?;
+				}
+				writeln!(self.writer, "&{})) {{", name)?;
+				self.indentation += 1;
+				match &*try_expression.fallback {
+					Expression::Error(_) => {
+						self.indent()?;
+						writeln!(self.writer, "return 1;")?;
+					},
+					fallback => {
+						self.hoist(fallback)?;
+						self.indent()?;
+						write!(self.writer, "{} = ", name)?;
+						self.expression(fallback.clone())?;
+						writeln!(self.writer, ";")?;
+					},
+				}
+				self.indentation -= 1;
+				self.indent()?;
+				writeln!(self.writer, "}}")
+			},
+			Expression::Number(_)
+			| Expression::String(_)
+			| Expression::Variable(_)
+			| Expression::Null(_)
+			| Expression::Error(_)
+			| Expression::Invalid(_) => Ok(()),
+		}
+	}
+
+	/// [Hoists](Self::hoist) expressions evaluated one after another. Those calling functions before a `try` are
+	/// evaluated into temporary variables as well, since the calls would be made after the hoisted `try` otherwise.
+	fn hoist_sequence(&mut self, expressions: &[&Expression]) -> io::Result<()> {
+		for (index, expression) in expressions.iter().enumerate() {
+			self.hoist(expression)?;
+			if expressions[index + 1..].iter().any(|expression| contains_try(expression)) && self.calls(expression) {
+				self.hoist_value(expression)?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Evaluates the `expression` into a temporary variable like `int64_t ftl_value_0 = f(a);`.
+	fn hoist_value(&mut self, expression: &Expression) -> io::Result<()> {
+		let position = expression.source_position();
+		let Some(data_type) = self.analysis.data_type(&position) else {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("{}: the type of the expression is unknown", position),
+			));
+		};
+		// Named before emitting the expression, which would be emitted as the temporary variable otherwise
+		let name = format!("ftl_value_{}", self.temporaries.len());
+		self.indent()?;
+		self.data_type(Spanned::new(data_type, position.clone()))?;
+		write!(self.writer, " {} = ", name)?;
+		self.expression(expression.clone())?;
+		writeln!(self.writer, ";")?;
+		self.temporaries.insert(position, name);
+		Ok(())
+	}
+
+	/// Whether emitting the [hoisted](Self::hoist) `expression` calls functions.
+	fn calls(&self, expression: &Expression) -> bool {
+		if self.temporaries.contains_key(&expression.source_position()) {
+			return false;
+		}
+		match expression {
+			Expression::BinaryExpression(binary_expression) => {
+				self.calls(&binary_expression.lhs) || self.calls(&binary_expression.rhs)
+			},
+			Expression::FieldAccess(field_access) => self.calls(&field_access.base),
+			Expression::FunctionCall(_) => true,
+			Expression::Try(try_expression) => self.calls(&try_expression.value),
+			Expression::Number(_)
+			| Expression::String(_)
+			| Expression::Variable(_)
+			| Expression::Null(_)
+			| Expression::Error(_)
+			| Expression::Invalid(_) => false,
+		}
+	}
+
+	/// Evaluates `a && b` with a `try` in `b` as `int64_t ftl_logical_0 = 0;` and
+	/// `if (a) { ftl_logical_0 = (b) != 0; }` after the statements of `b`, and `a || b` alike.
+	fn hoist_logical(&mut self, binary_expression: &ast::expression::BinaryExpression) -> io::Result<()> {
+		let name = self.temporary(&binary_expression.source_position(), "logical");
+		let lhs = (*binary_expression.lhs).clone();
+		self.indent()?;
+		if *binary_expression.operator == BinaryOperator::LogicalAnd {
+			writeln!(self.writer, "int64_t {} = 0;", name)?;
+			self.indent()?;
+			write!(self.writer, "if (")?;
+			self.expression(lhs)?;
+		} else {
+			writeln!(self.writer, "int64_t {} = 1;", name)?;
+			self.indent()?;
+			write!(self.writer, "if (!")?;
+			self.operand(lhs)?;
+		}
+		writeln!(self.writer, ") {{")?;
+		self.indentation += 1;
+		self.hoist(&binary_expression.rhs)?;
+		self.indent()?;
+		write!(self.writer, "{} = (", name)?;
+		self.expression((*binary_expression.rhs).clone())?;
+		writeln!(self.writer, ") != 0;")?;
+		self.indentation -= 1;
+		self.indent()?;
+		writeln!(self.writer, "}}")
+	}
+
+	/// Names a new temporary variable of the current function for the [hoisted](Self::hoist) expression at `position`.
+	fn temporary(&mut self, position: &SourcePositionRange, kind: &str) -> String {
+		let name = format!("ftl_{}_{}", kind, self.temporaries.len());
+		self.temporaries.insert(position.clone(), name.clone());
+		name
+	}
+
+	/// Emits an expression in place, without a terminating `;` or line break. [Hoisted](Self::hoist) expressions are
+	/// emitted as their temporary variable.
 	fn expression(&mut self, expression: ast::Expression) -> io::Result<()> {
+		if let Some(name) = self.temporaries.get(&expression.source_position()) {
+			return write!(self.writer, "{}", name);
+		}
 		match expression {
 			Expression::BinaryExpression(binary_expression) => self.binary_expression(binary_expression),
+			Expression::FieldAccess(field_access) => self.field_access(field_access),
 			Expression::FunctionCall(function_call) => self.function_call(function_call),
+			Expression::Null(position) => write!(self.writer, "{}", self.analysis.null_sentinel(&position)),
+			Expression::Try(try_expression) => self.try_(try_expression),
+			// Only returned, see `Self::return_`
+			Expression::Error(_) => write!(self.writer, "1"),
 			Expression::Number(number) => self.number(number),
+			Expression::String(string) => self.string(string),
 			Expression::Variable(variable) => self.variable(variable),
+			Expression::Invalid(position) => Err(emitter::invalid_code(&position)),
 		}
 	}
 
 	fn binary_expression(&mut self, binary_expression: ast::expression::BinaryExpression) -> io::Result<()> {
-		self.expression(*binary_expression.lhs)?;
+		// NAN is not equal to itself, so null floats are recognized with isnan()
+		let null_float = [&*binary_expression.lhs, &*binary_expression.rhs].into_iter().find_map(|side| match side {
+			Expression::Null(position) if self.analysis.null_sentinel(position) == "NAN" => Some(position.clone()),
+			_ => None,
+		});
+		if let Some(null_position) = null_float {
+			let negation = if *binary_expression.operator == BinaryOperator::NotEqual { "!" } else { "" };
+			let other = match *binary_expression.lhs {
+				Expression::Null(position) if position == null_position => *binary_expression.rhs,
+				lhs => lhs,
+			};
+			write!(self.writer, "{}isnan(", negation)?;
+			self.expression(other)?;
+			return write!(self.writer, ")");
+		}
+
+		// Comparisons of optional strings with `null` compare the pointers
+		let [lhs, rhs] = [&binary_expression.lhs, &binary_expression.rhs].map(|side| side.source_position());
+		if self.analysis.is_string(&lhs) && self.analysis.is_string(&rhs) {
+			return self.string_operation(binary_expression);
+		}
+
+		// `%` of C keeps the sign of the dividend, whereas the result of `mod` is never negative
+		if *binary_expression.operator == BinaryOperator::Modulus {
+			write!(self.writer, "ftl_mod(")?;
+			self.expression(*binary_expression.lhs)?;
+			write!(self.writer, ", ")?;
+			self.expression(*binary_expression.rhs)?;
+			return write!(self.writer, ")");
+		}
+
+		self.operand(*binary_expression.lhs)?;
 		let operator = match *binary_expression.operator {
 			ast::expression::BinaryOperator::Add => "+",
 			ast::expression::BinaryOperator::Subtract => "-",
 			ast::expression::BinaryOperator::Multiply => "*",
 			ast::expression::BinaryOperator::Divide => "/",
+			BinaryOperator::Modulus => unreachable!("handled above"),
 			BinaryOperator::Less => "<",
 			BinaryOperator::Greater => ">",
+			BinaryOperator::LessEqual => "<=",
+			BinaryOperator::GreaterEqual => ">=",
 			BinaryOperator::Equal => "==",
-			BinaryOperator::NotEqual => "=/=",
+			BinaryOperator::NotEqual => "!=",
+			BinaryOperator::LogicalAnd => "&&",
+			BinaryOperator::LogicalOr => "||",
 		};
 		write!(self.writer, " {} ", operator)?;
+		self.operand(*binary_expression.rhs)
+	}
+
+	/// Emits an operand of a binary expression. Nested binary expressions are enclosed in parentheses, since the AST
+	/// keeps their grouping, but not the parentheses of the source.
+	fn operand(&mut self, operand: ast::Expression) -> io::Result<()> {
+		match operand {
+			Expression::BinaryExpression(_) => {
+				write!(self.writer, "(")?;
+				self.expression(operand)?;
+				write!(self.writer, ")")
+			},
+			operand => self.expression(operand),
+		}
+	}
+
+	/// Emits `a + b` of strings as `ftl_str_concat(a, b)` and comparisons like `a < b` as `(strcmp(a, b) < 0)`.
+	fn string_operation(&mut self, binary_expression: ast::expression::BinaryExpression) -> io::Result<()> {
+		let comparison = match *binary_expression.operator {
+			BinaryOperator::Less => "<",
+			BinaryOperator::Greater => ">",
+			BinaryOperator::LessEqual => "<=",
+			BinaryOperator::GreaterEqual => ">=",
+			BinaryOperator::Equal => "==",
+			BinaryOperator::NotEqual => "!=",
+			BinaryOperator::Add
+			| BinaryOperator::Subtract
+			| BinaryOperator::Multiply
+			| BinaryOperator::Divide
+			| BinaryOperator::Modulus => {
+				write!(self.writer, "ftl_str_concat(")?;
+				self.expression(*binary_expression.lhs)?;
+				write!(self.writer, ", ")?;
+				self.expression(*binary_expression.rhs)?;
+				return write!(self.writer, ")");
+			},
+			BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => unreachable!("strings are not conditions"),
+		};
+		write!(self.writer, "(strcmp(")?;
+		self.expression(*binary_expression.lhs)?;
+		write!(self.writer, ", ")?;
 		self.expression(*binary_expression.rhs)?;
-		Ok(())
+		write!(self.writer, ") {} 0)", comparison)
+	}
+
+	/// Emits `try` expressions of values other than calls, which can't fail. Those of calls are emitted as the
+	/// temporary variable they were [hoisted](Self::hoist) into.
+	fn try_(&mut self, try_expression: ast::expression::TryExpression) -> io::Result<()> {
+		self.expression(*try_expression.value)
+	}
+
+	fn field_access(&mut self, field_access: ast::expression::FieldAccess) -> io::Result<()> {
+		let operator = if self.analysis.is_pointer(&field_access.base.source_position()) { "->" } else { "." };
+		self.expression(*field_access.base)?;
+		write!(self.writer, "{}{}", operator, mangle::identifier(&field_access.field))
 	}
 
 	fn function_call(&mut self, function_call: ast::expression::FunctionCall) -> io::Result<()> {
-		write!(self.writer, "{}(", *function_call.name)?;
-		for param in function_call.params {
-			self.expression(param)?;
+		match Intrinsic::from_name(&function_call.name) {
+			Some(Intrinsic::Print) => return self.print(function_call),
+			Some(Intrinsic::Dump) => return self.dump(function_call),
+			_ => (),
 		}
+		write!(self.writer, "{}(", self.mangling.function(&function_call.name))?;
+		intersperse(self, function_call.params, |this| write!(this.writer, ", "), Self::param)?;
 		write!(self.writer, ")")?;
 		Ok(())
 	}
 
+	/// Emits a call of `print` as comma expression of the runtime's print functions for each value.
+	fn print(&mut self, function_call: ast::expression::FunctionCall) -> io::Result<()> {
+		write!(self.writer, "(")?;
+		for value in emitter::print_values(function_call.params)? {
+			write!(self.writer, "ftl_print_value(")?;
+			self.expression(value)?;
+			write!(self.writer, "), ")?;
+		}
+		write!(self.writer, "(void)0)")
+	}
+
+	/// Emits a call of `dump` as call of the [writer](dump_function) of the type of the value, followed by a line
+	/// break.
+	fn dump(&mut self, function_call: ast::expression::FunctionCall) -> io::Result<()> {
+		let position = function_call.name.position.clone();
+		let value = function_call.params.into_iter().next();
+		let Some((value, function)) = value.and_then(|value| {
+			let function = dump_function(&self.analysis.data_type(&value.source_position())?)?;
+			Some((value, function))
+		}) else {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("{}: the type of the value of `dump` is unknown", position),
+			));
+		};
+		write!(self.writer, "({}(", function)?;
+		self.expression(value)?;
+		write!(self.writer, "), ftl_print(\"\\n\"), (void)0)")
+	}
+
+	/// Emits a parameter of a call. Structs passed to `ptr` arguments are passed by their address.
+	fn param(&mut self, param: Expression) -> io::Result<()> {
+		if self.analysis.is_reference(&param.source_position()) {
+			write!(self.writer, "&")?;
+		}
+		self.expression(param)
+	}
+
 	fn statement(&mut self, statement: ast::Statement) -> io::Result<()> {
 		match statement {
 			ast::statement::Statement::VariableDeclaration(variable_declaration) => {
 				self.variable_declaration(variable_declaration)
 			},
 			ast::statement::Statement::VariableAssignment(assignment) => self.variable_assignment(assignment),
-			ast::statement::Statement::Return(expression) => self.return_(expression),
+			ast::statement::Statement::FieldAssignment(assignment) => self.field_assignment(assignment),
+			ast::statement::Statement::Return(value) => self.return_(value.value),
 		}
 	}
 
 	fn variable_declaration(&mut self, variable_declaration: ast::statement::VariableDeclaration) -> io::Result<()> {
-		write!(self.writer, "{} {} = ", *variable_declaration.data_type, *variable_declaration.name)?;
+		self.data_type(variable_declaration.data_type)?;
+		write!(self.writer, " {} = ", mangle::identifier(&variable_declaration.name))?;
 		self.expression(variable_declaration.value)?;
 		writeln!(self.writer, ";")?;
 		Ok(())
 	}
 
 	fn variable_assignment(&mut self, assignment: ast::statement::VariableAssignment) -> io::Result<()> {
-		write!(self.writer, "{} = ", *assignment.name)?;
+		write!(self.writer, "{} = ", mangle::identifier(&assignment.name))?;
 		self.expression(assignment.value)?;
 		writeln!(self.writer, ";")?;
 		Ok(())
 	}
 
-	fn return_(&mut self, expression: ast::Expression) -> io::Result<()> {
-		write!(self.writer, "return ")?;
-		self.expression(expression)?;
+	fn field_assignment(&mut self, assignment: ast::statement::FieldAssignment) -> io::Result<()> {
+		self.field_access(assignment.target)?;
+		write!(self.writer, " = ")?;
+		self.expression(assignment.value)?;
+		writeln!(self.writer, ";")
+	}
+
+	/// Emits a `return`. Functions that can fail store their result in `ftl_result` and return the status code `0`,
+	/// or `1` for `error`.
+	fn return_(&mut self, value: Option<ast::Expression>) -> io::Result<()> {
+		if let Some(Expression::FunctionCall(function_call)) = &value {
+			if self.analysis.is_tail_call(&function_call.name.position) {
+				return self.tail_call(function_call.clone());
+			}
+		}
+		if self.fallible {
+			match value {
+				Some(Expression::Error(_)) => return writeln!(self.writer, "return 1;"),
+				Some(expression) => {
+					write!(self.writer, "*ftl_result = ")?;
+					self.expression(expression)?;
+					writeln!(self.writer, ";")?;
+					self.indent()?;
+					return writeln!(self.writer, "return 0;");
+				},
+				None => (),
+			}
+		}
+		write!(self.writer, "return")?;
+		if let Some(expression) = value {
+			write!(self.writer, " ")?;
+			self.expression(expression)?;
+		}
 		writeln!(self.writer, ";")?;
 		Ok(())
 	}
 
+	/// Emits a self-recursive tail call as assignment of the arguments and a jump to the start of the function.
+	fn tail_call(&mut self, function_call: ast::expression::FunctionCall) -> io::Result<()> {
+		// All parameters are evaluated before the first argument is assigned, since they may use the arguments
+		writeln!(self.writer, "{{")?;
+		self.indentation += 1;
+		for (arg, param) in iter::zip(self.args.clone(), function_call.params) {
+			self.indent()?;
+			self.data_type(arg.data_type)?;
+			write!(self.writer, " ftl_tail_{} = ", *arg.name)?;
+			self.param(param)?;
+			writeln!(self.writer, ";")?;
+		}
+		for arg in self.args.clone() {
+			self.indent()?;
+			writeln!(self.writer, "{} = ftl_tail_{};", mangle::identifier(&arg.name), *arg.name)?;
+		}
+		self.indent()?;
+		writeln!(self.writer, "goto ftl_tail_call;")?;
+		self.indentation -= 1;
+		self.indent()?;
+		writeln!(self.writer, "}}")
+	}
+
 	fn if_else(&mut self, if_else: ast::IfElse) -> io::Result<()> {
 		// if block, always present
 		write!(self.writer, "if (")?;
 		self.expression(if_else.condition)?;
-		writeln!(self.writer, ") {{")?;
-		for instruction in if_else.if_true {
-			self.instruction(instruction)?;
-		}
-		writeln!(self.writer, "}}")?;
+		write!(self.writer, ") ")?;
+		self.block(if_else.if_true)?;
 
 		// else block, optional
-		if if_else.if_false.is_empty() {
-			return Ok(());
-		}
-		writeln!(self.writer, "else {{")?;
-		for instruction in if_else.if_false {
-			self.instruction(instruction)?;
+		if !if_else.if_false.is_empty() {
+			write!(self.writer, " else ")?;
+			self.block(if_else.if_false)?;
 		}
-		writeln!(self.writer, "}}")?;
-
-		Ok(())
+		writeln!(self.writer)
 	}
 
+	/// Emits a `while` loop. A condition containing `try` is evaluated at the start of each iteration, which ends the
+	/// loop if it is false, so that its [hoisted](Self::hoist) expressions are evaluated each time.
 	fn while_loop(&mut self, while_loop: ast::WhileLoop) -> io::Result<()> {
+		if contains_try(&while_loop.condition) {
+			writeln!(self.writer, "while (1) {{")?;
+			self.indentation += 1;
+			self.hoist(&while_loop.condition)?;
+			self.indent()?;
+			write!(self.writer, "if (!(")?;
+			self.expression(while_loop.condition)?;
+			writeln!(self.writer, ")) {{")?;
+			self.indent()?;
+			writeln!(self.writer, "{}break;", INDENTATION)?;
+			self.indent()?;
+			writeln!(self.writer, "}}")?;
+			for instruction in while_loop.body {
+				self.instruction(instruction)?;
+			}
+			self.indentation -= 1;
+			self.indent()?;
+			return writeln!(self.writer, "}}");
+		}
 		write!(self.writer, "while (")?;
 		self.expression(while_loop.condition)?;
-		writeln!(self.writer, ") {{")?;
-		for instruction in while_loop.body {
-			self.instruction(instruction)?;
-		}
-		writeln!(self.writer, "}}")?;
-		Ok(())
+		write!(self.writer, ") ")?;
+		self.block(while_loop.body)?;
+		writeln!(self.writer)
 	}
 
 	fn function_argument(&mut self, function_argument: ast::statement::FunctionArgument) -> io::Result<()> {
 		self.data_type(function_argument.data_type)?;
-		write!(self.writer, " {}", *function_argument.name)?;
+		write!(self.writer, " {}", mangle::identifier(&function_argument.name))?;
 		Ok(())
 	}
 
-	fn data_type(&mut self, data_type: PositionContainer<ast::statement::DataType>) -> io::Result<()> {
+	fn data_type(&mut self, data_type: Spanned<ast::statement::DataType>) -> io::Result<()> {
 		match data_type.value {
 			DataType::Basic(basic_data_type) => self.basic_data_type(basic_data_type),
 			DataType::Struct(struct_name) => self.struct_name(struct_name),
 			DataType::Pointer(pointer) => self.pointer(*pointer),
+			// Optionals are represented like their inner type, see `NullSentinels`
+			DataType::Optional(inner) => self.data_type(*inner),
+			// The result, while the function returns a status code, see `Self::function_prototype`
+			DataType::Fallible(inner) => self.data_type(*inner),
+			DataType::Void => write!(self.writer, "void"),
 		}
 	}
 
 	fn basic_data_type(&mut self, basic_data_type: ast::statement::BasicDataType) -> io::Result<()> {
 		match basic_data_type {
-			BasicDataType::Int => write!(self.writer, "int"),
-			BasicDataType::Float => write!(self.writer, "float"),
+			BasicDataType::Int => write!(self.writer, "int64_t"),
+			BasicDataType::Float => write!(self.writer, "double"),
+			BasicDataType::Str => write!(self.writer, "char*"),
+		}
+	}
+
+	/// Emits the value of a field of the `data_type` without default value.
+	fn zero_value(&mut self, data_type: ast::statement::DataType) -> io::Result<()> {
+		match data_type {
+			ast::statement::DataType::Basic(ast::statement::BasicDataType::Int) => write!(self.writer, "0"),
+			ast::statement::DataType::Basic(ast::statement::BasicDataType::Float) => write!(self.writer, "0.0"),
+			ast::statement::DataType::Basic(ast::statement::BasicDataType::Str) => write!(self.writer, "\"\""),
+			ast::statement::DataType::Struct(name) => {
+				write!(self.writer, "ftl_default_{}()", mangle::identifier(&name))
+			},
+			ast::statement::DataType::Optional(inner) => match inner.value {
+				ast::statement::DataType::Basic(ast::statement::BasicDataType::Int) => write!(self.writer, "INT64_MIN"),
+				ast::statement::DataType::Basic(ast::statement::BasicDataType::Float) => write!(self.writer, "NAN"),
+				_ => write!(self.writer, "NULL"),
+			},
+			ast::statement::DataType::Pointer(_)
+			| ast::statement::DataType::Fallible(_)
+			| ast::statement::DataType::Void => write!(self.writer, "0"),
 		}
 	}
 
 	fn struct_name(&mut self, struct_name: String) -> io::Result<()> {
-		write!(self.writer, "{}", struct_name)
+		write!(self.writer, "{}", mangle::identifier(&struct_name))
 	}
 
-	fn pointer(&mut self, pointer: PositionContainer<ast::statement::DataType>) -> io::Result<()> {
-		write!(self.writer, "*")?;
-		self.data_type(pointer)
+	fn pointer(&mut self, pointer: Spanned<ast::statement::DataType>) -> io::Result<()> {
+		self.data_type(pointer)?;
+		write!(self.writer, "*")
 	}
 
 	fn number(&mut self, number: ast::expression::Number) -> io::Result<()> {
 		match *number {
 			ast::expression::NumberKind::Int(int) => write!(self.writer, "{}", int)?,
-			ast::expression::NumberKind::Float(float) => write!(self.writer, "{}", float)?,
+			// The lexer rejects infinite literals, but ASTs built in Rust may contain them
+			ast::expression::NumberKind::Float(float) if float.is_infinite() => write!(self.writer, "INFINITY")?,
+			// Debug formatting keeps the decimal point or exponent, without which C would treat the literal as int,
+			// e.g. in integer divisions. It writes the shortest literal that C reads as the same double
+			ast::expression::NumberKind::Float(float) => write!(self.writer, "{:?}", float)?,
 		}
 		Ok(())
 	}
 
+	fn string(&mut self, string: ast::expression::StringLiteral) -> io::Result<()> {
+		write!(self.writer, "\"")?;
+		for char in string.chars() {
+			match char {
+				'"' => write!(self.writer, "\\\"")?,
+				'\\' => write!(self.writer, "\\\\")?,
+				'\n' => write!(self.writer, "\\n")?,
+				'\r' => write!(self.writer, "\\r")?,
+				'\t' => write!(self.writer, "\\t")?,
+				// Octal escapes can't be confused with following digits, since they have at most three digits
+				char if char.is_ascii_control() => write!(self.writer, "\\{:03o}", char as u32)?,
+				char => write!(self.writer, "{}", char)?,
+			}
+		}
+		write!(self.writer, "\"")
+	}
+
 	fn variable(&mut self, variable: ast::expression::Variable) -> io::Result<()> {
-		write!(self.writer, "{}", *variable)?;
+		write!(self.writer, "{}", mangle::identifier(&variable))?;
 		Ok(())
 	}
 }
+
+/// Whether the `expression` contains a `try`, which has to be [hoisted](Codegen::hoist).
+fn contains_try(expression: &Expression) -> bool {
+	match expression {
+		Expression::BinaryExpression(binary_expression) => {
+			contains_try(&binary_expression.lhs) || contains_try(&binary_expression.rhs)
+		},
+		Expression::FieldAccess(field_access) => contains_try(&field_access.base),
+		Expression::FunctionCall(function_call) => function_call.params.iter().any(contains_try),
+		Expression::Try(_) => true,
+		Expression::Number(_)
+		| Expression::String(_)
+		| Expression::Variable(_)
+		| Expression::Null(_)
+		| Expression::Error(_)
+		| Expression::Invalid(_) => false,
+	}
+}
+
+/// Name of the function writing a value of the `data_type` for `dump`. The runtime provides those of basic types and
+/// files, while those of structs are generated with the [structs](Codegen::struct_).
+///
+/// Optional strings and pointers use `NULL` as `null` like pointers, so only optional numbers need writers of their own.
+/// Returns [`None`] for types that can't be dumped, like pointers to pointers.
+fn dump_function(data_type: &DataType) -> Option<String> {
+	let basic_name = |basic_data_type: &BasicDataType| match basic_data_type {
+		BasicDataType::Int => "int",
+		BasicDataType::Float => "float",
+		BasicDataType::Str => "str",
+	};
+	let name = match data_type {
+		DataType::Basic(basic_data_type) => basic_name(basic_data_type).to_owned(),
+		DataType::Struct(name) => mangle::identifier(name).into_owned(),
+		DataType::Optional(inner) => match &inner.value {
+			DataType::Basic(basic_data_type @ (BasicDataType::Int | BasicDataType::Float)) => {
+				format!("opt_{}", basic_name(basic_data_type))
+			},
+			inner => return dump_function(inner),
+		},
+		DataType::Pointer(pointee) => match &pointee.value {
+			DataType::Basic(basic_data_type) => format!("ptr_{}", basic_name(basic_data_type)),
+			DataType::Struct(name) => format!("ptr_{}", mangle::identifier(name)),
+			_ => return None,
+		},
+		DataType::Fallible(_) | DataType::Void => return None,
+	};
+	Some(format!("ftl_dump_{}", name))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, path::Path, sync::Arc};
+
+	use super::*;
+	use crate::{
+		differential::{assert_consistent, Backend, Outcome},
+		dump::Dumper,
+		emitter::codegen_to_string,
+		semantic_analyzer::CheckConfig,
+		source::Source,
+	};
+
+	/// Compares the C code generated for each FTL file in `golden/` with the C file of the same name, without the
+	/// prelude. Set the environment variable `FTL_UPDATE_GOLDEN` to overwrite the C files with the generated code.
+	#[test]
+	fn test_golden() {
+		let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/emitter/c/golden");
+		let mut ftl_paths: Vec<_> = fs::read_dir(&directory)
+			.unwrap()
+			.map(|entry| entry.unwrap().path())
+			.filter(|path| path.extension().is_some_and(|extension| extension == "ftl"))
+			.collect();
+		ftl_paths.sort();
+		assert!(!ftl_paths.is_empty());
+
+		for ftl_path in ftl_paths {
+			let source =
+				Arc::new(Source::new(ftl_path.to_string_lossy().into_owned(), fs::read_to_string(&ftl_path).unwrap()));
+			let ast_nodes =
+				crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+			let output = codegen_to_string(&Emitter::default(), &mut ast_nodes.into_iter()).unwrap();
+			let output = output.strip_prefix(PRELUDE).unwrap();
+
+			let c_path = ftl_path.with_extension("c");
+			if std::env::var_os("FTL_UPDATE_GOLDEN").is_some() {
+				fs::write(&c_path, output).unwrap();
+				continue;
+			}
+			let expected = fs::read_to_string(&c_path).unwrap_or_default();
+			assert_eq!(output, expected, "generated C code differs from {:?}", c_path);
+		}
+	}
+
+	#[test]
+	fn test_struct_parameters() {
+		let source_code = "struct P { x: int }\ndef f(p: ptr P, q: P): int {\n\tp.x = q.x\n\treturn f(q, q)\n}";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes =
+			crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+		let output = codegen_to_string(&Emitter::default(), &mut ast_nodes.into_iter()).unwrap();
+		assert!(
+			output.contains("int64_t ftl_fn_f(P* p, P q) {\n\tp->x = q.x;\n\treturn ftl_fn_f(&q, q);\n}"),
+			"{}",
+			output
+		);
+	}
+
+	#[test]
+	fn test_tail_calls() {
+		let source_code = "def f(a: int, b: float): float {\n\tif a < 1 {\n\t\treturn b\n\t}\n\treturn f((a - 1), 		                   (b * 2.0))\n}\ndef g(): float {\n\treturn f(1, 1.0)\n}";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes =
+			crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+		let output = codegen_to_string(&Emitter::default(), &mut ast_nodes.into_iter()).unwrap();
+		assert!(
+			output.contains(
+				"double ftl_fn_f(int64_t a, double b) {
+	ftl_tail_call:;
+	{
+		if (a < 1) {
+			return b;
+		}
+		{
+			int64_t ftl_tail_a = a - 1;
+			double ftl_tail_b = b * 2.0;
+			a = ftl_tail_a;
+			b = ftl_tail_b;
+			goto ftl_tail_call;
+		}
+	}
+}
+"
+			),
+			"{}",
+			output
+		);
+		assert!(output.contains("double ftl_fn_g() {\n\treturn ftl_fn_f(1, 1.0);\n}"), "{}", output);
+	}
+
+	#[test]
+	fn test_try() {
+		let source_code =
+			"def read(a: int): int or error {\n\treturn a\n}\ndef twice(a: int): int {\n\treturn a * 2\n}\ndef \
+		                   f(): int or error {\n\treturn twice((try read(1) else error)) + (try read(2) else 0)\n}";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes =
+			crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+		let output = codegen_to_string(&Emitter::default(), &mut ast_nodes.into_iter()).unwrap();
+		// Standard C has no statement expressions, so `try` and the calls before it are evaluated before the statement
+		assert!(
+			output.contains(
+				"static int ftl_fn_f(int64_t* ftl_result) {
+	int64_t ftl_try_0;
+	if (ftl_fn_read(1, &ftl_try_0)) {
+		return 1;
+	}
+	int64_t ftl_value_1 = ftl_fn_twice(ftl_try_0);
+	int64_t ftl_try_2;
+	if (ftl_fn_read(2, &ftl_try_2)) {
+		ftl_try_2 = 0;
+	}
+	*ftl_result = ftl_value_1 + ftl_try_2;
+	return 0;
+}
+"
+			),
+			"{}",
+			output
+		);
+	}
+
+	#[test]
+	fn test_float_literals() {
+		let source_code =
+			format!("def f(): float {{\n\treturn 2.0 + 0.1 * 1{}.0 + 1{}.0\n}}", "0".repeat(20), "0".repeat(300));
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code));
+		let ast_nodes =
+			crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+		let output = codegen_to_string(&Emitter::default(), &mut ast_nodes.into_iter()).unwrap();
+		assert!(output.contains("\treturn (2.0 + (0.1 * 1e20)) + 1e300;\n"), "{}", output);
+	}
+
+	#[test]
+	fn test_line_directives() {
+		let source_code = "def main(): int {\n\tvar x: int = 1\n\n\treturn x\n}";
+		let source = Arc::new(Source::new("dir/\"quoted\".ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes =
+			crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+		let emitter = Emitter { line_directives: true };
+		let output = codegen_to_string(&emitter, &mut ast_nodes.into_iter()).unwrap();
+		assert!(output.ends_with(
+			"#line 1 \"dir/\\\"quoted\\\".ftl\"
+int main() {
+#line 2 \"dir/\\\"quoted\\\".ftl\"
+	int64_t x = 1;
+#line 4 \"dir/\\\"quoted\\\".ftl\"
+	return x;
+}
+"
+		));
+	}
+
+	#[test]
+	fn test_float_literals_consistent() {
+		// `2.0` must not become the int `2` in C, which would make the divisions truncating
+		let outcome = assert_consistent(
+			"def main(): int {
+				ftl_print_float((1.0 / 2.0))
+				ftl_print_float((7.0 / 2.0 * 3.0))
+				ftl_print_float((100000000000000000000.0 / 1000000000000000000.0))
+				if 5.0 / 2.0 > 2.0 {
+					return 1
+				}
+				return 0
+			}",
+		);
+		assert_eq!(outcome.exit_code, 1);
+		assert_eq!(outcome.stdout, "0.50000010.500000100.000000");
+	}
+
+	#[test]
+	fn test_float_precision() {
+		// Neither value is exact in 32 bits, and the second one would overflow to infinity
+		let outcome = assert_consistent(
+			"def main(): int {
+				print(\"{} {}\\n\", 16777217.0, (1e39 / 1e30))
+				var rounding: float = (0.1 + 0.2 - 0.3)
+				dump((rounding * 1e20))
+				return 0
+			}",
+		);
+		assert_eq!(outcome.stdout, "16777217.000000 1000000000.000000\n5551.115123\n");
+	}
+
+	#[test]
+	fn test_forward_reference() {
+		assert_consistent(
+			"def main(): int {
+				return ftl_print(greeting())
+			}
+			def greeting(): str {
+				return \"hi\"
+			}",
+		);
+	}
+
+	#[test]
+	fn test_mutual_recursion() {
+		let outcome = assert_consistent(
+			"def is_even(n: int): int {
+				if n < 1 {
+					return 1
+				}
+				return is_odd((n - 1))
+			}
+			def is_odd(n: int): int {
+				if n < 1 {
+					return 0
+				}
+				return is_even((n - 1))
+			}
+			def main(): int {
+				return is_even(10) + is_odd(7) * 2
+			}",
+		);
+		assert_eq!(outcome.exit_code, 3);
+	}
+
+	#[test]
+	fn test_recursive_struct() {
+		let outcome = assert_consistent(
+			"struct Tree { value: int, children: ptr List }
+			struct List { head: ptr Tree, tail: ptr List }
+			def count(tree: ptr Tree): int {
+				return 0
+			}
+			def main(): int {
+				return 3
+			}",
+		);
+		assert_eq!(outcome.exit_code, 3);
+	}
+
+	#[test]
+	fn test_fallible_functions() {
+		let outcome = assert_consistent(
+			"def digit(c: int): int or error {
+				if c < 0 {
+					return error
+				}
+				if 9 < c {
+					return error
+				}
+				return c
+			}
+			def sum_digits(a: int, b: int): int or error {
+				var x: int = try digit(a) else error
+				var y: int = try digit(b) else error
+				return x + y
+			}
+			def outer(): int or error {
+				return try digit(try digit(12) else error) else 50
+			}
+			def main(): int {
+				var ok: int = try sum_digits(3, 4) else 100
+				var failed: int = try sum_digits(3, 12) else 20
+				var nested: int = try digit(try digit(5) else 0) else 1
+				var propagated: int = try outer() else 40
+				return ok + failed + nested + propagated
+			}",
+		);
+		assert_eq!(outcome.exit_code, 72);
+	}
+
+	#[test]
+	fn test_try_in_conditions() {
+		// The right side of `and` and `or` is only evaluated if needed, and loop conditions in each iteration
+		let outcome = assert_consistent(
+			"def noisy(c: int): int or error {
+				print(\"noisy {}\\n\", c)
+				if 9 < c {
+					return error
+				}
+				return c
+			}
+			def main(): int {
+				var i: int = 0
+				while (try noisy(i) else 0) < 2 {
+					i = i + 1
+				}
+				var n: int = 0
+				if (i = 3) and ((try noisy(11) else 5) = 5) {
+					n = n + 1
+				}
+				if (i = 2) or ((try noisy(12) else 5) = 5) {
+					n = n + 10
+				}
+				if (i = 3) or ((try noisy(13) else 5) = 5) {
+					n = n + 100
+				}
+				return n
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: 110, stdout: "noisy 0\nnoisy 1\nnoisy 2\nnoisy 13\n".to_owned() });
+	}
+
+	#[test]
+	fn test_try_evaluation_order() {
+		// Calls before a `try` are made before it, also in the C code, where the `try` is evaluated in statements before
+		let outcome = assert_consistent(
+			"def f(c: int): int {
+				print(\"f {}\\n\", c)
+				return c
+			}
+			def g(c: int): int or error {
+				print(\"g {}\\n\", c)
+				return c
+			}
+			def add(a: int, b: int, c: int): int {
+				return a + b + c
+			}
+			def main(): int {
+				var x: int = (f(1) + (try g(2) else 0))
+				var y: int = add(f(3), (f(4) * (try g(5) else 0)), f(6))
+				var z: int = ((f(7) + f(8)) - (try g(try g(9) else 0) else f(10)))
+				return x + y + z
+			}",
+		);
+		assert_eq!(outcome.stdout, "f 1\ng 2\nf 3\nf 4\ng 5\nf 6\nf 7\nf 8\ng 9\ng 9\n");
+		assert_eq!(outcome.exit_code, 3 + 29 + 6);
+	}
+
+	#[test]
+	fn test_nested_calls() {
+		let outcome = assert_consistent(
+			"def double(x: int): int {
+				return x * 2
+			}
+			def inc(x: int): int {
+				return x + 1
+			}
+			def main(): int {
+				var y: int = double(inc(1))
+				if inc(double(y)) < 10 {
+					y = inc(inc(y))
+				}
+				while double(y) < inc(double(y)) {
+					return abs(double(inc((0 - 4)))) + y
+				}
+				return y
+			}",
+		);
+		assert_eq!(outcome.exit_code, 12);
+	}
+
+	#[test]
+	fn test_tail_calls_consistent() {
+		let swap = "def swap(n: int, a: int, b: int): int {
+				if n < 1 {
+					return a * 10 + b
+				}
+				return swap((n - 1), b, a)
+			}";
+		let outcome = assert_consistent(&format!("{}\ndef main(): int {{ return swap(3, 1, 2) }}", swap));
+		assert_eq!(outcome.exit_code, 21);
+
+		// Without the jump, 10 million stack frames would overflow the stack, which the other backends limit
+		let count = "def count(n: int, total: int): int {
+				if n < 1 {
+					return total
+				}
+				return count((n - 1), (total + 1))
+			}
+			def main(): int {
+				return (count(10000000, 0) = 10000000)
+			}";
+		if Backend::C.is_available() {
+			assert_eq!(Backend::C.run(count).unwrap().exit_code, 1);
+		}
+	}
+}
@@ -0,0 +1,48 @@
+//! C emitter for a single translation unit of a multi-unit build.
+
+use std::io;
+
+use super::{Analysis, Codegen, Mangling};
+use crate::ast;
+
+/// Emits the function definitions of one source file of a [multi-unit build](crate::build).
+///
+/// Prelude, structs and the prototypes of functions that aren't `static` are not emitted, but included from the
+/// [shared header](super::SharedHeaderEmitter).
+pub struct Emitter {
+	/// File name of the shared header, which is included at the top.
+	pub shared_header: String,
+	/// Mangling of the whole program, since the unit may call functions of other units.
+	pub mangling: Mangling,
+	/// Types of the whole program, since they are only known after analyzing all units together.
+	pub analysis: Analysis,
+}
+
+impl crate::emitter::Emitter for Emitter {
+	fn file_extension(&self) -> &'static str {
+		"c"
+	}
+
+	fn codegen(&self, ast_nodes: &mut dyn Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()> {
+		let mut this = Codegen::new(writer, self.mangling.clone(), self.analysis.clone());
+
+		writeln!(this.writer, "#include \"{}\"", self.shared_header)?;
+
+		let functions: Vec<_> = ast_nodes
+			.filter_map(|ast_node| match ast_node {
+				ast::Node::Function(function) => Some(function),
+				_ => None,
+			})
+			.collect();
+		let static_prototypes = functions
+			.iter()
+			.map(|function| &function.prototype)
+			.filter(|prototype| self.mangling.is_static(&prototype.name));
+		this.function_prototypes(static_prototypes)?;
+		for function in functions {
+			writeln!(this.writer)?;
+			this.function(function)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,169 @@
+//! Style options of the FTL formatter.
+//!
+//! The options are read from a file `ftlfmt.toml` in the directory of the formatted file or one of its parents:
+//!
+//! ```toml
+//! # Indent with 4 spaces instead of a tab
+//! use_tabs = false
+//! indent_width = 4
+//! max_line_length = 100
+//! brace_style = "next-line"
+//! trailing_commas = true
+//! ```
+//!
+//! Only flat `key = value` pairs with integers, booleans and strings are supported.
+
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use crate::CompileError;
+
+/// File name of the formatter configuration.
+const CONFIG_FILE_NAME: &str = "ftlfmt.toml";
+
+/// Style options of the [FTL emitter](super::Emitter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FmtConfig {
+	/// Number of spaces of one level of indentation. With tabs, the width of a tab when measuring lines.
+	pub indent_width: usize,
+	/// Whether to indent with tabs instead of spaces.
+	pub use_tabs: bool,
+	/// Lines longer than this get the parameters of a call or the arguments of a function on separate lines.
+	pub max_line_length: usize,
+	/// Where the opening brace of a block is placed.
+	pub brace_style: BraceStyle,
+	/// Whether each field of a struct, which is on a line of its own, is followed by a comma.
+	pub trailing_commas: bool,
+}
+
+impl Default for FmtConfig {
+	fn default() -> Self {
+		Self {
+			indent_width: 4,
+			use_tabs: true,
+			max_line_length: 120,
+			brace_style: BraceStyle::SameLine,
+			trailing_commas: false,
+		}
+	}
+}
+
+/// Placement of the opening brace of functions, structs, `if`, `else` and `while`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BraceStyle {
+	/// `if x {`
+	SameLine,
+	/// The brace on its own line below `if x`.
+	NextLine,
+}
+
+impl FromStr for BraceStyle {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"same-line" => Ok(BraceStyle::SameLine),
+			"next-line" => Ok(BraceStyle::NextLine),
+			other => Err(format!("unknown brace style `{}`, expected one of same-line, next-line", other)),
+		}
+	}
+}
+
+impl fmt::Display for BraceStyle {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			BraceStyle::SameLine => write!(f, "same-line"),
+			BraceStyle::NextLine => write!(f, "next-line"),
+		}
+	}
+}
+
+impl FmtConfig {
+	/// Loads the configuration for the FTL file at `path` from the nearest `ftlfmt.toml`, or returns the default
+	/// configuration if there is none.
+	pub fn load(path: &Path) -> Result<Self, CompileError> {
+		let path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+		for directory in path.ancestors().skip(1) {
+			let config_path = directory.join(CONFIG_FILE_NAME);
+			if config_path.is_file() {
+				let content = fs::read_to_string(&config_path)
+					.map_err(CompileError::io(format!("Reading formatter configuration `{:?}`", config_path)))?;
+				return Self::parse(&content)
+					.map_err(|err| CompileError::Invalid(format!("{}: {}", config_path.display(), err)));
+			}
+		}
+		Ok(Self::default())
+	}
+
+	/// Parses the content of an `ftlfmt.toml`. Options that are not set keep their default.
+	pub fn parse(content: &str) -> Result<Self, String> {
+		let mut config = Self::default();
+		for (i, line) in content.lines().enumerate() {
+			let line = line.split('#').next().unwrap_or_default().trim();
+			if line.is_empty() {
+				continue;
+			}
+			let Some((key, value)) = line.split_once('=') else {
+				return Err(format!("line {}: expected `key = value`", i + 1));
+			};
+			let (key, value) = (key.trim(), value.trim());
+			let result = match key {
+				"indent_width" => value.parse().map(|width| config.indent_width = width).map_err(|err| err.to_string()),
+				"use_tabs" => value.parse().map(|use_tabs| config.use_tabs = use_tabs).map_err(|err| err.to_string()),
+				"max_line_length" => {
+					value.parse().map(|length| config.max_line_length = length).map_err(|err| err.to_string())
+				},
+				"brace_style" => match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+					Some(value) => value.parse().map(|style| config.brace_style = style),
+					None => Err("expected a string".to_owned()),
+				},
+				"trailing_commas" => {
+					value.parse().map(|commas| config.trailing_commas = commas).map_err(|err| err.to_string())
+				},
+				_ => Err("unknown option".to_owned()),
+			};
+			result.map_err(|err| format!("line {}: invalid value for `{}`: {}", i + 1, key, err))?;
+		}
+		Ok(config)
+	}
+
+	/// Characters of one level of indentation.
+	pub(super) fn indentation(&self) -> String {
+		if self.use_tabs {
+			"\t".to_owned()
+		} else {
+			" ".repeat(self.indent_width)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		let config = FmtConfig::parse(
+			"# Team style\nuse_tabs = false\nindent_width = 2 # narrow\n\nmax_line_length = 80\nbrace_style = \
+			 \"next-line\"\ntrailing_commas = true\n",
+		)
+		.unwrap();
+		assert_eq!(
+			config,
+			FmtConfig {
+				indent_width: 2,
+				use_tabs: false,
+				max_line_length: 80,
+				brace_style: BraceStyle::NextLine,
+				trailing_commas: true
+			}
+		);
+		assert_eq!(FmtConfig::parse("").unwrap(), FmtConfig::default());
+		assert_eq!(
+			FmtConfig::parse("use_tabs = false\nindent_width = four").unwrap_err(),
+			"line 2: invalid value for `indent_width`: invalid digit found in string"
+		);
+		assert_eq!(FmtConfig::parse("tabs = true").unwrap_err(), "line 1: invalid value for `tabs`: unknown option");
+		assert!(FmtConfig::parse("brace_style = next-line").is_err());
+		assert!(FmtConfig::parse("use_tabs").is_err());
+	}
+}
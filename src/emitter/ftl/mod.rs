@@ -1,6 +1,13 @@
 //! FTL emitter used to format existing FTL code.
+//!
+//! The same code generation renders AST nodes with [`Display`](fmt::Display), e.g. to show code fragments in
+//! diagnostics and tests.
 
-use std::io;
+mod config;
+
+use std::{collections::BTreeSet, fmt, io, iter::Peekable};
+
+pub use config::{BraceStyle, FmtConfig};
 
 use crate::{
 	ast::{
@@ -9,214 +16,871 @@ use crate::{
 		statement::{BasicDataType, DataType},
 		Expression,
 	},
-	source::PositionContainer,
+	emitter::intersperse,
+	lexer::{Block, BlockComment, BlockComments},
+	source::{SourcePositionRange, Spanned},
 };
 
 /// Emits FTL code.
 ///
 /// This is mainly used to format existing FTL code.
+#[derive(Debug, Clone, Default)]
 pub struct Emitter {
-	writer: Box<dyn io::Write>,
+	/// Style of the emitted code.
+	pub config: FmtConfig,
+	/// [Lines of the original code after an empty line](crate::lexer::lines_after_empty_line). If given, only the
+	/// nodes starting on these lines are separated by an empty line, instead of all of them. Instructions and comments
+	/// in blocks starting on these lines are preceded by an empty line as well.
+	pub empty_lines: Option<BTreeSet<u32>>,
+	/// [Comments in the blocks](crate::lexer::BlockComments) of the original code, which are not part of the AST. If
+	/// given, they are emitted before the instruction or `}` they preceded.
+	pub comments: Option<BlockComments>,
 }
 
 impl super::Emitter for Emitter {
-	fn codegen(ast_nodes: impl Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()> {
-		let mut this = Self { writer };
-		for ast_node in ast_nodes {
-			this.ast_node(ast_node)?;
+	fn file_extension(&self) -> &'static str {
+		"ftl"
+	}
+
+	fn codegen(&self, ast_nodes: &mut dyn Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()> {
+		let mut writer = IoWriter { writer, error: None };
+		let mut codegen = Codegen::new(&mut writer, self.config);
+		codegen.empty_lines = self.empty_lines.as_ref();
+		codegen.comments = self.comments.as_ref();
+		if codegen.ast_nodes(ast_nodes).is_err() {
+			return Err(writer.error.unwrap_or_else(|| io::Error::other("formatting FTL code failed")));
 		}
 		Ok(())
 	}
 }
 
+/// Adapts an [`io::Write`] to [`fmt::Write`], keeping the I/O error that [`fmt::Error`] can't carry.
+struct IoWriter {
+	writer: Box<dyn io::Write>,
+	error: Option<io::Error>,
+}
+
+impl fmt::Write for IoWriter {
+	fn write_str(&mut self, string: &str) -> fmt::Result {
+		self.writer.write_all(string.as_bytes()).map_err(|err| {
+			self.error = Some(err);
+			fmt::Error
+		})
+	}
+}
+
+/// State while emitting FTL code.
+struct Codegen<'a> {
+	writer: &'a mut dyn fmt::Write,
+	config: FmtConfig,
+	/// Current level of indentation.
+	indentation: usize,
+	/// Whether the next call or function prototype gets its parameters on separate lines, because the line would be
+	/// too long otherwise.
+	wrap: bool,
+	/// See [`Emitter::empty_lines`].
+	empty_lines: Option<&'a BTreeSet<u32>>,
+	/// See [`Emitter::comments`].
+	comments: Option<&'a BlockComments>,
+}
+
 /// Each of the functions in this impl block is responsible for emitting the corresponding AST node.
-impl Emitter {
-	fn ast_node(&mut self, node: ast::Node) -> io::Result<()> {
+impl<'a> Codegen<'a> {
+	fn new(writer: &'a mut dyn fmt::Write, config: FmtConfig) -> Self {
+		Self { writer, config, indentation: 0, wrap: false, empty_lines: None, comments: None }
+	}
+
+	/// Emits the nodes of a file, separated by empty lines where the original code had them, if `empty_lines` is given.
+	fn ast_nodes(&mut self, ast_nodes: &mut dyn Iterator<Item = ast::Node>) -> fmt::Result {
+		for (i, ast_node) in ast_nodes.enumerate() {
+			if i > 0 && self.empty_lines.is_none_or(|lines| lines.contains(&first_line(&ast_node))) {
+				writeln!(self.writer)?;
+			}
+			self.ast_node(&ast_node)?;
+		}
+		Ok(())
+	}
+
+	fn ast_node(&mut self, node: &ast::Node) -> fmt::Result {
 		match node {
 			ast::Node::Function(function) => self.function(function),
 			ast::Node::Struct(struct_) => self.struct_(struct_),
-			_ => todo!(),
+			ast::Node::FunctionPrototype(prototype) => self.extern_function(prototype),
+			// Following lines of the comment already start with `#`
+			ast::Node::Comment(comment) => writeln!(self.writer, "# {}", **comment),
+			ast::Node::Invalid(position) => writeln!(self.writer, "{}", position.code()),
 		}
 	}
 
-	fn function(&mut self, function: ast::FunctionDefinition) -> io::Result<()> {
-		// Function header
-		write!(self.writer, "function {}(", *function.prototype.name)?;
-		for arg in function.prototype.args {
-			self.function_argument(arg)?;
-			write!(self.writer, ", ")?; // TODO: Remove trailing comma
+	fn indent(&mut self) -> fmt::Result {
+		write!(self.writer, "{}", self.config.indentation().repeat(self.indentation))
+	}
+
+	/// Emits the (first) line of code produced by `emit`. If it exceeds the maximum line length, it is emitted again
+	/// with the parameters of the outermost call or function prototype on separate lines.
+	fn line(&mut self, emit: impl Fn(&mut Codegen<'_>) -> fmt::Result) -> fmt::Result {
+		let mut line = String::new();
+		emit(&mut Codegen::new(&mut line, self.config).at(self.indentation))?;
+		let width =
+			self.indentation * self.config.indent_width + line.lines().next().unwrap_or_default().chars().count();
+		if width > self.config.max_line_length {
+			line.clear();
+			let mut codegen = Codegen::new(&mut line, self.config).at(self.indentation);
+			codegen.wrap = true;
+			emit(&mut codegen)?;
 		}
-		writeln!(self.writer, ") {{")?;
+		write!(self.writer, "{}", line)
+	}
 
-		// Function body
-		for instruction in function.body {
-			self.instruction(instruction)?;
+	/// Sets the level of indentation.
+	fn at(mut self, indentation: usize) -> Self {
+		self.indentation = indentation;
+		self
+	}
+
+	/// Emits `items` separated by `, `, or each on its own indented line if the line is [wrapped](Self::line).
+	fn list<T>(
+		&mut self,
+		items: impl IntoIterator<Item = T>,
+		emit_item: impl FnMut(&mut Self, T) -> fmt::Result,
+	) -> fmt::Result {
+		if !std::mem::take(&mut self.wrap) {
+			return intersperse(self, items, |this| write!(this.writer, ", "), emit_item);
 		}
+		self.indentation += 1;
 		writeln!(self.writer)?;
-		writeln!(self.writer, "}}")?;
+		self.indent()?;
+		intersperse(
+			self,
+			items,
+			|this| {
+				writeln!(this.writer, ",")?;
+				this.indent()
+			},
+			emit_item,
+		)?;
+		self.indentation -= 1;
+		writeln!(self.writer)?;
+		self.indent()
+	}
+
+	/// Emits the doc comment of a function, struct or field, if it has one.
+	fn doc(&mut self, doc: &Option<Spanned<String>>) -> fmt::Result {
+		for line in doc.iter().flat_map(|doc| doc.lines()) {
+			self.indent()?;
+			match line {
+				"" => writeln!(self.writer, "##")?,
+				line => writeln!(self.writer, "## {}", line)?,
+			}
+		}
 		Ok(())
 	}
 
-	fn struct_(&mut self, struct_: ast::Struct) -> io::Result<()> {
-		writeln!(self.writer, "struct {} {{", *struct_.name)?;
-		for field in struct_.fields {
-			write!(self.writer, "{}: ", *field.name)?;
-			self.data_type(field.data_type)?;
-			writeln!(self.writer, ", ")?; // TODO: Remove trailing comma
+	fn function(&mut self, function: &ast::FunctionDefinition) -> fmt::Result {
+		self.doc(&function.prototype.doc)?;
+		for annotation in &function.annotations {
+			writeln!(self.writer, "@{}", **annotation)?;
+		}
+		self.line(|this| {
+			if function.prototype.exported {
+				write!(this.writer, "export ")?;
+			}
+			write!(this.writer, "def ")?;
+			this.function_prototype(&function.prototype)
+		})?;
+		self.block(&function.body, self.block_after(&function.prototype.name.position))?;
+		writeln!(self.writer)
+	}
+
+	fn extern_function(&mut self, prototype: &ast::FunctionPrototype) -> fmt::Result {
+		self.doc(&prototype.doc)?;
+		self.line(|this| {
+			write!(this.writer, "extern ")?;
+			this.function_prototype(prototype)
+		})?;
+		writeln!(self.writer)
+	}
+
+	/// Emits name, arguments and return type of a function.
+	fn function_prototype(&mut self, prototype: &ast::FunctionPrototype) -> fmt::Result {
+		write!(self.writer, "{}(", *prototype.name)?;
+		self.list(&prototype.args, Self::function_argument)?;
+		write!(self.writer, ")")?;
+		if let Some(return_type) = &prototype.return_type {
+			write!(self.writer, ": ")?;
+			self.data_type(return_type)?;
+		}
+		Ok(())
+	}
+
+	/// Emits the opening brace according to the brace style, after the code preceding it on the same line.
+	/// Emits the `{` of a block and ends its line. A comment following the `{` on its line, i.e. a trailing comment
+	/// preceding the byte `offset` of the first instruction or field, stays at its end.
+	fn opening_brace<'c>(
+		&mut self,
+		comments: &mut Peekable<impl Iterator<Item = &'c BlockComment>>,
+		offset: Option<usize>,
+	) -> fmt::Result {
+		match self.config.brace_style {
+			BraceStyle::SameLine => write!(self.writer, " {{")?,
+			BraceStyle::NextLine => {
+				writeln!(self.writer)?;
+				self.indent()?;
+				write!(self.writer, "{{")?;
+			},
+		}
+		let trailing = |comment: &&BlockComment| comment.trailing && offset.is_none_or(|offset| comment.next <= offset);
+		match comments.next_if(trailing) {
+			Some(comment) => {
+				write!(self.writer, " ")?;
+				// Following lines of the comment are in the block
+				self.indentation += 1;
+				self.comment(comment)?;
+				self.indentation -= 1;
+				Ok(())
+			},
+			None => writeln!(self.writer),
+		}
+	}
+
+	/// The block of the original code whose `{` follows the `position`, if the [comments](Emitter::comments) are
+	/// given.
+	fn block_after(&self, position: &SourcePositionRange) -> Option<&'a Block> {
+		self.comments?.block_after(position.byte_range().start)
+	}
+
+	/// Emits the instructions enclosed in curly braces, without a line break after the closing brace. The comments of
+	/// the `original` block are emitted between them.
+	fn block(&mut self, block: &[ast::Instruction], original: Option<&Block>) -> fmt::Result {
+		let mut comments = original.into_iter().flat_map(|original| &original.comments).peekable();
+		let first = block.first().map(|instruction| instruction.source_position().byte_range().start);
+		self.opening_brace(&mut comments, first)?;
+		self.indentation += 1;
+		// Start line of the previous instruction, whose line is not ended yet
+		let mut previous_line = None;
+		for (i, instruction) in block.iter().enumerate() {
+			let position = instruction.source_position();
+			let started = self.comments(&mut comments, Some(position.byte_range().start), previous_line)?;
+			self.empty_line(position.position.start.line, started)?;
+			self.instruction(instruction)?;
+			// Without `;`, the next instruction would be parsed as the value of the return
+			if let ast::Instruction::Statement(ast::Statement::Return(value)) = instruction {
+				if value.value.is_none() && i + 1 < block.len() {
+					write!(self.writer, ";")?;
+				}
+			}
+			previous_line = Some(position.position.start.line);
+		}
+		self.comments(&mut comments, None, previous_line)?;
+		self.indentation -= 1;
+		self.indent()?;
+		write!(self.writer, "}}")
+	}
+
+	/// Ends the line of the previous instruction on `line`, if any, and emits the comments preceding the byte `offset`,
+	/// or all remaining ones without an `offset`. A trailing comment on the `line` stays at its end. Returns whether
+	/// anything was emitted in the block so far.
+	fn comments<'c>(
+		&mut self,
+		comments: &mut Peekable<impl Iterator<Item = &'c BlockComment>>,
+		offset: Option<usize>,
+		line: Option<u32>,
+	) -> Result<bool, fmt::Error> {
+		let precedes = |comment: &&BlockComment| offset.is_none_or(|offset| comment.next <= offset);
+		if let Some(line) = line {
+			let trailing = |comment: &&BlockComment| {
+				comment.trailing && comment.comment.position.position.start.line == line && precedes(comment)
+			};
+			if let Some(comment) = comments.next_if(trailing) {
+				write!(self.writer, " ")?;
+				self.comment(comment)?;
+			} else {
+				writeln!(self.writer)?;
+			}
+		}
+		let mut started = line.is_some();
+		while let Some(comment) = comments.next_if(precedes) {
+			self.empty_line(comment.comment.position.position.start.line, started)?;
+			self.indent()?;
+			self.comment(comment)?;
+			started = true;
+		}
+		Ok(started)
+	}
+
+	/// Emits the comment and the indentation of its following lines, which already start with `#`.
+	fn comment(&mut self, comment: &BlockComment) -> fmt::Result {
+		for (i, line) in comment.comment.lines().enumerate() {
+			if i == 0 {
+				writeln!(self.writer, "# {}", line)?;
+			} else {
+				self.indent()?;
+				writeln!(self.writer, "{}", line)?;
+			}
 		}
-		writeln!(self.writer, "}}")?;
 		Ok(())
 	}
 
-	fn instruction(&mut self, instruction: ast::Instruction) -> io::Result<()> {
+	/// Emits an empty line before the code on `line` if the original code had one and it isn't at the start of the
+	/// block, i.e. nothing was `started` yet.
+	fn empty_line(&mut self, line: u32, started: bool) -> fmt::Result {
+		if started && self.empty_lines.is_some_and(|lines| lines.contains(&line)) {
+			writeln!(self.writer)?;
+		}
+		Ok(())
+	}
+
+	fn struct_(&mut self, struct_: &ast::Struct) -> fmt::Result {
+		self.doc(&struct_.doc)?;
+		if struct_.exported {
+			write!(self.writer, "export ")?;
+		}
+		write!(self.writer, "struct {}", *struct_.name)?;
+		// Comments are kept before the field they preceded or at the end of its line, like in blocks
+		let original = self.block_after(&struct_.name.position);
+		let mut comments = original.into_iter().flat_map(|original| &original.comments).peekable();
+		let start = |field: &ast::struct_::Field| {
+			field.doc.as_ref().map_or(&field.name.position, |doc| &doc.position).byte_range().start
+		};
+		self.opening_brace(&mut comments, struct_.fields.first().map(start))?;
+		self.indentation += 1;
+		let mut previous_line = None;
+		for field in &struct_.fields {
+			self.comments(&mut comments, Some(start(field)), previous_line)?;
+			self.doc(&field.doc)?;
+			self.indent()?;
+			write!(self.writer, "{}: ", *field.name)?;
+			self.data_type(&field.data_type)?;
+			if let Some(default) = &field.default {
+				write!(self.writer, " = ")?;
+				self.operand(default)?;
+			}
+			if self.config.trailing_commas {
+				write!(self.writer, ",")?;
+			}
+			previous_line = Some(field.name.position.position.start.line);
+		}
+		self.comments(&mut comments, None, previous_line)?;
+		self.indentation -= 1;
+		writeln!(self.writer, "}}")
+	}
+
+	/// Emits an indented instruction, without a line break at the end.
+	fn instruction(&mut self, instruction: &ast::Instruction) -> fmt::Result {
+		self.indent()?;
 		match instruction {
-			ast::Instruction::Expression(expression) => self.expression(expression),
-			ast::Instruction::Statement(statement) => self.statement(statement),
-			ast::Instruction::IfElse(if_else) => self.if_else(*if_else),
-			ast::Instruction::WhileLoop(while_loop) => self.while_loop(*while_loop),
+			ast::Instruction::Expression(expression) => self.line(|this| this.expression(expression)),
+			ast::Instruction::Statement(statement) => match invalid_statement(statement) {
+				Some(code) => write!(self.writer, "{}", code),
+				None => self.line(|this| this.statement(statement)),
+			},
+			ast::Instruction::IfElse(if_else) => self.if_else(if_else),
+			ast::Instruction::WhileLoop(while_loop) => self.while_loop(while_loop),
+			// Code that could not be parsed is kept as it is
+			ast::Instruction::Invalid(position) => write!(self.writer, "{}", position.code()),
 		}
 	}
 
-	fn expression(&mut self, expression: ast::Expression) -> io::Result<()> {
+	fn expression(&mut self, expression: &ast::Expression) -> fmt::Result {
 		match expression {
 			Expression::BinaryExpression(binary_expression) => self.binary_expression(binary_expression),
+			Expression::FieldAccess(field_access) => self.field_access(field_access),
 			Expression::FunctionCall(function_call) => self.function_call(function_call),
 			Expression::Number(number) => self.number(number),
+			Expression::String(string) => self.string(string),
 			Expression::Variable(variable) => self.variable(variable),
+			Expression::Null(_) => write!(self.writer, "null"),
+			Expression::Try(try_expression) => {
+				write!(self.writer, "try ")?;
+				self.operand(&try_expression.value)?;
+				write!(self.writer, " else ")?;
+				self.operand(&try_expression.fallback)
+			},
+			Expression::Error(_) => write!(self.writer, "error"),
+			Expression::Invalid(position) => write!(self.writer, "{}", position.code()),
 		}
 	}
 
-	fn binary_expression(&mut self, binary_expression: ast::expression::BinaryExpression) -> io::Result<()> {
-		self.expression(*binary_expression.lhs)?;
+	/// Emits an expression where the parser only accepts a primary expression, e.g. a call parameter. Binary
+	/// expressions are therefore enclosed in parentheses.
+	fn operand(&mut self, expression: &ast::Expression) -> fmt::Result {
+		match expression {
+			Expression::BinaryExpression(_) => {
+				write!(self.writer, "(")?;
+				self.expression(expression)?;
+				write!(self.writer, ")")
+			},
+			_ => self.expression(expression),
+		}
+	}
+
+	fn binary_expression(&mut self, binary_expression: &ast::expression::BinaryExpression) -> fmt::Result {
+		self.operand(&binary_expression.lhs)?;
 		let operator = match *binary_expression.operator {
 			ast::expression::BinaryOperator::Add => "+",
 			ast::expression::BinaryOperator::Subtract => "-",
 			ast::expression::BinaryOperator::Multiply => "*",
 			ast::expression::BinaryOperator::Divide => "/",
+			ast::expression::BinaryOperator::Modulus => "mod",
 			BinaryOperator::Less => "<",
 			BinaryOperator::Greater => ">",
-			BinaryOperator::Equal => "==",
+			BinaryOperator::LessEqual => "<=",
+			BinaryOperator::GreaterEqual => ">=",
+			BinaryOperator::Equal => "=",
 			BinaryOperator::NotEqual => "=/=",
+			BinaryOperator::LogicalAnd => "and",
+			BinaryOperator::LogicalOr => "or",
 		};
 		write!(self.writer, " {} ", operator)?;
-		self.expression(*binary_expression.rhs)?;
-		Ok(())
+		self.operand(&binary_expression.rhs)
 	}
 
-	fn function_call(&mut self, function_call: ast::expression::FunctionCall) -> io::Result<()> {
-		write!(self.writer, "{}(", *function_call.name)?;
-		for param in function_call.params {
-			self.expression(param)?;
+	fn field_access(&mut self, field_access: &ast::expression::FieldAccess) -> fmt::Result {
+		// The field would be accessed on the fallback otherwise
+		if let Expression::Try(_) = *field_access.base {
+			write!(self.writer, "(")?;
+			self.expression(&field_access.base)?;
+			write!(self.writer, ")")?;
+		} else {
+			self.operand(&field_access.base)?;
 		}
-		writeln!(self.writer, ")")?;
-		Ok(())
+		write!(self.writer, ".{}", *field_access.field)
 	}
 
-	fn statement(&mut self, statement: ast::Statement) -> io::Result<()> {
+	fn function_call(&mut self, function_call: &ast::expression::FunctionCall) -> fmt::Result {
+		write!(self.writer, "{}(", *function_call.name)?;
+		self.list(&function_call.params, Self::operand)?;
+		write!(self.writer, ")")
+	}
+
+	fn statement(&mut self, statement: &ast::Statement) -> fmt::Result {
 		match statement {
 			ast::statement::Statement::VariableDeclaration(variable_declaration) => {
 				self.variable_declaration(variable_declaration)
 			},
 			ast::statement::Statement::VariableAssignment(assignment) => self.assignment(assignment),
-			ast::Statement::Return(expression) => self.return_(expression),
+			ast::statement::Statement::FieldAssignment(assignment) => self.field_assignment(assignment),
+			ast::Statement::Return(value) => self.return_(value),
 		}
 	}
 
-	fn variable_declaration(&mut self, variable_declaration: ast::statement::VariableDeclaration) -> io::Result<()> {
-		write!(self.writer, "var {} = ", *variable_declaration.name)?;
-		self.expression(variable_declaration.value)?;
-		writeln!(self.writer)?;
-		Ok(())
+	fn variable_declaration(&mut self, variable_declaration: &ast::statement::VariableDeclaration) -> fmt::Result {
+		write!(self.writer, "var {}: ", *variable_declaration.name)?;
+		self.data_type(&variable_declaration.data_type)?;
+		write!(self.writer, " = ")?;
+		self.operand(&variable_declaration.value)
 	}
 
-	fn assignment(&mut self, assignment: ast::statement::VariableAssignment) -> io::Result<()> {
+	fn assignment(&mut self, assignment: &ast::statement::VariableAssignment) -> fmt::Result {
 		write!(self.writer, "{} = ", *assignment.name)?;
-		self.expression(assignment.value)?;
-		writeln!(self.writer)?;
-		Ok(())
+		self.expression(&assignment.value)
 	}
 
-	fn return_(&mut self, expression: ast::Expression) -> io::Result<()> {
-		write!(self.writer, "return ")?;
-		self.expression(expression)?;
-		writeln!(self.writer)?;
-		Ok(())
+	fn field_assignment(&mut self, assignment: &ast::statement::FieldAssignment) -> fmt::Result {
+		self.field_access(&assignment.target)?;
+		write!(self.writer, " = ")?;
+		self.expression(&assignment.value)
 	}
 
-	fn if_else(&mut self, if_else: ast::IfElse) -> io::Result<()> {
-		// if block, always present
-		write!(self.writer, "if (")?;
-		self.expression(if_else.condition)?;
-		writeln!(self.writer, ") {{")?;
-		for instruction in if_else.if_true {
-			self.instruction(instruction)?;
-		}
-		writeln!(self.writer, "}}")?;
-
-		// else block, optional
-		if if_else.if_false.is_empty() {
-			return Ok(());
+	fn return_(&mut self, value: &Option<ast::Expression>) -> fmt::Result {
+		write!(self.writer, "return")?;
+		if let Some(expression) = value {
+			write!(self.writer, " ")?;
+			self.expression(expression)?;
 		}
-		writeln!(self.writer, "else {{")?;
-		for instruction in if_else.if_false {
-			self.instruction(instruction)?;
-		}
-		writeln!(self.writer, "}}")?;
-
 		Ok(())
 	}
 
-	fn while_loop(&mut self, while_loop: ast::WhileLoop) -> io::Result<()> {
-		write!(self.writer, "while (")?;
-		self.expression(while_loop.condition)?;
-		writeln!(self.writer, ") {{")?;
-		for instruction in while_loop.body {
-			self.instruction(instruction)?;
+	fn if_else(&mut self, if_else: &ast::IfElse) -> fmt::Result {
+		self.line(|this| {
+			write!(this.writer, "if ")?;
+			this.expression(&if_else.condition)
+		})?;
+		let if_true = self.block_after(&if_else.condition.source_position());
+		self.block(&if_else.if_true, if_true)?;
+		let if_false = if_true.and_then(|if_true| self.comments?.block_after(if_true.closing?));
+		// An empty `else` block is left out, unless it contains comments
+		if !if_else.if_false.is_empty() || if_false.is_some_and(|if_false| !if_false.comments.is_empty()) {
+			match self.config.brace_style {
+				BraceStyle::SameLine => write!(self.writer, " else")?,
+				BraceStyle::NextLine => {
+					writeln!(self.writer)?;
+					self.indent()?;
+					write!(self.writer, "else")?;
+				},
+			}
+			self.block(&if_else.if_false, if_false)?;
 		}
-		writeln!(self.writer, "}}")?;
 		Ok(())
 	}
 
-	fn function_argument(&mut self, function_argument: ast::statement::FunctionArgument) -> io::Result<()> {
+	fn while_loop(&mut self, while_loop: &ast::WhileLoop) -> fmt::Result {
+		self.line(|this| {
+			write!(this.writer, "while ")?;
+			this.expression(&while_loop.condition)
+		})?;
+		self.block(&while_loop.body, self.block_after(&while_loop.condition.source_position()))
+	}
+
+	fn function_argument(&mut self, function_argument: &ast::statement::FunctionArgument) -> fmt::Result {
 		write!(self.writer, "{}: ", *function_argument.name)?;
-		self.data_type(function_argument.data_type)?;
-		Ok(())
+		self.data_type(&function_argument.data_type)
 	}
 
-	fn data_type(&mut self, data_type: PositionContainer<ast::statement::DataType>) -> io::Result<()> {
-		match data_type.value {
+	fn data_type(&mut self, data_type: &Spanned<ast::statement::DataType>) -> fmt::Result {
+		match &data_type.value {
 			DataType::Basic(basic_data_type) => self.basic_data_type(basic_data_type),
 			DataType::Struct(struct_name) => self.struct_name(struct_name),
-			DataType::Pointer(pointer) => self.pointer(*pointer),
+			DataType::Pointer(pointer) => self.pointer(pointer),
+			DataType::Optional(inner) => {
+				write!(self.writer, "opt ")?;
+				self.data_type(inner)
+			},
+			DataType::Fallible(inner) => {
+				self.data_type(inner)?;
+				write!(self.writer, " or error")
+			},
+			DataType::Void => write!(self.writer, "void"),
 		}
 	}
 
-	fn basic_data_type(&mut self, basic_data_type: ast::statement::BasicDataType) -> io::Result<()> {
+	fn basic_data_type(&mut self, basic_data_type: &ast::statement::BasicDataType) -> fmt::Result {
 		match basic_data_type {
 			BasicDataType::Int => write!(self.writer, "int"),
 			BasicDataType::Float => write!(self.writer, "float"),
+			BasicDataType::Str => write!(self.writer, "str"),
 		}
 	}
 
-	fn struct_name(&mut self, struct_name: String) -> io::Result<()> {
+	fn struct_name(&mut self, struct_name: &str) -> fmt::Result {
 		write!(self.writer, "{}", struct_name)
 	}
 
-	fn pointer(&mut self, pointer: PositionContainer<ast::statement::DataType>) -> io::Result<()> {
-		write!(self.writer, "ptr")?;
+	fn pointer(&mut self, pointer: &Spanned<ast::statement::DataType>) -> fmt::Result {
+		write!(self.writer, "ptr ")?;
 		self.data_type(pointer)
 	}
 
-	fn number(&mut self, number: ast::expression::Number) -> io::Result<()> {
-		match *number {
-			ast::expression::NumberKind::Int(int) => write!(self.writer, "{}", int)?,
-			ast::expression::NumberKind::Float(float) => write!(self.writer, "{}", float)?,
+	fn number(&mut self, number: &ast::expression::Number) -> fmt::Result {
+		match **number {
+			ast::expression::NumberKind::Int(int) => write!(self.writer, "{}", int),
+			// Debug formatting keeps the decimal point, without which the number would be lexed as int, and writes
+			// very large and small numbers with an exponent
+			ast::expression::NumberKind::Float(float) => write!(self.writer, "{:?}", float),
 		}
-		Ok(())
 	}
 
-	fn variable(&mut self, variable: ast::expression::Variable) -> io::Result<()> {
-		write!(self.writer, "{}", *variable)?;
-		Ok(())
+	fn string(&mut self, string: &ast::expression::StringLiteral) -> fmt::Result {
+		write!(self.writer, "\"")?;
+		for char in string.chars() {
+			match char {
+				'"' => write!(self.writer, "\\\"")?,
+				'\\' => write!(self.writer, "\\\\")?,
+				'\n' => write!(self.writer, "\\n")?,
+				'\r' => write!(self.writer, "\\r")?,
+				'\t' => write!(self.writer, "\\t")?,
+				'\0' => write!(self.writer, "\\0")?,
+				char if char.is_ascii_control() => write!(self.writer, "\\x{:02x}", char as u32)?,
+				char => write!(self.writer, "{}", char)?,
+			}
+		}
+		write!(self.writer, "\"")
+	}
+
+	fn variable(&mut self, variable: &ast::expression::Variable) -> fmt::Result {
+		write!(self.writer, "{}", **variable)
+	}
+}
+
+/// Line on which the code of the `node` starts.
+fn first_line(node: &ast::Node) -> u32 {
+	let position = match node {
+		ast::Node::Function(function) => function.prototype.doc.as_ref().map_or_else(
+			|| {
+				function
+					.annotations
+					.first()
+					.map_or(&function.prototype.name.position, |annotation| &annotation.position)
+			},
+			|doc| &doc.position,
+		),
+		ast::Node::FunctionPrototype(prototype) => {
+			prototype.doc.as_ref().map_or(&prototype.name.position, |doc| &doc.position)
+		},
+		ast::Node::Struct(struct_) => struct_.doc.as_ref().map_or(&struct_.name.position, |doc| &doc.position),
+		ast::Node::Comment(comment) => &comment.position,
+		ast::Node::Invalid(position) => position,
+	};
+	position.position.start.line
+}
+
+/// The code of a statement whose value could not be parsed, which is kept as it is like the value, from the start of the
+/// statement to the end of the value.
+fn invalid_statement(statement: &ast::Statement) -> Option<&str> {
+	let (start, value) = match statement {
+		ast::Statement::VariableDeclaration(declaration) => (&declaration.name.position, &declaration.value),
+		ast::Statement::VariableAssignment(assignment) => (&assignment.name.position, &assignment.value),
+		ast::Statement::FieldAssignment(assignment) => (&assignment.target.source_position(), &assignment.value),
+		ast::Statement::Return(value) => (&value.position, value.value.as_ref()?),
+	};
+	let Expression::Invalid(position) = value else { return None };
+	let text = &position.source.text;
+	let mut start = start.byte_range().start;
+	if let ast::Statement::VariableDeclaration(_) = statement {
+		// The position of a declaration is the one of its name, which follows `var`
+		start = text[..start].trim_end().strip_suffix("var").map_or(start, str::len);
+	}
+	Some(&text[start..position.byte_range().end])
+}
+
+/// Renders the node as FTL code.
+impl fmt::Display for ast::Node {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		Codegen::new(f, FmtConfig::default()).ast_node(self)
+	}
+}
+
+/// Renders the instruction as FTL code, including the trailing newline.
+impl fmt::Display for ast::Instruction {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		Codegen::new(f, FmtConfig::default()).instruction(self)?;
+		writeln!(f)
+	}
+}
+
+/// Renders the expression as FTL code.
+impl fmt::Display for ast::Expression {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		Codegen::new(f, FmtConfig::default()).expression(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		ast,
+		dump::Dumper,
+		emitter::codegen_to_string,
+		lexer::{self, Lexer},
+		source::Source,
+	};
+
+	/// Formats the source code with the FTL emitter.
+	fn format(source_code: &str) -> String {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		codegen_to_string(&Emitter::default(), &mut ast_nodes.into_iter()).unwrap()
+	}
+
+	/// Parses the source code and returns the debug representation of the AST without source positions, which differ
+	/// between original and formatted code.
+	fn ast_without_positions(source_code: &str) -> String {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast = format!("{:?}", crate::parse_source(source, &Dumper::default()).unwrap());
+		let mut result = String::new();
+		let mut rest = ast.as_str();
+		while let Some(start) = rest.find("SourcePositionRange {") {
+			result.push_str(&rest[..start]);
+			let mut depth = 0;
+			let end = rest[start..]
+				.char_indices()
+				.find_map(|(i, char)| {
+					match char {
+						'{' => depth += 1,
+						'}' if depth == 1 => return Some(start + i + 1),
+						'}' => depth -= 1,
+						_ => (),
+					}
+					None
+				})
+				.unwrap();
+			rest = &rest[end..];
+		}
+		result.push_str(rest);
+		result
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let source_code = "# Linked list
+# of numbers
+export struct Node {
+	value: float = 1.0, next: ptr Node
+}
+extern ftl_puts(s: str, n: int)
+@bench
+def main(): int {
+	var x: int = (1 + 2 * (3 - 4))
+	x = (x - 1) - 1
+	if x = 1 { return x } else { if x =/= 2 { ftl_print(\"a\\\"b\\n\") } }
+	while x < sum((x + 1), 2) { x = x + 1 }
+	var n: Node = first()
+	n.next.value = 2.0
+	return n.next
+}
+def first(): Node {
+	return;
+	first()
+}";
+		assert_ne!(ast_without_positions("def f(a: int) { }"), ast_without_positions("def f(b: int) { }"));
+		let formatted = format(source_code);
+		assert_eq!(ast_without_positions(&formatted), ast_without_positions(source_code), "{}", formatted);
+		assert_eq!(format(&formatted), formatted);
+		assert!(formatted.starts_with("# Linked list\n# of numbers\n\nexport struct Node {\n\tvalue: float = 1.0\n"));
+		assert!(formatted.contains("\tif x = 1 {\n\t\treturn x\n\t} else {\n"), "{}", formatted);
+		assert!(formatted.contains("\treturn;\n\tfirst()\n"), "{}", formatted);
+	}
+
+	#[test]
+	fn test_empty_lines() {
+		let source_code = "# Doc\nextern f()\nextern g()\n\n\n# Doc\n\n@bench\ndef h() {\n\n}\nstruct S { x: int }";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens: Vec<_> = Lexer::new(source.clone().iter()).with_trivia().collect::<Result<_, _>>().unwrap();
+		let emitter = Emitter { empty_lines: Some(lexer::lines_after_empty_line(&tokens)), ..Emitter::default() };
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		assert_eq!(
+			codegen_to_string(&emitter, &mut ast_nodes.into_iter()).unwrap(),
+			"# Doc\nextern f()\nextern g()\n\n# Doc\n\n@bench\ndef h() {\n}\nstruct S {\n\tx: int\n}\n"
+		);
+		assert!(format(source_code).starts_with("# Doc\n\nextern f()\n\nextern g()\n\n"));
+
+		// Doc comments belong to the following node, so the empty line is kept before them
+		let source_code = "extern f()\n\n##  Doc\n##\n## comment\n@bench\ndef h() {}\n## Doc\nstruct S {}";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens: Vec<_> = Lexer::new(source.clone().iter()).with_trivia().collect::<Result<_, _>>().unwrap();
+		let emitter = Emitter { empty_lines: Some(lexer::lines_after_empty_line(&tokens)), ..Emitter::default() };
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		assert_eq!(
+			codegen_to_string(&emitter, &mut ast_nodes.into_iter()).unwrap(),
+			"extern f()\n\n##  Doc\n##\n## comment\n@bench\ndef h() {\n}\n## Doc\nstruct S {\n}\n"
+		);
+	}
+
+	#[test]
+	fn test_block_comments() {
+		let source_code = "def main(): int { # Start
+	var x: int = 1   # trailing
+
+
+	# before if
+	if x < 2 { x = 2 # two
+	# end of if
+	} else {
+	# only comment
+	}
+	while x < 3 { # loop
+	x = x + 1 }
+	return x # after return
+}
+# After
+def empty() {
+	# todo
+}";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens: Vec<_> = Lexer::new(source.clone().iter()).with_trivia().collect::<Result<_, _>>().unwrap();
+		let emitter = Emitter {
+			empty_lines: Some(lexer::lines_after_empty_line(&tokens)),
+			comments: Some(lexer::BlockComments::new(&tokens)),
+			..Emitter::default()
+		};
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		assert_eq!(
+			codegen_to_string(&emitter, &mut ast_nodes.into_iter()).unwrap(),
+			"def main(): int { # Start
+	var x: int = 1 # trailing
+
+	# before if
+	if x < 2 {
+		x = 2 # two
+		# end of if
+	} else {
+		# only comment
+	}
+	while x < 3 { # loop
+		x = x + 1
+	}
+	return x # after return
+}
+# After
+def empty() {
+	# todo
+}
+"
+		);
+	}
+
+	#[test]
+	fn test_config() {
+		let source_code = "struct P {
+	## Doc
+	## comment
+	x: int, y: int }
+struct Empty {}
+def f(first: int, second: int): int {
+	if first < 1 { return f((first + 1), second) } else { return 2 }
+}";
+		let config = FmtConfig {
+			indent_width: 2,
+			use_tabs: false,
+			max_line_length: 24,
+			brace_style: BraceStyle::NextLine,
+			trailing_commas: true,
+		};
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let formatted =
+			codegen_to_string(&Emitter { config, ..Emitter::default() }, &mut ast_nodes.into_iter()).unwrap();
+		assert_eq!(
+			formatted,
+			"struct P
+{
+  ## Doc
+  ## comment
+  x: int,
+  y: int,
+}
+
+struct Empty
+{
+}
+
+def f(
+  first: int,
+  second: int
+): int
+{
+  if first < 1
+  {
+    return f(
+      (first + 1),
+      second
+    )
+  }
+  else
+  {
+    return 2
+  }
+}
+"
+		);
+		assert_eq!(ast_without_positions(&formatted), ast_without_positions(source_code));
+	}
+
+	#[test]
+	fn test_display() {
+		let source_code = "extern sqrt_of(x: float): float\ndef main(): int {\n\tvar x: int = (1 + 2)\n\treturn ftl_print(\"a\\\"b\")\n}";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		assert_eq!(ast_nodes[0].to_string(), "extern sqrt_of(x: float): float\n");
+		let ast::Node::Function(main) = &ast_nodes[1] else { panic!("expected function, got {:?}", ast_nodes[1]) };
+		assert_eq!(main.body[0].to_string(), "var x: int = (1 + 2)\n");
+		let ast::Instruction::Statement(ast::Statement::Return(value)) = &main.body[1] else {
+			panic!("expected return, got {:?}", main.body[1])
+		};
+		let expression = value.value.as_ref().unwrap();
+		assert_eq!(expression.to_string(), "ftl_print(\"a\\\"b\")");
 	}
 }
@@ -1,16 +1,380 @@
 //! Generating a specific target code from AST nodes.
+//!
+//! Emitters are selected by name via a [`Registry`]. Besides the [built-in](Registry::with_builtins) emitters,
+//! other crates can [register](Registry::register) their own.
 
 mod c;
 mod ftl;
+mod py;
 
-pub use c::Emitter as C;
-pub use ftl::Emitter as Ftl;
+use std::{cell::RefCell, collections::BTreeMap, io, rc::Rc};
+
+pub use c::{
+	Analysis as CAnalysis, Emitter as C, HeaderEmitter as CHeader, Mangling as CMangling,
+	SharedHeaderEmitter as CSharedHeader, UnitEmitter as CUnit,
+};
+pub use ftl::{BraceStyle, Emitter as Ftl, FmtConfig};
+pub use py::Emitter as Py;
+
+use crate::{ast, format_string, source::Spanned};
 
 /// Generates (target) code from AST nodes.
 pub trait Emitter {
+	/// File extension of the generated code, e.g. `c`.
+	fn file_extension(&self) -> &'static str;
+
 	/// Generate code from the AST nodes and write it to the `writer`.
-	fn codegen(
-		ast_nodes: impl Iterator<Item = crate::ast::Node>,
-		writer: Box<dyn std::io::Write>,
-	) -> std::io::Result<()>;
+	fn codegen(&self, ast_nodes: &mut dyn Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()>;
+}
+
+/// Collection of [`Emitter`]s, which can be looked up by name.
+pub struct Registry {
+	emitters: BTreeMap<String, Box<dyn Emitter>>,
+}
+
+impl Registry {
+	/// Creates a registry without any emitters.
+	pub fn new() -> Self {
+		Self { emitters: BTreeMap::new() }
+	}
+
+	/// Creates a registry containing all emitters built into the compiler.
+	pub fn with_builtins() -> Self {
+		let mut registry = Self::new();
+		registry.register("c", Box::new(C::default()));
+		registry.register("h", Box::new(CHeader));
+		registry.register("ftl", Box::new(Ftl::default()));
+		registry.register("py", Box::new(Py));
+		registry
+	}
+
+	/// Adds the `emitter` under the given `name`, replacing any previously registered emitter with this name.
+	pub fn register(&mut self, name: impl Into<String>, emitter: Box<dyn Emitter>) {
+		self.emitters.insert(name.into(), emitter);
+	}
+
+	/// Looks up the emitter registered under `name`.
+	pub fn get(&self, name: &str) -> Option<&dyn Emitter> {
+		self.emitters.get(name).map(|emitter| emitter.as_ref())
+	}
+
+	/// Returns the names of all registered emitters in alphabetical order.
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.emitters.keys().map(String::as_str)
+	}
+}
+
+impl Default for Registry {
+	fn default() -> Self {
+		Self::with_builtins()
+	}
+}
+
+/// Looks up a [built-in](Registry::with_builtins) emitter by name.
+pub fn get(name: &str) -> Option<Box<dyn Emitter>> {
+	Registry::with_builtins().emitters.remove(name)
+}
+
+/// Generates code with the `emitter` and returns it instead of writing it somewhere.
+pub fn codegen_to_string(emitter: &dyn Emitter, ast_nodes: &mut dyn Iterator<Item = ast::Node>) -> io::Result<String> {
+	let buffer = SharedBuffer::default();
+	emitter.codegen(ast_nodes, Box::new(buffer.clone()))?;
+	String::from_utf8(buffer.0.take()).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Error of emitters for placeholders of code that could not be parsed, which only occur in partial ASTs.
+pub(crate) fn invalid_code(position: &crate::source::SourcePositionRange) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidInput, format!("{}: code that could not be parsed can't be emitted", position))
+}
+
+/// Splits the parameters of a call of [`print`](crate::intrinsic::Intrinsic::Print) into the values to print in order,
+/// with the text of the format string as string literals.
+pub(crate) fn print_values(params: Vec<ast::Expression>) -> io::Result<Vec<ast::Expression>> {
+	let mut params = params.into_iter();
+	let Some(ast::Expression::String(format)) = params.next() else {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			"the format string of `print` must be a string literal",
+		));
+	};
+	let segments = format_string::parse(&format)
+		.map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", format.position, err)))?;
+	segments
+		.into_iter()
+		.map(|segment| match segment {
+			format_string::Segment::Text(text) => {
+				Ok(ast::Expression::String(Spanned::new(text, format.position.clone())))
+			},
+			format_string::Segment::Placeholder => params.next().ok_or_else(|| {
+				io::Error::new(
+					io::ErrorKind::InvalidInput,
+					format!("{}: too few arguments for format string", format.position),
+				)
+			}),
+		})
+		.collect()
+}
+
+/// Emits the `items` with `emit_item`, separated by `emit_separator`, e.g. the parameters of a call.
+///
+/// Both functions get the state of the emitter as `codegen`, since two closures can't borrow it mutably at once.
+pub(crate) fn intersperse<C, T, E>(
+	codegen: &mut C,
+	items: impl IntoIterator<Item = T>,
+	mut emit_separator: impl FnMut(&mut C) -> Result<(), E>,
+	mut emit_item: impl FnMut(&mut C, T) -> Result<(), E>,
+) -> Result<(), E> {
+	for (i, item) in items.into_iter().enumerate() {
+		if i != 0 {
+			emit_separator(codegen)?;
+		}
+		emit_item(codegen, item)?;
+	}
+	Ok(())
+}
+
+/// Writer appending to a shared buffer, so that the output can be inspected after the emitter consumed the writer.
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(pub(crate) Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for SharedBuffer {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.0.borrow_mut().write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::differential::{assert_consistent, Backend, Outcome};
+
+	#[test]
+	fn test_registry() {
+		struct Null;
+		impl Emitter for Null {
+			fn file_extension(&self) -> &'static str {
+				"null"
+			}
+
+			fn codegen(&self, _: &mut dyn Iterator<Item = ast::Node>, _: Box<dyn io::Write>) -> io::Result<()> {
+				Ok(())
+			}
+		}
+
+		let mut registry = Registry::with_builtins();
+		registry.register("null", Box::new(Null));
+
+		assert_eq!(registry.names().collect::<Vec<_>>(), ["c", "ftl", "h", "null", "py"]);
+		assert_eq!(registry.get("c").map(|emitter| emitter.file_extension()), Some("c"));
+		assert_eq!(registry.get("null").map(|emitter| emitter.file_extension()), Some("null"));
+		assert!(registry.get("wasm").is_none());
+	}
+
+	#[test]
+	fn test_modulus() {
+		// Unlike `%` of C and Python, the remainder of `mod` is never negative
+		let outcome = assert_consistent(
+			"def main(): int {
+				print(\"{} {} {} {} {}\\n\", (7 mod 3), ((0 - 7) mod 3), (7 mod (0 - 3)), ((0 - 7) mod (0 - 3)), ((0 - 6) mod 3))
+				print(\"{} {} {}\\n\", ((0 - 7) / 3), (7 / (0 - 3)), (2 + 7 mod 3 * 2))
+				return 0
+			}",
+		);
+		assert_eq!(outcome.stdout, "1 2 1 2 0\n-2 -2 4\n");
+	}
+
+	#[test]
+	fn test_int_range() {
+		// Each result is beyond the range of 32 bits
+		let outcome = assert_consistent(
+			"def main(): int {
+				var big: int = (3000000000 * 3)
+				var none: opt int = null
+				print(\"{} {} {}\\n\", big, (big / (0 - 7)), (pow(2, 40) + abs((0 - big))))
+				dump(none)
+				return 0
+			}",
+		);
+		assert_eq!(outcome.stdout, "9000000000 -1285714285 1108511627776\nnull\n");
+	}
+
+	#[test]
+	fn test_int_overflow() {
+		// Overflows are undefined behavior in C, but wrap around in the interpreter and in Python
+		let source_code = "def main(): int {
+			var max: int = 9223372036854775807
+			print(\"{} {} {}\\n\", (max + 1), (max * 3), (pow(3, 41)))
+			return 0
+		}";
+		for backend in [Backend::Interpreter, Backend::Python].into_iter().filter(Backend::is_available) {
+			let outcome = backend.run(source_code).unwrap();
+			assert_eq!(outcome.stdout, "-9223372036854775808 9223372036854775805 -420491770248316829\n", "{}", backend);
+		}
+	}
+
+	#[test]
+	fn test_void_function() {
+		let outcome = assert_consistent(
+			"def greet(name: str) {
+				ftl_print(name)
+				return;
+				ftl_print(\"unreachable\")
+			}
+			def main(): int {
+				greet(\"hi\")
+				return 0
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: 0, stdout: "hi".to_owned() });
+	}
+
+	#[test]
+	fn test_structs() {
+		let outcome = assert_consistent(
+			"struct Point { x: int = 1, y: float }
+			struct Line { start: Point, end: Point, label: str, next: ptr Line }
+			def length(line: Line): int {
+				line.start.x = 100
+				return line.end.x - line.start.x
+			}
+			def main(): int {
+				var line: Line = Line()
+				line.end.x = line.start.x + 10
+				var copy: Line = line
+				copy.end.x = 0
+				ftl_print(line.label)
+				if line.next {
+					return 1
+				}
+				return length(line) + 100 + line.start.x + copy.end.x
+			}",
+		);
+		assert_eq!(outcome.exit_code, 12);
+	}
+
+	#[test]
+	fn test_optionals() {
+		let outcome = assert_consistent(
+			"def find(n: int): opt int {
+				if n < 0 {
+					return null
+				}
+				return n
+			}
+			def half(x: opt float): float {
+				if x = null {
+					return 0.0
+				}
+				return x / 2.0
+			}
+			def main(): int {
+				var found: opt int = find(5)
+				var missing: opt int = find((0 - 1))
+				var name: opt str = null
+				var sum: int = 0
+				if found =/= null {
+					sum = sum + found
+				}
+				if missing = null {
+					sum = sum + 10
+				}
+				if name = null {
+					sum = sum + 20
+				}
+				if half(null) < 1.0 {
+					sum = sum + 40
+				}
+				return sum
+			}",
+		);
+		assert_eq!(outcome.exit_code, 75);
+	}
+
+	#[test]
+	fn test_comparisons() {
+		let outcome = assert_consistent(
+			"def main(): int {
+				var ints: int = ((1 <= 1) + (2 <= 1) * 2 + (1 >= 1) * 4 + (1 >= 2) * 8 + (2 > 1) * 16)
+				var strings: int = ((\"a\" <= \"a\") + (\"a\" >= \"b\") * 2 + (\"b\" > \"a\") * 4)
+				if 2.5 >= 1.5 {
+					strings = strings + 10
+				}
+				# Comparisons are ints, also when printed
+				print(\"{} {} {}\\n\", (1 < 2), (2.5 = 1.5), (\"a\" =/= \"b\"))
+				dump((3 > 2))
+				return ints * 100 + strings
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: (21 * 100 + 15) % 256, stdout: "1 0 1\n1\n".to_owned() });
+	}
+
+	#[test]
+	fn test_logical_operators() {
+		let outcome = assert_consistent(
+			"def side(value: int): int {
+				print(\"{}\", value)
+				return value
+			}
+
+			def main(): int {
+				var a: int = 2
+				var b: int = 3
+				var result: int = 0
+				if a < 3 and b > 2 {
+					result = result + 1
+				}
+				if a > 3 or b = 3 and a = 2 {
+					result = result + 2
+				}
+				result = result + (side(0) and side(1)) * 4 + (side(2) or side(3)) * 8 + (side(4) and 0.5) * 16
+				return result
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: 1 + 2 + 8 + 16, stdout: "024".to_owned() });
+	}
+
+	#[test]
+	fn test_escapes() {
+		let outcome = assert_consistent(
+			r#"def main(): int {
+				ftl_print("a\tb\\c \"d\" \x41\x7e\n")
+				return str_len("\x01\x1b")
+			}"#,
+		);
+		assert_eq!(outcome, Outcome { exit_code: 2, stdout: "a\tb\\c \"d\" A~\n".to_owned() });
+	}
+
+	#[test]
+	fn test_struct_parameters() {
+		// Python has no references to structs, since it never modifies them in place
+		let source_code = "struct P { x: int = 1 }
+			struct Line { start: P, end: P }
+			def copied(p: P): int {
+				p.x = 100
+				return p.x
+			}
+			def increment(p: ptr P) {
+				p.x = p.x + 1
+			}
+			def twice(p: ptr P) {
+				increment(p)
+				increment(p)
+			}
+			def main(): int {
+				var p: P = P()
+				var line: Line = Line()
+				increment(p)
+				twice(line.end)
+				return copied(p) + p.x * 10 + line.end.x
+			}";
+		for backend in [Backend::Interpreter, Backend::C].into_iter().filter(Backend::is_available) {
+			let outcome =
+				backend.run(source_code).unwrap_or_else(|err| panic!("Backend {} failed: {:#}", backend, err));
+			assert_eq!(outcome.exit_code, 123, "Backend {}", backend);
+		}
+	}
 }
@@ -0,0 +1,123 @@
+//! Names of FTL identifiers in the generated Python code.
+//!
+//! Identifiers get the prefix `ftl_id_` if they are Python keywords or names that the prelude and the generated code
+//! rely on, e.g. the builtin `int` that comparisons are wrapped in or the module `sys`. Since functions, structs and
+//! variables would shadow these, they are mangled alike. Only calls of [runtime](crate::runtime) functions keep their
+//! name, since the prelude implements them.
+
+use std::borrow::Cow;
+
+/// Keywords of Python 3, which can't be used as identifiers. Soft keywords like `match` can.
+const PYTHON_KEYWORDS: &[&str] = &[
+	"False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+	"elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal",
+	"not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+/// Builtins and imported names that the prelude and the generated code use, which identifiers must not shadow.
+const PYTHON_NAMES: &[&str] = &[
+	"AttributeError",
+	"Exception",
+	"NotImplementedError",
+	"OSError",
+	"abs",
+	"bool",
+	"getattr",
+	"int",
+	"isinstance",
+	"len",
+	"max",
+	"min",
+	"object",
+	"pow",
+	"str",
+	"type",
+	"builtins",
+	"math",
+	"os",
+	"random",
+	"sys",
+	"time",
+	"dataclass",
+	"field",
+	"fields",
+	"is_dataclass",
+	"replace",
+];
+
+/// Runtime functions, which the prelude defines under their FTL name.
+const RUNTIME_FUNCTIONS: &[&str] = &[
+	"ftl_print",
+	"ftl_print_int",
+	"ftl_print_float",
+	"ftl_read_line",
+	"ftl_str_concat",
+	"str_len",
+	"str_compare",
+	"substring",
+	"ftl_pow",
+	"random_int",
+	"random_float",
+	"now_millis",
+	"getenv",
+	"exit",
+	"open",
+	"read_line",
+	"write",
+	"close",
+];
+
+/// Prefixes of names that mangled identifiers could collide with. The helpers of the prelude start with `_`, which FTL
+/// identifiers can't.
+const PYTHON_RESERVED_PREFIXES: &[&str] = &["ftl_"];
+
+/// Returns the Python name of a variable, argument, struct or field `name`.
+pub fn identifier(name: &str) -> Cow<'_, str> {
+	let reserved = PYTHON_KEYWORDS.contains(&name)
+		|| PYTHON_NAMES.contains(&name)
+		|| RUNTIME_FUNCTIONS.contains(&name)
+		|| PYTHON_RESERVED_PREFIXES.iter().any(|prefix| name.starts_with(prefix));
+	if reserved {
+		Cow::Owned(format!("ftl_id_{}", name))
+	} else {
+		Cow::Borrowed(name)
+	}
+}
+
+/// Returns the Python name of the function or struct `name`, for its definition as well as for calls.
+pub fn function(name: &str) -> Cow<'_, str> {
+	if RUNTIME_FUNCTIONS.contains(&name) {
+		Cow::Borrowed(name)
+	} else {
+		identifier(name)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ast, runtime};
+
+	#[test]
+	fn test_names() {
+		assert_eq!(identifier("lambda"), "ftl_id_lambda");
+		assert_eq!(identifier("int"), "ftl_id_int");
+		assert_eq!(identifier("sys"), "ftl_id_sys");
+		assert_eq!(identifier("ftl_id_x"), "ftl_id_ftl_id_x");
+		assert_eq!(identifier("substring"), "ftl_id_substring");
+		assert_eq!(identifier("x"), "x");
+		assert_eq!(function("substring"), "substring");
+		assert_eq!(function("ftl_print"), "ftl_print");
+		assert_eq!(function("class"), "ftl_id_class");
+		assert_eq!(function("main"), "main");
+
+		let runtime_functions: Vec<_> = runtime::declarations()
+			.into_iter()
+			.filter_map(|node| match node {
+				ast::Node::FunctionPrototype(prototype) => Some(prototype.name.value),
+				_ => None,
+			})
+			.collect();
+		assert_eq!(runtime_functions, RUNTIME_FUNCTIONS);
+	}
+}
@@ -0,0 +1,639 @@
+//! Python emitter.
+//!
+//! Useful as a quick execution path on systems without a C toolchain and as a cross-check for the C emitter.
+
+mod mangle;
+
+use std::io;
+
+use crate::{
+	ast,
+	ast::{
+		expression::BinaryOperator,
+		statement::{BasicDataType, DataType},
+		Expression,
+	},
+	emitter::{self, intersperse},
+	intrinsic::Intrinsic,
+};
+
+/// Helper functions every generated Python program starts with.
+///
+/// `_ftl_wrap` wraps the results of integer arithmetic around to 64 bits like the interpreter, since Python's integers
+/// are unbounded. `_ftl_div` implements the FTL semantics of `/`, which truncates towards zero for integers like in C, whereas
+/// Python's `//` rounds towards negative infinity. `_ftl_mod` implements `mod`, whose result is never negative, whereas
+/// that of Python's `%` has the sign of the divisor. `_ftl_sqrt`, `_ftl_abs` and `_ftl_pow` implement the
+/// [intrinsics](Intrinsic)
+/// without a Python builtin of the same semantics, `_ftl_print` prints the values of the `print` intrinsic, `_ftl_dump`
+/// prints the value of the `dump` intrinsic like the interpreter with the FTL names of the [mangled](mangle) structs and
+/// fields, and the `ftl_` functions implement the [runtime](crate::runtime).
+/// Failures of functions returning `error` are raised as `_FtlError` and caught by `_ftl_try`.
+const PRELUDE: &str = "\
+import builtins
+import math
+import os
+import random
+import sys
+import time
+from dataclasses import dataclass, field, fields, is_dataclass, replace
+
+
+def _ftl_wrap(value):
+    if isinstance(value, int):
+        return (value + 2**63) % 2**64 - 2**63
+    return value
+
+
+def _ftl_div(lhs, rhs):
+    if isinstance(lhs, int) and isinstance(rhs, int):
+        quotient = abs(lhs) // abs(rhs)
+        return _ftl_wrap(quotient if (lhs >= 0) == (rhs >= 0) else -quotient)
+    return lhs / rhs
+
+
+def _ftl_mod(lhs, rhs):
+    return lhs % abs(rhs)
+
+
+def _ftl_sqrt(x):
+    return math.isqrt(x) if isinstance(x, int) else math.sqrt(x)
+
+
+def _ftl_abs(x):
+    return _ftl_wrap(abs(x))
+
+
+class _FtlError(Exception):
+    pass
+
+
+def _ftl_error():
+    raise _FtlError()
+
+
+def _ftl_try(fallback, function, *args):
+    try:
+        return function(*args)
+    except _FtlError:
+        return fallback()
+
+
+def _ftl_pow(base, exponent):
+    if isinstance(base, int) and exponent < 0:
+        # 1 / base**-exponent, truncated towards zero
+        return base ** (-exponent % 2) if abs(base) == 1 else 0
+    if isinstance(base, int):
+        return _ftl_wrap(pow(base, exponent, 2**64))
+    return base ** exponent
+
+
+def _ftl_print(*values):
+    for value in values:
+        if isinstance(value, float):
+            ftl_print_float(value)
+        elif isinstance(value, int):
+            ftl_print_int(value)
+        else:
+            ftl_print(value)
+
+
+def _ftl_dump_repr(value):
+    if value is None:
+        return 'null'
+    if isinstance(value, float):
+        return '%f' % value
+    if isinstance(value, int):
+        return str(int(value))
+    if isinstance(value, str):
+        return '\"' + value + '\"'
+    if is_dataclass(value):
+        values = ', '.join(_ftl_name(f.name) + ': ' + _ftl_dump_repr(getattr(value, f.name)) for f in fields(value))
+        return _ftl_name(type(value).__name__) + (' { ' + values + ' }' if values else ' {}')
+    return '<file>'
+
+
+def _ftl_name(name):
+    return name[len('ftl_id_'):] if name.startswith('ftl_id_') else name
+
+
+def _ftl_dump(value):
+    ftl_print(_ftl_dump_repr(value) + '\\n')
+
+
+def ftl_print(string):
+    sys.stdout.write(string)
+    return len(string.encode())
+
+
+def ftl_print_int(value):
+    return ftl_print(str(value))
+
+
+def ftl_print_float(value):
+    return ftl_print('%f' % value)
+
+
+def ftl_read_line():
+    line = sys.stdin.readline()
+    return line[:-1] if line.endswith('\\n') else line
+
+
+def ftl_str_concat(lhs, rhs):
+    return lhs + rhs
+
+
+def str_len(string):
+    return len(string.encode())
+
+
+def str_compare(lhs, rhs):
+    lhs, rhs = lhs.encode(), rhs.encode()
+    return (lhs > rhs) - (lhs < rhs)
+
+
+def substring(string, start, length):
+    data = string.encode()
+    begin = min(max(start, 0), len(data))
+    end = min(max(start + length, begin), len(data))
+    return data[begin:end].decode(errors='replace')
+
+
+def ftl_pow(base, exponent):
+    return base ** exponent
+
+
+def random_int(lo, hi):
+    return random.randint(lo, hi) if hi >= lo else lo
+
+
+def random_float():
+    return random.random()
+
+
+def getenv(name):
+    return os.environ.get(name, '')
+
+
+def exit(status):
+    sys.exit(status)
+
+
+def open(path, mode):
+    try:
+        return builtins.open(path, mode)
+    except OSError:
+        return None
+
+
+def read_line(file):
+    try:
+        line = file.readline()
+    except (AttributeError, OSError):
+        return ''
+    return line[:-1] if line.endswith('\\n') else line
+
+
+def write(file, text):
+    try:
+        file.write(text)
+    except (AttributeError, OSError):
+        return -1
+    return len(text.encode())
+
+
+def close(file):
+    try:
+        file.close()
+    except (AttributeError, OSError):
+        return -1
+    return 0
+
+
+_ftl_start = None
+
+
+def now_millis():
+    global _ftl_start
+    if _ftl_start is None:
+        _ftl_start = time.monotonic()
+    return int((time.monotonic() - _ftl_start) * 1000)
+";
+
+/// Python implementations of functions from the C standard library, which may be declared with `extern`.
+const EXTERN_FUNCTIONS: &[(&str, &str)] = &[("exit", "sys.exit(status)")];
+
+/// Number of spaces per indentation level.
+const INDENTATION_WIDTH: usize = 4;
+
+/// Emits Python 3 code.
+pub struct Emitter;
+
+impl super::Emitter for Emitter {
+	fn file_extension(&self) -> &'static str {
+		"py"
+	}
+
+	fn codegen(&self, ast_nodes: &mut dyn Iterator<Item = ast::Node>, writer: Box<dyn io::Write>) -> io::Result<()> {
+		let mut this = Codegen { writer, indentation: 0, references: Vec::new() };
+
+		write!(this.writer, "{}", PRELUDE)?;
+
+		for ast_node in ast_nodes {
+			writeln!(this.writer, "\n")?;
+			this.ast_node(ast_node)?;
+		}
+
+		// Use the return value of main as exit code like C does
+		writeln!(this.writer, "\n\nif __name__ == \"__main__\":")?;
+		writeln!(this.writer, "{}sys.exit({}())", " ".repeat(INDENTATION_WIDTH), mangle::function("main"))?;
+		Ok(())
+	}
+}
+
+/// State while emitting Python code.
+struct Codegen {
+	writer: Box<dyn io::Write>,
+	/// Current indentation level.
+	indentation: usize,
+	/// Names of the `ptr` arguments of the current function that structs are passed to by reference.
+	references: Vec<String>,
+}
+
+/// Each of the functions in this impl block is responsible for emitting the corresponding AST node.
+impl Codegen {
+	fn ast_node(&mut self, node: ast::Node) -> io::Result<()> {
+		match node {
+			ast::Node::Function(function) => self.function(function),
+			ast::Node::Struct(struct_) => self.struct_(struct_),
+			ast::Node::FunctionPrototype(prototype) => self.extern_function(prototype),
+			// Following lines of the comment already start with `#`
+			ast::Node::Comment(comment) => writeln!(self.writer, "# {}", *comment),
+			ast::Node::Invalid(position) => Err(emitter::invalid_code(&position)),
+		}
+	}
+
+	/// Writes the indentation for a new line.
+	fn indent(&mut self) -> io::Result<()> {
+		write!(self.writer, "{}", " ".repeat(self.indentation * INDENTATION_WIDTH))
+	}
+
+	fn function(&mut self, function: ast::FunctionDefinition) -> io::Result<()> {
+		let args: Vec<_> = function.prototype.args.iter().map(|arg| mangle::identifier(&arg.name)).collect();
+		self.references = function
+			.prototype
+			.args
+			.iter()
+			.filter(
+				|arg| matches!(&arg.data_type.value, DataType::Pointer(pointee) if matches!(pointee.value, DataType::Struct(_))),
+			)
+			.map(|arg| arg.name.value.clone())
+			.collect();
+		writeln!(self.writer, "def {}({}):", mangle::function(&function.prototype.name), args.join(", "))?;
+		self.block(function.body)
+	}
+
+	fn extern_function(&mut self, prototype: ast::FunctionPrototype) -> io::Result<()> {
+		let args: Vec<_> = prototype.args.iter().map(|arg| mangle::identifier(&arg.name)).collect();
+		writeln!(self.writer, "def {}({}):", mangle::function(&prototype.name), args.join(", "))?;
+		self.indentation += 1;
+		self.indent()?;
+		match EXTERN_FUNCTIONS.iter().find(|(name, _)| *name == prototype.name.value) {
+			Some((_, implementation)) => writeln!(self.writer, "{}", implementation)?,
+			None => writeln!(
+				self.writer,
+				"raise NotImplementedError(\"extern function `{}` is not available in Python\")",
+				*prototype.name
+			)?,
+		}
+		self.indentation -= 1;
+		Ok(())
+	}
+
+	fn struct_(&mut self, struct_: ast::Struct) -> io::Result<()> {
+		writeln!(self.writer, "@dataclass")?;
+		writeln!(self.writer, "class {}:", mangle::function(&struct_.name))?;
+		self.indentation += 1;
+		if struct_.fields.is_empty() {
+			self.indent()?;
+			writeln!(self.writer, "pass")?;
+		}
+		// Every field has a default value, since dataclass fields with defaults can't be followed by fields without
+		for field in struct_.fields {
+			self.indent()?;
+			write!(self.writer, "{}: object = ", mangle::identifier(&field.name))?;
+			match (field.default, field.data_type.value) {
+				(Some(default), _) => self.expression(default)?,
+				(None, DataType::Basic(BasicDataType::Int)) => write!(self.writer, "0")?,
+				(None, DataType::Basic(BasicDataType::Float)) => write!(self.writer, "0.0")?,
+				(None, DataType::Basic(BasicDataType::Str)) => write!(self.writer, "\"\"")?,
+				// Mutable defaults have to be created for each instance. The lambda allows structs defined later.
+				(None, DataType::Struct(name)) => {
+					write!(self.writer, "field(default_factory=lambda: {}())", mangle::function(&name))?
+				},
+				(None, DataType::Pointer(_) | DataType::Optional(_) | DataType::Fallible(_) | DataType::Void) => {
+					write!(self.writer, "None")?
+				},
+			}
+			writeln!(self.writer)?;
+		}
+		self.indentation -= 1;
+		Ok(())
+	}
+
+	/// Emits an indented block. Empty blocks are filled with `pass`, since Python does not allow empty blocks.
+	fn block(&mut self, block: ast::Block) -> io::Result<()> {
+		self.indentation += 1;
+		if block.is_empty() {
+			self.indent()?;
+			writeln!(self.writer, "pass")?;
+		}
+		for instruction in block {
+			self.instruction(instruction)?;
+		}
+		self.indentation -= 1;
+		Ok(())
+	}
+
+	fn instruction(&mut self, instruction: ast::Instruction) -> io::Result<()> {
+		self.indent()?;
+		match instruction {
+			ast::Instruction::Expression(expression) => {
+				self.expression(expression)?;
+				writeln!(self.writer)
+			},
+			ast::Instruction::Statement(statement) => self.statement(statement),
+			ast::Instruction::IfElse(if_else) => self.if_else(*if_else),
+			ast::Instruction::WhileLoop(while_loop) => self.while_loop(*while_loop),
+			ast::Instruction::Invalid(position) => Err(emitter::invalid_code(&position)),
+		}
+	}
+
+	fn expression(&mut self, expression: ast::Expression) -> io::Result<()> {
+		match expression {
+			Expression::BinaryExpression(binary_expression) => self.binary_expression(binary_expression),
+			Expression::FieldAccess(field_access) => self.field_access(field_access),
+			Expression::FunctionCall(function_call) => self.function_call(function_call),
+			Expression::Number(number) => self.number(number),
+			Expression::String(string) => self.string(string),
+			Expression::Variable(variable) => self.variable(variable),
+			Expression::Null(_) => write!(self.writer, "None"),
+			Expression::Try(try_expression) => self.try_(try_expression),
+			Expression::Error(_) => write!(self.writer, "_ftl_error()"),
+			Expression::Invalid(position) => Err(emitter::invalid_code(&position)),
+		}
+	}
+
+	/// Emits `try f(a) else fallback` as `_ftl_try(lambda: fallback, f, a)`, so that the arguments are evaluated
+	/// before and the fallback only after the failure.
+	fn try_(&mut self, try_expression: ast::expression::TryExpression) -> io::Result<()> {
+		write!(self.writer, "_ftl_try(lambda: ")?;
+		self.expression(*try_expression.fallback)?;
+		match *try_expression.value {
+			Expression::FunctionCall(function_call) => {
+				write!(self.writer, ", {}", mangle::function(&function_call.name))?;
+				for param in function_call.params {
+					write!(self.writer, ", ")?;
+					self.expression(param)?;
+				}
+			},
+			value => {
+				write!(self.writer, ", lambda: ")?;
+				self.expression(value)?;
+			},
+		}
+		write!(self.writer, ")")
+	}
+
+	fn binary_expression(&mut self, binary_expression: ast::expression::BinaryExpression) -> io::Result<()> {
+		if matches!(*binary_expression.operator, BinaryOperator::Divide | BinaryOperator::Modulus) {
+			let function = if *binary_expression.operator == BinaryOperator::Divide { "_ftl_div" } else { "_ftl_mod" };
+			write!(self.writer, "{}(", function)?;
+			self.expression(*binary_expression.lhs)?;
+			write!(self.writer, ", ")?;
+			self.expression(*binary_expression.rhs)?;
+			return write!(self.writer, ")");
+		}
+		// Python's `and` and `or` short-circuit too, but evaluate to one of the operands instead of `1` or `0`
+		if binary_expression.operator.is_logical() {
+			let operator = if *binary_expression.operator == BinaryOperator::LogicalAnd { "and" } else { "or" };
+			write!(self.writer, "int(bool(")?;
+			self.expression(*binary_expression.lhs)?;
+			write!(self.writer, ") {} bool(", operator)?;
+			self.expression(*binary_expression.rhs)?;
+			return write!(self.writer, "))");
+		}
+
+		let operator = match *binary_expression.operator {
+			BinaryOperator::Add => "+",
+			BinaryOperator::Subtract => "-",
+			BinaryOperator::Multiply => "*",
+			BinaryOperator::Divide | BinaryOperator::Modulus => unreachable!("handled above"),
+			BinaryOperator::Less => "<",
+			BinaryOperator::Greater => ">",
+			BinaryOperator::LessEqual => "<=",
+			BinaryOperator::GreaterEqual => ">=",
+			BinaryOperator::Equal => "==",
+			BinaryOperator::NotEqual => "!=",
+			BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => unreachable!("handled above"),
+		};
+		// Integer arithmetic wraps around, see `_ftl_wrap`, and comparisons evaluate to `1` or `0` instead of a bool
+		let function = match *binary_expression.operator {
+			BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply => "_ftl_wrap",
+			_ => "int",
+		};
+		write!(self.writer, "{}(", function)?;
+		self.expression(*binary_expression.lhs)?;
+		write!(self.writer, " {} ", operator)?;
+		self.expression(*binary_expression.rhs)?;
+		write!(self.writer, ")")?;
+		Ok(())
+	}
+
+	fn field_access(&mut self, field_access: ast::expression::FieldAccess) -> io::Result<()> {
+		self.expression(*field_access.base)?;
+		write!(self.writer, ".{}", mangle::identifier(&field_access.field))
+	}
+
+	/// Emits `line.start.x = 1` as `line = replace(line, start=replace(line.start, x=1))`. Structs are never modified
+	/// in place, so that they behave like values in C, although Python objects are shared on assignment.
+	fn field_assignment(&mut self, assignment: ast::statement::FieldAssignment) -> io::Result<()> {
+		let mut fields = vec![assignment.target.field.value];
+		let mut base = *assignment.target.base;
+		while let Expression::FieldAccess(field_access) = base {
+			fields.push(field_access.field.value);
+			base = *field_access.base;
+		}
+		fields.reverse();
+		let Expression::Variable(variable) = base else {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "only fields of variables can be assigned"));
+		};
+		// The caller would not see the new struct
+		if self.references.contains(&variable.value) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("fields of `ptr` argument `{}` can't be assigned in Python", *variable),
+			));
+		}
+
+		let mut path = mangle::identifier(&variable).into_owned();
+		write!(self.writer, "{} = ", path)?;
+		for field in &fields {
+			let field = mangle::identifier(field);
+			write!(self.writer, "replace({}, {}=", path, field)?;
+			path = format!("{}.{}", path, field);
+		}
+		self.expression(assignment.value)?;
+		write!(self.writer, "{}", ")".repeat(fields.len()))
+	}
+
+	fn function_call(&mut self, function_call: ast::expression::FunctionCall) -> io::Result<()> {
+		match Intrinsic::from_name(&function_call.name) {
+			Some(Intrinsic::Print) => {
+				write!(self.writer, "_ftl_print(")?;
+				let values = emitter::print_values(function_call.params)?;
+				intersperse(self, values, |this| write!(this.writer, ", "), Self::expression)?;
+				return write!(self.writer, ")");
+			},
+			Some(Intrinsic::Sqrt | Intrinsic::Abs | Intrinsic::Pow | Intrinsic::Dump) => {
+				write!(self.writer, "_ftl_{}(", *function_call.name)?
+			},
+			// The Python builtins min and max have the same semantics as the intrinsics
+			Some(Intrinsic::Min | Intrinsic::Max) => write!(self.writer, "{}(", *function_call.name)?,
+			None => write!(self.writer, "{}(", mangle::function(&function_call.name))?,
+		}
+		intersperse(self, function_call.params, |this| write!(this.writer, ", "), Self::expression)?;
+		write!(self.writer, ")")?;
+		Ok(())
+	}
+
+	fn statement(&mut self, statement: ast::Statement) -> io::Result<()> {
+		match statement {
+			ast::Statement::VariableDeclaration(variable_declaration) => {
+				write!(self.writer, "{} = ", mangle::identifier(&variable_declaration.name))?;
+				self.expression(variable_declaration.value)?;
+			},
+			ast::Statement::VariableAssignment(assignment) => {
+				write!(self.writer, "{} = ", mangle::identifier(&assignment.name))?;
+				self.expression(assignment.value)?;
+			},
+			ast::Statement::FieldAssignment(assignment) => self.field_assignment(assignment)?,
+			ast::Statement::Return(value) => {
+				write!(self.writer, "return")?;
+				if let Some(expression) = value.value {
+					write!(self.writer, " ")?;
+					self.expression(expression)?;
+				}
+			},
+		}
+		writeln!(self.writer)
+	}
+
+	fn if_else(&mut self, if_else: ast::IfElse) -> io::Result<()> {
+		write!(self.writer, "if ")?;
+		self.expression(if_else.condition)?;
+		writeln!(self.writer, ":")?;
+		self.block(if_else.if_true)?;
+
+		// else block, optional
+		if if_else.if_false.is_empty() {
+			return Ok(());
+		}
+		self.indent()?;
+		writeln!(self.writer, "else:")?;
+		self.block(if_else.if_false)
+	}
+
+	fn while_loop(&mut self, while_loop: ast::WhileLoop) -> io::Result<()> {
+		write!(self.writer, "while ")?;
+		self.expression(while_loop.condition)?;
+		writeln!(self.writer, ":")?;
+		self.block(while_loop.body)
+	}
+
+	fn number(&mut self, number: ast::expression::Number) -> io::Result<()> {
+		match *number {
+			ast::expression::NumberKind::Int(int) => write!(self.writer, "{}", int),
+			// Debug formatting keeps the decimal point, so that Python treats the number as float
+			ast::expression::NumberKind::Float(float) => write!(self.writer, "{:?}", float),
+		}
+	}
+
+	fn string(&mut self, string: ast::expression::StringLiteral) -> io::Result<()> {
+		write!(self.writer, "\"")?;
+		for char in string.chars() {
+			match char {
+				'"' => write!(self.writer, "\\\"")?,
+				'\\' => write!(self.writer, "\\\\")?,
+				'\n' => write!(self.writer, "\\n")?,
+				'\r' => write!(self.writer, "\\r")?,
+				'\t' => write!(self.writer, "\\t")?,
+				char if char.is_ascii_control() => write!(self.writer, "\\x{:02x}", char as u32)?,
+				char => write!(self.writer, "{}", char)?,
+			}
+		}
+		write!(self.writer, "\"")
+	}
+
+	fn variable(&mut self, variable: ast::expression::Variable) -> io::Result<()> {
+		write!(self.writer, "{}", mangle::identifier(&variable))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{differential, emitter::codegen_to_string, lexer::Lexer, parser::Parser, source::Source};
+
+	#[test]
+	fn test_codegen() {
+		let source = Arc::new(Source::new(
+			"test.ftl".to_owned(),
+			"def main(): int {\n\tvar x: int = (7 / 2)\n\tif x < 4 {\n\t\treturn x\n\t} else {\n\t}\n\treturn 0\n}"
+				.to_owned(),
+		));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let ast_nodes = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap();
+
+		let output = codegen_to_string(&Emitter, &mut ast_nodes.into_iter()).unwrap();
+
+		let expected_main = "\
+def main():
+    x = _ftl_div(7, 2)
+    if int(x < 4):
+        return x
+    return 0
+";
+		assert!(output.starts_with(PRELUDE));
+		assert!(output.contains(expected_main), "{}", output);
+		assert!(output.ends_with("if __name__ == \"__main__\":\n    sys.exit(main())\n"));
+	}
+
+	#[test]
+	fn test_reserved_names() {
+		// Python keywords, builtins the generated code uses and names of the prelude
+		let outcome = differential::assert_consistent(
+			"struct class {
+				lambda: int
+				int: int
+			}
+			def assert(sys: int, ftl_print: int): int {
+				return (sys + ftl_print)
+			}
+			def main(): int {
+				var int: int = 3
+				var lambda: class = class()
+				lambda.int = assert(int, 2)
+				var substring: int = str_len(\"abc\")
+				dump(lambda)
+				if int < 4 {
+					return (lambda.int + substring)
+				}
+				return 0
+			}",
+		);
+		assert_eq!(outcome.stdout, "class { lambda: 0, int: 5 }\n");
+		assert_eq!(outcome.exit_code, 8);
+	}
+}
@@ -0,0 +1,114 @@
+//! Errors of the library API, so that callers can handle them without downcasting.
+
+use std::io;
+
+use crate::{diagnostic::Diagnostic, lexer, parser, semantic_analyzer, source::SourcePositionRange};
+
+/// Why compiling FTL code failed.
+#[derive(Debug, thiserror::Error)]
+pub enum CompileError {
+	/// The source code contains invalid symbols.
+	#[error("Lexing error: {0}")]
+	Lex(lexer::Error),
+	/// The tokens don't form a valid program.
+	#[error("Parser error: {0}")]
+	Parse(parser::Error),
+	/// The program is syntactically valid, but e.g. calls undefined functions or mixes types.
+	#[error("Semantic analysis error: {0}")]
+	Semantic(semantic_analyzer::Error),
+	/// Reading or writing a file failed, e.g. the source file or an output file.
+	#[error("{context}: {error}")]
+	Io {
+		/// What was done when the error occurred, e.g. "Reading FTL source file `main.ftl`".
+		context: String,
+		error: io::Error,
+	},
+	/// The C compiler rejected the generated code or couldn't link it.
+	#[error("C compiler failed: {stderr}")]
+	CCompiler {
+		/// Error output of the C compiler.
+		stderr: String,
+	},
+	/// The input of the compiler is invalid apart from the source code, e.g. a malformed configuration file.
+	#[error("{0}")]
+	Invalid(String),
+}
+
+impl CompileError {
+	/// Creates a function wrapping an [`io::Error`] into an [`Io`](Self::Io) error with the `context`, for use with
+	/// [`Result::map_err`].
+	pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+		move |error| CompileError::Io { context: context.into(), error }
+	}
+
+	/// The diagnostic of errors in the source code, or [`None`] for errors that are not about the source code.
+	pub fn diagnostic(&self) -> Option<Diagnostic> {
+		match self {
+			CompileError::Lex(err) => Some(Diagnostic::from(err)),
+			CompileError::Parse(err) => Some(Diagnostic::from(err)),
+			CompileError::Semantic(err) => Some(Diagnostic::from(err)),
+			CompileError::Io { .. } | CompileError::CCompiler { .. } | CompileError::Invalid(_) => None,
+		}
+	}
+
+	/// Position in the source code the error refers to, if any.
+	pub fn position(&self) -> Option<SourcePositionRange> {
+		self.diagnostic()?.primary_label().map(|label| label.position.clone())
+	}
+}
+
+impl From<lexer::Error> for CompileError {
+	fn from(err: lexer::Error) -> Self {
+		CompileError::Lex(err)
+	}
+}
+
+impl From<parser::Error> for CompileError {
+	fn from(err: parser::Error) -> Self {
+		CompileError::Parse(err)
+	}
+}
+
+impl From<semantic_analyzer::Error> for CompileError {
+	fn from(err: semantic_analyzer::Error) -> Self {
+		CompileError::Semantic(err)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{dump::Dumper, semantic_analyzer::CheckConfig, source::Source};
+
+	fn compile(source_code: &str) -> CompileError {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap_err()
+	}
+
+	#[test]
+	fn test_compile_error() {
+		let err = compile("def main(): int { return $ }");
+		assert!(matches!(err, CompileError::Lex(_)));
+		assert_eq!(err.position().unwrap().to_string(), "test.ftl:1:26");
+
+		let err = compile("def main(): int { return }}");
+		assert!(matches!(err, CompileError::Parse(_)));
+
+		let err = compile("def main(): int { return undefined() }");
+		assert!(matches!(err, CompileError::Semantic(_)));
+		assert_eq!(err.position().unwrap().to_string(), "test.ftl:1:26");
+		assert!(err.to_string().starts_with("Semantic analysis error: test.ftl:1:26: UndefinedFunctionCall"));
+
+		let err = crate::compiler_pipeline(
+			"missing.ftl".as_ref(),
+			&CheckConfig::default(),
+			&Dumper::default(),
+			&mut Vec::new(),
+		);
+		let err = err.unwrap_err();
+		assert!(matches!(err, CompileError::Io { .. }));
+		assert!(err.diagnostic().is_none());
+	}
+}
@@ -0,0 +1,12 @@
+# Computes the 10th Fibonacci number recursively and exits with it.
+
+def fib(n: int): int {
+	if n < 2 {
+		return n
+	}
+	return fib((n - 1)) + fib((n - 2))
+}
+
+def main(): int {
+	return fib(10)
+}
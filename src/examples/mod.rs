@@ -0,0 +1,49 @@
+//! Example programs bundled with the compiler, which `ftl examples` lists and runs.
+//!
+//! Each example starts with a comment describing what it does. They are run by every backend in the tests, so they
+//! double as smoke tests of the whole compiler pipeline.
+
+/// An example program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Example {
+	/// Name to select the example with, e.g. `ftl examples fib`.
+	pub name: &'static str,
+	/// FTL source code of the example.
+	pub source: &'static str,
+}
+
+impl Example {
+	/// The first line of the comment at the start of the source code.
+	pub fn description(&self) -> &'static str {
+		self.source.lines().next().and_then(|line| line.strip_prefix("# ")).unwrap_or_default()
+	}
+}
+
+/// All examples, from the simplest to the most complex.
+pub const ALL: &[Example] = &[
+	Example { name: "fib", source: include_str!("fib.ftl") },
+	Example { name: "primes", source: include_str!("primes.ftl") },
+	Example { name: "structs", source: include_str!("structs.ftl") },
+];
+
+/// Returns the example called `name`.
+pub fn get(name: &str) -> Option<&'static Example> {
+	ALL.iter().find(|example| example.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::differential;
+
+	#[test]
+	fn test_examples() {
+		let expected_exit_codes = [("fib", 55), ("primes", 25), ("structs", 42)];
+		assert_eq!(ALL.len(), expected_exit_codes.len());
+		for (name, exit_code) in expected_exit_codes {
+			let example = get(name).unwrap();
+			assert!(!example.description().is_empty(), "{} has no description", name);
+			assert_eq!(differential::assert_consistent(example.source).exit_code, exit_code, "{}", name);
+		}
+	}
+}
@@ -0,0 +1,29 @@
+# Counts the prime numbers below 100 by trial division and exits with the count.
+
+def is_divisible(n: int, divisor: int): int {
+	return (n / divisor) * divisor = n
+}
+
+def is_prime(n: int): int {
+	if n < 2 {
+		return 0
+	}
+	var divisor: int = 2
+	while divisor * divisor < (n + 1) {
+		if is_divisible(n, divisor) {
+			return 0
+		}
+		divisor = divisor + 1
+	}
+	return 1
+}
+
+def main(): int {
+	var count: int = 0
+	var n: int = 0
+	while n < 100 {
+		count = count + is_prime(n)
+		n = n + 1
+	}
+	return count
+}
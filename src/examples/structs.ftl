@@ -0,0 +1,27 @@
+# Computes the area of a rectangle spanned by two points and exits with it.
+
+struct Point {
+	x: int
+	y: int
+}
+
+struct Rectangle {
+	top_left: Point
+	bottom_right: Point
+}
+
+def area(rectangle: Rectangle): int {
+	var width: int = (rectangle.bottom_right.x - rectangle.top_left.x)
+	var height: int = (rectangle.bottom_right.y - rectangle.top_left.y)
+	return width * height
+}
+
+def main(): int {
+	var rectangle: Rectangle = Rectangle()
+	rectangle.top_left.x = 1
+	rectangle.top_left.y = 2
+	rectangle.bottom_right.x = 7
+	rectangle.bottom_right.y = 9
+	ftl_print("Computing the area\n")
+	return area(rectangle)
+}
@@ -0,0 +1,95 @@
+//! Format strings of [`print`](crate::intrinsic::Intrinsic::Print), in which each `{}` is replaced by the next
+//! argument. `{{` and `}}` stand for literal braces.
+//!
+//! Arguments are formatted like this in every backend: `int`s in decimal, `float`s with six decimal places like `%f`
+//! in C, and `str`s as they are.
+
+use std::fmt;
+
+/// A part of a format string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+	/// Text that is printed as it is.
+	Text(String),
+	/// `{}`, which is replaced by the next argument.
+	Placeholder,
+}
+
+/// A brace in a format string that is neither part of `{}` nor escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnmatchedBrace {
+	/// The brace.
+	pub brace: char,
+	/// Index of the brace in chars.
+	pub index: usize,
+}
+
+impl fmt::Display for UnmatchedBrace {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"unmatched `{}` at character {}, write `{}{}` for a literal brace",
+			self.brace,
+			self.index + 1,
+			self.brace,
+			self.brace
+		)
+	}
+}
+
+/// Splits the `format` string into text and placeholders. Adjacent text is merged and empty text is left out.
+pub fn parse(format: &str) -> Result<Vec<Segment>, UnmatchedBrace> {
+	let mut segments = Vec::new();
+	let mut text = String::new();
+	let mut chars = format.chars().enumerate().peekable();
+	while let Some((index, char)) = chars.next() {
+		match (char, chars.peek().map(|(_, next)| *next)) {
+			('{', Some('}')) => {
+				chars.next();
+				if !text.is_empty() {
+					segments.push(Segment::Text(std::mem::take(&mut text)));
+				}
+				segments.push(Segment::Placeholder);
+			},
+			('{', Some('{')) | ('}', Some('}')) => {
+				chars.next();
+				text.push(char);
+			},
+			('{' | '}', _) => return Err(UnmatchedBrace { brace: char, index }),
+			_ => text.push(char),
+		}
+	}
+	if !text.is_empty() {
+		segments.push(Segment::Text(text));
+	}
+	Ok(segments)
+}
+
+/// Number of placeholders in the `segments`, i.e. of arguments the format string expects.
+pub fn placeholders(segments: &[Segment]) -> usize {
+	segments.iter().filter(|segment| **segment == Segment::Placeholder).count()
+}
+
+/// Formats a `float` argument.
+pub fn float(float: f64) -> String {
+	format!("{:.6}", float)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		let text = |text: &str| Segment::Text(text.to_owned());
+		assert_eq!(
+			parse("x = {}, {{y}} = {}{}"),
+			Ok(vec![text("x = "), Segment::Placeholder, text(", {y} = "), Segment::Placeholder, Segment::Placeholder])
+		);
+		assert_eq!(parse(""), Ok(vec![]));
+		assert_eq!(placeholders(&parse("{} and {}").unwrap()), 2);
+		assert_eq!(parse("a { b"), Err(UnmatchedBrace { brace: '{', index: 2 }));
+		assert_eq!(parse("a }"), Err(UnmatchedBrace { brace: '}', index: 2 }));
+		assert_eq!(float(2.5), "2.500000");
+	}
+}
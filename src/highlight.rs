@@ -0,0 +1,299 @@
+//! Syntax highlighting of FTL source code, e.g. for documentation.
+//!
+//! The [tokens](crate::token) are [classified](classify) by their meaning, which also takes their neighbours into
+//! account: an identifier followed by `(` is a function, one following `:` or `ptr` is a type. The classification
+//! is then rendered as standalone [HTML](Format::Html) or with [ANSI](Format::Ansi) escape codes for terminals.
+//! Code between the tokens, i.e. whitespace, is kept as it is.
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use crate::{
+	lexer::{self, Lexer},
+	source::Source,
+	token::{Token, TokenKind},
+	CompileError,
+};
+
+/// Meaning of a token for highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+	/// `def`, `if`, `return`, ...
+	Keyword,
+	/// Basic data types and struct names.
+	Type,
+	/// Names of defined or called functions.
+	Function,
+	/// Variables, arguments and fields.
+	Variable,
+	/// `@` and the name of an annotation.
+	Annotation,
+	/// Int and float literals.
+	Number,
+	/// String literals.
+	String,
+	/// Comments.
+	Comment,
+	/// Arithmetic and comparison operators.
+	Operator,
+	/// Parentheses, braces, commas, ...
+	Punctuation,
+}
+
+impl TokenClass {
+	/// Name of the CSS class in the [HTML output](Format::Html).
+	pub fn css_class(&self) -> &'static str {
+		match self {
+			TokenClass::Keyword => "keyword",
+			TokenClass::Type => "type",
+			TokenClass::Function => "function",
+			TokenClass::Variable => "variable",
+			TokenClass::Annotation => "annotation",
+			TokenClass::Number => "number",
+			TokenClass::String => "string",
+			TokenClass::Comment => "comment",
+			TokenClass::Operator => "operator",
+			TokenClass::Punctuation => "punctuation",
+		}
+	}
+
+	/// ANSI escape code selecting the color of the [terminal output](Format::Ansi), if the class is colored.
+	fn ansi_color(&self) -> Option<&'static str> {
+		match self {
+			TokenClass::Keyword => Some("\x1b[35m"),
+			TokenClass::Type => Some("\x1b[33m"),
+			TokenClass::Function => Some("\x1b[34m"),
+			TokenClass::Annotation => Some("\x1b[36m"),
+			TokenClass::Number => Some("\x1b[31m"),
+			TokenClass::String => Some("\x1b[32m"),
+			TokenClass::Comment => Some("\x1b[90m"),
+			TokenClass::Variable | TokenClass::Operator | TokenClass::Punctuation => None,
+		}
+	}
+}
+
+/// Output format of [`highlight`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// Standalone HTML document with embedded CSS.
+	Html,
+	/// Text with ANSI escape codes for terminals.
+	Ansi,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"html" => Ok(Format::Html),
+			"ansi" => Ok(Format::Ansi),
+			other => Err(format!("unknown format `{}`, expected one of html, ansi", other)),
+		}
+	}
+}
+
+impl fmt::Display for Format {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Format::Html => write!(f, "html"),
+			Format::Ansi => write!(f, "ansi"),
+		}
+	}
+}
+
+/// Colors of the [HTML output](Format::Html).
+const CSS: &str = "body { background: #fafafa; }
+pre { font-family: monospace; tab-size: 4; }
+.keyword { color: #a626a4; font-weight: bold; }
+.type { color: #c18401; }
+.function { color: #4078f2; }
+.annotation { color: #0184bc; }
+.number { color: #986801; }
+.string { color: #50a14f; }
+.comment { color: #a0a1a7; font-style: italic; }
+";
+
+/// Highlights the `source` in the given `format`. Fails if the source can't be lexed.
+pub fn highlight(source: Arc<Source>, format: Format) -> Result<String, CompileError> {
+	let tokens = Lexer::new(Arc::clone(&source).iter()).collect::<Result<Vec<Token>, lexer::Error>>()?;
+	let classes = classify(&tokens);
+	let text = &*source.text;
+
+	let mut output = String::new();
+	if format == Format::Html {
+		output.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+		output.push_str(&format!("<title>{}</title>\n", escape_html(&source.name)));
+		output.push_str(&format!("<style>\n{}</style>\n</head>\n<body>\n<pre><code>", CSS));
+	}
+	let mut end = 0;
+	for (token, class) in tokens.iter().zip(classes) {
+		let range = token.position.byte_range();
+		let (between, code) = (&text[end..range.start], &text[range.clone()]);
+		end = range.end;
+		match format {
+			Format::Html => {
+				output.push_str(&escape_html(between));
+				output.push_str(&format!("<span class=\"{}\">{}</span>", class.css_class(), escape_html(code)));
+			},
+			Format::Ansi => {
+				output.push_str(between);
+				match class.ansi_color() {
+					Some(color) => output.push_str(&format!("{}{}\x1b[0m", color, code)),
+					None => output.push_str(code),
+				}
+			},
+		}
+	}
+	match format {
+		Format::Html => {
+			output.push_str(&escape_html(&text[end..]));
+			output.push_str("</code></pre>\n</body>\n</html>\n");
+		},
+		Format::Ansi => output.push_str(&text[end..]),
+	}
+	Ok(output)
+}
+
+/// Classifies each of the `tokens`, considering the tokens before and after it.
+pub fn classify(tokens: &[Token]) -> Vec<TokenClass> {
+	let mut classes = Vec::with_capacity(tokens.len());
+	for (i, token) in tokens.iter().enumerate() {
+		let previous = i.checked_sub(1).map(|i| &tokens[i].value);
+		let next = tokens.get(i + 1).map(|token| &token.value);
+		let class = match &token.value {
+			TokenKind::Identifier(name) => match (previous, next) {
+				(Some(TokenKind::At), _) => TokenClass::Annotation,
+				(Some(TokenKind::Colon | TokenKind::Pointer | TokenKind::Optional | TokenKind::Struct), _) => {
+					TokenClass::Type
+				},
+				(_, Some(TokenKind::OpeningParentheses)) => TokenClass::Function,
+				_ if matches!(name.as_str(), "int" | "float" | "str" | "void") => TokenClass::Type,
+				_ => TokenClass::Variable,
+			},
+			TokenKind::Def
+			| TokenKind::Extern
+			| TokenKind::Export
+			| TokenKind::If
+			| TokenKind::Else
+			| TokenKind::While
+			| TokenKind::Pointer
+			| TokenKind::Optional
+			| TokenKind::Null
+			| TokenKind::Try
+			| TokenKind::Or
+			| TokenKind::And
+			| TokenKind::Error
+			| TokenKind::Struct
+			| TokenKind::Var
+			| TokenKind::Return => TokenClass::Keyword,
+			TokenKind::At => TokenClass::Annotation,
+			TokenKind::Int(_) | TokenKind::Float(_) => TokenClass::Number,
+			TokenKind::StringLiteral(_) => TokenClass::String,
+			TokenKind::Comment(_) | TokenKind::DocComment(_) => TokenClass::Comment,
+			TokenKind::Plus
+			| TokenKind::Star
+			| TokenKind::Minus
+			| TokenKind::Slash
+			| TokenKind::Less
+			| TokenKind::Greater
+			| TokenKind::LessEqual
+			| TokenKind::GreaterEqual
+			| TokenKind::Equal
+			| TokenKind::NotEqual
+			| TokenKind::BitOr
+			| TokenKind::BitAnd
+			| TokenKind::Modulus => TokenClass::Operator,
+			TokenKind::OpeningParentheses
+			| TokenKind::ClosingParentheses
+			| TokenKind::OpeningCurlyBraces
+			| TokenKind::ClosingCurlyBraces
+			| TokenKind::OpeningSquareBrackets
+			| TokenKind::ClosingSquareBrackets
+			| TokenKind::Comma
+			| TokenKind::Semicolon
+			| TokenKind::Colon
+			| TokenKind::Dot
+			| TokenKind::EndOfLine => TokenClass::Punctuation,
+		};
+		classes.push(class);
+	}
+	classes
+}
+
+/// Escapes the characters with a special meaning in HTML.
+fn escape_html(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for char in text.chars() {
+		match char {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			char => escaped.push(char),
+		}
+	}
+	escaped
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn source(code: &str) -> Arc<Source> {
+		Arc::new(Source::new("test.ftl".to_owned(), code.to_owned()))
+	}
+
+	#[test]
+	fn test_classify() {
+		let source = source("@bench\ndef f(p: ptr Point): int {\n\treturn g(p.x) < 1\n}");
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let classified: Vec<_> = tokens.iter().map(|token| token.position.code()).zip(classify(&tokens)).collect();
+		assert_eq!(
+			classified,
+			[
+				("@", TokenClass::Annotation),
+				("bench", TokenClass::Annotation),
+				("def", TokenClass::Keyword),
+				("f", TokenClass::Function),
+				("(", TokenClass::Punctuation),
+				("p", TokenClass::Variable),
+				(":", TokenClass::Punctuation),
+				("ptr", TokenClass::Keyword),
+				("Point", TokenClass::Type),
+				(")", TokenClass::Punctuation),
+				(":", TokenClass::Punctuation),
+				("int", TokenClass::Type),
+				("{", TokenClass::Punctuation),
+				("return", TokenClass::Keyword),
+				("g", TokenClass::Function),
+				("(", TokenClass::Punctuation),
+				("p", TokenClass::Variable),
+				(".", TokenClass::Punctuation),
+				("x", TokenClass::Variable),
+				(")", TokenClass::Punctuation),
+				("<", TokenClass::Operator),
+				("1", TokenClass::Number),
+				("}", TokenClass::Punctuation),
+			]
+		);
+	}
+
+	#[test]
+	fn test_highlight() {
+		let code = "# a < b\ndef main(): int {\n\treturn ftl_print(\"<&>\")\n}\n";
+		let html = highlight(source(code), Format::Html).unwrap();
+		assert!(html.starts_with("<!DOCTYPE html>"));
+		assert!(html.contains("<span class=\"comment\"># a &lt; b</span>\n<span class=\"keyword\">def</span> "));
+		assert!(html.contains("\t<span class=\"keyword\">return</span> <span class=\"function\">ftl_print</span>"));
+		assert!(html.contains("<span class=\"string\">&quot;&lt;&amp;&gt;&quot;</span>"));
+
+		let ansi = highlight(source(code), Format::Ansi).unwrap();
+		assert!(ansi.contains("\x1b[35mdef\x1b[0m \x1b[34mmain\x1b[0m(): \x1b[33mint\x1b[0m {\n"), "{:?}", ansi);
+		// Without the escape codes, the code is unchanged
+		let mut plain = ansi.clone();
+		for color in ["\x1b[0m", "\x1b[31m", "\x1b[32m", "\x1b[33m", "\x1b[34m", "\x1b[35m", "\x1b[90m"] {
+			plain = plain.replace(color, "");
+		}
+		assert_eq!(plain, code);
+	}
+}
@@ -0,0 +1,431 @@
+//! Index of the functions and structs that the files of a project define and use, for searches across many files
+//! like `ftl refs`.
+//!
+//! The index is persisted to a file together with a hash of each indexed file, so that later searches only parse the
+//! files that changed since. The file has a line per indexed file, followed by its definitions and the names it uses,
+//! each with the position of the name:
+//!
+//! ```text
+//! ftl-index 1
+//! file 905f693e728d66c8 src/main.ftl
+//! def function main 1:5:4-1:8:7
+//! ref square 2:9:26-2:14:31
+//! ```
+
+use std::{
+	collections::BTreeMap,
+	fmt, fs,
+	path::{Path, PathBuf},
+	str::FromStr,
+	sync::Arc,
+};
+
+use crate::{
+	ast::{self, statement::DataType, Expression},
+	source::{Position, PositionRange, Source, Spanned},
+	CompileError,
+};
+
+/// First line of an index file. Indexes of other versions are rebuilt.
+const HEADER: &str = "ftl-index 1";
+
+/// What a [`Definition`] defines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+	Function,
+	/// Function declared with `extern`.
+	Extern,
+	Struct,
+}
+
+impl fmt::Display for SymbolKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			SymbolKind::Function => "function",
+			SymbolKind::Extern => "extern",
+			SymbolKind::Struct => "struct",
+		};
+		write!(f, "{}", name)
+	}
+}
+
+impl FromStr for SymbolKind {
+	type Err = String;
+
+	fn from_str(kind: &str) -> Result<Self, Self::Err> {
+		match kind {
+			"function" => Ok(SymbolKind::Function),
+			"extern" => Ok(SymbolKind::Extern),
+			"struct" => Ok(SymbolKind::Struct),
+			_ => Err(format!("unknown symbol kind `{}`", kind)),
+		}
+	}
+}
+
+/// A function or struct defined in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+	pub kind: SymbolKind,
+	pub name: String,
+	/// Position of the name.
+	pub position: PositionRange,
+}
+
+/// A call of a function or constructor, or a struct used as data type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+	pub name: String,
+	pub position: PositionRange,
+}
+
+/// The symbols of one file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileSymbols {
+	/// [Hash](hash) of the content of the file when it was indexed.
+	pub hash: u64,
+	pub definitions: Vec<Definition>,
+	pub references: Vec<Reference>,
+}
+
+impl FileSymbols {
+	/// Collects the symbols of the `ast_nodes` of a file with the content `hash`.
+	pub fn new(ast_nodes: &[ast::Node], hash: u64) -> Self {
+		let mut symbols = Self { hash, ..Self::default() };
+		for ast_node in ast_nodes {
+			match ast_node {
+				ast::Node::Function(function) => {
+					symbols.define(SymbolKind::Function, &function.prototype.name);
+					symbols.prototype(&function.prototype);
+					symbols.block(&function.body);
+				},
+				ast::Node::FunctionPrototype(prototype) => {
+					symbols.define(SymbolKind::Extern, &prototype.name);
+					symbols.prototype(prototype);
+				},
+				ast::Node::Struct(struct_) => {
+					symbols.define(SymbolKind::Struct, &struct_.name);
+					for field in &struct_.fields {
+						symbols.data_type(&field.data_type);
+						symbols.expressions(&field.default);
+					}
+				},
+				ast::Node::Comment(_) | ast::Node::Invalid(_) => (),
+			}
+		}
+		symbols
+	}
+
+	fn define(&mut self, kind: SymbolKind, name: &Spanned<String>) {
+		self.definitions.push(Definition { kind, name: name.value.clone(), position: name.position.position.clone() });
+	}
+
+	fn refer(&mut self, name: &str, position: PositionRange) {
+		self.references.push(Reference { name: name.to_owned(), position });
+	}
+
+	fn prototype(&mut self, prototype: &ast::FunctionPrototype) {
+		for arg in &prototype.args {
+			self.data_type(&arg.data_type);
+		}
+		if let Some(return_type) = &prototype.return_type {
+			self.data_type(return_type);
+		}
+	}
+
+	fn data_type(&mut self, data_type: &Spanned<DataType>) {
+		match &data_type.value {
+			DataType::Struct(name) => self.refer(name, data_type.position.position.clone()),
+			DataType::Pointer(inner) | DataType::Optional(inner) | DataType::Fallible(inner) => self.data_type(inner),
+			DataType::Basic(_) | DataType::Void => (),
+		}
+	}
+
+	fn block(&mut self, instructions: &[ast::Instruction]) {
+		for instruction in instructions {
+			match instruction {
+				ast::Instruction::Expression(expression) => self.expression(expression),
+				ast::Instruction::Statement(ast::Statement::VariableDeclaration(declaration)) => {
+					self.data_type(&declaration.data_type);
+					self.expression(&declaration.value);
+				},
+				ast::Instruction::Statement(ast::Statement::VariableAssignment(assignment)) => {
+					self.expression(&assignment.value)
+				},
+				ast::Instruction::Statement(ast::Statement::FieldAssignment(assignment)) => {
+					self.expression(&assignment.target.base);
+					self.expression(&assignment.value);
+				},
+				ast::Instruction::Statement(ast::Statement::Return(value)) => self.expressions(&value.value),
+				ast::Instruction::IfElse(if_else) => {
+					self.expression(&if_else.condition);
+					self.block(&if_else.if_true);
+					self.block(&if_else.if_false);
+				},
+				ast::Instruction::WhileLoop(while_loop) => {
+					self.expression(&while_loop.condition);
+					self.block(&while_loop.body);
+				},
+				ast::Instruction::Invalid(_) => (),
+			}
+		}
+	}
+
+	fn expressions<'a>(&mut self, expressions: impl IntoIterator<Item = &'a Expression>) {
+		expressions.into_iter().for_each(|expression| self.expression(expression));
+	}
+
+	fn expression(&mut self, expression: &Expression) {
+		match expression {
+			Expression::BinaryExpression(binary_expression) => {
+				self.expression(&binary_expression.lhs);
+				self.expression(&binary_expression.rhs);
+			},
+			Expression::FieldAccess(field_access) => self.expression(&field_access.base),
+			Expression::FunctionCall(function_call) => {
+				self.refer(&function_call.name, function_call.name.position.position.clone());
+				self.expressions(&function_call.params);
+			},
+			Expression::Try(try_expression) => {
+				self.expression(&try_expression.value);
+				self.expression(&try_expression.fallback);
+			},
+			Expression::Number(_)
+			| Expression::String(_)
+			| Expression::Variable(_)
+			| Expression::Null(_)
+			| Expression::Error(_)
+			| Expression::Invalid(_) => (),
+		}
+	}
+}
+
+/// The symbols of the indexed files, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolIndex {
+	files: BTreeMap<PathBuf, FileSymbols>,
+}
+
+impl SymbolIndex {
+	/// Reads the index file at `path`. A missing or unreadable index is empty, so that it is rebuilt.
+	pub fn load(path: &Path) -> Self {
+		let index = fs::read_to_string(path).map_err(|err| err.to_string()).and_then(|index| index.parse());
+		index.unwrap_or_else(|err| {
+			tracing::info!(?path, err, "rebuilding symbol index");
+			Self::default()
+		})
+	}
+
+	/// Writes the index to the file at `path`, creating its directory if necessary.
+	pub fn save(&self, path: &Path) -> Result<(), CompileError> {
+		if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+			fs::create_dir_all(directory).map_err(CompileError::io(format!("Creating directory `{:?}`", directory)))?;
+		}
+		crate::build::write_if_changed(path, &self.to_string()).map(|_changed| ())
+	}
+
+	/// Brings the index up to date with the files at `paths`, which replace the previously indexed files. Only files
+	/// whose content changed are parsed, [leniently](crate::parse_lenient) so that files being edited are indexed as
+	/// far as possible.
+	///
+	/// Returns the number of parsed files.
+	pub fn update(&mut self, paths: &[PathBuf]) -> Result<usize, CompileError> {
+		let mut files = BTreeMap::new();
+		let mut parsed = 0;
+		for path in paths {
+			let content =
+				fs::read_to_string(path).map_err(CompileError::io(format!("Reading FTL source file `{:?}`", path)))?;
+			let hash = hash(&content);
+			let symbols = match self.files.remove(path) {
+				Some(symbols) if symbols.hash == hash => symbols,
+				_ => {
+					parsed += 1;
+					let source = Arc::new(Source::new(path.to_string_lossy().into_owned(), content));
+					FileSymbols::new(&crate::parse_lenient(source).0, hash)
+				},
+			};
+			files.insert(path.clone(), symbols);
+		}
+		self.files = files;
+		Ok(parsed)
+	}
+
+	/// The indexed files with their symbols, ordered by path.
+	pub fn files(&self) -> impl Iterator<Item = (&Path, &FileSymbols)> {
+		self.files.iter().map(|(path, symbols)| (path.as_path(), symbols))
+	}
+
+	/// The definitions of functions and structs called `name`, with the file they are in.
+	pub fn definitions<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (&'a Path, &'a Definition)> {
+		self.files().flat_map(move |(path, symbols)| {
+			symbols
+				.definitions
+				.iter()
+				.filter(move |definition| definition.name == name)
+				.map(move |definition| (path, definition))
+		})
+	}
+
+	/// The uses of the function or struct `name`, with the file they are in.
+	pub fn references<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (&'a Path, &'a Reference)> {
+		self.files().flat_map(move |(path, symbols)| {
+			symbols
+				.references
+				.iter()
+				.filter(move |reference| reference.name == name)
+				.map(move |reference| (path, reference))
+		})
+	}
+
+	/// The definitions whose name contains the `query`, ignoring case, e.g. to jump to a symbol of the workspace.
+	pub fn search<'a>(&'a self, query: &str) -> impl Iterator<Item = (&'a Path, &'a Definition)> {
+		let query = query.to_lowercase();
+		self.files().flat_map(move |(path, symbols)| {
+			let query = query.clone();
+			symbols
+				.definitions
+				.iter()
+				.filter(move |definition| definition.name.to_lowercase().contains(&query))
+				.map(move |definition| (path, definition))
+		})
+	}
+}
+
+impl fmt::Display for SymbolIndex {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "{}", HEADER)?;
+		for (path, symbols) in &self.files {
+			writeln!(f, "file {:016x} {}", symbols.hash, path.display())?;
+			for definition in &symbols.definitions {
+				writeln!(
+					f,
+					"def {} {} {}",
+					definition.kind,
+					definition.name,
+					serialize_position(&definition.position)
+				)?;
+			}
+			for reference in &symbols.references {
+				writeln!(f, "ref {} {}", reference.name, serialize_position(&reference.position))?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl FromStr for SymbolIndex {
+	type Err = String;
+
+	fn from_str(index: &str) -> Result<Self, Self::Err> {
+		let mut lines = index.lines();
+		if lines.next() != Some(HEADER) {
+			return Err(format!("index does not start with `{}`", HEADER));
+		}
+		let mut files = BTreeMap::new();
+		let mut current: Option<&mut FileSymbols> = None;
+		for line in lines {
+			let invalid = || format!("invalid line `{}`", line);
+			let mut words = line.splitn(4, ' ');
+			match words.next() {
+				Some("file") => {
+					let hash = words.next().and_then(|hash| u64::from_str_radix(hash, 16).ok()).ok_or_else(invalid)?;
+					// Paths may contain spaces, so the path is the rest of the line
+					let path = line.splitn(3, ' ').nth(2).ok_or_else(invalid)?;
+					let symbols = files.entry(PathBuf::from(path)).or_insert_with(FileSymbols::default);
+					symbols.hash = hash;
+					current = Some(symbols);
+				},
+				Some("def") => {
+					let (Some(kind), Some(name), Some(position)) = (words.next(), words.next(), words.next()) else {
+						return Err(invalid());
+					};
+					let definition = Definition {
+						kind: kind.parse()?,
+						name: name.to_owned(),
+						position: parse_position(position).ok_or_else(invalid)?,
+					};
+					current.as_mut().ok_or_else(invalid)?.definitions.push(definition);
+				},
+				Some("ref") => {
+					let (Some(name), Some(position)) = (words.next(), words.next()) else {
+						return Err(invalid());
+					};
+					let reference =
+						Reference { name: name.to_owned(), position: parse_position(position).ok_or_else(invalid)? };
+					current.as_mut().ok_or_else(invalid)?.references.push(reference);
+				},
+				_ => return Err(invalid()),
+			}
+		}
+		Ok(Self { files })
+	}
+}
+
+/// Serializes the `position` as `line:column:offset-line:column:offset`.
+fn serialize_position(position: &PositionRange) -> String {
+	let serialize = |position: &Position| format!("{}:{}:{}", position.line, position.column, position.offset);
+	format!("{}-{}", serialize(&position.start), serialize(&position.end))
+}
+
+/// Parses a position serialized by [`serialize_position`].
+fn parse_position(position: &str) -> Option<PositionRange> {
+	let parse = |position: &str| -> Option<Position> {
+		let mut numbers = position.split(':').map(str::parse);
+		let position =
+			Position { line: numbers.next()?.ok()?, column: numbers.next()?.ok()?, offset: numbers.next()?.ok()? };
+		numbers.next().is_none().then_some(position)
+	};
+	let (start, end) = position.split_once('-')?;
+	Some(PositionRange { start: parse(start)?, end: parse(end)? })
+}
+
+/// Hash of the content of a file with the FNV-1a algorithm, which, unlike the hasher of the standard library, is the
+/// same in every version of the compiler.
+pub fn hash(content: &str) -> u64 {
+	content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::TempDir;
+
+	#[test]
+	fn test_symbol_index() {
+		let directory = TempDir::new().unwrap();
+		let main_path = directory.path().join("main.ftl");
+		let point_path = directory.path().join("point.ftl");
+		fs::write(&main_path, "def main(): int {\n\tvar p: Point = Point()\n\treturn norm(p)\n}").unwrap();
+		fs::write(&point_path, "struct Point { x: int }\ndef norm(p: ptr Point): int {\n\treturn p.x\n}").unwrap();
+		let paths = [main_path.clone(), point_path.clone()];
+
+		let mut index = SymbolIndex::default();
+		assert_eq!(index.update(&paths).unwrap(), 2);
+		let line = |path: &Path, position: &PositionRange| {
+			(path.file_name().unwrap().to_str().unwrap().to_owned(), position.start.line)
+		};
+		let definitions: Vec<_> =
+			index.definitions("Point").map(|(path, symbol)| line(path, &symbol.position)).collect();
+		assert_eq!(definitions, [("point.ftl".into(), 1)]);
+		let references: Vec<_> = index.references("Point").map(|(path, symbol)| line(path, &symbol.position)).collect();
+		assert_eq!(references, [("main.ftl".into(), 2), ("main.ftl".into(), 2), ("point.ftl".into(), 2)]);
+		let found: Vec<_> =
+			index.search("OR").map(|(_, definition)| (definition.kind, definition.name.as_str())).collect();
+		assert_eq!(found, [(SymbolKind::Function, "norm")]);
+
+		// The index survives saving and loading, and only changed files are parsed again
+		let index_path = directory.path().join("build/symbols.index");
+		index.save(&index_path).unwrap();
+		let mut loaded = SymbolIndex::load(&index_path);
+		assert_eq!(loaded, index);
+		assert_eq!(loaded.update(&paths).unwrap(), 0);
+		fs::write(&main_path, "def main(): int {\n\treturn 0\n}").unwrap();
+		assert_eq!(loaded.update(&paths).unwrap(), 1);
+		assert_eq!(loaded.references("Point").count(), 1);
+		assert_eq!(loaded.update(&paths[1..]).unwrap(), 0);
+		assert_eq!(loaded.files().count(), 1);
+
+		assert_eq!(SymbolIndex::load(&directory.path().join("missing.index")), SymbolIndex::default());
+		assert!("ftl-index 0\n".parse::<SymbolIndex>().is_err());
+		assert!("ftl-index 1\ndef function f 1:1:0-1:1:0\n".parse::<SymbolIndex>().is_err());
+	}
+}
@@ -0,0 +1,101 @@
+use std::ops::Deref;
+
+use crate::{
+	diagnostic::Diagnostic,
+	source::{SourcePositionRange, Spanned},
+};
+
+/// Errors occurring while interpreting a program.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum Error {
+	#[error("MissingMain: The program has no `main` function.")]
+	MissingMain,
+
+	#[error("{}: UndefinedFunction: Function `{}(...)` is not defined.", name.position, name.deref())]
+	UndefinedFunction { name: Spanned<String> },
+
+	#[error("{}: UnsupportedExtern: Extern function `{}(...)` is not available in the interpreter.", name.position, name.deref())]
+	UnsupportedExtern { name: Spanned<String> },
+
+	#[error("{}: NativeFunction: Extern function `{}(...)` failed: {message}", name.position, name.deref())]
+	NativeFunction { name: Spanned<String>, message: String },
+
+	#[error("{}: UnknownField: Field `{}` does not exist.", field.position, field.deref())]
+	UnknownField { field: Spanned<String> },
+
+	#[error("{}: UndeclaredVariable: Variable `{}` is not declared.", name.position, name.deref())]
+	UndeclaredVariable { name: Spanned<String> },
+
+	#[error("{position}: DivisionByZero: Division by zero.")]
+	DivisionByZero { position: SourcePositionRange },
+
+	#[error("{position}: TypeMismatch: Operands of different types.")]
+	TypeMismatch { position: SourcePositionRange },
+
+	#[error("{}: WrongArgumentCount: Function `{}(...)` expects {expected} arguments, but {found} were passed.", name.position, name.deref())]
+	WrongArgumentCount { name: Spanned<String>, expected: usize, found: usize },
+
+	#[error("{}: StackOverflow: Calling `{}(...)` exceeds the maximum call depth of {max_call_depth}.", name.position, name.deref())]
+	StackOverflow { name: Spanned<String>, max_call_depth: usize },
+
+	#[error("{position}: UnhandledFailure: `error` returned to the caller of the program.")]
+	UnhandledFailure { position: SourcePositionRange },
+
+	#[error("{position}: InvalidCode: Code that could not be parsed was executed.")]
+	InvalidCode { position: SourcePositionRange },
+}
+
+impl From<&Error> for Diagnostic {
+	fn from(err: &Error) -> Self {
+		match err {
+			Error::MissingMain => Diagnostic::error("MissingMain", "The program has no `main` function")
+				.with_help("add `def main(): int { ... }`"),
+			Error::UndefinedFunction { name } => {
+				Diagnostic::error("UndefinedFunction", format!("Function `{}(...)` is not defined", name.deref()))
+					.with_primary_label(name.position.clone(), "called here")
+			},
+			Error::UnsupportedExtern { name } => Diagnostic::error(
+				"UnsupportedExtern",
+				format!("Extern function `{}(...)` is not available in the interpreter", name.deref()),
+			)
+			.with_primary_label(name.position.clone(), "called here"),
+			Error::NativeFunction { name, message } => Diagnostic::error(
+				"NativeFunction",
+				format!("Extern function `{}(...)` failed: {}", name.deref(), message),
+			)
+			.with_primary_label(name.position.clone(), "called here"),
+			Error::UnknownField { field } => {
+				Diagnostic::error("UnknownField", format!("Field `{}` does not exist", field.deref()))
+					.with_primary_label(field.position.clone(), "accessed here")
+			},
+			Error::UndeclaredVariable { name } => {
+				Diagnostic::error("UndeclaredVariable", format!("Variable `{}` is not declared", name.deref()))
+					.with_primary_label(name.position.clone(), "used here")
+			},
+			Error::DivisionByZero { position } => Diagnostic::error("DivisionByZero", "Division by zero")
+				.with_primary_label(position.clone(), "divisor is zero"),
+			Error::TypeMismatch { position } => Diagnostic::error("TypeMismatch", "Operands of different types")
+				.with_primary_label(position.clone(), "operator applied here"),
+			Error::WrongArgumentCount { name, expected, found } => Diagnostic::error(
+				"WrongArgumentCount",
+				format!("Function `{}(...)` expects {} arguments, but {} were passed", name.deref(), expected, found),
+			)
+			.with_primary_label(name.position.clone(), "called here"),
+			Error::StackOverflow { name, max_call_depth } => Diagnostic::error(
+				"StackOverflow",
+				format!("Calling `{}(...)` exceeds the maximum call depth of {}", name.deref(), max_call_depth),
+			)
+			.with_primary_label(name.position.clone(), "called here")
+			.with_help("check that the recursion ends, or increase the maximum call depth"),
+			Error::UnhandledFailure { position } => {
+				Diagnostic::error("UnhandledFailure", "`error` returned to the caller of the program")
+					.with_primary_label(position.clone(), "failed here")
+					.with_help("handle the failure with `try ... else ...`")
+			},
+			Error::InvalidCode { position } => {
+				Diagnostic::error("InvalidCode", "Code that could not be parsed was executed")
+					.with_primary_label(position.clone(), "executed here")
+			},
+		}
+	}
+}
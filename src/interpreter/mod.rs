@@ -0,0 +1,1010 @@
+//! Executing an [AST](crate::ast) directly, without generating code first.
+
+mod error;
+mod trace;
+mod value;
+
+use std::{
+	collections::HashMap,
+	env, fs,
+	io::{self, BufRead, BufReader, Write},
+	mem,
+	ops::Deref,
+	rc::Rc,
+	thread,
+	time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub use error::Error;
+pub use trace::{Event, EventKind};
+pub use value::{StructInstance, Value};
+
+use crate::{
+	ast::{
+		self,
+		expression::{BinaryExpression, BinaryOperator, FieldAccess, FunctionCall, NumberKind, TryExpression},
+		statement::{BasicDataType, DataType},
+		Block, Expression, FunctionDefinition, FunctionPrototype,
+	},
+	coverage::Counts,
+	format_string::{self, Segment},
+	intrinsic::Intrinsic,
+	profile::Profile,
+	source::{SourcePositionRange, Spanned},
+};
+
+/// Why the execution of the current function stopped early.
+enum Unwind {
+	/// A `return` was executed, with a value unless the function has no return value.
+	Return(Option<Value>),
+	/// The program called `exit(code)`.
+	Exit(i64),
+	/// A function that can fail returned `error` at the position, which is handled by the nearest `try`.
+	Fail(SourcePositionRange),
+	/// A runtime error occurred.
+	Error(Error),
+}
+
+impl From<Error> for Unwind {
+	fn from(err: Error) -> Self {
+		Unwind::Error(err)
+	}
+}
+
+/// Variables of one function invocation. Each block opens a new scope.
+type CallStackFrame = Vec<HashMap<String, Value>>;
+
+/// Callback of the host application implementing an `extern` function, see [`Interpreter::register`].
+///
+/// Returns the return value of the function, or [`None`] if it has none. An error message stops the program with
+/// a [`NativeFunction`](Error::NativeFunction) error.
+pub type NativeFunction = Box<dyn FnMut(&[Value]) -> Result<Option<Value>, String>>;
+
+/// Default of [`Interpreter::with_max_call_depth`].
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// Stack size reserved for each call of an FTL function by [`with_stack_for`]. A call takes several kilobytes of
+/// the native stack in debug builds.
+const STACK_SIZE_PER_CALL: usize = 64 * 1024;
+
+/// Runs `f` on a thread whose stack is large enough for the interpreter to reach `max_call_depth` nested calls,
+/// instead of overflowing the native stack before a [`StackOverflow`](Error::StackOverflow) is reported.
+///
+/// Fails if the stack can't be allocated, e.g. for a huge `max_call_depth`.
+pub fn with_stack_for<T: Send>(max_call_depth: usize, f: impl FnOnce() -> T + Send) -> io::Result<T> {
+	let stack_size = max_call_depth.saturating_mul(STACK_SIZE_PER_CALL).max(8 * 1024 * 1024);
+	thread::scope(|scope| {
+		let thread = thread::Builder::new().stack_size(stack_size).spawn_scoped(scope, f)?;
+		Ok(thread.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+	})
+}
+
+/// A tree-walking interpreter for FTL programs.
+pub struct Interpreter {
+	/// All functions defined in the program.
+	functions: HashMap<String, FunctionDefinition>,
+	/// All functions declared with `extern`.
+	extern_functions: HashMap<String, FunctionPrototype>,
+	/// All structs defined in the program, which are constructed by calling them like `Point()`.
+	structs: HashMap<String, ast::Struct>,
+	/// Callbacks that `extern` functions with the same name resolve to.
+	native_functions: HashMap<String, NativeFunction>,
+	/// One frame for each function that is currently executed.
+	call_stack: Vec<CallStackFrame>,
+	/// Maximum length of the [call stack](Self::call_stack), so that infinite recursion is reported as error instead
+	/// of overflowing the stack of the interpreter itself.
+	max_call_depth: usize,
+	/// Where the output of the program is written to.
+	stdout: Box<dyn io::Write>,
+	/// State of the random number generator.
+	random_state: u64,
+	/// Reference point of `now_millis`.
+	start: Option<Instant>,
+	/// Files opened by the program, indexed by the handle of [`Value::File`]. Closed files are [`None`].
+	files: Vec<Option<BufReader<fs::File>>>,
+	/// Variables declared by [`Self::execute`], which persist across executions.
+	globals: HashMap<String, Value>,
+	/// The events recorded so far if [tracing](Self::with_trace) is enabled.
+	trace: Option<Vec<Event>>,
+	/// How often each instruction and function was executed, if [coverage](Self::with_coverage) is enabled.
+	coverage: Option<Counts>,
+	/// Calls and time of each function, if [profiling](Self::with_profile) is enabled.
+	profile: Option<Profile>,
+	/// Values of the `ptr` arguments of the last returned function by their index, which the caller copies back to
+	/// the structs it passed by reference.
+	references: Vec<(usize, Value)>,
+}
+
+impl Interpreter {
+	/// Creates an interpreter for the program consisting of the `ast_nodes`.
+	pub fn new<'a>(ast_nodes: impl Iterator<Item = &'a ast::Node>) -> Self {
+		// Any non-zero seed is fine for xorshift
+		let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_nanos() as u64) | 1;
+		let mut interpreter = Self {
+			functions: HashMap::new(),
+			extern_functions: HashMap::new(),
+			structs: HashMap::new(),
+			native_functions: HashMap::new(),
+			call_stack: Vec::new(),
+			max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+			stdout: Box::new(io::stdout()),
+			random_state: seed,
+			start: None,
+			files: Vec::new(),
+			globals: HashMap::new(),
+			trace: None,
+			coverage: None,
+			profile: None,
+			references: Vec::new(),
+		};
+		for ast_node in ast_nodes {
+			interpreter.define(ast_node);
+		}
+		interpreter
+	}
+
+	/// Adds the function, extern declaration or struct of the `ast_node` to the program, replacing a previous
+	/// definition with the same name.
+	pub fn define(&mut self, ast_node: &ast::Node) {
+		match ast_node {
+			ast::Node::Function(function) => {
+				self.extern_functions.remove(function.prototype.name.as_str());
+				self.functions.insert(function.prototype.name.value.clone(), function.clone());
+			},
+			ast::Node::FunctionPrototype(prototype) => {
+				self.functions.remove(prototype.name.as_str());
+				self.extern_functions.insert(prototype.name.value.clone(), prototype.clone());
+			},
+			ast::Node::Struct(struct_) => {
+				self.structs.insert(struct_.name.value.clone(), struct_.clone());
+			},
+			ast::Node::Comment(_) | ast::Node::Invalid(_) => (),
+		}
+	}
+
+	/// Registers a callback of the host application, which is called by the program through an `extern` declaration
+	/// of the same name, e.g. `extern print_int(x: int)`. A previous callback with the same name is replaced.
+	///
+	/// The callback is passed the arguments after their count has been checked against the declaration.
+	pub fn register(
+		&mut self,
+		name: impl Into<String>,
+		function: impl FnMut(&[Value]) -> Result<Option<Value>, String> + 'static,
+	) {
+		self.native_functions.insert(name.into(), Box::new(function));
+	}
+
+	/// Writes the output of the program to `stdout` instead of the standard output of this process.
+	pub fn with_stdout(mut self, stdout: Box<dyn io::Write>) -> Self {
+		self.stdout = stdout;
+		self
+	}
+
+	/// Limits how deeply functions may call each other, e.g. in a recursion, before a
+	/// [`StackOverflow`](Error::StackOverflow) is reported.
+	pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+		self.max_call_depth = max_call_depth;
+		self
+	}
+
+	/// Logs each function call, return and assignment as [`Event`] with the target `ftl::trace` and records it for
+	/// [`Self::events`].
+	pub fn with_trace(mut self) -> Self {
+		self.trace = Some(Vec::new());
+		self
+	}
+
+	/// The events recorded since [tracing](Self::with_trace) was enabled, in the order of execution.
+	pub fn events(&self) -> &[Event] {
+		self.trace.as_deref().unwrap_or_default()
+	}
+
+	/// Counts how often each instruction and function is executed, see [`coverage`](crate::coverage).
+	pub fn with_coverage(mut self) -> Self {
+		self.coverage = Some(Counts::new());
+		self
+	}
+
+	/// The execution counts if [coverage](Self::with_coverage) is enabled.
+	pub fn coverage(&self) -> Option<&Counts> {
+		self.coverage.as_ref()
+	}
+
+	/// Measures the calls and the time spent in each FTL function, see [`profile`](crate::profile).
+	pub fn with_profile(mut self) -> Self {
+		self.profile = Some(Profile::default());
+		self
+	}
+
+	/// The measurements if [profiling](Self::with_profile) is enabled.
+	pub fn profile(&self) -> Option<&Profile> {
+		self.profile.as_ref()
+	}
+
+	/// Runs the `main` function and returns the exit code of the program.
+	///
+	/// The exit code is either the value returned by `main` or the argument of a call to `exit`.
+	#[tracing::instrument(skip_all)]
+	pub fn run(&mut self) -> Result<i64, Error> {
+		let main = self.functions.get("main").ok_or(Error::MissingMain)?.prototype.name.clone();
+		match self.call_function(&main)? {
+			Some(Value::Int(exit_code)) => Ok(exit_code),
+			_ => Ok(0),
+		}
+	}
+
+	/// Calls the function `name` without arguments and returns its return value.
+	///
+	/// A call to `exit` stops the function as if it returned the exit code.
+	pub fn call_function(&mut self, name: &Spanned<String>) -> Result<Option<Value>, Error> {
+		match self.call(name, Vec::new()) {
+			Ok(value) => Ok(value),
+			Err(Unwind::Exit(exit_code)) => Ok(Some(Value::Int(exit_code))),
+			Err(Unwind::Error(err)) => Err(err),
+			Err(Unwind::Fail(position)) => Err(Error::UnhandledFailure { position }),
+			Err(Unwind::Return(_)) => unreachable!("returns are handled by call()"),
+		}
+	}
+
+	/// Executes the `instructions` outside of any function. Variables declared by them are kept for the next call.
+	///
+	/// Returns the value of the last instruction if it is an expression, or the value of a `return` or `exit`.
+	pub fn execute(&mut self, instructions: &[ast::Instruction]) -> Result<Option<Value>, Error> {
+		self.call_stack.push(vec![mem::take(&mut self.globals)]);
+		let result = self.execute_instructions(instructions);
+		self.globals = self.call_stack.pop().and_then(|mut frame| frame.pop()).unwrap_or_default();
+		match result {
+			Ok(value) => Ok(value),
+			Err(Unwind::Return(value)) => Ok(value),
+			Err(Unwind::Exit(exit_code)) => Ok(Some(Value::Int(exit_code))),
+			Err(Unwind::Error(err)) => Err(err),
+			Err(Unwind::Fail(position)) => Err(Error::UnhandledFailure { position }),
+		}
+	}
+
+	/// Executes the `instructions` in the current scope and returns the value of the last one.
+	fn execute_instructions(&mut self, instructions: &[ast::Instruction]) -> Result<Option<Value>, Unwind> {
+		let mut value = None;
+		for instruction in instructions {
+			value = match instruction {
+				ast::Instruction::Expression(expression) => Some(self.expression(expression)?),
+				_ => {
+					self.instruction(instruction)?;
+					None
+				},
+			};
+		}
+		Ok(value)
+	}
+
+	/// Calls the function `name` with the `args` and returns its return value.
+	fn call(&mut self, name: &Spanned<String>, args: Vec<Value>) -> Result<Option<Value>, Unwind> {
+		if self.trace.is_none() {
+			return self.dispatch(name, args);
+		}
+		self.record(name.position.clone(), || EventKind::Call { name: name.value.clone(), args: args.clone() });
+		let result = self.dispatch(name, args);
+		if let Ok(value) = &result {
+			self.record(name.position.clone(), || EventKind::Return { name: name.value.clone(), value: value.clone() });
+		}
+		result
+	}
+
+	/// Calls the function, extern function, intrinsic or struct constructor `name`, see [`Self::call`].
+	fn dispatch(&mut self, name: &Spanned<String>, args: Vec<Value>) -> Result<Option<Value>, Unwind> {
+		self.references.clear();
+		if Intrinsic::from_name(name) == Some(Intrinsic::Print) {
+			return self.print_format(name, args).map(|()| None);
+		}
+		if Intrinsic::from_name(name) == Some(Intrinsic::Dump) {
+			let [value] = &args[..] else {
+				return Err(Error::WrongArgumentCount { name: name.clone(), expected: 1, found: args.len() }.into());
+			};
+			self.print(&format!("{}\n", value.dump()));
+			return Ok(None);
+		}
+		if let Some(intrinsic) = Intrinsic::from_name(name) {
+			return Self::call_intrinsic(intrinsic, name, args).map(Some);
+		}
+		if self.extern_functions.contains_key(name.deref()) {
+			return self.call_extern(name, args);
+		}
+		let Some(function) = self.functions.get(name.deref()).cloned() else {
+			if self.structs.contains_key(name.deref()) {
+				if !args.is_empty() {
+					return Err(Error::WrongArgumentCount { name: name.clone(), expected: 0, found: args.len() }.into());
+				}
+				return self.construct(name).map(Some);
+			}
+			return self.call_runtime(name, args);
+		};
+
+		if args.len() != function.prototype.args.len() {
+			let (expected, found) = (function.prototype.args.len(), args.len());
+			return Err(Error::WrongArgumentCount { name: name.clone(), expected, found }.into());
+		}
+		if self.call_stack.len() >= self.max_call_depth {
+			return Err(Error::StackOverflow { name: name.clone(), max_call_depth: self.max_call_depth }.into());
+		}
+		if let Some(counts) = &mut self.coverage {
+			*counts.entry(function.prototype.name.position.position.start).or_default() += 1;
+		}
+
+		// Bind the parameters to the names of the arguments in a fresh scope
+		let scope = function.prototype.args.iter().map(|arg| arg.name.value.clone()).zip(args).collect();
+		self.call_stack.push(vec![scope]);
+		if let Some(profile) = &mut self.profile {
+			profile.enter(name);
+		}
+		let result = self.block(&function.body);
+		if let Some(profile) = &mut self.profile {
+			profile.exit();
+		}
+		let mut frame = self.call_stack.pop().unwrap_or_default();
+		self.references = function
+			.prototype
+			.args
+			.iter()
+			.enumerate()
+			.filter(|(_, arg)| matches!(arg.data_type.value, DataType::Pointer(_)))
+			.filter_map(|(index, arg)| match frame.first_mut()?.remove(arg.name.as_str())? {
+				value @ Value::Struct(_) => Some((index, value)),
+				_ => None,
+			})
+			.collect();
+
+		match result {
+			Ok(()) => Ok(None),
+			Err(Unwind::Return(value)) => Ok(value),
+			Err(unwind) => Err(unwind),
+		}
+	}
+
+	/// Constructs the struct `name` with the default values of its fields. Fields without default value are zero,
+	/// empty or constructed the same way.
+	fn construct(&mut self, name: &Spanned<String>) -> Result<Value, Unwind> {
+		let struct_ = self.structs[name.deref()].clone();
+		let mut fields = Vec::with_capacity(struct_.fields.len());
+		for field in &struct_.fields {
+			let value = match (&field.default, &field.data_type.value) {
+				(Some(default), _) => self.expression(default)?,
+				(None, DataType::Basic(BasicDataType::Int) | DataType::Void) => Value::Int(0),
+				(None, DataType::Basic(BasicDataType::Float)) => Value::Float(0.0),
+				(None, DataType::Basic(BasicDataType::Str)) => Value::Str(Rc::from("")),
+				(None, DataType::Struct(struct_name)) => {
+					self.construct(&field.data_type.as_ref().map(|_| struct_name.clone()))?
+				},
+				(None, DataType::Pointer(_) | DataType::Optional(_) | DataType::Fallible(_)) => Value::Pointer,
+			};
+			fields.push((Rc::from(field.name.as_str()), value));
+		}
+		Ok(Value::Struct(StructInstance { name: Rc::from(name.as_str()), fields }))
+	}
+
+	/// Calls a function declared with `extern`, which is either a [registered](Self::register) callback or `exit`.
+	fn call_extern(&mut self, name: &Spanned<String>, args: Vec<Value>) -> Result<Option<Value>, Unwind> {
+		if let Some(function) = self.native_functions.get_mut(name.as_str()) {
+			let expected = self.extern_functions[name.as_str()].args.len();
+			if args.len() != expected {
+				return Err(Error::WrongArgumentCount { name: name.clone(), expected, found: args.len() }.into());
+			}
+			return function(&args).map_err(|message| Error::NativeFunction { name: name.clone(), message }.into());
+		}
+		match (name.as_str(), args.as_slice()) {
+			("exit", [Value::Int(exit_code)]) => Err(Unwind::Exit(*exit_code)),
+			_ => Err(Error::UnsupportedExtern { name: name.clone() }.into()),
+		}
+	}
+
+	/// Calls an [`Intrinsic`], selecting the overload based on the types of the `args`.
+	fn call_intrinsic(intrinsic: Intrinsic, name: &Spanned<String>, args: Vec<Value>) -> Result<Value, Unwind> {
+		let value = match (intrinsic, args.as_slice()) {
+			(Intrinsic::Sqrt, [Value::Int(x)]) => Value::Int((*x as f64).sqrt() as i64),
+			(Intrinsic::Sqrt, [Value::Float(x)]) => Value::Float(x.sqrt()),
+			(Intrinsic::Abs, [Value::Int(x)]) => Value::Int(x.wrapping_abs()),
+			(Intrinsic::Abs, [Value::Float(x)]) => Value::Float(x.abs()),
+			(Intrinsic::Min, [Value::Int(lhs), Value::Int(rhs)]) => Value::Int(*lhs.min(rhs)),
+			(Intrinsic::Min, [Value::Float(lhs), Value::Float(rhs)]) => Value::Float(lhs.min(*rhs)),
+			(Intrinsic::Max, [Value::Int(lhs), Value::Int(rhs)]) => Value::Int(*lhs.max(rhs)),
+			(Intrinsic::Max, [Value::Float(lhs), Value::Float(rhs)]) => Value::Float(lhs.max(*rhs)),
+			(Intrinsic::Pow, [Value::Int(base), Value::Int(exponent)]) => Value::Int(match u32::try_from(*exponent) {
+				Ok(exponent) => base.wrapping_pow(exponent),
+				// 1 / base^-exponent, truncated towards zero
+				Err(_) if base.abs() == 1 => base.pow((exponent % 2).unsigned_abs() as u32),
+				Err(_) => 0,
+			}),
+			(Intrinsic::Pow, [Value::Float(base), Value::Float(exponent)]) => Value::Float(base.powf(*exponent)),
+			_ => return Err(Error::TypeMismatch { position: name.position.clone() }.into()),
+		};
+		Ok(value)
+	}
+
+	/// Calls the `print` intrinsic, whose first argument is the [format string](format_string).
+	fn print_format(&mut self, name: &Spanned<String>, args: Vec<Value>) -> Result<(), Unwind> {
+		let type_mismatch = || Error::TypeMismatch { position: name.position.clone() };
+		let Some((Value::Str(format), mut values)) = args.split_first().map(|(format, values)| (format, values.iter()))
+		else {
+			return Err(type_mismatch().into());
+		};
+		for segment in format_string::parse(format).map_err(|_| type_mismatch())? {
+			let text = match segment {
+				Segment::Text(text) => text,
+				Segment::Placeholder => match values.next() {
+					Some(Value::Int(value)) => value.to_string(),
+					Some(Value::Float(value)) => format_string::float(*value),
+					Some(Value::Str(value)) => value.to_string(),
+					_ => return Err(type_mismatch().into()),
+				},
+			};
+			self.print(&text);
+		}
+		Ok(())
+	}
+
+	/// Writes the `string` to stdout and returns the number of written bytes or a negative value on error, like printf.
+	fn print(&mut self, string: &str) -> Value {
+		match self.stdout.write_all(string.as_bytes()) {
+			Ok(()) => Value::Int(string.len() as i64),
+			Err(_) => Value::Int(-1),
+		}
+	}
+
+	/// Calls a function of the [runtime](crate::runtime).
+	fn call_runtime(&mut self, name: &Spanned<String>, args: Vec<Value>) -> Result<Option<Value>, Unwind> {
+		let value = match (name.as_str(), args.as_slice()) {
+			("ftl_print", [Value::Str(string)]) => self.print(string),
+			("ftl_print_int", [Value::Int(value)]) => self.print(&value.to_string()),
+			("ftl_print_float", [Value::Float(value)]) => self.print(&format_string::float(*value)),
+			("ftl_read_line", []) => {
+				let mut line = String::new();
+				let _ = io::stdin().lock().read_line(&mut line);
+				Value::Str(Rc::from(line.strip_suffix('\n').unwrap_or(&line)))
+			},
+			("ftl_str_concat", [Value::Str(lhs), Value::Str(rhs)]) => Value::Str(Rc::from(format!("{}{}", lhs, rhs))),
+			("str_len", [Value::Str(string)]) => Value::Int(string.len() as i64),
+			("str_compare", [Value::Str(lhs), Value::Str(rhs)]) => Value::Int(lhs.cmp(rhs) as i64),
+			("substring", [Value::Str(string), Value::Int(start), Value::Int(length)]) => {
+				let begin = (*start).clamp(0, string.len() as i64);
+				let end = start.saturating_add(*length).clamp(begin, string.len() as i64);
+				Value::Str(Rc::from(String::from_utf8_lossy(&string.as_bytes()[begin as usize..end as usize])))
+			},
+			("ftl_pow", [Value::Float(base), Value::Float(exponent)]) => Value::Float(base.powf(*exponent)),
+			("random_int", [Value::Int(lo), Value::Int(hi)]) => match u64::try_from(*hi as i128 - *lo as i128 + 1) {
+				Ok(range) => Value::Int((*lo as i128 + (self.next_random() % range) as i128) as i64),
+				Err(_) => Value::Int(*lo),
+			},
+			// Use the upper 53 bits, which a float can represent exactly
+			("random_float", []) => Value::Float((self.next_random() >> 11) as f64 / (1u64 << 53) as f64),
+			("getenv", [Value::Str(name)]) => Value::Str(Rc::from(env::var(name.as_ref()).unwrap_or_default())),
+			("exit", [Value::Int(exit_code)]) => return Err(Unwind::Exit(*exit_code)),
+			("open", [Value::Str(path), Value::Str(mode)]) => Value::File(self.open(path, mode)),
+			("read_line", [Value::File(handle)]) => {
+				let mut line = String::new();
+				if let Some(file) = self.file(*handle) {
+					let _ = file.read_line(&mut line);
+				}
+				Value::Str(Rc::from(line.strip_suffix('\n').unwrap_or(&line)))
+			},
+			("write", [Value::File(handle), Value::Str(text)]) => {
+				match self.file(*handle).map(|file| file.get_mut().write_all(text.as_bytes())) {
+					Some(Ok(())) => Value::Int(text.len() as i64),
+					_ => Value::Int(-1),
+				}
+			},
+			("close", [Value::File(handle)]) => match handle.and_then(|handle| self.files[handle].take()) {
+				Some(_) => Value::Int(0),
+				None => Value::Int(-1),
+			},
+			("now_millis", []) => Value::Int(self.start.get_or_insert_with(Instant::now).elapsed().as_millis() as i64),
+			_ => return Err(Error::UndefinedFunction { name: name.clone() }.into()),
+		};
+		Ok(Some(value))
+	}
+
+	/// Opens the file at `path` with a C-like `mode` and returns its handle, or [`None`] if opening failed.
+	fn open(&mut self, path: &str, mode: &str) -> Option<usize> {
+		let mut options = fs::OpenOptions::new();
+		match mode {
+			"r" => options.read(true),
+			"w" => options.write(true).create(true).truncate(true),
+			"a" => options.append(true).create(true),
+			"r+" => options.read(true).write(true),
+			"w+" => options.read(true).write(true).create(true).truncate(true),
+			"a+" => options.read(true).append(true).create(true),
+			_ => return None,
+		};
+		let file = options.open(path).ok()?;
+		self.files.push(Some(BufReader::new(file)));
+		Some(self.files.len() - 1)
+	}
+
+	/// Looks up an open file by its handle.
+	fn file(&mut self, handle: Option<usize>) -> Option<&mut BufReader<fs::File>> {
+		self.files.get_mut(handle?)?.as_mut()
+	}
+
+	/// Generates the next random number with xorshift64*.
+	fn next_random(&mut self) -> u64 {
+		self.random_state ^= self.random_state >> 12;
+		self.random_state ^= self.random_state << 25;
+		self.random_state ^= self.random_state >> 27;
+		self.random_state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+	}
+
+	/// Records the event created by `kind` if tracing is enabled.
+	fn record(&mut self, position: SourcePositionRange, kind: impl FnOnce() -> EventKind) {
+		let depth = self.call_stack.len();
+		let Some(events) = &mut self.trace else {
+			return;
+		};
+		let event = Event { position, depth, kind: kind() };
+		tracing::info!(target: "ftl::trace", "{}", event);
+		events.push(event);
+	}
+
+	/// Executes the instructions of the `block` in a new scope.
+	fn block(&mut self, block: &Block) -> Result<(), Unwind> {
+		self.frame().push(HashMap::new());
+		let result = block.iter().try_for_each(|instruction| self.instruction(instruction));
+		self.frame().pop();
+		result
+	}
+
+	/// The frame of the currently executed function.
+	fn frame(&mut self) -> &mut CallStackFrame {
+		self.call_stack.last_mut().expect("call stack is empty")
+	}
+
+	fn instruction(&mut self, instruction: &ast::Instruction) -> Result<(), Unwind> {
+		if let Some(counts) = &mut self.coverage {
+			*counts.entry(instruction.source_position().position.start).or_default() += 1;
+		}
+		match instruction {
+			ast::Instruction::Expression(expression) => self.expression(expression).map(|_| ()),
+			ast::Instruction::Statement(statement) => self.statement(statement),
+			ast::Instruction::IfElse(if_else) => {
+				if self.condition(&if_else.condition)? {
+					self.block(&if_else.if_true)
+				} else {
+					self.block(&if_else.if_false)
+				}
+			},
+			ast::Instruction::WhileLoop(while_loop) => {
+				while self.condition(&while_loop.condition)? {
+					self.block(&while_loop.body)?;
+				}
+				Ok(())
+			},
+			ast::Instruction::Invalid(position) => Err(Error::InvalidCode { position: position.clone() }.into()),
+		}
+	}
+
+	fn statement(&mut self, statement: &ast::Statement) -> Result<(), Unwind> {
+		match statement {
+			ast::Statement::VariableDeclaration(declaration) => {
+				let value = self.expression(&declaration.value)?;
+				self.record(declaration.name.position.clone(), || EventKind::Assign {
+					target: declaration.name.value.clone(),
+					value: value.clone(),
+				});
+				let scope = self.frame().last_mut().expect("no scope");
+				scope.insert(declaration.name.value.clone(), value);
+				Ok(())
+			},
+			ast::Statement::VariableAssignment(assignment) => {
+				let value = self.expression(&assignment.value)?;
+				self.record(assignment.name.position.clone(), || EventKind::Assign {
+					target: assignment.name.value.clone(),
+					value: value.clone(),
+				});
+				*self.variable_mut(&assignment.name)? = value;
+				Ok(())
+			},
+			ast::Statement::FieldAssignment(assignment) => {
+				let value = self.expression(&assignment.value)?;
+				let position = assignment.target.source_position();
+				self.record(position.clone(), || EventKind::Assign {
+					target: position.code().to_owned(),
+					value: value.clone(),
+				});
+				*self.field_mut(&assignment.target)? = value;
+				Ok(())
+			},
+			ast::Statement::Return(value) => {
+				let value = value.value.as_ref().map(|expression| self.expression(expression)).transpose()?;
+				Err(Unwind::Return(value))
+			},
+		}
+	}
+
+	/// Looks up a variable, starting in the innermost scope.
+	fn variable_mut(&mut self, name: &Spanned<String>) -> Result<&mut Value, Error> {
+		self.frame()
+			.iter_mut()
+			.rev()
+			.find_map(|scope| scope.get_mut(name.deref()))
+			.ok_or(Error::UndeclaredVariable { name: name.clone() })
+	}
+
+	/// Looks up the field of a variable, e.g. `line.start.x`.
+	fn field_mut(&mut self, field_access: &FieldAccess) -> Result<&mut Value, Error> {
+		let base = match &*field_access.base {
+			Expression::Variable(variable) => self.variable_mut(variable)?,
+			Expression::FieldAccess(base) => self.field_mut(base)?,
+			// Other expressions are temporary values, whose fields can't be assigned
+			_ => return Err(Error::UnknownField { field: field_access.field.clone() }),
+		};
+		match base {
+			Value::Struct(instance) => instance.field_mut(&field_access.field),
+			_ => None,
+		}
+		.ok_or(Error::UnknownField { field: field_access.field.clone() })
+	}
+
+	fn expression(&mut self, expression: &Expression) -> Result<Value, Unwind> {
+		match expression {
+			Expression::BinaryExpression(binary_expression) => self.binary_expression(binary_expression),
+			Expression::FieldAccess(field_access) => match self.expression(&field_access.base)? {
+				Value::Struct(mut instance) => match instance.field_mut(&field_access.field) {
+					Some(value) => Ok(mem::replace(value, Value::Pointer)),
+					None => Err(Error::UnknownField { field: field_access.field.clone() }.into()),
+				},
+				_ => Err(Error::UnknownField { field: field_access.field.clone() }.into()),
+			},
+			Expression::FunctionCall(function_call) => self.function_call(function_call),
+			Expression::Number(number) => Ok(match number.value {
+				NumberKind::Int(int) => Value::Int(int),
+				NumberKind::Float(float) => Value::Float(float),
+			}),
+			Expression::String(string) => Ok(Value::Str(Rc::from(string.as_str()))),
+			Expression::Variable(variable) => Ok(self.variable_mut(variable)?.clone()),
+			Expression::Null(_) => Ok(Value::Pointer),
+			Expression::Try(try_expression) => self.try_(try_expression),
+			Expression::Error(position) => Err(Unwind::Fail(position.clone())),
+			Expression::Invalid(position) => Err(Error::InvalidCode { position: position.clone() }.into()),
+		}
+	}
+
+	/// Evaluates `try value else fallback`. The arguments of the call `value` are evaluated before, so that their
+	/// failures are passed on instead of being handled by this `try`.
+	fn try_(&mut self, try_expression: &TryExpression) -> Result<Value, Unwind> {
+		let result = match &*try_expression.value {
+			Expression::FunctionCall(function_call) => {
+				let args =
+					function_call.params.iter().map(|param| self.expression(param)).collect::<Result<Vec<_>, _>>()?;
+				let result = self.call(&function_call.name, args);
+				self.write_back(function_call)?;
+				result.map(|value| value.unwrap_or(Value::Int(0)))
+			},
+			value => self.expression(value),
+		};
+		match result {
+			Err(Unwind::Fail(_)) => self.expression(&try_expression.fallback),
+			result => result,
+		}
+	}
+
+	fn function_call(&mut self, function_call: &FunctionCall) -> Result<Value, Unwind> {
+		let args = function_call.params.iter().map(|param| self.expression(param)).collect::<Result<Vec<_>, _>>()?;
+		let result = self.call(&function_call.name, args);
+		self.write_back(function_call)?;
+		// Calls to functions without return value evaluate to 0
+		Ok(result?.unwrap_or(Value::Int(0)))
+	}
+
+	/// Copies the structs that the finished call modified through `ptr` arguments back to the variables and fields
+	/// that were passed by reference.
+	fn write_back(&mut self, function_call: &FunctionCall) -> Result<(), Error> {
+		for (index, value) in mem::take(&mut self.references) {
+			match &function_call.params[index] {
+				Expression::Variable(variable) => *self.variable_mut(variable)? = value,
+				Expression::FieldAccess(field_access) => *self.field_mut(field_access)? = value,
+				_ => (),
+			}
+		}
+		Ok(())
+	}
+
+	/// Evaluates the `condition` of an if-else, a while loop, `and` or `or`.
+	fn condition(&mut self, condition: &Expression) -> Result<bool, Unwind> {
+		let value = self.expression(condition)?;
+		value.is_truthy().ok_or_else(|| Error::TypeMismatch { position: condition.source_position() }.into())
+	}
+
+	fn binary_expression(&mut self, binary_expression: &BinaryExpression) -> Result<Value, Unwind> {
+		if binary_expression.operator.is_logical() {
+			// The rhs of `and` and `or` is only evaluated if lhs does not decide the result already
+			let lhs = self.condition(&binary_expression.lhs)?;
+			if lhs == (*binary_expression.operator == BinaryOperator::LogicalOr) {
+				return Ok(Value::from(lhs));
+			}
+			return Ok(Value::from(self.condition(&binary_expression.rhs)?));
+		}
+		let lhs = self.expression(&binary_expression.lhs)?;
+		let rhs = self.expression(&binary_expression.rhs)?;
+		let position = binary_expression.operator.position.clone();
+		let value = match (lhs, rhs) {
+			(Value::Int(lhs), Value::Int(rhs)) => match *binary_expression.operator {
+				BinaryOperator::Add => Value::Int(lhs.wrapping_add(rhs)),
+				BinaryOperator::Subtract => Value::Int(lhs.wrapping_sub(rhs)),
+				BinaryOperator::Multiply => Value::Int(lhs.wrapping_mul(rhs)),
+				BinaryOperator::Divide | BinaryOperator::Modulus if rhs == 0 => {
+					return Err(Error::DivisionByZero { position }.into())
+				},
+				// Integer division truncates towards zero like in C
+				BinaryOperator::Divide => Value::Int(lhs.wrapping_div(rhs)),
+				// Unlike `%` of C, the remainder is never negative
+				BinaryOperator::Modulus => Value::Int(lhs.wrapping_rem_euclid(rhs)),
+				BinaryOperator::Less => Value::from(lhs < rhs),
+				BinaryOperator::Greater => Value::from(lhs > rhs),
+				BinaryOperator::LessEqual => Value::from(lhs <= rhs),
+				BinaryOperator::GreaterEqual => Value::from(lhs >= rhs),
+				BinaryOperator::Equal => Value::from(lhs == rhs),
+				BinaryOperator::NotEqual => Value::from(lhs != rhs),
+				BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => unreachable!("handled above"),
+			},
+			(Value::Float(lhs), Value::Float(rhs)) => match *binary_expression.operator {
+				BinaryOperator::Add => Value::Float(lhs + rhs),
+				BinaryOperator::Subtract => Value::Float(lhs - rhs),
+				BinaryOperator::Multiply => Value::Float(lhs * rhs),
+				BinaryOperator::Divide => Value::Float(lhs / rhs),
+				BinaryOperator::Modulus => return Err(Error::TypeMismatch { position }.into()),
+				BinaryOperator::Less => Value::from(lhs < rhs),
+				BinaryOperator::Greater => Value::from(lhs > rhs),
+				BinaryOperator::LessEqual => Value::from(lhs <= rhs),
+				BinaryOperator::GreaterEqual => Value::from(lhs >= rhs),
+				BinaryOperator::Equal => Value::from(lhs == rhs),
+				BinaryOperator::NotEqual => Value::from(lhs != rhs),
+				BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => unreachable!("handled above"),
+			},
+			// Strings compare by their bytes like `strcmp()`
+			(Value::Str(lhs), Value::Str(rhs)) => match *binary_expression.operator {
+				BinaryOperator::Add => Value::Str(Rc::from(format!("{}{}", lhs, rhs))),
+				BinaryOperator::Less => Value::from(lhs < rhs),
+				BinaryOperator::Greater => Value::from(lhs > rhs),
+				BinaryOperator::LessEqual => Value::from(lhs <= rhs),
+				BinaryOperator::GreaterEqual => Value::from(lhs >= rhs),
+				BinaryOperator::Equal => Value::from(lhs == rhs),
+				BinaryOperator::NotEqual => Value::from(lhs != rhs),
+				_ => return Err(Error::TypeMismatch { position }.into()),
+			},
+			// Structs are not compared field by field, like in C
+			(Value::Struct(_), _) | (_, Value::Struct(_)) => return Err(Error::TypeMismatch { position }.into()),
+			// Other values of the same kind, e.g. files, can only be compared for equality
+			(lhs, rhs) if mem::discriminant(&lhs) == mem::discriminant(&rhs) => match *binary_expression.operator {
+				BinaryOperator::Equal => Value::from(lhs == rhs),
+				BinaryOperator::NotEqual => Value::from(lhs != rhs),
+				_ => return Err(Error::TypeMismatch { position }.into()),
+			},
+			// Values of optional types are compared with `null`, which differs from all other values
+			(Value::Pointer, _) | (_, Value::Pointer) => match *binary_expression.operator {
+				BinaryOperator::Equal => Value::from(false),
+				BinaryOperator::NotEqual => Value::from(true),
+				_ => return Err(Error::TypeMismatch { position }.into()),
+			},
+			_ => return Err(Error::TypeMismatch { position }.into()),
+		};
+		Ok(value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{emitter::SharedBuffer, lexer::Lexer, parser::Parser, source::Source};
+
+	fn run(source_code: &str) -> Result<i64, Error> {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let ast_nodes = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		Interpreter::new(ast_nodes.iter()).run()
+	}
+
+	#[test]
+	fn test_arithmetic() {
+		assert_eq!(run("def main(): int { return (0 - 7) / 2 * 3 }"), Ok(-9));
+		assert_eq!(run("def main(): int { return (0 - 7) mod 3 * 10 + 7 mod (0 - 3) }"), Ok(21));
+	}
+
+	#[test]
+	fn test_control_flow() {
+		let source_code = "
+			def main(): int {
+				var i: int = 0
+				var sum: int = 0
+				while i < 5 {
+					i = i + 1
+					if i = 3 { } else { sum = sum + i }
+				}
+				return sum
+			}";
+		assert_eq!(run(source_code), Ok(12));
+	}
+
+	#[test]
+	fn test_exit() {
+		assert_eq!(run("extern exit(code: int)\ndef main(): int { exit(3)\nreturn 0 }"), Ok(3));
+	}
+
+	#[test]
+	fn test_runtime() {
+		let source_code = "def main(): int { return ftl_print(ftl_str_concat(\"Hello, \", \"World!\")) }";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let ast_nodes = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let stdout = SharedBuffer::default();
+		let exit_code = Interpreter::new(ast_nodes.iter()).with_stdout(Box::new(stdout.clone())).run();
+		assert_eq!(exit_code, Ok(13));
+		assert_eq!(stdout.0.take(), b"Hello, World!");
+	}
+
+	#[test]
+	fn test_intrinsics() {
+		assert_eq!(run("def main(): int { return sqrt(50) + abs((0 - 3)) }"), Ok(10));
+		assert_eq!(run("def main(): int { return min(4, 9) * max(4, 9) }"), Ok(36));
+		assert_eq!(run("def main(): int { return pow(3, 4) + pow(2, (0 - 1)) + pow((0 - 1), (0 - 3)) }"), Ok(80));
+	}
+
+	#[test]
+	fn test_random() {
+		assert_eq!(run("def main(): int { return random_int(5, 5) }"), Ok(5));
+		for _ in 0..10 {
+			let random = run("def main(): int { return random_int((0 - 2), 2) }").unwrap();
+			assert!((-2..=2).contains(&random), "{}", random);
+		}
+		assert_eq!(run("def main(): int { if random_float() < 1.0 { return 1 } return 0 }"), Ok(1));
+	}
+
+	#[test]
+	fn test_file_io() {
+		let path = std::env::temp_dir().join(format!("ftl-interpreter-test-{}.txt", std::process::id()));
+		let source_code = format!(
+			"def main(): int {{
+				var file: File = open(\"{path}\", \"w\")
+				write(file, \"first\\nsecond\\n\")
+				close(file)
+				file = open(\"{path}\", \"r\")
+				var first: str = read_line(file)
+				ftl_print(read_line(file))
+				close(file)
+				return close(open(\"{path}/missing\", \"r\"))
+			}}",
+			path = path.display()
+		);
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let ast_nodes = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let stdout = SharedBuffer::default();
+		let exit_code = Interpreter::new(ast_nodes.iter()).with_stdout(Box::new(stdout.clone())).run();
+		std::fs::remove_file(path).unwrap();
+		assert_eq!(exit_code, Ok(-1));
+		assert_eq!(stdout.0.take(), b"second");
+	}
+
+	#[test]
+	fn test_structs() {
+		let source_code = "
+			struct Point { x: int = 1, y: float }
+			struct Line { start: Point, end: Point, next: ptr Line }
+			def main(): int {
+				var line: Line = Line()
+				line.end.x = line.start.x + 10
+				var copy: Line = line
+				copy.start.y = 2.5
+				if line.next { return 100 }
+				if copy.start.y = line.start.y { return 200 }
+				copy.start.y = 0.0
+				if copy.start.y =/= line.start.y { return 300 }
+				return line.end.x + copy.start.x
+			}";
+		assert_eq!(run(source_code), Ok(12));
+	}
+
+	#[test]
+	fn test_native_functions() {
+		let source_code = "
+			extern add(a: int, b: int): int
+			extern print_int(x: int)
+			extern fail()
+			def main(): int {
+				print_int(add(40, 2))
+				print_int(7)
+				return add(1, 2)
+			}
+			def failing(): int {
+				fail()
+				return 0
+			}";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let ast_nodes = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let printed = Rc::new(std::cell::RefCell::new(Vec::new()));
+		let mut interpreter = Interpreter::new(ast_nodes.iter());
+		interpreter.register("add", |args| match args {
+			[Value::Int(a), Value::Int(b)] => Ok(Some(Value::Int(a + b))),
+			_ => Err("expected two ints".to_owned()),
+		});
+		let printed_by_callback = Rc::clone(&printed);
+		interpreter.register("print_int", move |args| {
+			printed_by_callback.borrow_mut().push(args[0].clone());
+			Ok(None)
+		});
+		interpreter.register("fail", |_| Err("failed on purpose".to_owned()));
+		assert_eq!(interpreter.run(), Ok(3));
+		assert_eq!(*printed.borrow(), [Value::Int(42), Value::Int(7)]);
+
+		let ast::Node::Function(failing) = &ast_nodes[4] else { panic!() };
+		let Err(Error::NativeFunction { name, message }) = interpreter.call_function(&failing.prototype.name) else {
+			panic!()
+		};
+		assert_eq!((name.as_str(), message.as_str()), ("fail", "failed on purpose"));
+	}
+
+	#[test]
+	fn test_trace() {
+		let source_code = "struct P { x: int }
+def square(a: int): int { return a * a }
+def main(): int {
+	var p: P = P()
+	p.x = square(3)
+	return p.x
+}";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let ast_nodes = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let mut interpreter = Interpreter::new(ast_nodes.iter()).with_trace();
+		assert_eq!(interpreter.run(), Ok(9));
+		let log: Vec<_> = interpreter.events().iter().map(Event::to_string).collect();
+		assert_eq!(
+			log,
+			[
+				"test.ftl:3:5 [0] call main()",
+				"test.ftl:4:13 [1] call P()",
+				"test.ftl:4:13 [1] return P = P { x: 0 }",
+				"test.ftl:4:6 [1] assign p = P { x: 0 }",
+				"test.ftl:5:8 [1] call square(3)",
+				"test.ftl:5:8 [1] return square = 9",
+				"test.ftl:5:2 [1] assign p.x = 9",
+				"test.ftl:3:5 [0] return main = 9",
+			]
+		);
+		assert!(Interpreter::new(ast_nodes.iter()).events().is_empty());
+	}
+
+	#[test]
+	fn test_recursion() {
+		let source_code = "
+			def fib(n: int): int {
+				if n < 2 { return n }
+				return fib((n - 1)) + fib((n - 2))
+			}
+			def down(n: int): int {
+				if n = 0 { return 0 }
+				return down((n - 1))
+			}
+			def main(): int { return fib(15) + down(900) }";
+		assert_eq!(with_stack_for(DEFAULT_MAX_CALL_DEPTH, || run(source_code)).unwrap(), Ok(610));
+	}
+
+	#[test]
+	fn test_stack_overflow() {
+		let source_code = "def f(n: int): int { return f((n + 1)) }\ndef main(): int { return f(0) }";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let ast_nodes = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>().unwrap();
+		let result = Interpreter::new(ast_nodes.iter()).with_max_call_depth(50).run();
+		let Err(Error::StackOverflow { name, max_call_depth: 50 }) = result else {
+			panic!("expected stack overflow, got {:?}", result)
+		};
+		// The recursive call inside of `f`
+		assert_eq!(name.position.to_string(), "test.ftl:1:29");
+	}
+
+	#[test]
+	fn test_division_by_zero() {
+		assert!(matches!(run("def main(): int { return 1 / 0 }"), Err(Error::DivisionByZero { .. })));
+		assert!(matches!(run("def main(): int { return 1 mod (1 - 1) }"), Err(Error::DivisionByZero { .. })));
+	}
+}
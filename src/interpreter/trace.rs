@@ -0,0 +1,69 @@
+use std::fmt;
+
+use super::Value;
+use crate::source::SourcePositionRange;
+
+/// A step of the execution, reported when [tracing](super::Interpreter::with_trace) is enabled.
+///
+/// Each event is formatted as one line, so that a recorded log can be stepped through afterwards:
+///
+/// ```text
+/// test.ftl:3:13 [1] call square(3)
+/// test.ftl:3:13 [1] return square = 9
+/// test.ftl:3:6 [1] assign x = 9
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+	/// Where the call or assignment happens in the code. Returns refer to their call.
+	pub position: SourcePositionRange,
+	/// Number of functions that are currently executed, i.e. `1` in `main`.
+	pub depth: usize,
+	/// What happened.
+	pub kind: EventKind,
+}
+
+/// Kind of an [`Event`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind {
+	/// The function `name` is called with the `args`.
+	Call { name: String, args: Vec<Value> },
+	/// The function `name` returned, with a value unless it has no return value.
+	Return { name: String, value: Option<Value> },
+	/// The variable or field `target`, e.g. `point.x`, is declared or assigned the `value`.
+	Assign { target: String, value: Value },
+}
+
+impl fmt::Display for Event {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} [{}] {}", self.position, self.depth, self.kind)
+	}
+}
+
+impl fmt::Display for EventKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			EventKind::Call { name, args } => {
+				write!(f, "call {}(", name)?;
+				for (i, arg) in args.iter().enumerate() {
+					write!(f, "{}{}", if i == 0 { "" } else { ", " }, Quoted(arg))?;
+				}
+				write!(f, ")")
+			},
+			EventKind::Return { name, value: Some(value) } => write!(f, "return {} = {}", name, Quoted(value)),
+			EventKind::Return { name, value: None } => write!(f, "return {}", name),
+			EventKind::Assign { target, value } => write!(f, "assign {} = {}", target, Quoted(value)),
+		}
+	}
+}
+
+/// Formats strings as literals, so that they can be told apart from other values in the log.
+struct Quoted<'a>(&'a Value);
+
+impl fmt::Display for Quoted<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.0 {
+			Value::Str(string) => write!(f, "{:?}", string),
+			value => write!(f, "{}", value),
+		}
+	}
+}
@@ -0,0 +1,101 @@
+use std::{fmt, rc::Rc};
+
+use crate::format_string;
+
+/// A value computed by the [`Interpreter`](super::Interpreter).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+	/// An integer number.
+	Int(i64),
+	/// A floating point number.
+	Float(f64),
+	/// A string.
+	Str(Rc<str>),
+	/// Handle of a file opened by the interpreter, [`None`] if opening the file failed.
+	File(Option<usize>),
+	/// An instance of a struct. Like in C, it is copied when assigned or passed to a function. A `ptr` argument holds
+	/// the struct passed by reference instead, which is copied back when the function returns.
+	Struct(StructInstance),
+	/// A null pointer. FTL can't take the address of a value yet, so pointers only come from fields without default
+	/// value. This is also the value of `null` for all optional types.
+	Pointer,
+}
+
+/// Values of the fields of a struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructInstance {
+	/// Name of the struct.
+	pub name: Rc<str>,
+	/// Names and values of the fields in the order of their definition.
+	pub fields: Vec<(Rc<str>, Value)>,
+}
+
+impl StructInstance {
+	/// The value of the field `name`.
+	pub fn field_mut(&mut self, name: &str) -> Option<&mut Value> {
+		self.fields.iter_mut().find(|(field, _)| &**field == name).map(|(_, value)| value)
+	}
+}
+
+impl Value {
+	/// Whether this value counts as `true` in a condition, i.e. whether it is not zero.
+	///
+	/// Strings are always `true` and files are `true` if they were opened successfully, like non-null pointers in C.
+	/// Structs are no scalars and can't be conditions, like in C, so they result in [`None`].
+	pub fn is_truthy(&self) -> Option<bool> {
+		match self {
+			Value::Int(int) => Some(*int != 0),
+			Value::Float(float) => Some(*float != 0.0),
+			Value::Str(_) => Some(true),
+			Value::File(handle) => Some(handle.is_some()),
+			Value::Struct(_) => None,
+			Value::Pointer => Some(false),
+		}
+	}
+
+	/// The representation of the value printed by [`dump`](crate::intrinsic::Intrinsic::Dump), which all backends
+	/// share: numbers like in [format strings](crate::format_string), strings in quotes, structs with their fields
+	/// like `Point { x: 1, y: 2.500000 }`, and `null`, null pointers and files that could not be opened as `null`.
+	pub fn dump(&self) -> String {
+		match self {
+			Value::Int(int) => int.to_string(),
+			Value::Float(float) => format_string::float(*float),
+			Value::Str(string) => format!("\"{}\"", string),
+			Value::File(Some(_)) => "<file>".to_owned(),
+			Value::File(None) | Value::Pointer => "null".to_owned(),
+			Value::Struct(instance) if instance.fields.is_empty() => format!("{} {{}}", instance.name),
+			Value::Struct(instance) => {
+				let fields: Vec<_> =
+					instance.fields.iter().map(|(name, value)| format!("{}: {}", name, value.dump())).collect();
+				format!("{} {{ {} }}", instance.name, fields.join(", "))
+			},
+		}
+	}
+}
+
+impl From<bool> for Value {
+	/// Comparisons evaluate to `1` or `0` like in C.
+	fn from(boolean: bool) -> Self {
+		Value::Int(boolean as i64)
+	}
+}
+
+impl fmt::Display for Value {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Value::Int(int) => write!(f, "{}", int),
+			Value::Float(float) => write!(f, "{:?}", float),
+			Value::Str(string) => write!(f, "{}", string),
+			Value::File(Some(handle)) => write!(f, "<file {}>", handle),
+			Value::File(None) => write!(f, "<invalid file>"),
+			Value::Struct(instance) => {
+				write!(f, "{} {{ ", instance.name)?;
+				for (i, (name, value)) in instance.fields.iter().enumerate() {
+					write!(f, "{}{}: {}", if i == 0 { "" } else { ", " }, name, value)?;
+				}
+				write!(f, " }}")
+			},
+			Value::Pointer => write!(f, "null"),
+		}
+	}
+}
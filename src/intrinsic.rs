@@ -0,0 +1,152 @@
+//! Functions built into the compiler.
+//!
+//! Unlike normal functions, the math intrinsics are overloaded for `int` and `float`: All arguments must have the same
+//! numeric type, which is also the return type. `print` takes any number of arguments for the placeholders of its
+//! [format string](crate::format_string), and `dump` takes a value of any type. The names of intrinsics are reserved, i.e. programs can't define functions
+//! with these names.
+
+use crate::types::{TypeId, TypeTable};
+
+/// An intrinsic function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intrinsic {
+	/// `sqrt(x)`: Square root, rounded down for `int`.
+	Sqrt,
+	/// `abs(x)`: Absolute value.
+	Abs,
+	/// `min(a, b)`: The smaller of both arguments.
+	Min,
+	/// `max(a, b)`: The larger of both arguments.
+	Max,
+	/// `pow(base, exponent)`: `base` raised to the power of `exponent`. Negative exponents truncate to zero for `int`.
+	Pow,
+	/// `print(format, args...)`: Writes the format string to stdout, with each `{}` replaced by the next argument.
+	Print,
+	/// `dump(value)`: Writes a representation of the value to stdout, including the fields of structs, followed by a
+	/// line break. Meant for debugging.
+	Dump,
+}
+
+impl Intrinsic {
+	/// All intrinsics.
+	pub const ALL: [Intrinsic; 7] = [
+		Intrinsic::Sqrt,
+		Intrinsic::Abs,
+		Intrinsic::Min,
+		Intrinsic::Max,
+		Intrinsic::Pow,
+		Intrinsic::Print,
+		Intrinsic::Dump,
+	];
+
+	/// Looks up the intrinsic called `name`.
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|intrinsic| intrinsic.name() == name)
+	}
+
+	/// Name of the intrinsic in FTL code.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Intrinsic::Sqrt => "sqrt",
+			Intrinsic::Abs => "abs",
+			Intrinsic::Min => "min",
+			Intrinsic::Max => "max",
+			Intrinsic::Pow => "pow",
+			Intrinsic::Print => "print",
+			Intrinsic::Dump => "dump",
+		}
+	}
+
+	/// Number of arguments the intrinsic expects. For `print`, this is the format string without the arguments of its
+	/// placeholders.
+	pub fn arity(&self) -> usize {
+		match self {
+			Intrinsic::Sqrt | Intrinsic::Abs | Intrinsic::Print | Intrinsic::Dump => 1,
+			Intrinsic::Min | Intrinsic::Max | Intrinsic::Pow => 2,
+		}
+	}
+
+	/// Selects the overload of a math intrinsic for the `arg_types` and returns its return type, or [`None`] if there is
+	/// no such overload.
+	pub fn return_type(&self, types: &TypeTable, arg_types: &[TypeId]) -> Option<TypeId> {
+		if matches!(self, Intrinsic::Print | Intrinsic::Dump) || arg_types.len() != self.arity() {
+			return None;
+		}
+		let (first, rest) = arg_types.split_first()?;
+		let common_type = rest.iter().try_fold(*first, |common_type, type_| types.common_type(common_type, *type_))?;
+		types.is_numeric(common_type).then_some(common_type)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::differential::{assert_consistent, Outcome};
+
+	#[test]
+	fn test_overloads() {
+		let types = TypeTable::default();
+		let (int, float, str) = (TypeId::INT, TypeId::FLOAT, TypeId::STR);
+
+		assert_eq!(Intrinsic::from_name("pow"), Some(Intrinsic::Pow));
+		assert_eq!(Intrinsic::from_name("main"), None);
+		assert_eq!(Intrinsic::Sqrt.return_type(&types, &[float]), Some(float));
+		assert_eq!(Intrinsic::Max.return_type(&types, &[int, int]), Some(int));
+		assert_eq!(Intrinsic::Max.return_type(&types, &[int, float]), None);
+		assert_eq!(Intrinsic::Abs.return_type(&types, &[int, int]), None);
+		assert_eq!(Intrinsic::Abs.return_type(&types, &[str]), None);
+	}
+
+	#[test]
+	fn test_intrinsics() {
+		assert_eq!(assert_consistent("def main(): int { return sqrt(50) + abs((0 - 3)) }").exit_code, 10);
+	}
+
+	#[test]
+	fn test_dump() {
+		let outcome = assert_consistent(
+			"struct Point { x: int, y: float }
+			struct Empty {}
+			struct Node { value: opt int, label: opt str, position: Point, next: ptr Node, empty: Empty }
+
+			def show(point: ptr Point) {
+				dump(point)
+			}
+
+			def main(): int {
+				var point: Point = Point()
+				point.y = 2.5
+				show(point)
+				var node: Node = Node()
+				dump(node)
+				node.value = 3
+				node.label = \"a\"
+				dump(node)
+				dump((1 + 2))
+				dump(\"text\")
+				return 0
+			}",
+		);
+		let stdout = "Point { x: 0, y: 2.500000 }
+Node { value: null, label: null, position: Point { x: 0, y: 0.000000 }, next: null, empty: Empty {} }
+Node { value: 3, label: \"a\", position: Point { x: 0, y: 0.000000 }, next: null, empty: Empty {} }
+3
+\"text\"
+";
+		assert_eq!(outcome, Outcome { exit_code: 0, stdout: stdout.to_owned() });
+	}
+
+	#[test]
+	fn test_print() {
+		let outcome = assert_consistent(
+			"def main(): int {
+				var name: str = \"x\"
+				print(\"{} = {}, {{{}}}\\n\", name, (1 + 2), 2.5)
+				print(\"{}{}\", (0 - 7), \"\\n\")
+				print(\"\")
+				return 0
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: 0, stdout: "x = 3, {2.500000}\n-7\n".to_owned() });
+	}
+}
@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use crate::source::{PositionContainer, Symbol};
+use crate::{
+	diagnostic::Diagnostic,
+	source::{SourcePositionRange, Spanned, Symbol},
+};
 
 /// Lexer errors.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
@@ -10,5 +13,78 @@ pub enum Error {
 	#[error("Illegal symbol {}", .0.as_ref().map(|s| s.to_string()).unwrap_or("None".to_owned()))]
 	IllegalSymbol(Option<Symbol>),
 	#[error("Could not parse number {0}")]
-	ParseNumberError(PositionContainer<String>),
+	ParseNumberError(Spanned<String>),
+	#[error("Integer {0} exceeds the range of int")]
+	IntOverflow(Spanned<String>),
+	#[error("Float {0} exceeds the range of float")]
+	FloatOverflow(Spanned<String>),
+	#[error("Expected digits after the decimal point of number {0}")]
+	MissingDecimalPlaces(Spanned<String>),
+	#[error("Expected digits after the exponent of number {0}")]
+	MissingExponent(Spanned<String>),
+	#[error("Unexpected second decimal point at {dot} in number {number}")]
+	SecondDecimalPoint { number: Spanned<String>, dot: SourcePositionRange },
+	#[error("Operator {operator} of other languages, FTL writes `{replacement}`")]
+	ForeignOperator { operator: Spanned<String>, replacement: &'static str },
+	#[error("Invalid escape {0} in string literal")]
+	InvalidEscape(Spanned<String>),
+}
+
+impl From<&Error> for Diagnostic {
+	fn from(err: &Error) -> Self {
+		match err {
+			Error::UnknownSymbol(symbol) => {
+				Diagnostic::error("UnknownSymbol", format!("Unknown symbol `{}`", symbol.value))
+					.with_primary_label(symbol.position.clone(), "this symbol is not part of FTL")
+			},
+			Error::IllegalSymbol(Some(symbol)) => {
+				Diagnostic::error("IllegalSymbol", format!("Illegal symbol `{}`", symbol.value))
+					.with_primary_label(symbol.position.clone(), "not allowed here")
+			},
+			Error::IllegalSymbol(None) => Diagnostic::error("IllegalSymbol", "Unexpected end of file"),
+			Error::ParseNumberError(number) => {
+				Diagnostic::error("ParseNumberError", format!("Could not parse number `{}`", number.value))
+					.with_primary_label(number.position.clone(), "invalid number literal")
+			},
+			Error::IntOverflow(number) => {
+				Diagnostic::error("IntOverflow", format!("Integer `{}` exceeds the range of `int`", number.value))
+					.with_primary_label(number.position.clone(), "too large")
+					.with_note(format!("`int` ranges from {} to {}", i64::MIN, i64::MAX))
+					.with_help(format!("write `{}.0` for a float, which is less precise", number.value))
+			},
+			Error::FloatOverflow(number) => {
+				Diagnostic::error("FloatOverflow", format!("Float `{}` exceeds the range of `float`", number.value))
+					.with_primary_label(number.position.clone(), "too large")
+					.with_note(format!("the largest `float` is about {:e}", f64::MAX))
+			},
+			Error::MissingDecimalPlaces(number) => Diagnostic::error(
+				"MissingDecimalPlaces",
+				format!("Expected digits after the decimal point of `{}`", number.value),
+			)
+			.with_primary_label(number.position.clone(), "decimal places missing")
+			.with_help(format!("write `{}0` for a float, numbers have no fields", number.value)),
+			Error::MissingExponent(number) => Diagnostic::error(
+				"MissingExponent",
+				format!("Expected digits after the exponent of `{}`", number.value),
+			)
+			.with_primary_label(number.position.clone(), "exponent missing")
+			.with_help(format!("write e.g. `{}3`", number.value)),
+			Error::SecondDecimalPoint { number, dot } => Diagnostic::error(
+				"SecondDecimalPoint",
+				format!("Unexpected second decimal point in `{}`", number.value),
+			)
+			.with_primary_label(dot.clone(), "second decimal point")
+			.with_secondary_label(number.position.clone(), "in this number"),
+			Error::ForeignOperator { operator, replacement } => {
+				Diagnostic::error("ForeignOperator", format!("`{}` is not an operator of FTL", operator.value))
+					.with_primary_label(operator.position.clone(), format!("write `{}` instead", replacement))
+					.with_note("FTL compares with `=` and `=/=`, assignments are instructions of their own")
+			},
+			Error::InvalidEscape(escape) => {
+				Diagnostic::error("InvalidEscape", format!("Invalid escape `{}` in string literal", escape.value))
+					.with_primary_label(escape.position.clone(), "unknown escape")
+					.with_note("valid escapes are `\\n`, `\\r`, `\\t`, `\\0`, `\\\\`, `\\\"` and `\\x00` to `\\x7f`")
+			},
+		}
+	}
 }
@@ -0,0 +1,10 @@
+# Adds two numbers
+export def add(a: int, b: int): int {
+	return a + b
+}
+
+extern puts(s: ptr str): int
+
+struct Point {
+	x: float
+}
@@ -0,0 +1,4 @@
+puts("Hello, \"World\"\n")
+var pi: float = 3.14
+var answer: int = 42
+var größe: int = 7 # Trailing comment
@@ -0,0 +1,5 @@
+var x: int = 1 + 2 * 3 - 4 / 5
+if x < 1 { x = 2 } else { while 0 < x { x = x - 1 } }
+x =/= 3; p.x = [0]
+@bench
+x <= 1; x >= 2; x > 3; x<=-1
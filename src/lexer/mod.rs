@@ -3,13 +3,16 @@
 mod error;
 #[cfg(test)]
 mod test;
+mod trivia;
 
-use std::{iter::Peekable, ops::Deref};
+use std::{iter::Peekable, num::IntErrorKind};
 
 pub use error::Error;
+use smol_str::SmolStr;
+pub use trivia::{lines_after_empty_line, Block, BlockComment, BlockComments, Trivia, TriviaLexer, TriviaToken};
 
 use crate::{
-	source::{PositionContainer, Symbol},
+	source::{SourcePositionRange, Spanned, Symbol},
 	token::{Token, TokenKind},
 };
 
@@ -38,6 +41,12 @@ where
 		Self { symbols: symbols.peekable() }
 	}
 
+	/// Switches to trivia mode, which attaches the whitespace and comments preceding each token to it instead of
+	/// skipping them.
+	pub fn with_trivia(self) -> TriviaLexer<T> {
+		TriviaLexer::new(self)
+	}
+
 	/// Checks whether [`Self::symbols`] is going to yield a whitespace next.
 	///
 	/// This is used to skip irrelevant symbols. If [`Self::symbols`] is going to yield [`None`],
@@ -64,18 +73,13 @@ where
 				let read_string = self.read_string();
 				parse_string(read_string)
 			},
-			symbol if symbol.is_numeric() => {
-				let number = self.read_number();
-				parse_number(number)
-			},
+			symbol if symbol.is_numeric() => self.read_number(),
+			symbol if starts_doc_comment(&symbol) => Ok(self.read_doc_comment().map(TokenKind::DocComment)),
 			symbol if is_comment(*symbol) => {
 				let comment = self.read_comment();
-				Ok(Token::new(TokenKind::Comment((*comment).clone()), comment.position))
-			},
-			symbol if *symbol == '"' => {
-				let string = self.read_string_literal();
-				Ok(Token::new(TokenKind::StringLiteral(string.value), string.position))
+				Ok(comment.map(TokenKind::Comment))
 			},
+			symbol if *symbol == '"' => self.read_string_literal(),
 			/*symbol if symbol == '\n' => {
 				// Consume newline
 				assert_eq!(self.letters.next().map(&|(_, letter)| letter), Some('\n'));
@@ -94,73 +98,126 @@ where
 		Some(token)
 	}
 
-	/// Reads a string literal, i.e. something enclosed by `"`, while also taking care of escaping.
-	fn read_string_literal(&mut self) -> PositionContainer<String> {
+	/// Reads a string literal, i.e. something enclosed by `"`, and decodes its escapes `\n`, `\r`, `\t`, `\0`, `\\`,
+	/// `\"` and `\xNN`. An invalid escape is reported as [`Error::InvalidEscape`] after the rest of the literal has
+	/// been read, so that lexing continues after it.
+	fn read_string_literal(&mut self) -> LexResult {
 		// Discard starting quotes
 		let starting_quotes = self.symbols.next().unwrap();
 		assert_eq!(starting_quotes.value, '"');
 
 		let mut position = starting_quotes.position.clone();
 		let mut string = String::new();
+		let mut invalid_escape = None;
 
-		while let Some(mut symbol) = self.symbols.peek().cloned() {
-			if *symbol == '"' {
-				break
+		while let Some(symbol) = self.symbols.next_if(|symbol| **symbol != '"') {
+			position.position.end = symbol.position.position.end;
+			if *symbol != '\\' {
+				string.push(*symbol);
+				continue;
+			}
+			match self.read_escape(symbol.position.clone()) {
+				Ok(char) => {
+					position.position.end = char.position.position.end;
+					string.push(*char);
+				},
+				Err(escape) => {
+					position.position.end = escape.position.position.end;
+					invalid_escape.get_or_insert(escape);
+				},
 			}
+		}
 
-			// Escaping
-			if *symbol == '\\' {
-				// Read next escaped character
-				self.symbols.next();
-				symbol = match self.symbols.peek() {
-					Some(symbol) => symbol.clone(),
-					None => break
-				};
+		// Discard closing quotes
+		if let Some(closing_quotes) = self.symbols.next_if(|symbol| **symbol == '"') {
+			position.position.end = closing_quotes.position.position.end;
+		}
+		match invalid_escape {
+			Some(escape) => Err(Error::InvalidEscape(escape)),
+			None => Ok(Token::new(TokenKind::StringLiteral(string), position)),
+		}
+	}
 
-				symbol.value = match symbol.value {
-					'n' => '\n',
-					'r' => '\r',
-					symbol => symbol,
+	/// Reads the escape after the `\` at `position` and returns the char it stands for, or the invalid escape.
+	fn read_escape(&mut self, mut position: SourcePositionRange) -> Result<Spanned<char>, Spanned<String>> {
+		let Some(symbol) = self.symbols.next() else {
+			return Err(Spanned::new(position.code().to_owned(), position));
+		};
+		position.position.end = symbol.position.position.end;
+		let char = match *symbol {
+			'n' => '\n',
+			'r' => '\r',
+			't' => '\t',
+			'0' => '\0',
+			'\\' | '"' => *symbol,
+			'x' => {
+				for _ in 0..2 {
+					match self.symbols.next_if(|symbol| symbol.is_ascii_hexdigit()) {
+						Some(digit) => position.position.end = digit.position.position.end,
+						None => return Err(Spanned::new(position.code().to_owned(), position)),
+					}
 				}
-			}
+				// Like in Rust, `\xNN` stands for an ASCII char, as other chars are more than one byte in UTF-8
+				match u8::from_str_radix(&position.code()[2..], 16) {
+					Ok(byte) if byte.is_ascii() => char::from(byte),
+					_ => return Err(Spanned::new(position.code().to_owned(), position)),
+				}
+			},
+			_ => return Err(Spanned::new(position.code().to_owned(), position)),
+		};
+		Ok(Spanned::new(char, position))
+	}
 
-			string.push(*symbol);
-			position.position.end = symbol.position.position.end;
-			self.symbols.next();
-		}
-		PositionContainer::new(string, position)
+	/// Reads an identifier or keyword from [`Self::symbols`] and returns its position in the source code.
+	fn read_string(&mut self) -> SourcePositionRange {
+		self.read_while(|symbol| symbol.is_alphanumeric() || symbol == '_')
 	}
 
-	/// Reads a string from [`Self::symbols`].
-	fn read_string(&mut self) -> PositionContainer<String> {
-		let mut string = String::new();
-		let mut position = self.symbols.peek().unwrap().position.clone();
-		while let Some(symbol) = self.symbols.peek().cloned() {
-			let is_string_char = symbol.is_alphanumeric() || *symbol == '_';
-			if !is_string_char {
-				break;
+	/// Reads a number from [`Self::symbols`] and parses it to a [`TokenKind::Int`] or [`TokenKind::Float`].
+	///
+	/// Numbers have no fields, so a `.` after digits always starts the decimal places, which must follow. `5.` and
+	/// `5.x` are therefore errors. A second `.` like in `1.2.3` is consumed as part of the invalid number. Numbers may
+	/// end with an exponent like in `1.5e-3` and `2E6`, which makes them floats.
+	fn read_number(&mut self) -> LexResult {
+		let mut position = self.read_while(char::is_numeric);
+		if let Some(dot) = self.symbols.next_if(|symbol| **symbol == '.') {
+			position.position.end = dot.position.position.end;
+			if !self.symbols.peek().is_some_and(|symbol| symbol.is_numeric()) {
+				return Err(Error::MissingDecimalPlaces(Spanned::new(position.code().to_owned(), position)));
 			}
-			string.push(*symbol);
-			position.position.end = symbol.position.position.end;
-			self.symbols.next();
+			position.position.end = self.read_while(char::is_numeric).position.end;
+		}
+
+		if let Some(e) = self.symbols.next_if(|symbol| matches!(**symbol, 'e' | 'E')) {
+			position.position.end = e.position.position.end;
+			if let Some(sign) = self.symbols.next_if(|symbol| matches!(**symbol, '+' | '-')) {
+				position.position.end = sign.position.position.end;
+			}
+			if !self.symbols.peek().is_some_and(|symbol| symbol.is_numeric()) {
+				return Err(Error::MissingExponent(Spanned::new(position.code().to_owned(), position)));
+			}
+			position.position.end = self.read_while(char::is_numeric).position.end;
+		}
+
+		if let Some(second_dot) = self.symbols.peek().filter(|symbol| ***symbol == '.').map(|dot| dot.position.clone())
+		{
+			position.position.end = self.read_while(|symbol| symbol.is_numeric() || symbol == '.').position.end;
+			let number = Spanned::new(position.code().to_owned(), position);
+			return Err(Error::SecondDecimalPoint { number, dot: second_dot });
 		}
-		PositionContainer::new(string, position)
+		parse_number(position)
 	}
 
-	/// Reads a number from [`Self::symbols`].
-	fn read_number(&mut self) -> PositionContainer<String> {
-		let mut number = String::new();
+	/// Consumes symbols as long as they fulfill the `predicate`, which must be true for the next symbol.
+	///
+	/// Instead of copying the symbols, the position range is returned, which can be resolved to a slice of the source
+	/// code with [`SourcePositionRange::code`].
+	fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> SourcePositionRange {
 		let mut position = self.symbols.peek().unwrap().position.clone();
-		while let Some(symbol) = self.symbols.peek().cloned() {
-			let is_number_char = symbol.is_numeric() || *symbol == '.';
-			if !is_number_char {
-				break;
-			}
-			number.push(*symbol);
+		while let Some(symbol) = self.symbols.next_if(|symbol| predicate(**symbol)) {
 			position.position.end = symbol.position.position.end;
-			self.symbols.next();
 		}
-		PositionContainer::new(number, position)
+		position
 	}
 
 	/// Reads a special character from [`Self::symbols`], e.g. operators and parenthesis.
@@ -176,9 +233,22 @@ where
 			')' => Ok(Token::new(TokenKind::ClosingParentheses, position)),
 			'{' => Ok(Token::new(TokenKind::OpeningCurlyBraces, position)),
 			'}' => Ok(Token::new(TokenKind::ClosingCurlyBraces, position)),
-			'<' => Ok(Token::new(TokenKind::Less, position)),
-			'>' => Ok(Token::new(TokenKind::Greater, position)),
+			'<' | '>' => {
+				let mut position = position;
+				let or_equal = self.symbols.next_if(|symbol| **symbol == '=');
+				if let Some(equal) = &or_equal {
+					position.position.end = equal.position.position.end;
+				}
+				let kind = match (*symbol, or_equal.is_some()) {
+					('<', false) => TokenKind::Less,
+					('<', true) => TokenKind::LessEqual,
+					(_, false) => TokenKind::Greater,
+					(_, true) => TokenKind::GreaterEqual,
+				};
+				Ok(Token::new(kind, position))
+			},
 			'.' => Ok(Token::new(TokenKind::Dot, position)),
+			'@' => Ok(Token::new(TokenKind::At, position)),
 			':' => Ok(Token::new(TokenKind::Colon, position)),
 			'/' => Ok(Token::new(TokenKind::Slash, position)),
 			';' => Ok(Token::new(TokenKind::Semicolon, position)),
@@ -188,6 +258,8 @@ where
 				match self.symbols.peek() {
 					// Read token is `=/` so far
 					Some(symbol) if **symbol == '/' => self.symbols.next(),
+					// `==` of other languages
+					Some(symbol) if **symbol == '=' => return Err(self.foreign_operator(position, "=")),
 					// Ok, only a single `=` as token
 					_ => return Ok(Token::new(TokenKind::Equal, position)),
 				};
@@ -198,12 +270,25 @@ where
 					symbol => Err(Error::IllegalSymbol(symbol))?,
 				}
 			},
+			// `!=` of other languages, a single `!` is no symbol of FTL
+			'!' if self.symbols.peek().is_some_and(|symbol| **symbol == '=') => {
+				Err(self.foreign_operator(position, "=/="))
+			},
+			'!' => Err(Error::UnknownSymbol(symbol)),
 			_ => Err(Error::IllegalSymbol(Some(symbol))),
 		}
 	}
 
+	/// Consumes the second symbol of an operator of other languages starting at `position`, like the `=` of `!=`,
+	/// and returns an [`Error::ForeignOperator`] suggesting the `replacement`.
+	fn foreign_operator(&mut self, mut position: SourcePositionRange, replacement: &'static str) -> Error {
+		let second = self.symbols.next().unwrap();
+		position.position.end = second.position.position.end;
+		Error::ForeignOperator { operator: Spanned::new(position.code().to_owned(), position), replacement }
+	}
+
 	/// Reads a comment and returns its content.
-	fn read_comment(&mut self) -> PositionContainer<String> {
+	fn read_comment(&mut self) -> Spanned<String> {
 		// Skip comment symbol
 		let mut postion = self.symbols.next().unwrap().position;
 
@@ -214,10 +299,11 @@ where
 				Some(symbol) if **symbol == '\n' => {
 					// Detected newline. Check if the next line is also a comment. If yes, continue parsing the next line
 					self.symbols.next(); // Consume \n
-					self.skip_whitespaces(); // Skip possible leading whitespaces
+						  // Skip possible leading whitespaces. An empty line ends the comment
+					while self.symbols.next_if(|symbol| symbol.is_whitespace() && **symbol != '\n').is_some() {}
 					match self.symbols.peek() {
-						Some(symbol) if is_comment(**symbol) => (), // Is comment. Continue parsing
-						_ => break,                                 // Either none or not a comment. End parsing
+						Some(symbol) if is_comment(**symbol) && !starts_doc_comment(symbol) => (), // Is comment. Continue parsing
+						_ => break, // Either none or not a comment. End parsing
 					};
 					comment.push('\n');
 				},
@@ -233,39 +319,78 @@ where
 		}
 		// Remove potential trailing whitespaces
 		comment = comment.trim().to_owned();
-		PositionContainer::new(comment, postion)
+		Spanned::new(comment, postion)
+	}
+
+	/// Reads a doc comment, i.e. the lines starting with `##` that directly follow each other, and returns the lines
+	/// without the `##` and the space after it.
+	fn read_doc_comment(&mut self) -> Spanned<String> {
+		let mut position = self.symbols.peek().unwrap().position.clone();
+		let mut lines = Vec::new();
+		while self.symbols.peek().is_some_and(starts_doc_comment) {
+			self.symbols.next(); // Consume first `#`
+			position.position.end = self.symbols.next().unwrap().position.position.end;
+			let mut line = String::new();
+			while let Some(symbol) = self.symbols.next_if(|symbol| **symbol != '\n') {
+				position.position.end = symbol.position.position.end;
+				line.push(*symbol);
+			}
+			lines.push(line.strip_prefix(' ').unwrap_or(&line).trim_end().to_owned());
+			// An empty line ends the doc comment
+			self.symbols.next_if(|symbol| **symbol == '\n');
+			while self.symbols.next_if(|symbol| symbol.is_whitespace() && **symbol != '\n').is_some() {}
+		}
+		Spanned::new(lines.join("\n"), position)
 	}
 }
 
 /// Parses a string to a keyword (`def`, `if`, `else`, ...), or to a [`TokenKind::Identifier`] otherwise.
-fn parse_string(string: PositionContainer<String>) -> LexResult {
-	Ok(match string.as_str() {
-		"def" => Token::new(TokenKind::Def, string.position),
-		"extern" => Token::new(TokenKind::Extern, string.position),
-		"bitor" => Token::new(TokenKind::BitOr, string.position),
-		"bitand" => Token::new(TokenKind::BitAnd, string.position),
-		"mod" => Token::new(TokenKind::Modulus, string.position),
-		"if" => Token::new(TokenKind::If, string.position),
-		"else" => Token::new(TokenKind::Else, string.position),
-		"while" => Token::new(TokenKind::While, string.position),
-		"ptr" => Token::new(TokenKind::Pointer, string.position),
-		"struct" => Token::new(TokenKind::Struct, string.position),
-		"var" => Token::new(TokenKind::Var, string.position),
-		"return" => Token::new(TokenKind::Return, string.position),
-		_ => Token::new(TokenKind::Identifier(string.deref().to_owned()), string.position),
-	})
+fn parse_string(position: SourcePositionRange) -> LexResult {
+	let kind = match position.code() {
+		"def" => TokenKind::Def,
+		"extern" => TokenKind::Extern,
+		"export" => TokenKind::Export,
+		"bitor" => TokenKind::BitOr,
+		"bitand" => TokenKind::BitAnd,
+		"mod" => TokenKind::Modulus,
+		"if" => TokenKind::If,
+		"else" => TokenKind::Else,
+		"while" => TokenKind::While,
+		"ptr" => TokenKind::Pointer,
+		"opt" => TokenKind::Optional,
+		"null" => TokenKind::Null,
+		"try" => TokenKind::Try,
+		"or" => TokenKind::Or,
+		"and" => TokenKind::And,
+		"error" => TokenKind::Error,
+		"struct" => TokenKind::Struct,
+		"var" => TokenKind::Var,
+		"return" => TokenKind::Return,
+		identifier => TokenKind::Identifier(SmolStr::new(identifier)),
+	};
+	Ok(Token::new(kind, position))
 }
 
-/// Parses a number to a [`TokenKind::Float`].
-fn parse_number(number_str: PositionContainer<String>) -> LexResult {
-	let is_float = number_str.contains('.');
-	if is_float {
-		let float: f64 = number_str.parse().map_err(|_| Error::ParseNumberError(number_str.clone()))?;
-		Ok(Token::new(TokenKind::Float(float), number_str.position))
+/// Parses a number to a [`TokenKind::Float`] or [`TokenKind::Int`]. Numbers too large for their type are reported
+/// as [`Error::IntOverflow`] and [`Error::FloatOverflow`], other invalid numbers, e.g. with digits of other scripts,
+/// as [`Error::ParseNumberError`].
+fn parse_number(position: SourcePositionRange) -> LexResult {
+	let number_str = position.code();
+	let number = || Spanned::new(number_str.to_owned(), position.clone());
+	let kind = if number_str.contains(['.', 'e', 'E']) {
+		match number_str.parse::<f64>() {
+			Ok(float) if float.is_infinite() => return Err(Error::FloatOverflow(number())),
+			Ok(float) => TokenKind::Float(float),
+			Err(_) => return Err(Error::ParseNumberError(number())),
+		}
 	} else {
-		let int: i64 = number_str.parse().map_err(|_| Error::ParseNumberError(number_str.clone()))?;
-		Ok(Token::new(TokenKind::Int(int), number_str.position))
-	}
+		match number_str.parse() {
+			Ok(int) => TokenKind::Int(int),
+			Err(err) if *err.kind() == IntErrorKind::PosOverflow => return Err(Error::IntOverflow(number())),
+			Err(_) => return Err(Error::ParseNumberError(number())),
+		}
+	};
+	Ok(Token::new(kind, position))
 }
 
 /// Checks whether `letter` is a letter that starts a comment line.
@@ -273,9 +398,14 @@ fn is_comment(letter: char) -> bool {
 	letter == '#'
 }
 
+/// Checks whether the `symbol` is the first `#` of a `##`, which starts a doc comment.
+fn starts_doc_comment(symbol: &Symbol) -> bool {
+	symbol.position.source.text[symbol.position.byte_range().start..].starts_with("##")
+}
+
 /// Checks whether `letter` is a special character like `+`, `-`, `=`, `*`.
 fn is_special_char(letter: char) -> bool {
-	['+', '-', '=', '<', '*', '(', ')', '{', '}', '.', ':', ',', '/', ';', '[', ']'].contains(&letter)
+	['+', '-', '=', '<', '>', '*', '(', ')', '{', '}', '.', ':', ',', '/', ';', '[', ']', '@', '!'].contains(&letter)
 }
 
 impl<T> Iterator for Lexer<T>
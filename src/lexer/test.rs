@@ -1,7 +1,9 @@
+use std::fs;
+use std::path::Path;
 use std::sync::Arc;
 use crate::lexer::*;
 use crate::source::Source;
-use crate::token::Token;
+use crate::token::{text, Token};
 
 
 /// Tests that the lexer can read a string literal containing escaped quotes.
@@ -11,11 +13,55 @@ fn test_read_string_literal() {
     assert_eq!(tokens[0].value, TokenKind::StringLiteral(r#"hello "name"!"#.to_owned()));
 }
 
+/// Tests that escapes are decoded, and that invalid escapes are reported without stopping the lexer.
+#[test]
+fn test_escapes() {
+    let tokens = lexer(r#""\n\r\t\0\\\"\x41\x7f""#);
+    assert_eq!(tokens[0].value, TokenKind::StringLiteral("\n\r\t\0\\\"A\x7f".to_owned()));
+
+    let lex = |source_code: &str| {
+        let source = Arc::new(Source::new("testfile".to_owned(), source_code.to_owned()));
+        Lexer::new(source.iter()).collect::<Vec<LexResult>>()
+    };
+    for (source_code, escape, column) in [(r#""a\q" b"#, r"\q", 3), (r#""\x4" b"#, r"\x4", 2), (r#""\x80\q" b"#, r"\x80", 2)] {
+        let tokens = lex(source_code);
+        let Err(Error::InvalidEscape(invalid)) = &tokens[0] else { panic!("{:?}", tokens) };
+        assert_eq!((invalid.value.as_str(), invalid.position.position.start.column), (escape, column));
+        assert_eq!(tokens[1].as_ref().map(|token| &token.value), Ok(&TokenKind::Identifier("b".into())));
+    }
+}
+
+/// Tests that `##` starts a doc comment, which ends at lines without `##`, also at ordinary comments.
+#[test]
+fn test_doc_comment() {
+    let tokens = lexer("## Adds\n##\n##   indented\n\n## Next\n# ordinary\n## doc\ndef");
+    let kinds: Vec<_> = tokens.iter().map(|token| token.value.clone()).collect();
+    assert_eq!(kinds, [
+        TokenKind::DocComment("Adds\n\n  indented".to_owned()),
+        TokenKind::DocComment("Next".to_owned()),
+        TokenKind::Comment("ordinary".to_owned()),
+        TokenKind::DocComment("doc".to_owned()),
+        TokenKind::Def,
+    ]);
+    assert_eq!((tokens[0].position.position.start.line, tokens[0].position.position.end.line), (1, 3));
+}
+
+/// Tests that consecutive comment lines form one comment, which ends at an empty line.
+#[test]
+fn test_comment() {
+    let tokens = lexer("# First\n    # second\n\n# Next");
+    let kinds: Vec<_> = tokens.iter().map(|token| token.value.clone()).collect();
+    assert_eq!(kinds, [
+        TokenKind::Comment("First\n# second".to_owned()),
+        TokenKind::Comment("Next".to_owned()),
+    ]);
+}
+
 /// Tests that the lexer can read an identifier.
 #[test]
 fn test_read_identifier() {
     let tokens = lexer("hello");
-    assert_eq!(tokens[0].value, TokenKind::Identifier("hello".to_owned()));
+    assert_eq!(tokens[0].value, TokenKind::Identifier("hello".into()));
 }
 
 /// Tests that the lexer can read a float.
@@ -32,10 +78,166 @@ fn test_read_float() {
     assert_eq!(tokens[0].value, TokenKind::Float(4.2));
 }
 
+/// Tests that a `.` in a number must be followed by decimal places, and that there is only one.
+#[test]
+fn test_decimal_points() {
+    let lex = |source_code: &str| {
+        let source = Arc::new(Source::new("testfile".to_owned(), source_code.to_owned()));
+        Lexer::new(source.iter()).collect::<Vec<LexResult>>()
+    };
+    let tokens = lex("1.2.3 + 4");
+    let Err(Error::SecondDecimalPoint { number, dot }) = &tokens[0] else { panic!("{:?}", tokens) };
+    assert_eq!((number.value.as_str(), dot.position.start.column), ("1.2.3", 4));
+    // Lexing continues after the invalid number
+    assert_eq!(tokens[1].as_ref().map(|token| &token.value), Ok(&TokenKind::Plus));
+
+    for source_code in ["5.", "5. + 1", "5.x"] {
+        let tokens = lex(source_code);
+        let Err(Error::MissingDecimalPlaces(number)) = &tokens[0] else { panic!("{:?}", tokens) };
+        assert_eq!(number.value, "5.");
+    }
+    assert_eq!(lexer("5.25")[0].value, TokenKind::Float(5.25));
+    assert_eq!(lexer("5 .x").len(), 3);
+}
+
+/// Tests that `==` and `!=` of other languages are reported with the FTL operators to write instead.
+#[test]
+fn test_foreign_operators() {
+    let lex = |source_code: &str| {
+        let source = Arc::new(Source::new("testfile".to_owned(), source_code.to_owned()));
+        Lexer::new(source.iter()).collect::<Vec<LexResult>>()
+    };
+    let tokens = lex("a == b != c");
+    let Err(Error::ForeignOperator { operator, replacement: "=" }) = &tokens[1] else { panic!("{:?}", tokens) };
+    assert_eq!((operator.value.as_str(), operator.position.position.end.column), ("==", 4));
+    let Err(Error::ForeignOperator { operator, replacement: "=/=" }) = &tokens[3] else { panic!("{:?}", tokens) };
+    assert_eq!(operator.value, "!=");
+    // Lexing continues after the operators
+    assert_eq!(tokens[4].as_ref().map(|token| &token.value), Ok(&TokenKind::Identifier("c".into())));
+    assert!(matches!(&lex("!a")[0], Err(Error::UnknownSymbol(symbol)) if symbol.value == '!'));
+    assert_eq!(lexer("a = b =/= c")[3].value, TokenKind::NotEqual);
+}
+
+/// Tests that numbers with an exponent are floats, and that the exponent must have digits.
+#[test]
+fn test_exponents() {
+    for (source_code, float) in [("1.5e-3", 1.5e-3), ("2E6", 2e6), ("3e+2", 300.0), ("1e0", 1.0)] {
+        assert_eq!(lexer(source_code)[0].value, TokenKind::Float(float), "{}", source_code);
+    }
+    assert_eq!(lexer("2e3 + x").len(), 3);
+
+    let lex = |source_code: &str| {
+        let source = Arc::new(Source::new("testfile".to_owned(), source_code.to_owned()));
+        Lexer::new(source.iter()).next().unwrap()
+    };
+    for (source_code, number) in [("1e", "1e"), ("1.5e-x", "1.5e-"), ("2E +1", "2E")] {
+        assert!(matches!(lex(source_code), Err(Error::MissingExponent(invalid)) if invalid.value == number), "{}", source_code);
+    }
+    assert!(matches!(lex("1e999"), Err(Error::FloatOverflow(_))));
+}
+
+/// Tests that numbers too large for their type are reported as overflow, unlike other invalid numbers.
+#[test]
+fn test_number_overflow() {
+    let lex = |source_code: &str| {
+        let source = Arc::new(Source::new("testfile".to_owned(), source_code.to_owned()));
+        Lexer::new(source.iter()).next().unwrap()
+    };
+    assert_eq!(lex("9223372036854775807").map(|token| token.value), Ok(TokenKind::Int(i64::MAX)));
+    assert!(matches!(lex("9223372036854775808"), Err(Error::IntOverflow(number)) if number.value == "9223372036854775808"));
+    let huge_float = format!("{}.0", "9".repeat(400));
+    assert!(matches!(lex(&huge_float), Err(Error::FloatOverflow(_))));
+    // Digits of other scripts are numeric, but not valid in numbers
+    assert!(matches!(lex("٣"), Err(Error::ParseNumberError(_))));
+}
+
+/// Tests that every keyword is lexed as its token, and that reserved words without a construct yet, like `for`, are
+/// still identifiers.
+#[test]
+fn test_keywords() {
+    let keywords = [
+        ("def", TokenKind::Def),
+        ("extern", TokenKind::Extern),
+        ("export", TokenKind::Export),
+        ("bitor", TokenKind::BitOr),
+        ("bitand", TokenKind::BitAnd),
+        ("mod", TokenKind::Modulus),
+        ("if", TokenKind::If),
+        ("else", TokenKind::Else),
+        ("while", TokenKind::While),
+        ("ptr", TokenKind::Pointer),
+        ("opt", TokenKind::Optional),
+        ("null", TokenKind::Null),
+        ("try", TokenKind::Try),
+        ("or", TokenKind::Or),
+        ("and", TokenKind::And),
+        ("error", TokenKind::Error),
+        ("struct", TokenKind::Struct),
+        ("var", TokenKind::Var),
+        ("return", TokenKind::Return),
+    ];
+    for (keyword, kind) in keywords {
+        assert_eq!(lexer(keyword)[0].value, kind, "{}", keyword);
+        // Keywords are only recognized as whole words
+        assert_eq!(lexer(&format!("{}_", keyword))[0].value, TokenKind::Identifier(format!("{}_", keyword).into()));
+    }
+    assert_eq!(lexer("for")[0].value, TokenKind::Identifier("for".into()));
+
+    let tokens = lexer("extern write(fd: int): int");
+    assert_eq!(tokens[0].value, TokenKind::Extern);
+    assert_eq!(tokens[1].value, TokenKind::Identifier("write".into()));
+}
+
+/// Tests that positions are byte offsets, also for multibyte chars.
+#[test]
+fn test_multibyte_identifier() {
+    let tokens = lexer("größe def");
+    assert_eq!(tokens[0].value, TokenKind::Identifier("größe".into()));
+    assert_eq!(tokens[0].position.byte_range(), 0..7);
+    assert_eq!(tokens[1].position.code(), "def");
+    assert_eq!(tokens[1].position.position.start.column, 7);
+}
+
+
+/// Compares the tokens of each FTL file in `fixtures/` with the serialized tokens in the `.tokens` file of the same
+/// name. Set the environment variable `FTL_UPDATE_GOLDEN` to overwrite the `.tokens` files with the lexed tokens.
+#[test]
+fn test_fixtures() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/lexer/fixtures");
+    let mut ftl_paths: Vec<_> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ftl"))
+        .collect();
+    ftl_paths.sort();
+    assert!(!ftl_paths.is_empty());
+
+    for ftl_path in ftl_paths {
+        let source = Arc::new(Source::new("fixture.ftl".to_owned(), fs::read_to_string(&ftl_path).unwrap()));
+        let tokens = Lexer::new(Arc::clone(&source).iter()).collect::<Result<Vec<Token>, Error>>().unwrap();
+        let tokens_path = ftl_path.with_extension("tokens");
+        if std::env::var_os("FTL_UPDATE_GOLDEN").is_some() {
+            fs::write(&tokens_path, text::serialize(&tokens)).unwrap();
+            continue;
+        }
+        let expected = text::deserialize(&fs::read_to_string(&tokens_path).unwrap_or_default(), &source);
+        assert_eq!(
+            Ok(text::serialize(&tokens)),
+            expected.map(|expected| text::serialize(&expected)),
+            "tokens differ from {:?}",
+            tokens_path
+        );
+    }
+}
 
 /// Boilerplate code for converting source code into tokens using a lexer.
 fn lexer(source_code: &str) -> Vec<Token> {
     let source = Arc::new(Source::new("testfile".to_owned(), source_code.to_owned()));
     let lexer = Lexer::new(source.iter());
     lexer.collect::<Result<Vec<Token>, Error>>().unwrap()
-}
\ No newline at end of file
+}
+/// Tests that tokens stay compact, since the lexer produces one for each word of the source code.
+#[test]
+fn test_token_size() {
+    assert!(std::mem::size_of::<Token>() <= 64, "Token is {} bytes", std::mem::size_of::<Token>());
+}
@@ -0,0 +1,218 @@
+//! Trivia mode of the [`Lexer`], which keeps the whitespace and comments between the tokens, e.g. for the formatter.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+	lexer::{Error, Lexer},
+	source::{Spanned, Symbol},
+	token::{Token, TokenKind},
+};
+
+/// Whitespace or comment preceding a token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+	/// Whitespace containing the given number of line breaks.
+	Whitespace { line_breaks: usize },
+	/// Comment, which is a token of its own outside of trivia mode.
+	Comment(Spanned<String>),
+}
+
+/// A [`Token`] with the trivia between it and the previous token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriviaToken {
+	pub leading: Vec<Trivia>,
+	pub token: Token,
+}
+
+/// [`Lexer`] that attaches the whitespace and comments preceding a token to it. Created by [`Lexer::with_trivia`].
+pub struct TriviaLexer<T>
+where
+	T: Iterator<Item = Symbol>,
+{
+	lexer: Lexer<T>,
+	/// Byte offset after the previous token or comment.
+	previous_end: usize,
+}
+
+impl<T> TriviaLexer<T>
+where
+	T: Iterator<Item = Symbol>,
+{
+	pub(super) fn new(lexer: Lexer<T>) -> Self {
+		Self { lexer, previous_end: 0 }
+	}
+
+	/// Counts the line breaks between the previous token and the `token`.
+	fn whitespace_before(&mut self, token: &Token) -> Option<Trivia> {
+		let range = token.position.byte_range();
+		let whitespace = token.position.source.text.get(self.previous_end..range.start).unwrap_or_default();
+		self.previous_end = range.end;
+		(!whitespace.is_empty()).then(|| Trivia::Whitespace { line_breaks: whitespace.matches('\n').count() })
+	}
+}
+
+impl<T> Iterator for TriviaLexer<T>
+where
+	T: Iterator<Item = Symbol>,
+{
+	type Item = Result<TriviaToken, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let mut leading = Vec::new();
+		loop {
+			let token = match self.lexer.next()? {
+				Ok(token) => token,
+				Err(err) => return Some(Err(err)),
+			};
+			leading.extend(self.whitespace_before(&token));
+			match token.value {
+				TokenKind::Comment(comment) => leading.push(Trivia::Comment(Spanned::new(comment, token.position))),
+				_ => return Some(Ok(TriviaToken { leading, token })),
+			}
+		}
+	}
+}
+
+/// Comment in a block, which the parser skips, so that the formatter can put it back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockComment {
+	pub comment: Spanned<String>,
+	/// Whether the comment follows code on the same line.
+	pub trailing: bool,
+	/// Byte offset of the token following the comment.
+	pub next: usize,
+}
+
+/// Comments of a block enclosed in curly braces.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block {
+	/// Byte offset of the `}`, if the block is closed.
+	pub closing: Option<usize>,
+	pub comments: Vec<BlockComment>,
+}
+
+/// The comments in the blocks of a file, i.e. those that are not [`Node::Comment`](crate::ast::Node::Comment)s.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockComments {
+	/// The blocks by the byte offset of their `{`.
+	blocks: BTreeMap<usize, Block>,
+}
+
+impl BlockComments {
+	/// Assigns the comments of the `tokens` to the innermost block containing them.
+	pub fn new<'a>(tokens: impl IntoIterator<Item = &'a TriviaToken>) -> Self {
+		let mut blocks = BTreeMap::new();
+		// Offsets of the `{` of the blocks containing the current token
+		let mut open: Vec<usize> = Vec::new();
+		for (i, token) in tokens.into_iter().enumerate() {
+			let offset = token.token.position.byte_range().start;
+			let mut trailing = i > 0;
+			for trivia in &token.leading {
+				match trivia {
+					Trivia::Whitespace { line_breaks } => trailing &= *line_breaks == 0,
+					Trivia::Comment(comment) => {
+						if let Some(opening) = open.last() {
+							let comments = &mut blocks.entry(*opening).or_insert_with(Block::default).comments;
+							comments.push(BlockComment { comment: comment.clone(), trailing, next: offset });
+						}
+						trailing = false;
+					},
+				}
+			}
+			match token.token.value {
+				TokenKind::OpeningCurlyBraces => {
+					open.push(offset);
+					blocks.insert(offset, Block::default());
+				},
+				TokenKind::ClosingCurlyBraces => {
+					if let Some(block) = open.pop().and_then(|opening| blocks.get_mut(&opening)) {
+						block.closing = Some(offset);
+					}
+				},
+				_ => (),
+			}
+		}
+		Self { blocks }
+	}
+
+	/// The first block whose `{` is at or after the byte `offset`.
+	pub fn block_after(&self, offset: usize) -> Option<&Block> {
+		self.blocks.range(offset..).next().map(|(_, block)| block)
+	}
+}
+
+/// Lines on which a comment or token starts after an empty line, so that the formatter can keep the empty lines.
+pub fn lines_after_empty_line<'a>(tokens: impl IntoIterator<Item = &'a TriviaToken>) -> BTreeSet<u32> {
+	let mut lines = BTreeSet::new();
+	for token in tokens {
+		let mut empty_line = false;
+		for trivia in &token.leading {
+			match trivia {
+				Trivia::Whitespace { line_breaks } => empty_line = *line_breaks >= 2,
+				Trivia::Comment(comment) => {
+					if empty_line {
+						lines.insert(comment.position.position.start.line);
+					}
+					empty_line = false;
+				},
+			}
+		}
+		if empty_line {
+			lines.insert(token.token.position.position.start.line);
+		}
+	}
+	lines
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::source::Source;
+
+	fn lex(source_code: &str) -> Vec<TriviaToken> {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		Lexer::new(source.iter()).with_trivia().collect::<Result<_, _>>().unwrap()
+	}
+
+	#[test]
+	fn test_trivia() {
+		let tokens = lex("extern f()\n\n# Doc\n# comment\ndef g() {\n}\n\n\n\nextern h()");
+		let kinds: Vec<_> = tokens.iter().map(|token| token.token.value.clone()).collect();
+		assert!(!kinds.iter().any(|kind| matches!(kind, TokenKind::Comment(_))));
+		assert_eq!(tokens[0].leading, []);
+		assert_eq!(tokens[1].leading, [Trivia::Whitespace { line_breaks: 0 }]);
+
+		let def = &tokens[4];
+		assert_eq!(def.token.value, TokenKind::Def);
+		assert!(matches!(def.leading[..], [
+			Trivia::Whitespace { line_breaks: 2 },
+			Trivia::Comment(ref comment),
+			Trivia::Whitespace { line_breaks: 1 },
+		] if comment.value == "Doc\n# comment"));
+		assert_eq!(tokens[10].leading, [Trivia::Whitespace { line_breaks: 4 }]);
+
+		assert_eq!(lines_after_empty_line(&tokens), BTreeSet::from([3, 10]));
+	}
+
+	#[test]
+	fn test_block_comments() {
+		let tokens = lex("# Top\ndef f() { # a\n\tif x {\n\t\t# b\n\t}\n\n\t# c\n\t# d\n\treturn # e\n}\n# After");
+		let block_comments = BlockComments::new(&tokens);
+		let summary = |block: &Block| -> Vec<_> {
+			let comments = block.comments.iter();
+			comments.map(|comment| (comment.comment.value.clone(), comment.trailing, comment.next)).collect()
+		};
+
+		let function = block_comments.block_after(0).unwrap();
+		assert_eq!(function.closing, Some(60));
+		assert_eq!(
+			summary(function),
+			[("a".to_owned(), true, 21), ("c\n# d".to_owned(), false, 49), ("e".to_owned(), true, 60),]
+		);
+		let if_ = block_comments.block_after(15).unwrap();
+		assert_eq!((if_.closing, summary(if_)), (Some(35), vec![("b".to_owned(), false, 35)]));
+		assert_eq!(block_comments.block_after(36), None);
+	}
+}
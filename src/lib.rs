@@ -1,34 +1,202 @@
-use std::{fs, path::Path, sync::Arc};
+use std::{
+	cmp::Reverse,
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
 
-use anyhow::Context;
+use diagnostic::{sink::DiagnosticSink, Diagnostic};
+use dump::{Artifact, Dumper};
+pub use error::CompileError;
 use lexer::Lexer;
 use parser::Parser;
-use semantic_analyzer::{SymbolTable, TypeChecker};
+use semantic_analyzer::{CheckConfig, TypedProgram};
 use source::Source;
 use token::Token;
 
 pub mod ast;
+pub mod bench;
+pub mod bench_support;
+pub mod build;
+pub mod cfg;
+pub mod coverage;
+pub mod dataflow;
+pub mod deps;
+pub mod diagnostic;
+pub mod differential;
+pub mod doctest;
+pub mod driver;
+pub mod dump;
 pub mod emitter;
+pub mod error;
+pub mod examples;
+pub mod format_string;
+pub mod highlight;
+pub mod index;
+pub mod interpreter;
+pub mod intrinsic;
 pub mod lexer;
 pub mod parser;
+pub mod profile;
+pub mod project;
+pub mod repl;
+pub mod runtime;
+pub mod sanitizer;
 pub mod semantic_analyzer;
 pub mod source;
+pub(crate) mod test_support;
 pub mod token;
+pub mod types;
 
 /// Combines lexer, parser, and semantic analysis into a single function.
-pub fn compiler_pipeline(path: &Path) -> anyhow::Result<Vec<ast::Node>> {
-	let content = fs::read_to_string(path).context(format!("Reading FTL source file `{:?}`", path))?;
+///
+/// The `config` selects optional checks of the semantic analysis. Intermediate artifacts are written to files by the
+/// `dumper`, if enabled. The warnings of the lints are emitted to the `sink`, while an error stopping the pipeline is
+/// returned.
+pub fn compiler_pipeline(
+	path: &Path,
+	config: &CheckConfig,
+	dumper: &Dumper,
+	sink: &mut dyn DiagnosticSink,
+) -> Result<Vec<ast::Node>, CompileError> {
+	compile_source(read_source(path)?, config, dumper, sink)
+}
 
-	let source = Arc::new(Source::new(path.to_str().unwrap().to_string(), content));
-	let lexer = Lexer::new(source.iter());
-	let tokens = lexer.collect::<Result<Vec<Token>, lexer::Error>>().context("Lexing error")?;
+/// Like [`compiler_pipeline`], but in [script mode](Parser::with_script), where instructions outside of functions
+/// are wrapped into a `main` function.
+pub fn script_pipeline(
+	path: &Path,
+	config: &CheckConfig,
+	dumper: &Dumper,
+	sink: &mut dyn DiagnosticSink,
+) -> Result<Vec<ast::Node>, CompileError> {
+	let tokens = lex_source(read_source(path)?, dumper)?;
+	let ast_nodes = collect_ast(path, Parser::new(tokens.into_iter()).with_script(true), dumper)?;
+	analyze_into(path, &ast_nodes, config, dumper, sink)?;
+	Ok(ast_nodes)
+}
 
-	let parser = Parser::new(tokens.into_iter());
-	let ast_nodes = parser.collect::<Result<Vec<_>, _>>().context("Parser error")?;
-	tracing::trace!("AST parsed: {:#?}", ast_nodes);
+/// Reads the FTL source file at `path`.
+pub(crate) fn read_source(path: &Path) -> Result<Arc<Source>, CompileError> {
+	let content =
+		fs::read_to_string(path).map_err(CompileError::io(format!("Reading FTL source file `{:?}`", path)))?;
+	Ok(Arc::new(Source::new(path.to_string_lossy().into_owned(), content)))
+}
+
+/// Like [`compiler_pipeline`], but for source code that is already in memory.
+pub fn compile_source(
+	source: Arc<Source>,
+	config: &CheckConfig,
+	dumper: &Dumper,
+	sink: &mut dyn DiagnosticSink,
+) -> Result<Vec<ast::Node>, CompileError> {
+	let path = PathBuf::from(&source.name);
+	let ast_nodes = parse_source(source, dumper)?;
+	analyze_into(&path, &ast_nodes, config, dumper, sink)?;
+	Ok(ast_nodes)
+}
+
+/// Analyzes the `ast_nodes` of the pipelines and emits the warnings to the `sink`.
+fn analyze_into(
+	path: &Path,
+	ast_nodes: &[ast::Node],
+	config: &CheckConfig,
+	dumper: &Dumper,
+	sink: &mut dyn DiagnosticSink,
+) -> Result<(), CompileError> {
+	let program = analyze(path, ast_nodes.iter(), config, dumper)?;
+	program.diagnostics.into_iter().for_each(|diagnostic| sink.emit(diagnostic));
+	Ok(())
+}
 
-	let symbol_table = SymbolTable::global_symbol_scan(ast_nodes.iter()).context("Global symbol scan error")?;
-	TypeChecker::type_check(symbol_table, ast_nodes.iter()).context("Type checking error")?;
+/// Lexes and parses the `source` without any semantic analysis.
+pub fn parse_source(source: Arc<Source>, dumper: &Dumper) -> Result<Vec<ast::Node>, CompileError> {
+	let path = PathBuf::from(&source.name);
+	let tokens = lex_source(source, dumper)?;
+	parse_tokens(&path, tokens, dumper)
+}
+
+/// Lexes and parses the `source` as far as possible without ever failing, for tools working on code that is being
+/// edited, like the formatter.
+///
+/// Invalid symbols are skipped and code that can't be parsed is replaced by placeholders, see
+/// [`parser::parse_lenient`]. Returns the partial AST and the diagnostics of
+/// everything that was skipped.
+pub fn parse_lenient(source: Arc<Source>) -> (Vec<ast::Node>, Vec<Diagnostic>) {
+	let mut diagnostics = Vec::new();
+	let tokens: Vec<Token> = Lexer::new(source.iter())
+		.filter_map(|token| token.map_err(|err| diagnostics.push(Diagnostic::from(&err))).ok())
+		.collect();
+	let (ast_nodes, errors) = parser::parse_lenient(tokens.into_iter());
+	diagnostics.extend(errors.iter().map(Diagnostic::from));
+	(ast_nodes, diagnostics)
+}
+
+/// Splits the `source` into tokens.
+pub fn lex_source(source: Arc<Source>, dumper: &Dumper) -> Result<Vec<Token>, CompileError> {
+	let path = PathBuf::from(&source.name);
+	let lexer = Lexer::new(source.iter());
+	let tokens = lexer.collect::<Result<Vec<Token>, lexer::Error>>()?;
+	dumper
+		.dump(Artifact::Tokens, &path, || token::text::serialize(&tokens))
+		.map_err(CompileError::io("Dumping tokens"))?;
+	Ok(tokens)
+}
 
+/// Builds the AST from the `tokens` of the source file at `path`.
+pub fn parse_tokens(path: &Path, tokens: Vec<Token>, dumper: &Dumper) -> Result<Vec<ast::Node>, CompileError> {
+	collect_ast(path, Parser::new(tokens.into_iter()), dumper)
+}
+
+/// Collects the nodes of the `parser` and dumps the AST.
+fn collect_ast(
+	path: &Path,
+	parser: Parser<impl Iterator<Item = Token>>,
+	dumper: &Dumper,
+) -> Result<Vec<ast::Node>, CompileError> {
+	let ast_nodes = parser.collect::<Result<Vec<_>, _>>()?;
+	tracing::trace!("AST parsed: {:#?}", ast_nodes);
+	dumper.dump(Artifact::Ast, path, || format!("{:#?}", ast_nodes)).map_err(CompileError::io("Dumping the AST"))?;
 	Ok(ast_nodes)
 }
+
+/// Runs the semantic analysis on the `ast_nodes`, which may stem from multiple source files, and returns what it
+/// found out about them.
+///
+/// `path` determines the name of the dumped symbol table and expression types.
+pub fn analyze<'a>(
+	path: &Path,
+	ast_nodes: impl Iterator<Item = &'a ast::Node> + Clone,
+	config: &CheckConfig,
+	dumper: &Dumper,
+) -> Result<TypedProgram, CompileError> {
+	let program = semantic_analyzer::analyze(ast_nodes, config)?;
+	let symbol_table = &program.symbol_table;
+	dumper
+		.dump(Artifact::Symbols, path, || {
+			// Sort symbols by name to get a deterministic dump
+			let mut functions: Vec<_> = symbol_table.functions.values().collect();
+			functions.sort_by(|a, b| a.name.value.cmp(&b.name.value));
+			let mut structs: Vec<_> = symbol_table.structs.values().collect();
+			structs.sort_by(|a, b| a.name.value.cmp(&b.name.value));
+			format!("functions: {:#?}\nstructs: {:#?}\n", functions, structs)
+		})
+		.map_err(CompileError::io("Dumping the symbol table"))?;
+	dumper
+		.dump(Artifact::TypedAst, path, || {
+			// Sort expressions by position to get a deterministic dump, with enclosing expressions first
+			let mut expressions: Vec<_> = program.expression_types.iter().collect();
+			expressions.sort_by_key(|(position, _)| {
+				let range = position.byte_range();
+				(&position.source.name, range.start, Reverse(range.end))
+			});
+			expressions
+				.into_iter()
+				.map(|(position, type_)| {
+					format!("{}: `{}`: {}\n", position, position.code(), program.types.display(*type_))
+				})
+				.collect()
+		})
+		.map_err(CompileError::io("Dumping the expression types"))?;
+	Ok(program)
+}
@@ -2,21 +2,138 @@ use std::iter::Peekable;
 
 use super::Result;
 use crate::{
-	ast::Instruction,
-	parser::{helper, instruction::parse_instruction},
+	ast::{self, Expression, Instruction},
+	parser::{expression, helper, instruction::parse_instruction, Error},
+	source::SourcePositionRange,
 	token::{Token, TokenKind},
 };
 
-pub fn parse_block(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<Vec<Instruction>> {
-	let mut block: Vec<Instruction> = Vec::new();
-	helper::parse_opening_curly_parenthesis(tokens.next())?;
-	while let Some(token) = tokens.peek() {
-		if let TokenKind::ClosingCurlyBraces = **token {
-			tokens.next(); // Consume TokenKind::ClosingParentheses
-			break; // End of block
-		}
-		let instruction = parse_instruction(tokens)?;
-		block.push(instruction);
+/// A block being parsed by [`parse_block`], by the instruction it belongs to.
+enum OpenBlock {
+	/// The block `parse_block` was called for.
+	Outermost,
+	/// The block of an if-else executed if the condition is true. `start` is the position of the `if`.
+	IfTrue { start: SourcePositionRange, condition: Expression },
+	/// The `else` block of an if-else.
+	IfFalse { condition: Expression, if_true: Vec<Instruction> },
+	/// The body of a while loop.
+	While { condition: Expression },
+}
+
+/// Parses a block. Invalid instructions are replaced by [`Instruction::Invalid`] and their errors added to the
+/// `errors`, so that the rest of the block is still parsed.
+///
+/// The blocks of nested if-elses and loops are kept on an explicit stack instead of recursing. Their depth is still
+/// [limited](helper::check_nesting), since the analyzer and the backends recurse into them. If the input ends inside a
+/// block, the innermost unclosed `{` is reported as [`Error::UnclosedBlock`].
+pub fn parse_block(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+) -> Result<Vec<Instruction>> {
+	helper::nested(tokens, |tokens| parse_nested_blocks(tokens, errors))
+}
+
+fn parse_nested_blocks(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+) -> Result<Vec<Instruction>> {
+	let opening = helper::parse_opening_curly_parenthesis(tokens.next())?;
+	let mut blocks = vec![(OpenBlock::Outermost, opening, Vec::new())];
+	loop {
+		let instruction = match tokens.peek().map(|token| &token.value) {
+			Some(TokenKind::ClosingCurlyBraces) => {
+				tokens.next(); // Consume TokenKind::ClosingCurlyBraces
+				let (block, _, instructions) = blocks.pop().expect("the outermost block is closed last");
+				match close_block(tokens, errors, block, instructions)? {
+					Closed::Outermost(instructions) => return Ok(instructions),
+					Closed::Instruction(instruction) => {
+						helper::add_nesting(-1);
+						instruction
+					},
+					Closed::Else(block, opening) => {
+						blocks.push((block, opening, Vec::new()));
+						continue;
+					},
+				}
+			},
+			Some(TokenKind::If | TokenKind::While) => {
+				match helper::recover(tokens, errors, |tokens, _| open_block(tokens).map(Ok), Err)? {
+					Ok((block, opening)) => {
+						helper::add_nesting(1);
+						blocks.push((block, opening, Vec::new()));
+						continue;
+					},
+					Err(position) => Instruction::Invalid(position),
+				}
+			},
+			// Comments in blocks are trivia, which only the formatter keeps
+			Some(TokenKind::Comment(_)) => {
+				tokens.next();
+				continue;
+			},
+			// The input ended without closing the block
+			None => {
+				let (_, opening, _) = blocks.pop().expect("the outermost block is still open");
+				return Err(Error::UnclosedBlock { opening });
+			},
+			Some(_) => helper::recover(tokens, errors, parse_instruction, Instruction::Invalid)?,
+		};
+		blocks.last_mut().expect("the outermost block is still open").2.push(instruction);
 	}
-	Ok(block)
+}
+
+/// Parses the start of an if-else or while loop up to the `{` of its block, and returns the position of the `{`. Fails
+/// before consuming any tokens if the block would be nested too deeply.
+fn open_block(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<(OpenBlock, SourcePositionRange)> {
+	let start = tokens.peek().expect("the `if` or `while` was peeked").position.clone();
+	helper::check_nesting(1, &start)?;
+	let block = match tokens.next().map(|token| token.value) {
+		Some(TokenKind::If) => {
+			let condition = expression::parse_binary_expression(tokens)?;
+			OpenBlock::IfTrue { start, condition }
+		},
+		Some(TokenKind::While) => OpenBlock::While { condition: expression::parse_binary_expression(tokens)? },
+		found => unreachable!("blocks are opened by `if` or `while`, not {:?}", found),
+	};
+	Ok((block, helper::parse_opening_curly_parenthesis(tokens.next())?))
+}
+
+/// What closing a block results in.
+enum Closed {
+	/// The instructions of the outermost block, which is complete.
+	Outermost(Vec<Instruction>),
+	/// An if-else or while loop, which is complete.
+	Instruction(Instruction),
+	/// The `else` block of an if-else, which was opened by the `{` at the position.
+	Else(OpenBlock, SourcePositionRange),
+}
+
+/// Closes the `block` with the `instructions` after its `}`, opening the `else` block of an if-else if it follows.
+fn close_block(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+	block: OpenBlock,
+	instructions: Vec<Instruction>,
+) -> Result<Closed> {
+	let if_else = match block {
+		OpenBlock::Outermost => return Ok(Closed::Outermost(instructions)),
+		OpenBlock::While { condition } => {
+			let while_loop = ast::WhileLoop { condition, body: instructions };
+			return Ok(Closed::Instruction(Instruction::WhileLoop(Box::new(while_loop))));
+		},
+		OpenBlock::IfTrue { start, condition } => {
+			if tokens.next_if(|token| token.value == TokenKind::Else).is_none() {
+				ast::IfElse { condition, if_true: instructions, if_false: Vec::new() }
+			} else {
+				return match helper::parse_opening_curly_parenthesis(tokens.next()) {
+					Ok(opening) => Ok(Closed::Else(OpenBlock::IfFalse { condition, if_true: instructions }, opening)),
+					// The whole if-else is invalid, like other instructions with errors
+					Err(err) => helper::skip_after_error(tokens, errors, &start, err)
+						.map(|position| Closed::Instruction(Instruction::Invalid(position))),
+				};
+			}
+		},
+		OpenBlock::IfFalse { condition, if_true } => ast::IfElse { condition, if_true, if_false: instructions },
+	};
+	Ok(Closed::Instruction(Instruction::IfElse(Box::new(if_else))))
 }
@@ -0,0 +1,12 @@
+@bench
+struct P {
+	x: int
+}
+
+export extern f(): int
+
+def intact(): int {
+	return 0
+}
+
+@
\ No newline at end of file
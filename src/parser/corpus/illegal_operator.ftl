@@ -0,0 +1,10 @@
+def f(a: int): int {
+	if a =/ 1 {
+		return 0
+	}
+	return 1
+}
+
+def intact(): int {
+	return 0
+}
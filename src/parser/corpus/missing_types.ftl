@@ -0,0 +1,7 @@
+def f(a, b): {
+	return a
+}
+
+def intact(): int {
+	return 0
+}
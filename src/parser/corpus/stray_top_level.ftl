@@ -0,0 +1,9 @@
+}
+) ] 42
+return 0
+
+def intact(): int {
+	return 0
+}
+
+var x: int = 1
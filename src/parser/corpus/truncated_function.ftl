@@ -0,0 +1,6 @@
+def intact(): int {
+	return 0
+}
+
+def f(n: int): int {
+	var x: int = 
\ No newline at end of file
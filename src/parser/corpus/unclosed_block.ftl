@@ -0,0 +1,9 @@
+def f(): int {
+	if 1 < 2 {
+		return 1
+	return 0
+}
+
+def intact(): int {
+	return 0
+}
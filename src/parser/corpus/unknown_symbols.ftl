@@ -0,0 +1,7 @@
+def f(): int {
+	return 1 $ 2
+}
+
+def intact(): int {
+	return 0 ? 
+}
@@ -0,0 +1,10 @@
+def intact(): int {
+	return 0
+}
+
+extern puts(s: str): int
+
+def main(): int {
+	puts("Hello)
+	return 0
+}
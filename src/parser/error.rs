@@ -2,13 +2,46 @@ use core::fmt;
 
 use thiserror::Error;
 
-use crate::token::{Token, TokenKind};
+use crate::{
+	diagnostic::Diagnostic,
+	source::SourcePositionRange,
+	token::{Token, TokenKind},
+};
 
 #[derive(Debug, Clone, PartialEq, Error)]
 pub enum Error {
-	ExpectedToken { expected: TokenKind, found: Option<Token> },
+	ExpectedToken {
+		expected: TokenKind,
+		found: Option<Token>,
+	},
 
-	IllegalToken { token: Option<Token>, context: &'static str },
+	IllegalToken {
+		token: Option<Token>,
+		context: &'static str,
+	},
+
+	/// Blocks, calls, operators or types are nested deeper than the [maximum](super::Parser::with_max_nesting) at
+	/// `position`.
+	TooDeeplyNested {
+		position: SourcePositionRange,
+		max_nesting: usize,
+	},
+
+	UnclosedBlock {
+		opening: SourcePositionRange,
+	},
+}
+
+impl Error {
+	/// Whether the error is caused by the end of the input, i.e. whether appending more input could fix it.
+	pub fn is_incomplete_input(&self) -> bool {
+		matches!(
+			self,
+			Error::ExpectedToken { found: None, .. }
+				| Error::IllegalToken { token: None, .. }
+				| Error::UnclosedBlock { .. }
+		)
+	}
 }
 
 impl fmt::Display for Error {
@@ -22,6 +55,44 @@ impl fmt::Display for Error {
 				Some(token) => write!(f, "{} Illegal token '{:?}' in {}", token.position, token.value, context),
 				None => write!(f, "Illegal token in {}", context),
 			},
+			Error::TooDeeplyNested { position, max_nesting } => {
+				write!(f, "{} Program too deeply nested, the maximum nesting depth is {}", position, max_nesting)
+			},
+			Error::UnclosedBlock { opening } => write!(f, "{} Unclosed `{{`, the file ends before its `}}`", opening),
+		}
+	}
+}
+
+impl From<&Error> for Diagnostic {
+	fn from(err: &Error) -> Self {
+		match err {
+			Error::ExpectedToken { expected, found: Some(found) } => {
+				Diagnostic::error("ExpectedToken", format!("Expected token {:?}, found {:?}", expected, found.value))
+					.with_primary_label(found.position.clone(), format!("expected {:?} here", expected))
+			},
+			Error::ExpectedToken { expected, found: None } => {
+				Diagnostic::error("ExpectedToken", format!("Expected token {:?}, found end of file", expected))
+			},
+			Error::IllegalToken { token: Some(token), context } => {
+				Diagnostic::error("IllegalToken", format!("Illegal token {:?} in {}", token.value, context))
+					.with_primary_label(token.position.clone(), format!("not allowed in {}", context))
+			},
+			Error::IllegalToken { token: None, context } => {
+				Diagnostic::error("IllegalToken", format!("Unexpected end of file in {}", context))
+			},
+			Error::TooDeeplyNested { position, max_nesting } => {
+				Diagnostic::error("TooDeeplyNested", "Program too deeply nested")
+					.with_primary_label(position.clone(), format!("nested deeper than {} levels", max_nesting))
+					.with_help("move parts of the code into variables or functions")
+			},
+			Error::UnclosedBlock { opening } => {
+				// Point at the last code of the file, as the end of the file itself has no position
+				let mut end = opening.extend_to(opening.source.text.len());
+				end.position.start = end.position.end;
+				Diagnostic::error("UnclosedBlock", "Unclosed `{`")
+					.with_primary_label(opening.clone(), "unclosed `{` opened here")
+					.with_secondary_label(end, "the file ends here, expected `}`")
+			},
 		}
 	}
 }
@@ -8,41 +8,96 @@ use crate::{
 		Expression,
 	},
 	parser::{function::parse_function_call, helper, helper::parse_operator, Error},
-	source::PositionContainer,
+	source::{SourcePositionRange, Spanned},
 	token::{Token, TokenKind},
 };
 
 pub(crate) fn parse_primary_expression(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::Expression> {
-	match tokens.peek() {
-		Some(Token { value: TokenKind::Identifier(_), .. }) => Ok(parse_identifier_expression(tokens)?),
-		Some(Token { value: TokenKind::Float(_), .. }) => Ok(ast::Expression::Number(parse_float(tokens)?)),
-		Some(Token { value: TokenKind::Int(_), .. }) => Ok(ast::Expression::Number(parse_int(tokens)?)),
-		Some(Token { value: TokenKind::OpeningParentheses, .. }) => Ok(parse_parentheses(tokens)?),
-		other => Err(Error::IllegalToken { token: other.cloned(), context: "expression" }),
+	// Parentheses, calls and `try` contain further primary expressions
+	helper::nested(tokens, parse_nested_primary_expression)
+}
+
+fn parse_nested_primary_expression(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::Expression> {
+	let expression = match tokens.peek() {
+		Some(Token { value: TokenKind::Identifier(_), .. }) => parse_identifier_expression(tokens)?,
+		Some(Token { value: TokenKind::Float(_), .. }) => ast::Expression::Number(parse_float(tokens)?),
+		Some(Token { value: TokenKind::Int(_), .. }) => ast::Expression::Number(parse_int(tokens)?),
+		Some(Token { value: TokenKind::StringLiteral(_), .. }) => ast::Expression::String(parse_string(tokens)?),
+		Some(Token { value: TokenKind::OpeningParentheses, .. }) => parse_parentheses(tokens)?,
+		Some(Token { value: TokenKind::Null, .. }) => ast::Expression::Null(parse_null(tokens)?),
+		Some(Token { value: TokenKind::Try, .. }) => ast::Expression::Try(parse_try(tokens)?),
+		Some(Token { value: TokenKind::Error, .. }) => ast::Expression::Error(parse_error(tokens)?),
+		other => return Err(Error::IllegalToken { token: other.cloned(), context: "expression" }),
+	};
+	parse_field_accesses(expression, tokens)
+}
+
+/// Parses a chain of field accesses like `.a.b` after the `base` expression.
+pub(crate) fn parse_field_accesses(
+	mut base: ast::Expression,
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+) -> Result<ast::Expression> {
+	while tokens.next_if(|token| token.value == TokenKind::Dot).is_some() {
+		let field = helper::parse_identifier(tokens.next())?;
+		base = ast::Expression::FieldAccess(ast::expression::FieldAccess { base: Box::new(base), field });
 	}
+	Ok(base)
 }
 
-pub fn parse_float(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<PositionContainer<NumberKind>> {
+pub fn parse_float(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<Spanned<NumberKind>> {
 	match tokens.next() {
 		Some(Token { value: TokenKind::Float(float), position }) => {
-			Ok(PositionContainer::new(NumberKind::Float(float), position))
-		},
-		Some(Token { value: TokenKind::Int(int), position }) => {
-			Ok(PositionContainer::new(NumberKind::Int(int), position))
+			Ok(Spanned::new(NumberKind::Float(float), position))
 		},
+		Some(Token { value: TokenKind::Int(int), position }) => Ok(Spanned::new(NumberKind::Int(int), position)),
 		other => Err(Error::ExpectedToken { expected: TokenKind::Float(0.0), found: other }),
 	}
 }
 
-pub fn parse_int(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<PositionContainer<NumberKind>> {
+pub fn parse_int(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<Spanned<NumberKind>> {
 	match tokens.next() {
-		Some(Token { value: TokenKind::Int(int), position }) => {
-			Ok(PositionContainer::new(NumberKind::Int(int), position))
-		},
+		Some(Token { value: TokenKind::Int(int), position }) => Ok(Spanned::new(NumberKind::Int(int), position)),
 		other => Err(Error::ExpectedToken { expected: TokenKind::Int(0), found: other }),
 	}
 }
 
+pub fn parse_string(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::expression::StringLiteral> {
+	match tokens.next() {
+		Some(Token { value: TokenKind::StringLiteral(string), position }) => Ok(Spanned::new(string, position)),
+		other => Err(Error::ExpectedToken { expected: TokenKind::StringLiteral(String::new()), found: other }),
+	}
+}
+
+pub fn parse_null(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<SourcePositionRange> {
+	match tokens.next() {
+		Some(Token { value: TokenKind::Null, position }) => Ok(position),
+		other => Err(Error::ExpectedToken { expected: TokenKind::Null, found: other }),
+	}
+}
+
+pub fn parse_error(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<SourcePositionRange> {
+	match tokens.next() {
+		Some(Token { value: TokenKind::Error, position }) => Ok(position),
+		other => Err(Error::ExpectedToken { expected: TokenKind::Error, found: other }),
+	}
+}
+
+/// Parses `try value else fallback`. Like the values of variable declarations, `value` and `fallback` are primary
+/// expressions, so binary expressions have to be enclosed in parentheses.
+pub fn parse_try(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::expression::TryExpression> {
+	let position = match tokens.next() {
+		Some(Token { value: TokenKind::Try, position }) => position,
+		other => return Err(Error::ExpectedToken { expected: TokenKind::Try, found: other }),
+	};
+	let value = parse_primary_expression(tokens)?;
+	match tokens.next() {
+		Some(Token { value: TokenKind::Else, .. }) => (),
+		other => return Err(Error::ExpectedToken { expected: TokenKind::Else, found: other }),
+	}
+	let fallback = parse_primary_expression(tokens)?;
+	Ok(ast::expression::TryExpression { position, value: Box::new(value), fallback: Box::new(fallback) })
+}
+
 pub fn parse_identifier_expression(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::Expression> {
 	let identifier = helper::parse_identifier(tokens.next())?;
 	match tokens.peek() {
@@ -60,47 +115,74 @@ pub fn parse_parentheses(tokens: &mut Peekable<impl Iterator<Item = Token>>) ->
 	Ok(expression)
 }
 
-pub(crate) fn parse_binary_expression(
-	tokens: &mut Peekable<impl Iterator<Item = Token>>,
-) -> Result<ast::expression::Expression> {
-	let lhs = parse_primary_expression(tokens)?;
-	parse_binary_expression_rhs(lhs, None, tokens)
+/// An operator waiting for its rhs, or an opening parenthesis waiting for its closing one, in
+/// [`parse_binary_expression`].
+enum Pending {
+	Operator(Spanned<BinaryOperator>),
+	Parenthesis,
 }
 
-fn parse_binary_expression_rhs(
-	lhs: Expression,
-	min_operator: Option<&BinaryOperator>,
+/// Parses an expression of operands connected by binary operators, like `1 + 2 * (3 - 4)`.
+///
+/// Operators and parentheses are kept on an explicit stack instead of recursing (the shunting-yard algorithm), so
+/// that deeply nested parentheses, e.g. in generated code, don't overflow the stack. The depth of the resulting tree
+/// of binary expressions is still [limited](helper::check_nesting), since the analyzer and the backends recurse into
+/// it, e.g. for long chains like `1 + 2 + ... + n`.
+pub(crate) fn parse_binary_expression(
 	tokens: &mut Peekable<impl Iterator<Item = Token>>,
 ) -> Result<ast::expression::Expression> {
-	let mut lhs: ast::Expression = lhs;
+	// Each operand with the depth of its tree of binary expressions
+	let mut operands: Vec<(Expression, usize)> = Vec::new();
+	let mut pending: Vec<Pending> = Vec::new();
+	let mut open_parentheses = 0_usize;
 	loop {
-		// Read the operator after lhs and before rhs
-		let operator = match parse_operator(tokens.peek().cloned()) {
-			// Found an operator
-			Ok(operator) => operator,
-			// No operator found
-			Err(_) => return Ok(lhs),
-		};
-		// Consume operator
-		tokens.next();
-
-		// Parse the primary expression after the operator as rhs
-		let mut rhs = parse_primary_expression(tokens)?;
-
-		// Inspect the next operator after rhs. If it has a higher precedence than the current operator,
-		// let rhs be the result of a recursive call to parse_binary_expression_rhs with rhs as lhs.
-		if let Ok(next_operator) = parse_operator(tokens.peek().cloned()) {
-			if next_operator > operator {
-				rhs = parse_binary_expression_rhs(rhs, Some(&next_operator), tokens)?;
+		while tokens.next_if(|token| token.value == TokenKind::OpeningParentheses).is_some() {
+			pending.push(Pending::Parenthesis);
+			open_parentheses += 1;
+		}
+		operands.push((parse_primary_expression(tokens)?, 0));
+
+		// Closing parentheses after the operand, then an operator or the end of the expression
+		loop {
+			if let Ok(operator) = parse_operator(tokens.peek().cloned()) {
+				// Operators of the same precedence are left-associative, e.g. `1 - 2 - 3` is `(1 - 2) - 3`
+				reduce(&mut operands, &mut pending, |top| *top >= operator.value)?;
+				tokens.next(); // Consume the operator
+				pending.push(Pending::Operator(operator));
+				break;
 			}
+			if open_parentheses == 0 {
+				reduce(&mut operands, &mut pending, |_| true)?;
+				return Ok(operands.pop().expect("all operators are reduced to one operand").0);
+			}
+			helper::parse_closing_parenthesis(tokens.next())?;
+			reduce(&mut operands, &mut pending, |_| true)?;
+			pending.pop(); // The matching Pending::Parenthesis
+			open_parentheses -= 1;
+			let (operand, depth) = operands.pop().expect("parentheses enclose an operand");
+			operands.push((parse_field_accesses(operand, tokens)?, depth));
 		}
+	}
+}
 
-		// Merge lhs and rhs into a new lhs
-		lhs = ast::Expression::BinaryExpression(ast::expression::BinaryExpression {
-			lhs: Box::new(lhs),
-			rhs: Box::new(rhs),
-			operator,
-		});
+/// Merges the operators on top of the `pending` stack with their operands into binary expressions, as long as
+/// `binds` is true for them. Stops at an opening parenthesis. Fails if a binary expression is nested too deeply.
+fn reduce(
+	operands: &mut Vec<(Expression, usize)>,
+	pending: &mut Vec<Pending>,
+	binds: impl Fn(&BinaryOperator) -> bool,
+) -> Result<()> {
+	while let Some(Pending::Operator(operator)) = pending.last() {
+		if !binds(&operator.value) {
+			break;
+		}
+		let Some(Pending::Operator(operator)) = pending.pop() else { unreachable!("the last element is an operator") };
+		let (rhs, rhs_depth) = operands.pop().expect("operators have a rhs");
+		let (lhs, lhs_depth) = operands.pop().expect("operators have a lhs");
+		let depth = lhs_depth.max(rhs_depth) + 1;
+		helper::check_nesting(depth, &operator.position)?;
+		let binary_expression = ast::expression::BinaryExpression { lhs: Box::new(lhs), rhs: Box::new(rhs), operator };
+		operands.push((ast::Expression::BinaryExpression(binary_expression), depth));
 	}
-	Ok(lhs)
+	Ok(())
 }
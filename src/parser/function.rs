@@ -4,18 +4,23 @@ use super::Result;
 use crate::{
 	ast,
 	ast::Expression,
-	parser::{block::parse_block, expression::parse_primary_expression, helper, variable},
-	source::PositionContainer,
+	parser::{
+		block::parse_block,
+		expression::{self, parse_primary_expression},
+		helper, variable, Error,
+	},
+	source::Spanned,
 	token::{Token, TokenKind},
 };
 
 pub fn parse_function_definition(
 	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
 ) -> Result<ast::statement::FunctionDefinition> {
 	tokens.next(); // Consume TokenKind::FunctionDefinition
 	let prototype = parse_function_prototype(tokens)?;
-	let body = parse_block(tokens)?;
-	Ok(ast::statement::FunctionDefinition { prototype, body })
+	let body = parse_block(tokens, errors)?;
+	Ok(ast::statement::FunctionDefinition { prototype, body, annotations: Vec::new() })
 }
 
 pub fn parse_extern_function_declaration(
@@ -31,7 +36,7 @@ fn parse_function_prototype(
 	let name = helper::parse_identifier(tokens.next())?;
 	let args = parse_function_argument_list(tokens)?;
 	let return_type = parse_function_prototype_return_type(tokens)?;
-	Ok(ast::statement::FunctionPrototype { name, args, return_type })
+	Ok(ast::statement::FunctionPrototype { doc: None, name, args, return_type, exported: false })
 }
 
 fn parse_function_argument_list(
@@ -65,7 +70,7 @@ fn parse_function_argument_list(
 
 fn parse_function_prototype_return_type(
 	tokens: &mut Peekable<impl Iterator<Item = Token>>,
-) -> Result<Option<PositionContainer<ast::statement::DataType>>> {
+) -> Result<Option<Spanned<ast::statement::DataType>>> {
 	match tokens.peek() {
 		// No return type specified
 		Some(Token { value: TokenKind::OpeningCurlyBraces, .. }) => Ok(None),
@@ -73,7 +78,14 @@ fn parse_function_prototype_return_type(
 		Some(Token { value: TokenKind::Colon, .. }) => {
 			tokens.next(); // Consume TokenKind::Colon
 			let data_type = variable::parse_data_type(tokens)?;
-			Ok(Some(data_type))
+			// `int or error` for functions that can fail
+			if tokens.next_if(|token| token.value == TokenKind::Or).is_none() {
+				return Ok(Some(data_type));
+			}
+			let error_position = expression::parse_error(tokens)?;
+			let mut position = data_type.position.clone();
+			position.position.end = error_position.position.end;
+			Ok(Some(Spanned::new(ast::statement::DataType::Fallible(Box::new(data_type)), position)))
 		},
 		_ => Ok(None),
 	}
@@ -81,7 +93,7 @@ fn parse_function_prototype_return_type(
 
 pub(crate) fn parse_function_call(
 	tokens: &mut Peekable<impl Iterator<Item = Token>>,
-	identifier: PositionContainer<String>,
+	identifier: Spanned<String>,
 ) -> Result<ast::expression::FunctionCall> {
 	Ok(ast::expression::FunctionCall { name: identifier, params: parse_function_parameters(tokens)? })
 }
@@ -1,14 +1,142 @@
+use std::{cell::Cell, iter::Peekable};
+
+use smol_str::SmolStr;
+
 use super::{Error, Result};
 use crate::{
 	ast::expression::BinaryOperator,
-	source::PositionContainer,
+	source::{SourcePositionRange, Spanned},
 	token::{Token, TokenKind},
 };
 
-pub(crate) fn parse_identifier(token: Option<Token>) -> Result<PositionContainer<String>> {
+/// Default maximum nesting depth of blocks, calls, operators, `try` and types, see [`Parser::with_max_nesting`].
+///
+/// [`Parser::with_max_nesting`]: super::Parser::with_max_nesting
+pub const DEFAULT_MAX_NESTING: usize = 128;
+
+thread_local! {
+	/// Nesting depth of the code being parsed on this thread and its maximum, see [`nested`].
+	static NESTING: Cell<(usize, usize)> = const { Cell::new((0, DEFAULT_MAX_NESTING)) };
+}
+
+/// Parses nested code like the parameters of a call with `parse`, unless the maximum nesting depth is exceeded, in which
+/// case the recursive descent could overflow the stack.
+pub(crate) fn nested<I: Iterator<Item = Token>, T>(
+	tokens: &mut Peekable<I>,
+	parse: impl FnOnce(&mut Peekable<I>) -> Result<T>,
+) -> Result<T> {
+	let (depth, max_nesting) = NESTING.get();
+	if depth >= max_nesting {
+		if let Some(token) = tokens.peek() {
+			return Err(Error::TooDeeplyNested { position: token.position.clone(), max_nesting });
+		}
+	}
+	NESTING.set((depth + 1, max_nesting));
+	let result = parse(tokens);
+	NESTING.set((depth, max_nesting));
+	result
+}
+
+/// Checks that code at `position` may be nested `levels` deeper than the current code, like [`nested`] but for code
+/// that is parsed without recursion, e.g. blocks and operators. The analyzer and the backends still recurse into it.
+pub(crate) fn check_nesting(levels: usize, position: &SourcePositionRange) -> Result<()> {
+	let (depth, max_nesting) = NESTING.get();
+	match depth + levels > max_nesting {
+		true => Err(Error::TooDeeplyNested { position: position.clone(), max_nesting }),
+		false => Ok(()),
+	}
+}
+
+/// Enters `levels` of nesting that were [checked](check_nesting) before, or leaves them if negative. Returning from
+/// [`nested`] restores the depth before it, also after errors.
+pub(crate) fn add_nesting(levels: isize) {
+	let (depth, max_nesting) = NESTING.get();
+	NESTING.set((depth.checked_add_signed(levels).expect("only entered levels are left"), max_nesting));
+}
+
+/// Runs `parse` with the maximum nesting depth `max_nesting` for [`nested`].
+pub(crate) fn with_max_nesting<T>(max_nesting: usize, parse: impl FnOnce() -> T) -> T {
+	let (depth, previous) = NESTING.get();
+	NESTING.set((depth, max_nesting));
+	let result = parse();
+	NESTING.set((depth, previous));
+	result
+}
+
+/// Parses with `parse` and recovers from a syntax error by skipping the invalid code and replacing it with the
+/// placeholder `invalid` creates from its position, e.g. [`Instruction::Invalid`](crate::ast::Instruction::Invalid).
+/// The error is added to the `errors`, which `parse` also gets for the errors it recovered from itself.
+///
+/// Errors at the end of the input are not recovered from, since more input could fix them, nor are errors at
+/// tokens that are never [skipped](skip_invalid), e.g. a `def` following an unclosed block.
+pub(crate) fn recover<I: Iterator<Item = Token>, T>(
+	tokens: &mut Peekable<I>,
+	errors: &mut Vec<Error>,
+	parse: impl FnOnce(&mut Peekable<I>, &mut Vec<Error>) -> Result<T>,
+	invalid: impl FnOnce(SourcePositionRange) -> T,
+) -> Result<T> {
+	let Some(start) = tokens.peek().map(|token| token.position.clone()) else {
+		return parse(tokens, errors);
+	};
+	match parse(tokens, errors) {
+		Err(err) => skip_after_error(tokens, errors, &start, err).map(invalid),
+		result => result,
+	}
+}
+
+/// Recovers from the `err` in the code starting at `start` like [`recover`], for code whose parsing has already
+/// failed. Returns the position of the skipped invalid code, or the `err` if it can't be recovered from.
+pub(crate) fn skip_after_error(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+	start: &SourcePositionRange,
+	err: Error,
+) -> Result<SourcePositionRange> {
+	if err.is_incomplete_input() {
+		return Err(err);
+	}
+	match skip_invalid(tokens, start) {
+		Some(position) => {
+			errors.push(err);
+			Ok(position)
+		},
+		None => Err(err),
+	}
+}
+
+/// Skips the rest of the invalid code starting at `start` after a syntax error: the tokens up to the end of the line
+/// and the blocks opened on it. A `}` closing the enclosing block and the tokens that start a top-level node are never
+/// skipped. Returns the position of the invalid code, or [`None`] if there is none.
+fn skip_invalid(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	start: &SourcePositionRange,
+) -> Option<SourcePositionRange> {
+	let next_offset = |tokens: &mut Peekable<_>| {
+		tokens.peek().map_or(start.source.text.len(), |token: &Token| token.position.position.start.offset as usize)
+	};
+	// Tokens were already consumed until the error, maybe on a later line
+	let mut line = start.extend_to(next_offset(tokens)).position.end.line;
+	let mut depth = 0_usize;
+	while let Some(token) = tokens.peek() {
+		match token.value {
+			TokenKind::Def | TokenKind::Extern | TokenKind::Struct | TokenKind::Export | TokenKind::At => break,
+			TokenKind::ClosingCurlyBraces if depth == 0 => break,
+			TokenKind::ClosingCurlyBraces => depth -= 1,
+			TokenKind::OpeningCurlyBraces => depth += 1,
+			_ if depth == 0 && token.position.position.start.line != line => break,
+			_ => (),
+		}
+		line = token.position.position.end.line;
+		tokens.next();
+	}
+	let end = next_offset(tokens);
+	(end > start.position.start.offset as usize).then(|| start.extend_to(end))
+}
+
+pub(crate) fn parse_identifier(token: Option<Token>) -> Result<Spanned<String>> {
 	match token {
-		Some(Token { position, value: TokenKind::Identifier(ident) }) => Ok(PositionContainer::new(ident, position)),
-		_ => Err(Error::ExpectedToken { expected: TokenKind::Identifier(String::new()), found: token }),
+		Some(Token { position, value: TokenKind::Identifier(ident) }) => Ok(Spanned::new(ident.to_string(), position)),
+		_ => Err(Error::ExpectedToken { expected: TokenKind::Identifier(SmolStr::default()), found: token }),
 	}
 }
 
@@ -33,34 +161,14 @@ pub(crate) fn parse_colon(token: Option<Token>) -> Result<()> {
 	}
 }
 
-pub(crate) fn parse_comma(token: Option<Token>) -> Result<()> {
-	match token.as_deref() {
-		Some(TokenKind::Comma) => Ok(()),
-		_ => Err(Error::ExpectedToken { expected: TokenKind::Comma, found: token }),
-	}
-}
-
-pub(crate) fn parse_semicolon(token: Option<Token>) -> Result<()> {
-	match token.as_deref() {
-		Some(TokenKind::Semicolon) => Ok(()),
-		_ => Err(Error::ExpectedToken { expected: TokenKind::Semicolon, found: token }),
-	}
-}
-
-pub(crate) fn parse_opening_curly_parenthesis(token: Option<Token>) -> Result<()> {
-	match token.as_deref() {
-		Some(TokenKind::OpeningCurlyBraces) => Ok(()),
+/// Parses a `{` and returns its position.
+pub(crate) fn parse_opening_curly_parenthesis(token: Option<Token>) -> Result<SourcePositionRange> {
+	match token {
+		Some(Token { value: TokenKind::OpeningCurlyBraces, position }) => Ok(position),
 		_ => Err(Error::ExpectedToken { expected: TokenKind::OpeningCurlyBraces, found: token }),
 	}
 }
 
-pub(crate) fn parse_closing_curly_parenthesis(token: Option<Token>) -> Result<()> {
-	match token.as_deref() {
-		Some(TokenKind::ClosingCurlyBraces) => Ok(()),
-		_ => Err(Error::ExpectedToken { expected: TokenKind::ClosingCurlyBraces, found: token }),
-	}
-}
-
 pub(crate) fn parse_variable_declaration(token: Option<Token>) -> Result<()> {
 	match token.as_deref() {
 		Some(TokenKind::Var) => Ok(()),
@@ -96,24 +204,25 @@ pub(crate) fn parse_while(token: Option<Token>) -> Result<()> {
 	}
 }
 
-pub(crate) fn parse_operator(token: Option<Token>) -> Result<PositionContainer<BinaryOperator>> {
-	match token {
-		Some(token) => Ok(PositionContainer {
-			position: token.position.clone(),
-			value: match token.value {
-				TokenKind::Plus => BinaryOperator::Add,
-				TokenKind::Minus => BinaryOperator::Subtract,
-				TokenKind::Star => BinaryOperator::Multiply,
-				TokenKind::Slash => BinaryOperator::Divide,
-				TokenKind::Equal => BinaryOperator::Equal,
-				TokenKind::NotEqual => BinaryOperator::NotEqual,
-				TokenKind::Less => BinaryOperator::Less,
-				// TokenKind::LessEqual => BinaryOperator::LessEqual,
-				TokenKind::Greater => BinaryOperator::Greater,
-				// TokenKind::GreaterEqual => BinaryOperator::GreaterEqual,
-				_ => return Err(Error::ExpectedToken { expected: TokenKind::Plus, found: Some(token) }),
-			},
-		}),
-		None => Err(Error::IllegalToken { token, context: "operator" }),
-	}
+pub(crate) fn parse_operator(token: Option<Token>) -> Result<Spanned<BinaryOperator>> {
+	let Some(token) = token else {
+		return Err(Error::IllegalToken { token, context: "operator" });
+	};
+	let operator = match *token {
+		TokenKind::Plus => BinaryOperator::Add,
+		TokenKind::Minus => BinaryOperator::Subtract,
+		TokenKind::Star => BinaryOperator::Multiply,
+		TokenKind::Slash => BinaryOperator::Divide,
+		TokenKind::Modulus => BinaryOperator::Modulus,
+		TokenKind::Equal => BinaryOperator::Equal,
+		TokenKind::NotEqual => BinaryOperator::NotEqual,
+		TokenKind::Less => BinaryOperator::Less,
+		TokenKind::LessEqual => BinaryOperator::LessEqual,
+		TokenKind::Greater => BinaryOperator::Greater,
+		TokenKind::GreaterEqual => BinaryOperator::GreaterEqual,
+		TokenKind::And => BinaryOperator::LogicalAnd,
+		TokenKind::Or => BinaryOperator::LogicalOr,
+		_ => return Err(Error::ExpectedToken { expected: TokenKind::Plus, found: Some(token) }),
+	};
+	Ok(token.map(|_| operator))
 }
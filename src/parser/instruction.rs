@@ -16,9 +16,12 @@ use crate::{
 	token::{Token, TokenKind},
 };
 
-pub fn parse_instruction(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::Instruction> {
+pub fn parse_instruction(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+) -> Result<ast::Instruction> {
 	match tokens.peek() {
-		Some(Token { value: TokenKind::Identifier(_), .. }) => Ok(parse_identifier_instruction(tokens)?),
+		Some(Token { value: TokenKind::Identifier(_), .. }) => Ok(parse_identifier_instruction(tokens, errors)?),
 		Some(Token { value: TokenKind::Float(_), .. }) => {
 			Ok(ast::Instruction::Expression(ast::Expression::Number(parse_float(tokens)?)))
 		},
@@ -28,29 +31,43 @@ pub fn parse_instruction(tokens: &mut Peekable<impl Iterator<Item = Token>>) ->
 		Some(Token { value: TokenKind::OpeningParentheses, .. }) => {
 			Ok(ast::Instruction::Expression(parse_parentheses(tokens)?))
 		},
-		Some(Token { value: TokenKind::If, .. }) => Ok(ast::Instruction::IfElse(Box::new(parse_if_else(tokens)?))),
+		Some(Token { value: TokenKind::If, .. }) => {
+			Ok(ast::Instruction::IfElse(Box::new(parse_if_else(tokens, errors)?)))
+		},
 		Some(Token { value: TokenKind::While, .. }) => {
-			Ok(ast::Instruction::WhileLoop(Box::new(parse_while_loop(tokens)?)))
+			Ok(ast::Instruction::WhileLoop(Box::new(parse_while_loop(tokens, errors)?)))
 		},
 		Some(Token { value: TokenKind::Var, .. }) => {
-			Ok(ast::Instruction::Statement(Statement::VariableDeclaration(parse_variable_declaration(tokens)?)))
+			Ok(ast::Instruction::Statement(Statement::VariableDeclaration(parse_variable_declaration(tokens, errors)?)))
 		},
 		Some(Token { value: TokenKind::Return, .. }) => {
-			tokens.next(); // Consume the TokenKind::Return
-			Ok(ast::Instruction::Statement(Statement::Return(expression::parse_binary_expression(tokens)?)))
+			let return_ = tokens.next().unwrap(); // Consume the TokenKind::Return
+			let value = match tokens.peek().map(|token| &token.value) {
+				// `return;` or `return }` in functions without return value
+				Some(TokenKind::Semicolon) => {
+					tokens.next();
+					None
+				},
+				Some(TokenKind::ClosingCurlyBraces | TokenKind::Comment(_)) | None => None,
+				_ => Some(parse_value(tokens, errors)?),
+			};
+			Ok(ast::Instruction::Statement(Statement::Return(return_.map(|_| value))))
 		},
 		other => Err(Error::IllegalToken { token: other.cloned(), context: "instruction" }),
 	}
 }
 
-pub fn parse_if_else(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::IfElse> {
+pub fn parse_if_else(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+) -> Result<ast::IfElse> {
 	helper::parse_if(tokens.next())?;
 	let condition = expression::parse_binary_expression(tokens)?;
-	let if_true = parse_block(tokens)?;
+	let if_true = parse_block(tokens, errors)?;
 	let if_false = match tokens.peek() {
 		Some(Token { value: TokenKind::Else, .. }) => {
 			tokens.next(); // Consume the TokenKind::Else
-			parse_block(tokens)?
+			parse_block(tokens, errors)?
 		},
 		_ => Vec::new(),
 	};
@@ -58,14 +75,20 @@ pub fn parse_if_else(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Resu
 	Ok(ast::IfElse { condition, if_true, if_false })
 }
 
-pub fn parse_while_loop(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::WhileLoop> {
+pub fn parse_while_loop(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+) -> Result<ast::WhileLoop> {
 	helper::parse_while(tokens.next())?;
 	let condition = expression::parse_binary_expression(tokens)?;
-	let body = parse_block(tokens)?;
+	let body = parse_block(tokens, errors)?;
 	Ok(ast::WhileLoop { condition, body })
 }
 
-pub fn parse_identifier_instruction(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::Instruction> {
+pub fn parse_identifier_instruction(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+) -> Result<ast::Instruction> {
 	let identifier = helper::parse_identifier(tokens.next())?;
 	match tokens.peek() {
 		Some(Token { value: TokenKind::OpeningParentheses, .. }) => {
@@ -75,9 +98,27 @@ pub fn parse_identifier_instruction(tokens: &mut Peekable<impl Iterator<Item = T
 			tokens.next(); // Consume the TokenKind::Equal
 			Ok(ast::Instruction::Statement(ast::Statement::VariableAssignment(ast::statement::VariableAssignment {
 				name: identifier,
-				value: expression::parse_binary_expression(tokens)?,
+				value: parse_value(tokens, errors)?,
 			})))
 		},
+		Some(Token { value: TokenKind::Dot, .. }) => {
+			let target = expression::parse_field_accesses(ast::Expression::Variable(identifier), tokens)?;
+			match (target, tokens.next_if(|token| token.value == TokenKind::Equal)) {
+				(ast::Expression::FieldAccess(target), Some(_)) => {
+					let value = parse_value(tokens, errors)?;
+					Ok(ast::Instruction::Statement(Statement::FieldAssignment(ast::statement::FieldAssignment {
+						target,
+						value,
+					})))
+				},
+				(target, _) => Ok(ast::Instruction::Expression(target)),
+			}
+		},
 		_ => Ok(ast::Instruction::Expression(ast::Expression::Variable(identifier))),
 	}
 }
+
+/// Parses the value of an assignment or `return`, which is replaced by [`ast::Expression::Invalid`] if it is invalid.
+fn parse_value(tokens: &mut Peekable<impl Iterator<Item = Token>>, errors: &mut Vec<Error>) -> Result<ast::Expression> {
+	helper::recover(tokens, errors, |tokens, _| expression::parse_binary_expression(tokens), ast::Expression::Invalid)
+}
@@ -7,18 +7,26 @@ mod function;
 mod helper;
 mod instruction;
 mod struct_;
+#[cfg(test)]
+mod test;
 mod variable;
 
-use std::iter::Peekable;
+use std::{iter::Peekable, mem};
 
 pub use error::Error;
+pub use helper::DEFAULT_MAX_NESTING;
 
 use crate::{
-	ast::Node,
+	ast::{
+		expression::NumberKind,
+		statement::{BasicDataType, DataType},
+		Expression, FunctionDefinition, FunctionPrototype, Instruction, Node, Statement,
+	},
 	parser::{
 		function::{parse_extern_function_declaration, parse_function_definition},
 		struct_::parse_struct_definition,
 	},
+	source::{SourcePositionRange, Spanned},
 	token::{Token, TokenKind},
 };
 
@@ -30,6 +38,12 @@ where
 	T: Iterator<Item = Token>,
 {
 	tokens: Peekable<T>,
+	/// See [`Self::with_max_nesting`].
+	max_nesting: usize,
+	/// See [`Self::with_script`].
+	script: bool,
+	/// The top-level instructions parsed so far in script mode.
+	script_instructions: Vec<Instruction>,
 }
 
 impl<T> Parser<T>
@@ -37,25 +51,229 @@ where
 	T: Iterator<Item = Token>,
 {
 	pub fn new(tokens: T) -> Self {
-		Self { tokens: tokens.peekable() }
+		Self {
+			tokens: tokens.peekable(),
+			max_nesting: DEFAULT_MAX_NESTING,
+			script: false,
+			script_instructions: Vec::new(),
+		}
+	}
+
+	/// Limits how deep blocks, calls, binary expressions, `try` and types may be nested. Deeper code is rejected with
+	/// [`Error::TooDeeplyNested`] instead of overflowing the stack, in the parser or in the passes after it. Defaults
+	/// to [`DEFAULT_MAX_NESTING`].
+	///
+	/// Parentheses don't count themselves, only the binary expressions in them.
+	pub fn with_max_nesting(mut self, max_nesting: usize) -> Self {
+		self.max_nesting = max_nesting;
+		self
+	}
+
+	/// Enables script mode, in which instructions are allowed outside of functions. They are wrapped into a `main`
+	/// function returning `0`, which follows all other nodes. Files without top-level instructions are parsed as
+	/// usual.
+	pub fn with_script(mut self, script: bool) -> Self {
+		self.script = script;
+		self
+	}
+
+	/// The `main` function synthesized from the top-level instructions in [script mode](Self::with_script), if there
+	/// are any.
+	fn script_main(&mut self) -> Option<Node> {
+		let mut body = mem::take(&mut self.script_instructions);
+		let position = SourcePositionRange::synthetic(Some(&body.first()?.source_position()));
+		if !matches!(body.last(), Some(Instruction::Statement(Statement::Return(_)))) {
+			let zero = Expression::Number(Spanned::new(NumberKind::Int(0), position.clone()));
+			body.push(Instruction::Statement(Statement::Return(Spanned::new(Some(zero), position.clone()))));
+		}
+		Some(Node::Function(FunctionDefinition {
+			prototype: FunctionPrototype {
+				doc: None,
+				name: Spanned::new("main".to_owned(), position.clone()),
+				args: Vec::new(),
+				return_type: Some(Spanned::new(DataType::Basic(BasicDataType::Int), position)),
+				exported: false,
+			},
+			body,
+			annotations: Vec::new(),
+		}))
 	}
 }
 
-fn parse_top_level_node(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Option<Result<Node>> {
+/// Parses the next top-level node. Errors in instructions are [recovered](helper::recover) from and added to the
+/// `errors`.
+fn parse_top_level_node(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
+) -> Option<Result<Node>> {
 	let token = tokens.peek()?;
 	match **token {
-		TokenKind::Def => Some(parse_function_definition(tokens).map(Node::Function)),
+		TokenKind::Def => Some(parse_function_definition(tokens, errors).map(Node::Function)),
 		TokenKind::Extern => Some(parse_extern_function_declaration(tokens).map(Node::FunctionPrototype)),
 		TokenKind::Struct => Some(parse_struct_definition(tokens).map(Node::Struct)),
+		TokenKind::Export => Some(parse_export(tokens, errors)),
+		TokenKind::At => Some(parse_annotated(tokens, errors)),
+		TokenKind::DocComment(_) => Some(parse_documented(tokens, errors)),
 		TokenKind::Comment(ref comment) => {
-			tracing::warn!("Skipping {}", token);
-			tokens.next();
-			parse_top_level_node(tokens)
+			let comment = comment.clone();
+			let position = tokens.next()?.position;
+			Some(Ok(Node::Comment(Spanned::new(comment, position))))
 		},
 		_ => Some(Err(Error::IllegalToken { token: Some(tokens.next()?), context: "top level node" })),
 	}
 }
 
+/// A top-level node or an instruction outside of any function, as entered in the REPL.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplItem {
+	/// A function, struct or extern declaration.
+	Node(Node),
+	/// An instruction that is executed immediately.
+	Instruction(Instruction),
+}
+
+/// Parses the next [`ReplItem`]. Returns [`None`] if the `tokens` are drained.
+pub fn parse_repl_item(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Option<Result<ReplItem>> {
+	let mut errors = Vec::new();
+	let item = match **tokens.peek()? {
+		TokenKind::Def
+		| TokenKind::Extern
+		| TokenKind::Struct
+		| TokenKind::Export
+		| TokenKind::At
+		| TokenKind::DocComment(_) => parse_top_level_node(tokens, &mut errors)?.map(ReplItem::Node),
+		TokenKind::Comment(_) => {
+			tokens.next();
+			return parse_repl_item(tokens);
+		},
+		_ => instruction::parse_instruction(tokens, &mut errors).map(ReplItem::Instruction),
+	};
+	Some(first_error(item, errors))
+}
+
+/// The first of the `errors` that were recovered from while parsing, or else the `result`. Used where a partial AST
+/// is of no use, but the code has to be valid.
+fn first_error<T>(result: Result<T>, errors: Vec<Error>) -> Result<T> {
+	match errors.into_iter().next() {
+		Some(err) => Err(err),
+		None => result,
+	}
+}
+
+/// Parses a function or struct definition marked with `export`.
+fn parse_export(tokens: &mut Peekable<impl Iterator<Item = Token>>, errors: &mut Vec<Error>) -> Result<Node> {
+	tokens.next(); // Consume TokenKind::Export
+	match tokens.peek().map(|token| &token.value) {
+		Some(TokenKind::Def) => {
+			let mut function = parse_function_definition(tokens, errors)?;
+			function.prototype.exported = true;
+			Ok(Node::Function(function))
+		},
+		Some(TokenKind::Struct) => {
+			let mut struct_ = parse_struct_definition(tokens)?;
+			struct_.exported = true;
+			Ok(Node::Struct(struct_))
+		},
+		_ => Err(Error::IllegalToken { token: tokens.next(), context: "export" }),
+	}
+}
+
+/// Parses a function definition preceded by annotations like `@bench`.
+fn parse_annotated(tokens: &mut Peekable<impl Iterator<Item = Token>>, errors: &mut Vec<Error>) -> Result<Node> {
+	let mut annotations = Vec::new();
+	while tokens.next_if(|token| token.value == TokenKind::At).is_some() {
+		annotations.push(helper::parse_identifier(tokens.next())?);
+	}
+	let mut function = match tokens.peek().map(|token| &token.value) {
+		Some(TokenKind::Def) => parse_function_definition(tokens, errors)?,
+		Some(TokenKind::Export) => {
+			tokens.next(); // Consume TokenKind::Export
+			if tokens.peek().map(|token| &token.value) != Some(&TokenKind::Def) {
+				return Err(Error::IllegalToken { token: tokens.next(), context: "annotation" });
+			}
+			let mut function = parse_function_definition(tokens, errors)?;
+			function.prototype.exported = true;
+			function
+		},
+		// Only functions can be annotated
+		_ => return Err(Error::IllegalToken { token: tokens.next(), context: "annotation" }),
+	};
+	function.annotations = annotations;
+	Ok(Node::Function(function))
+}
+
+/// Parses a function, extern declaration or struct and attaches the doc comment preceding it.
+fn parse_documented(tokens: &mut Peekable<impl Iterator<Item = Token>>, errors: &mut Vec<Error>) -> Result<Node> {
+	let Some(Token { value: TokenKind::DocComment(doc), position }) = tokens.next() else {
+		unreachable!("doc comment expected")
+	};
+	let doc = Some(Spanned::new(doc, position));
+	// Only functions and structs can be documented
+	let documentable = matches!(
+		tokens.peek().map(|token| &token.value),
+		Some(TokenKind::Def | TokenKind::Extern | TokenKind::Struct | TokenKind::Export | TokenKind::At)
+	);
+	if !documentable {
+		return Err(Error::IllegalToken { token: tokens.next(), context: "doc comment" });
+	}
+	let mut node = parse_top_level_node(tokens, errors).expect("a documentable token")?;
+	match &mut node {
+		Node::Function(function) => function.prototype.doc = doc,
+		Node::FunctionPrototype(prototype) => prototype.doc = doc,
+		Node::Struct(struct_) => struct_.doc = doc,
+		Node::Comment(_) | Node::Invalid(_) => unreachable!("comments and invalid code are not documentable"),
+	}
+	Ok(node)
+}
+
+/// Parses all top-level nodes of the `tokens`, producing a partial AST instead of stopping at the first error.
+///
+/// Invalid instructions and values in functions are replaced by [`Instruction::Invalid`] and
+/// [`Expression::Invalid`](crate::ast::Expression::Invalid), so that the rest of the function is kept. Other errors
+/// replace the whole node by a [`Node::Invalid`]: tokens are skipped until the next token that starts a top-level node,
+/// e.g. `def`, so that the nodes following an incomplete function are still parsed. Comments are skipped as part of the
+/// invalid code. Returns the parsed nodes and the errors of the invalid code, one per skipped node.
+pub fn parse_lenient(tokens: impl Iterator<Item = Token>) -> (Vec<Node>, Vec<Error>) {
+	let mut tokens = tokens.peekable();
+	let (mut nodes, mut errors) = (Vec::new(), Vec::new());
+	loop {
+		let previous_errors = errors.len();
+		let start = tokens.peek().map(|token| token.position.clone());
+		let Some(node) = parse_top_level_node(&mut tokens, &mut errors) else { break };
+		match node {
+			Ok(node) => nodes.push(node),
+			Err(err) => {
+				// Errors of a skipped node follow from its first one
+				let first = errors.drain(previous_errors..).next();
+				errors.push(first.unwrap_or(err));
+				let skipped =
+					|token: &Token| matches!(token.value, TokenKind::Comment(_)) || !starts_top_level_node(token);
+				while tokens.next_if(skipped).is_some() {}
+				let start = start.expect("a node was parsed from the next token");
+				let end = tokens
+					.peek()
+					.map_or(start.source.text.len(), |token| token.position.position.start.offset as usize);
+				nodes.push(Node::Invalid(start.extend_to(end)));
+			},
+		}
+	}
+	(nodes, errors)
+}
+
+/// Whether the `token` is the first one of a top-level node.
+fn starts_top_level_node(token: &Token) -> bool {
+	matches!(
+		token.value,
+		TokenKind::Def
+			| TokenKind::Extern
+			| TokenKind::Struct
+			| TokenKind::Export
+			| TokenKind::At
+			| TokenKind::Comment(_)
+			| TokenKind::DocComment(_)
+	)
+}
+
 impl<T> Iterator for Parser<T>
 where
 	T: Iterator<Item = Token>,
@@ -63,6 +281,24 @@ where
 	type Item = Result<Node>;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		parse_top_level_node(&mut self.tokens)
+		loop {
+			let mut errors = Vec::new();
+			let is_instruction = self.script && self.tokens.peek().is_some_and(|token| !starts_top_level_node(token));
+			if !is_instruction {
+				let Some(node) =
+					helper::with_max_nesting(self.max_nesting, || parse_top_level_node(&mut self.tokens, &mut errors))
+				else {
+					return self.script_main().map(Ok);
+				};
+				return Some(first_error(node, errors));
+			}
+			let instruction = helper::with_max_nesting(self.max_nesting, || {
+				instruction::parse_instruction(&mut self.tokens, &mut errors)
+			});
+			match first_error(instruction, errors) {
+				Ok(instruction) => self.script_instructions.push(instruction),
+				Err(err) => return Some(Err(err)),
+			}
+		}
 	}
 }
@@ -1,9 +1,10 @@
 use std::iter::Peekable;
 
-use super::Result;
+use super::{Error, Result};
 use crate::{
 	ast,
-	parser::{helper, variable::parse_data_type},
+	parser::{expression, helper, variable::parse_data_type},
+	source::Spanned,
 	token::{Token, TokenKind},
 };
 
@@ -13,21 +14,46 @@ pub(crate) fn parse_struct_definition(
 	helper::parse_struct(tokens.next())?;
 	let name = helper::parse_identifier(tokens.next())?;
 	helper::parse_opening_curly_parenthesis(tokens.next())?;
+	// Structs may be empty, and the last field may be followed by a comma
 	let mut fields: Vec<ast::struct_::Field> = Vec::new();
-	while let Some(token) = tokens.peek() {
-		if let TokenKind::ClosingCurlyBraces = **token {
-			tokens.next(); // Consume TokenKind::ClosingParentheses
-			break; // End of block
+	loop {
+		// Comments in structs are trivia like in blocks, which only the formatter keeps
+		while tokens.next_if(|token| matches!(token.value, TokenKind::Comment(_))).is_some() {}
+		let doc = match tokens.next_if(|token| matches!(token.value, TokenKind::DocComment(_))) {
+			Some(Token { value: TokenKind::DocComment(doc), position }) => Some(Spanned::new(doc, position)),
+			_ => None,
+		};
+		if let Some(closing) = tokens.next_if(|token| token.value == TokenKind::ClosingCurlyBraces) {
+			// A doc comment must precede a field
+			if doc.is_some() {
+				return Err(Error::IllegalToken { token: Some(closing), context: "doc comment" });
+			}
+			break;
 		}
-		let field = parse_field(tokens)?;
+		let field = parse_field(tokens, doc)?;
 		fields.push(field);
+		// Fields may be separated by commas
+		if let Some(Token { value: TokenKind::Comma, .. }) = tokens.peek() {
+			tokens.next(); // Consume TokenKind::Comma
+		}
 	}
-	Ok(ast::struct_::Struct { name, fields })
+	Ok(ast::struct_::Struct { doc: None, name, fields, exported: false })
 }
 
-pub(crate) fn parse_field(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Result<ast::struct_::Field> {
+/// Parses a field, whose doc comment was parsed before.
+pub(crate) fn parse_field(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	doc: Option<Spanned<String>>,
+) -> Result<ast::struct_::Field> {
 	let name = helper::parse_identifier(tokens.next())?;
 	helper::parse_colon(tokens.next())?;
 	let data_type = parse_data_type(tokens)?;
-	Ok(ast::struct_::Field { name, data_type })
+	let default = match tokens.peek() {
+		Some(Token { value: TokenKind::Equal, .. }) => {
+			tokens.next(); // Consume TokenKind::Equal
+			Some(expression::parse_primary_expression(tokens)?)
+		},
+		_ => None,
+	};
+	Ok(ast::struct_::Field { doc, name, data_type, default })
 }
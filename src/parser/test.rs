@@ -0,0 +1,327 @@
+use std::{fs, path::Path, sync::Arc};
+
+use crate::{
+	ast::{
+		self,
+		build::{
+			assign, assign_field, binary, call, comment, declare, expr, field, float, float_value, function, if_else,
+			int, int_value, invalid, invalid_instr, invalid_node, pointer, ret, str, str_value, struct_, struct_type,
+			var, while_loop, ClearPositions,
+		},
+		expression::BinaryOperator::{Add, Divide, Equal, Less, Multiply, NotEqual, Subtract},
+		Node,
+	},
+	dump::Dumper,
+	emitter::{codegen_to_string, Ftl},
+	lexer::{BlockComments, Lexer},
+	parser::{Error, Parser, DEFAULT_MAX_NESTING},
+	semantic_analyzer::CheckConfig,
+	source::Source,
+	token::Token,
+};
+
+/// Parses the `source_code` and clears the positions, so that the AST can be compared with one
+/// [built](crate::ast::build) in the test.
+fn parse(source_code: &str) -> Vec<Node> {
+	let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+	let mut ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+	ast_nodes.clear_positions();
+	ast_nodes
+}
+
+/// `def intact(): int { return 0 }`, which each file of the corpus contains.
+fn intact() -> Node {
+	function("intact").returns(int()).body([ret(int_value(0))]).into()
+}
+
+#[test]
+fn test_function() {
+	assert_eq!(
+		parse("def add(a: int, b: int): int { return a + b }\n@bench\nexport def nothing() { return }"),
+		[
+			function("add").arg("a", int()).arg("b", int()).returns(int()).body([ret(binary(var("a"), Add, var("b")))]),
+			function("nothing").exported().annotation("bench").body([ret(None)]),
+		]
+		.map(Node::from)
+	);
+}
+
+#[test]
+fn test_declarations() {
+	let point = struct_("Point")
+		.field_with_default("x", float(), float_value(1.5))
+		.field("next", pointer(struct_type("Point")))
+		.exported();
+	assert_eq!(
+		parse("# Points\nexport struct Point {\n\tx: float = 1.5\n\tnext: ptr Point\n}\nextern puts(s: str): int"),
+		[
+			comment("Points"),
+			point.into(),
+			Node::FunctionPrototype(function("puts").arg("s", str()).returns(int()).build_extern())
+		]
+	);
+
+	// Fields may have doc comments and be followed by commas, and structs may be empty. Other comments are skipped.
+	let pair = struct_("Pair").field("a", int()).doc("First\nelement").field("b", int());
+	assert_eq!(
+		parse("struct Pair {\n\t# Skipped\n\t## First\n\t## element\n\ta: int, # Skipped\n\tb: int,\n\t# Last\n}\nstruct Marker {}"),
+		[pair.into(), struct_("Marker").into()]
+	);
+}
+
+#[test]
+fn test_doc_comments() {
+	let source_code =
+		"## Adds `a`\n## and `b`\n@bench\nexport def add(a: int, b: int): int { return a + b }\n\n## Prints\n\
+		extern puts(s: str): int\n## A point\nstruct Point {}\n# Ordinary\ndef main(): int { return 0 }";
+	let ast_nodes = parse(source_code);
+	let docs: Vec<_> = ast_nodes.iter().map(|node| node.doc().map(|doc| doc.as_str())).collect();
+	assert_eq!(docs, [Some("Adds `a`\nand `b`"), Some("Prints"), Some("A point"), None, None]);
+	let Node::Function(add) = &ast_nodes[0] else { panic!("expected a function, found {:?}", ast_nodes[0]) };
+	assert!(add.prototype.exported && add.annotations.len() == 1);
+
+	// Doc comments must precede a function, struct or field
+	for source_code in [
+		"## Nothing",
+		"## Comment\n# ordinary\ndef f() {}",
+		"def f() {\n\t## Instruction\n\treturn\n}",
+		"struct S {\n\tx: int\n\t## Nothing\n}",
+	] {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens = crate::lex_source(source, &Dumper::default()).unwrap();
+		let result = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>();
+		assert!(matches!(result, Err(Error::IllegalToken { .. })), "{:?}", result);
+	}
+}
+
+#[test]
+fn test_precedence() {
+	let expected = binary(
+		binary(int_value(1), Add, binary(int_value(2), Multiply, int_value(3))),
+		Less,
+		binary(int_value(4), Divide, binary(int_value(5), Subtract, int_value(6))),
+	);
+	assert_eq!(
+		parse("def f(): int { return 1 + 2 * 3 < 4 / (5 - 6) }"),
+		[function("f").returns(int()).body([ret(expected)]).into()]
+	);
+	let expected = binary(int_value(1), Less, binary(binary(int_value(2), Multiply, int_value(3)), Add, int_value(4)));
+	assert_eq!(
+		parse("def f(): int { return 1 < 2 * 3 + 4 }"),
+		[function("f").returns(int()).body([ret(expected)]).into()]
+	);
+}
+
+#[test]
+fn test_instructions() {
+	let source_code = "def main(): int {
+	var i: int = 0
+	var greeting: str = \"Hi\"
+	while i =/= 10 {
+		i = i + 1
+	}
+	if i = 10 {
+		print(greeting, (i * 2))
+	} else {
+		p.x = p.y
+	}
+	return 0
+}";
+	let main = function("main").returns(int()).body([
+		declare("i", int(), int_value(0)),
+		declare("greeting", str(), str_value("Hi")),
+		while_loop(binary(var("i"), NotEqual, int_value(10)), [assign("i", binary(var("i"), Add, int_value(1)))]),
+		if_else(
+			binary(var("i"), Equal, int_value(10)),
+			[expr(call("print", [var("greeting"), binary(var("i"), Multiply, int_value(2))]))],
+			[assign_field(var("p"), "x", field(var("p"), "y"))],
+		),
+		ret(int_value(0)),
+	]);
+	assert_eq!(parse(source_code), [main.into()]);
+
+	// Comments in blocks are skipped, also after a `return` without value
+	let source_code = "def f() { # start\n\tif 1 { # if\n\t}\n\t# before return\n\treturn # nothing\n\t# end\n}";
+	assert_eq!(parse(source_code), [function("f").body([if_else(int_value(1), [], []), ret(None)]).into()]);
+}
+
+/// Parses each malformed FTL file in `corpus/`, collected from fuzzing and from code that is being edited.
+/// Strict parsing has to fail with an error instead of panicking, and lenient parsing has to report the error and
+/// still salvage the function `intact` that each file contains.
+#[test]
+fn test_corpus() {
+	let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/parser/corpus");
+	let mut paths: Vec<_> = fs::read_dir(&directory).unwrap().map(|entry| entry.unwrap().path()).collect();
+	paths.sort();
+	assert!(!paths.is_empty());
+
+	for path in paths {
+		let source = Arc::new(Source::new(path.to_string_lossy().into_owned(), fs::read_to_string(&path).unwrap()));
+		assert!(crate::parse_source(source.clone(), &Dumper::default()).is_err(), "{:?} parsed", path);
+
+		let (mut ast_nodes, diagnostics) = crate::parse_lenient(source);
+		assert!(!diagnostics.is_empty(), "no diagnostics for {:?}", path);
+		ast_nodes.clear_positions();
+		assert!(ast_nodes.contains(&intact()), "`intact` not salvaged from {:?}", path);
+	}
+}
+
+#[test]
+fn test_parse_lenient() {
+	let source_code = "def a(): int { return 0 }\ndef b(): int { return\ndef c(): int { return 0 }\n$";
+	let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+	let (mut ast_nodes, diagnostics) = crate::parse_lenient(source);
+	ast_nodes.clear_positions();
+	let function = |name| function(name).returns(int()).body([ret(int_value(0))]).into();
+	assert_eq!(ast_nodes, [function("a"), invalid_node(), function("c")]);
+	assert_eq!(diagnostics.len(), 2);
+}
+
+#[test]
+fn test_partial_ast() {
+	let source_code = "def f(n: int): int {\n\tvar x: int = )\n\tx = x +\n\t# comment\n\tif n < ) {\n\t\treturn \
+	                   1\n\t}\n\treturn x\n}\n";
+	let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+	let tokens: Vec<_> = Lexer::new(source.clone().iter()).with_trivia().collect::<Result<_, _>>().unwrap();
+	let (mut ast_nodes, diagnostics) = crate::parse_lenient(source);
+	// The comment is no instruction, but trivia
+	assert_eq!(diagnostics.len(), 3);
+
+	// Invalid code causes no further errors, e.g. `x` is still declared
+	let program = crate::semantic_analyzer::analyze(ast_nodes.iter(), &CheckConfig::default()).unwrap();
+	assert!(program.diagnostics.is_empty());
+	// The formatter keeps invalid code and comments as they are
+	let emitter = Ftl { comments: Some(BlockComments::new(&tokens)), ..Ftl::default() };
+	assert_eq!(codegen_to_string(&emitter, &mut ast_nodes.clone().into_iter()).unwrap(), source_code);
+
+	ast_nodes.clear_positions();
+	let body = [declare("x", int(), invalid()), assign("x", invalid()), invalid_instr(), ret(var("x"))];
+	assert_eq!(ast_nodes, [function("f").arg("n", int()).returns(int()).body(body).into()]);
+	// Strict parsing fails at the first error
+	assert!(crate::parse_source(
+		Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned())),
+		&Dumper::default()
+	)
+	.is_err());
+}
+
+#[test]
+fn test_max_nesting() {
+	let tokens = |source_code: String| {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code));
+		crate::lex_source(source, &Dumper::default()).unwrap()
+	};
+	let parse = |tokens: Vec<Token>, max_nesting: usize| {
+		Parser::new(tokens.into_iter()).with_max_nesting(max_nesting).collect::<Result<Vec<_>, _>>()
+	};
+
+	let calls =
+		|depth: usize| tokens(format!("def f(): int {{\n\treturn {}1{}\n}}", "f(".repeat(depth), ")".repeat(depth)));
+	// The function body is nested as well
+	assert!(parse(calls(DEFAULT_MAX_NESTING - 2), DEFAULT_MAX_NESTING).is_ok());
+	let Err(Error::TooDeeplyNested { position, max_nesting }) = parse(calls(10_000), DEFAULT_MAX_NESTING) else {
+		panic!("expected TooDeeplyNested")
+	};
+	assert_eq!(
+		(position.position.start.column, max_nesting),
+		(2 * (DEFAULT_MAX_NESTING as u32 - 1) + 9, DEFAULT_MAX_NESTING)
+	);
+
+	// The function body is nested as well
+	let blocks = tokens("def f() {\n\tif 1 {\n\t\twhile 1 {\n\t\t}\n\t} else {\n\t}\n}".to_owned());
+	assert!(parse(blocks.clone(), 3).is_ok());
+	assert!(matches!(parse(blocks, 2), Err(Error::TooDeeplyNested { .. })));
+	let operators = tokens("def f(): int {\n\treturn ((1)) + 2 * 3\n}".to_owned());
+	assert!(parse(operators.clone(), 3).is_ok());
+	let Err(Error::TooDeeplyNested { position, .. }) = parse(operators, 2) else { panic!("expected TooDeeplyNested") };
+	assert_eq!(position.code(), "+");
+
+	let types = tokens("extern f(p: ptr ptr opt int)".to_owned());
+	assert!(parse(types.clone(), 4).is_ok());
+	assert!(matches!(parse(types, 3), Err(Error::TooDeeplyNested { .. })));
+}
+
+#[test]
+fn test_deep_nesting() {
+	// Parentheses are parsed without recursion and don't count towards the maximum nesting depth
+	let depth = 2000;
+	let parentheses = format!("def f(): int {{\n\treturn {}1 + 2{} * 3\n}}", "(".repeat(depth), ")".repeat(depth));
+	let ast_nodes = parse(&parentheses);
+	assert_eq!(
+		ast_nodes,
+		[function("f")
+			.returns(int())
+			.body([ret(binary(binary(int_value(1), Add, int_value(2)), Multiply, int_value(3)))])
+			.into()]
+	);
+
+	// Blocks are parsed without recursion too, but the passes after the parser recurse into them
+	let blocks = |depth: usize| {
+		format!("def f() {{\n{}return\n{}}}", "while 1 {\nif 1 {\n} else {\n".repeat(depth), "}\n}\n".repeat(depth))
+	};
+	let source = Arc::new(Source::new("test.ftl".to_owned(), blocks(5000)));
+	let tokens = crate::lex_source(source, &Dumper::default()).unwrap();
+	let Err(Error::TooDeeplyNested { position, .. }) = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>()
+	else {
+		panic!("expected TooDeeplyNested")
+	};
+	// The `if` of the 64th repetition is nested 129 levels deep, with the function body
+	assert_eq!((position.code(), position.position.start.line), ("if", 3 * 63 + 3));
+
+	// The function body is nested as well
+	let depth = (DEFAULT_MAX_NESTING - 1) / 2;
+	let Node::Function(function) = &parse(&blocks(depth))[0] else { panic!("expected a function") };
+	let mut body = &function.body;
+	for _ in 0..depth {
+		let [ast::Instruction::WhileLoop(while_loop)] = &body[..] else { panic!("expected a while loop") };
+		let [ast::Instruction::IfElse(if_else)] = &while_loop.body[..] else { panic!("expected an if-else") };
+		body = &if_else.if_false;
+	}
+	assert_eq!(body, &[ret(None)]);
+}
+
+#[test]
+fn test_unclosed_block() {
+	let source_code = "def f(n: int) {\n\twhile n {\n\t\tif n < 1 {\n\t\t\tn = 1\n\t\t}\n\t\tn = n - 1\n\n";
+	let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+	let tokens = crate::lex_source(source, &Dumper::default()).unwrap();
+	let Err(err) = Parser::new(tokens.into_iter()).collect::<Result<Vec<_>, _>>() else { panic!("expected an error") };
+	let Error::UnclosedBlock { opening } = &err else { panic!("expected UnclosedBlock, found {:?}", err) };
+	// The innermost unclosed block is reported, which is the while loop
+	assert_eq!((opening.position.start.line, opening.position.start.column), (2, 10));
+	assert!(err.is_incomplete_input());
+
+	let diagnostic = crate::diagnostic::Diagnostic::from(&err);
+	let positions: Vec<_> = diagnostic
+		.labels
+		.iter()
+		.map(|label| (label.primary, label.position.position.start.line, label.position.position.start.column))
+		.collect();
+	assert_eq!(positions, [(true, 2, 10), (false, 6, 11)]);
+}
+
+#[test]
+fn test_script() {
+	let parse_script = |source_code: &str| {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens = crate::lex_source(source, &Dumper::default()).unwrap();
+		let mut ast_nodes = Parser::new(tokens.into_iter()).with_script(true).collect::<Result<Vec<_>, _>>()?;
+		ast_nodes.clear_positions();
+		Ok::<_, Error>(ast_nodes)
+	};
+	let twice = function("twice").arg("x", int()).returns(int()).body([ret(binary(var("x"), Multiply, int_value(2)))]);
+	assert_eq!(
+		parse_script("var x: int = 1\ndef twice(x: int): int { return x * 2 }\nx = twice(x)"),
+		Ok(vec![
+			twice.into(),
+			function("main")
+				.returns(int())
+				.body([declare("x", int(), int_value(1)), assign("x", call("twice", [var("x")])), ret(int_value(0))])
+				.into(),
+		])
+	);
+	assert_eq!(parse_script("return 3"), Ok(vec![function("main").returns(int()).body([ret(int_value(3))]).into()]));
+	// Files without top-level instructions are parsed as usual
+	assert_eq!(parse_script("def main(): int { return 1 }"), Ok(parse("def main(): int { return 1 }")));
+	assert!(parse_script("x = (1").is_err());
+}
@@ -1,49 +1,67 @@
 use std::iter::Peekable;
 
+use smol_str::SmolStr;
+
 use super::Result;
 use crate::{
 	ast,
 	parser::{expression, helper, variable, Error},
-	source::PositionContainer,
+	source::Spanned,
 	token::{Token, TokenKind},
 };
 
+/// Parses a variable declaration. An invalid value is replaced by [`ast::Expression::Invalid`], so that the variable
+/// is still declared.
 pub fn parse_variable_declaration(
 	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+	errors: &mut Vec<Error>,
 ) -> Result<ast::statement::VariableDeclaration> {
 	helper::parse_variable_declaration(tokens.next())?;
 	let name = helper::parse_identifier(tokens.next())?;
 	helper::parse_colon(tokens.next())?;
 	let data_type = variable::parse_data_type(tokens)?;
 	helper::parse_equal(tokens.next())?;
-	let value = expression::parse_primary_expression(tokens)?;
+	let value = helper::recover(
+		tokens,
+		errors,
+		|tokens, _| expression::parse_primary_expression(tokens),
+		ast::Expression::Invalid,
+	)?;
 	Ok(ast::statement::VariableDeclaration { name, data_type, value })
 }
 
 pub(crate) fn parse_data_type(
 	tokens: &mut Peekable<impl Iterator<Item = Token>>,
-) -> Result<PositionContainer<ast::statement::DataType>> {
+) -> Result<Spanned<ast::statement::DataType>> {
+	helper::nested(tokens, parse_nested_data_type)
+}
+
+fn parse_nested_data_type(
+	tokens: &mut Peekable<impl Iterator<Item = Token>>,
+) -> Result<Spanned<ast::statement::DataType>> {
 	match tokens.next() {
 		// Pointer type
-		Some(Token { value: TokenKind::Pointer, position }) => {
+		Some(token @ Token { value: TokenKind::Pointer, .. }) => {
 			// Recursively call parse_data_type to parse the type the pointer points to. This recursive calling
 			// allows types like `ptr ptr int` to be parsed.
 			let type_to_point_to = parse_data_type(tokens)?;
-			Ok(PositionContainer { value: ast::statement::DataType::Pointer(Box::new(type_to_point_to)), position })
+			Ok(token.map(|_| ast::statement::DataType::Pointer(Box::new(type_to_point_to))))
+		},
+		// Optional type
+		Some(token @ Token { value: TokenKind::Optional, .. }) => {
+			let inner = parse_data_type(tokens)?;
+			Ok(token.map(|_| ast::statement::DataType::Optional(Box::new(inner))))
 		},
 		// Normal type
 		Some(Token { value: TokenKind::Identifier(type_str), position }) => {
-			match ast::statement::BasicDataType::try_from(type_str.as_str()) {
+			let data_type = match ast::statement::BasicDataType::try_from(type_str.as_str()) {
 				// Basic data type
-				Ok(basic_data_type) => {
-					Ok(PositionContainer { value: ast::statement::DataType::Basic(basic_data_type), position })
-				},
-				Err(_) => {
-					// User-defined data type (struct)
-					Ok(PositionContainer { value: ast::statement::DataType::Struct(type_str), position })
-				},
-			}
+				Ok(basic_data_type) => ast::statement::DataType::Basic(basic_data_type),
+				// User-defined data type (struct)
+				Err(_) => ast::statement::DataType::Struct(type_str.to_string()),
+			};
+			Ok(Spanned::new(data_type, position))
 		},
-		other => Err(Error::ExpectedToken { expected: TokenKind::Identifier(String::new()), found: other }),
+		other => Err(Error::ExpectedToken { expected: TokenKind::Identifier(SmolStr::default()), found: other }),
 	}
 }
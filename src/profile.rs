@@ -0,0 +1,129 @@
+//! Finding hot functions of programs executed by the [interpreter](crate::interpreter).
+//!
+//! With [profiling](crate::interpreter::Interpreter::with_profile) enabled, the interpreter measures how often each
+//! FTL function is called and how much time is spent in it. The [report](Profile::report) lists the functions with
+//! the most time spent in their own code first.
+
+use std::{
+	collections::HashMap,
+	fmt,
+	time::{Duration, Instant},
+};
+
+/// Calls and time spent in one function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionProfile {
+	/// Name of the function.
+	pub name: String,
+	/// Number of calls.
+	pub calls: u64,
+	/// Time spent in the function including the functions it called. Recursive calls are counted only once.
+	pub total: Duration,
+	/// Time spent in the code of the function itself, excluding the functions it called.
+	pub self_time: Duration,
+}
+
+/// A function that is currently executed.
+#[derive(Debug)]
+struct Frame {
+	name: String,
+	start: Instant,
+	/// Time spent in the functions called by this one so far.
+	children: Duration,
+}
+
+/// Measurements of all functions, which are recorded by the interpreter when entering and leaving them.
+#[derive(Debug, Default)]
+pub struct Profile {
+	functions: HashMap<String, FunctionProfile>,
+	stack: Vec<Frame>,
+}
+
+impl Profile {
+	/// Starts measuring a call of the function `name`.
+	pub fn enter(&mut self, name: &str) {
+		self.stack.push(Frame { name: name.to_owned(), start: Instant::now(), children: Duration::ZERO });
+	}
+
+	/// Stops measuring the call of the function entered last.
+	pub fn exit(&mut self) {
+		let Some(frame) = self.stack.pop() else {
+			return;
+		};
+		let elapsed = frame.start.elapsed();
+		if let Some(parent) = self.stack.last_mut() {
+			parent.children += elapsed;
+		}
+		let function = self.functions.entry(frame.name.clone()).or_insert_with(|| FunctionProfile {
+			name: frame.name.clone(),
+			calls: 0,
+			total: Duration::ZERO,
+			self_time: Duration::ZERO,
+		});
+		function.calls += 1;
+		function.self_time += elapsed.saturating_sub(frame.children);
+		// The time of a recursive call is already part of the outermost call of the function
+		if !self.stack.iter().any(|outer| outer.name == frame.name) {
+			function.total += elapsed;
+		}
+	}
+
+	/// The measurements of the functions, sorted by their [self time](FunctionProfile::self_time), highest first.
+	pub fn report(&self) -> Report {
+		let mut functions: Vec<_> = self.functions.values().cloned().collect();
+		functions.sort_by(|a, b| b.self_time.cmp(&a.self_time).then_with(|| a.name.cmp(&b.name)));
+		Report(functions)
+	}
+}
+
+/// Sorted measurements of [`Profile::report`], displayed as a table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report(pub Vec<FunctionProfile>);
+
+impl fmt::Display for Report {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "{:<24} {:>10} {:>12} {:>12}", "function", "calls", "total ms", "self ms")?;
+		for function in &self.0 {
+			writeln!(
+				f,
+				"{:<24} {:>10} {:>12.3} {:>12.3}",
+				function.name,
+				function.calls,
+				function.total.as_secs_f64() * 1000.0,
+				function.self_time.as_secs_f64() * 1000.0
+			)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use crate::{dump::Dumper, interpreter::Interpreter, source::Source};
+
+	#[test]
+	fn test_profile() {
+		let source_code = "
+			def fib(n: int): int {
+				if n < 2 { return n }
+				return fib((n - 1)) + fib((n - 2))
+			}
+			def once(): int { return 1 }
+			def main(): int { return fib(10) + once() }";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let mut interpreter = Interpreter::new(ast_nodes.iter()).with_profile();
+		assert_eq!(interpreter.run(), Ok(56));
+		let report = interpreter.profile().unwrap().report();
+
+		let calls: Vec<_> = report.0.iter().map(|function| (function.name.as_str(), function.calls)).collect();
+		assert_eq!(calls.len(), 3);
+		assert!(calls.contains(&("fib", 177)) && calls.contains(&("main", 1)) && calls.contains(&("once", 1)));
+		assert!(report.0.windows(2).all(|pair| pair[0].self_time >= pair[1].self_time));
+		let total = |name: &str| report.0.iter().find(|function| function.name == name).unwrap().total;
+		assert!(total("main") >= total("fib"));
+		assert!(report.to_string().starts_with("function                      calls"));
+	}
+}
@@ -0,0 +1,81 @@
+//! Scaffolding of new FTL projects, see [`create`].
+
+use std::{fs, path::Path};
+
+use crate::CompileError;
+
+/// Program printing a greeting, as a starting point of new projects.
+const MAIN: &str = "# Run this program using: ftl run src/main.ftl
+
+def main(): int {
+	ftl_print(\"Hello, World!\\n\")
+	return 0
+}
+";
+
+/// Files and directories generated by the compiler, which don't belong in version control.
+const GITIGNORE: &str = "# Generated by `ftl build`, `ftl compile` and `--dump`
+/ftl-build/
+/ftl-dump/
+*.c
+*.h
+*.o
+*.ftlcov
+/src/main
+";
+
+/// Creates the project `directory` with an `ftl.toml` naming the project after the directory, a hello-world
+/// program in `src/main.ftl` and a `.gitignore` for the build artifacts.
+///
+/// Fails if the `directory` already exists, so that no files are overwritten.
+pub fn create(directory: &Path) -> Result<(), CompileError> {
+	let Some(name) = directory.file_name().and_then(|name| name.to_str()) else {
+		return Err(CompileError::Invalid(format!("Can't name a project after `{}`", directory.display())));
+	};
+	if directory.exists() {
+		return Err(CompileError::Invalid(format!("Destination `{}` already exists", directory.display())));
+	}
+
+	let source_directory = directory.join("src");
+	fs::create_dir_all(&source_directory)
+		.map_err(CompileError::io(format!("Creating directory `{}`", source_directory.display())))?;
+	let manifest = format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name);
+	for (path, content) in [
+		(directory.join("ftl.toml"), manifest.as_str()),
+		(source_directory.join("main.ftl"), MAIN),
+		(directory.join(".gitignore"), GITIGNORE),
+	] {
+		fs::write(&path, content).map_err(CompileError::io(format!("Writing `{}`", path.display())))?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{dump::Dumper, interpreter::Interpreter, semantic_analyzer::CheckConfig, test_support::TempDir};
+
+	#[test]
+	fn test_create() {
+		let directory = TempDir::new().unwrap();
+		let project = directory.path().join("hello");
+		create(&project).unwrap();
+
+		assert_eq!(
+			fs::read_to_string(project.join("ftl.toml")).unwrap(),
+			"[package]\nname = \"hello\"\nversion = \"0.1.0\"\n"
+		);
+		assert!(fs::read_to_string(project.join(".gitignore")).unwrap().contains("/ftl-build/"));
+		let ast_nodes = crate::compiler_pipeline(
+			&project.join("src/main.ftl"),
+			&CheckConfig::strict(),
+			&Dumper::default(),
+			&mut Vec::new(),
+		)
+		.unwrap();
+		assert_eq!(Interpreter::new(ast_nodes.iter()).run().unwrap(), 0);
+
+		// Existing projects are not overwritten
+		assert!(matches!(create(&project), Err(CompileError::Invalid(_))));
+	}
+}
@@ -0,0 +1,235 @@
+//! Evaluating FTL code interactively, one input at a time.
+//!
+//! Lexer and parser run in a single pass: The parser pulls the tokens lazily from the lexer. If the parser runs out of
+//! tokens, the input is reported as [incomplete](Evaluation::Incomplete) instead of as syntax error, so that the REPL
+//! can ask for continuation lines, e.g. after an unclosed `{`.
+
+use std::{collections::HashMap, io, iter, sync::Arc};
+
+use crate::{
+	ast::{
+		self,
+		statement::{DataType, FunctionArgument},
+		FunctionDefinition, FunctionPrototype,
+	},
+	diagnostic::Diagnostic,
+	interpreter::{self, Interpreter, Value},
+	lexer::Lexer,
+	parser::{self, ReplItem},
+	semantic_analyzer::{CheckConfig, SymbolTable, TypeChecker},
+	source::{Source, SourcePositionRange, Spanned},
+	CompileError,
+};
+
+/// Name of the function holding the instructions of an input during semantic analysis. It is not a valid identifier,
+/// so it can't clash with user-defined functions.
+const INPUT_FUNCTION: &str = "<input>";
+
+/// Result of lexing and parsing one input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parsed {
+	/// The input is syntactically complete.
+	Complete(Vec<ReplItem>),
+	/// The input ended too early, e.g. before a block was closed.
+	Incomplete,
+}
+
+/// Why an input couldn't be evaluated.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// The input is invalid.
+	#[error(transparent)]
+	Compile(#[from] CompileError),
+	/// The interpreter failed while executing the input.
+	#[error(transparent)]
+	Interpreter(#[from] interpreter::Error),
+}
+
+impl Error {
+	/// The diagnostic describing the error in the input.
+	pub fn diagnostic(&self) -> Option<Diagnostic> {
+		match self {
+			Error::Compile(err) => err.diagnostic(),
+			Error::Interpreter(err) => Some(Diagnostic::from(err)),
+		}
+	}
+}
+
+/// Lexes and parses the `source` in a single pass.
+pub fn parse(source: Arc<Source>) -> Result<Parsed, CompileError> {
+	let mut lex_error = None;
+	let parse_result = {
+		let mut tokens =
+			Lexer::new(source.iter()).map_while(|token| token.map_err(|err| lex_error = Some(err)).ok()).peekable();
+		iter::from_fn(|| parser::parse_repl_item(&mut tokens)).collect::<Result<Vec<_>, _>>()
+	};
+	// A lexer error ends the token stream early, so it takes precedence over the parser error it may cause
+	if let Some(err) = lex_error {
+		return Err(err.into());
+	}
+	match parse_result {
+		Ok(items) => Ok(Parsed::Complete(items)),
+		Err(err) if err.is_incomplete_input() => Ok(Parsed::Incomplete),
+		Err(err) => Err(err.into()),
+	}
+}
+
+/// Result of evaluating one input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Evaluation {
+	/// The input is incomplete. It should be evaluated again once more lines were appended.
+	Incomplete,
+	/// The input was evaluated. Contains the value of the last instruction if it is an expression.
+	Complete(Option<Value>),
+}
+
+/// State of a REPL, i.e. the definitions and variables of all previous inputs.
+///
+/// Functions, structs and variables can be redefined by later inputs, even with different types.
+pub struct Session {
+	/// Functions and structs of all previous inputs.
+	symbol_table: SymbolTable,
+	/// Types of the variables declared outside of functions by previous inputs.
+	variables: HashMap<String, Spanned<DataType>>,
+	interpreter: Interpreter,
+	/// Number of evaluated inputs, used to name their sources.
+	inputs: usize,
+}
+
+impl Default for Session {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Session {
+	/// Creates a session without any definitions.
+	pub fn new() -> Self {
+		let Ok(symbol_table) = SymbolTable::global_symbol_scan(iter::empty());
+		Self { symbol_table, variables: HashMap::new(), interpreter: Interpreter::new(iter::empty()), inputs: 0 }
+	}
+
+	/// Writes the output of evaluated code to `stdout` instead of the standard output of this process.
+	pub fn with_stdout(mut self, stdout: Box<dyn io::Write>) -> Self {
+		self.interpreter = self.interpreter.with_stdout(stdout);
+		self
+	}
+
+	/// Evaluates the `input`, which may contain definitions as well as instructions.
+	///
+	/// Definitions and variables are only kept if the whole input passes the semantic analysis.
+	pub fn eval(&mut self, input: &str) -> Result<Evaluation, Error> {
+		self.inputs += 1;
+		let source = Arc::new(Source::new(format!("<repl {}>", self.inputs), input.to_owned()));
+		let items = match parse(Arc::clone(&source))? {
+			Parsed::Complete(items) => items,
+			Parsed::Incomplete => return Ok(Evaluation::Incomplete),
+		};
+		let (mut definitions, mut instructions) = (Vec::new(), Vec::new());
+		for item in items {
+			match item {
+				ReplItem::Node(mut node) => {
+					// Each input is a source file of its own, but later inputs may use its definitions
+					match &mut node {
+						ast::Node::Function(function) => function.prototype.exported = true,
+						ast::Node::Struct(struct_) => struct_.exported = true,
+						ast::Node::FunctionPrototype(_) | ast::Node::Comment(_) | ast::Node::Invalid(_) => (),
+					}
+					definitions.push(node)
+				},
+				ReplItem::Instruction(instruction) => instructions.push(instruction),
+			}
+		}
+		let declarations: Vec<_> = instructions
+			.iter()
+			.filter_map(|instruction| match instruction {
+				ast::Instruction::Statement(ast::Statement::VariableDeclaration(declaration)) => Some(declaration),
+				_ => None,
+			})
+			.collect();
+
+		// Check the instructions as body of a function, which takes the variables of previous inputs as arguments.
+		// Variables declared again are left out, so that they can be redeclared with a different type.
+		let args = self
+			.variables
+			.iter()
+			.filter(|(name, _)| declarations.iter().all(|declaration| declaration.name.as_str() != name.as_str()))
+			.map(|(name, data_type)| FunctionArgument {
+				name: data_type.as_ref().map(|_| name.clone()),
+				data_type: data_type.clone(),
+			})
+			.collect();
+		let position =
+			SourcePositionRange::synthetic(instructions.first().map(ast::Instruction::source_position).as_ref());
+		let input_function = ast::Node::Function(FunctionDefinition {
+			prototype: FunctionPrototype {
+				doc: None,
+				name: Spanned::new(INPUT_FUNCTION.to_owned(), position),
+				args,
+				return_type: None,
+				exported: false,
+			},
+			body: instructions.clone(),
+			annotations: Vec::new(),
+		});
+		let mut symbol_table = self.symbol_table.clone();
+		for definition in &definitions {
+			symbol_table.replace(definition);
+		}
+		let program = TypeChecker::type_check(
+			symbol_table,
+			definitions.iter().chain(iter::once(&input_function)),
+			&CheckConfig::default(),
+		)
+		.map_err(CompileError::Semantic)?;
+
+		self.symbol_table = program.symbol_table;
+		for declaration in declarations {
+			self.variables.insert(declaration.name.value.clone(), declaration.data_type.clone());
+		}
+		for definition in &definitions {
+			self.interpreter.define(definition);
+		}
+		Ok(Evaluation::Complete(self.interpreter.execute(&instructions)?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_incomplete_input() {
+		let mut session = Session::new();
+		assert_eq!(session.eval("def square(a: int): int {").unwrap(), Evaluation::Incomplete);
+		assert_eq!(session.eval("def square(a: int): int {\n\treturn a *").unwrap(), Evaluation::Incomplete);
+		assert_eq!(session.eval("def square(a: int): int {\n\treturn a * a\n}").unwrap(), Evaluation::Complete(None));
+		assert_eq!(session.eval("square((1 + 2))").unwrap(), Evaluation::Complete(Some(Value::Int(9))));
+		// Syntax errors are not reported as incomplete input
+		assert!(session.eval("def ) {").is_err());
+		assert!(session.eval("square(1) }").is_err());
+	}
+
+	#[test]
+	fn test_rejected_input_is_discarded() {
+		let mut session = Session::new();
+		assert!(session.eval("def f(): int { return 1 }\nundefined()").is_err());
+		assert!(session.eval("f()").is_err());
+	}
+
+	#[test]
+	fn test_redefinition() {
+		let mut session = Session::new();
+		session.eval("var x: int = 2").unwrap();
+		session.eval("def f(a: int): int { return a + 1 }").unwrap();
+		assert_eq!(session.eval("f(x)").unwrap(), Evaluation::Complete(Some(Value::Int(3))));
+		session.eval("x = 5").unwrap();
+		session.eval("def f(a: int): int { return a * 10 }").unwrap();
+		assert_eq!(session.eval("f(x)").unwrap(), Evaluation::Complete(Some(Value::Int(50))));
+
+		// Redeclaration with a different type
+		session.eval("var x: str = \"text\"").unwrap();
+		assert!(session.eval("f(x)").is_err());
+		assert_eq!(session.eval("x").unwrap(), Evaluation::Complete(Some(Value::Str("text".into()))));
+	}
+}
@@ -0,0 +1,22 @@
+# Opaque handle of an open file
+struct File {
+}
+
+extern ftl_print(string: str): int
+extern ftl_print_int(value: int): int
+extern ftl_print_float(value: float): int
+extern ftl_read_line(): str
+extern ftl_str_concat(lhs: str, rhs: str): str
+extern str_len(string: str): int
+extern str_compare(lhs: str, rhs: str): int
+extern substring(string: str, start: int, length: int): str
+extern ftl_pow(base: float, exponent: float): float
+extern random_int(lo: int, hi: int): int
+extern random_float(): float
+extern now_millis(): int
+extern getenv(name: str): str
+extern exit(status: int)
+extern open(path: str, mode: str): File
+extern read_line(file: File): str
+extern write(file: File, text: str): int
+extern close(file: File): int
@@ -0,0 +1,255 @@
+/* FTL runtime library, which is linked to every compiled FTL program. */
+/* For clock_gettime */
+#define _POSIX_C_SOURCE 199309L
+
+#include <inttypes.h>
+#include <limits.h>
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+
+/* Allocates memory and aborts the program if no memory is left. */
+static void* ftl_alloc(size_t size) {
+	void* memory = malloc(size);
+	if (memory == NULL) {
+		fputs("ftl: out of memory\n", stderr);
+		abort();
+	}
+	return memory;
+}
+
+/* Writes the string to stdout and returns the number of written bytes, or a negative value on error. */
+int64_t ftl_print(char* string) {
+	return printf("%s", string);
+}
+
+/* Writes the integer in decimal to stdout, like `ftl_print`. */
+int64_t ftl_print_int(int64_t value) {
+	return printf("%" PRId64, value);
+}
+
+/* Writes the float with six decimal places to stdout, like `ftl_print`. */
+int64_t ftl_print_float(double value) {
+	return printf("%f", value);
+}
+
+/* Reads a line from the stream without the trailing newline. Returns an empty string at the end of the input. */
+static char* ftl_read_line_from(FILE* stream) {
+	size_t capacity = 64;
+	size_t length = 0;
+	char* line = ftl_alloc(capacity);
+	int c;
+	while ((c = getc(stream)) != EOF && c != '\n') {
+		if (length + 1 == capacity) {
+			char* larger = ftl_alloc(capacity * 2);
+			memcpy(larger, line, length);
+			free(line);
+			line = larger;
+			capacity *= 2;
+		}
+		line[length++] = (char)c;
+	}
+	line[length] = '\0';
+	return line;
+}
+
+/* Reads a line from stdin without the trailing newline. Returns an empty string at the end of the input. */
+char* ftl_read_line(void) {
+	return ftl_read_line_from(stdin);
+}
+
+/* Returns a newly allocated string consisting of `lhs` followed by `rhs`. */
+char* ftl_str_concat(char* lhs, char* rhs) {
+	size_t lhs_length = strlen(lhs);
+	size_t rhs_length = strlen(rhs);
+	char* result = ftl_alloc(lhs_length + rhs_length + 1);
+	memcpy(result, lhs, lhs_length);
+	memcpy(result + lhs_length, rhs, rhs_length + 1);
+	return result;
+}
+
+/* Returns the length of the string in bytes. */
+int64_t str_len(char* string) {
+	return (int64_t)strlen(string);
+}
+
+/* Compares the strings byte by byte. Returns -1, 0 or 1 if `lhs` is less than, equal to or greater than `rhs`. */
+int64_t str_compare(char* lhs, char* rhs) {
+	int result = strcmp(lhs, rhs);
+	return (result > 0) - (result < 0);
+}
+
+/* Returns a newly allocated string of the `length` bytes of `string` starting at byte `start`. Bytes outside of the
+ * string are left out. */
+char* substring(char* string, int64_t start, int64_t length) {
+	int64_t string_length = (int64_t)strlen(string);
+	int64_t from = start < 0 ? 0 : (start > string_length ? string_length : start);
+	/* The end saturates instead of overflowing, like in the interpreter */
+	int64_t to;
+	if (length > 0 && start > INT64_MAX - length) {
+		to = INT64_MAX;
+	} else if (length < 0 && start < INT64_MIN - length) {
+		to = INT64_MIN;
+	} else {
+		to = start + length;
+	}
+	to = to < from ? from : (to > string_length ? string_length : to);
+	char* result = ftl_alloc((size_t)(to - from) + 1);
+	memcpy(result, string + from, (size_t)(to - from));
+	result[to - from] = '\0';
+	return result;
+}
+
+/* Raises `base` to the power of `exponent`. */
+double ftl_pow(double base, double exponent) {
+	return pow(base, exponent);
+}
+
+/* Seeds the random number generator on first use. */
+static void ftl_seed_random(void) {
+	static int seeded = 0;
+	if (!seeded) {
+		srand((unsigned)time(NULL) ^ (unsigned)clock());
+		seeded = 1;
+	}
+}
+
+/* Returns a random integer between `lo` and `hi`, both inclusive. Returns `lo` if `hi` is less than `lo`. */
+int64_t random_int(int64_t lo, int64_t hi) {
+	if (hi < lo) {
+		return lo;
+	}
+	ftl_seed_random();
+	double fraction = rand() / ((double)RAND_MAX + 1);
+	return lo + (int64_t)(fraction * ((double)hi - lo + 1));
+}
+
+/* Returns a random float between 0 (inclusive) and 1 (exclusive). */
+double random_float(void) {
+	ftl_seed_random();
+	/* Only use as many bits as a float can represent exactly, so that rounding never results in 1 */
+	return (double)(rand() % 16777216) / 16777216.0;
+}
+
+/* Returns the milliseconds elapsed since the first call of this function. Only differences are meaningful. */
+int64_t now_millis(void) {
+	static struct timespec start;
+	static int started = 0;
+	struct timespec now;
+	clock_gettime(CLOCK_MONOTONIC, &now);
+	if (!started) {
+		start = now;
+		started = 1;
+	}
+	return (int64_t)((now.tv_sec - start.tv_sec) * 1000 + (now.tv_nsec - start.tv_nsec) / 1000000);
+}
+
+/* Returns the value of the environment variable `name`, or an empty string if it is not set. */
+char* ftl_getenv(char* name) {
+	char* value = getenv(name);
+	return value == NULL ? "" : value;
+}
+
+/* Opens the file at `path` with a mode like "r", "w" or "a". Returns NULL if the file can't be opened. */
+FILE* ftl_open(char* path, char* mode) {
+	return fopen(path, mode);
+}
+
+/* Reads a line from the file without the trailing newline. Returns an empty string at the end of the file. */
+char* ftl_file_read_line(FILE* file) {
+	if (file == NULL) {
+		return "";
+	}
+	return ftl_read_line_from(file);
+}
+
+/* Writes the text to the file and returns the number of written bytes, or a negative value on error. */
+int64_t ftl_write(FILE* file, char* text) {
+	if (file == NULL || fputs(text, file) == EOF) {
+		return -1;
+	}
+	return (int64_t)strlen(text);
+}
+
+/* Closes the file and returns 0 on success or a negative value on error. */
+int64_t ftl_close(FILE* file) {
+	if (file == NULL || fclose(file) != 0) {
+		return -1;
+	}
+	return 0;
+}
+
+/*
+ * Writes values for the `dump` intrinsic: numbers like `ftl_print_int` and `ftl_print_float`, strings in quotes, and
+ * `null` for the sentinels of optionals, null pointers and files that could not be opened. The C emitter generates
+ * the functions for structs.
+ */
+void ftl_dump_int(int64_t value) {
+	ftl_print_int(value);
+}
+
+void ftl_dump_float(double value) {
+	ftl_print_float(value);
+}
+
+void ftl_dump_str(char* value) {
+	if (value == NULL) {
+		ftl_print("null");
+		return;
+	}
+	printf("\"%s\"", value);
+}
+
+void ftl_dump_File(FILE* value) {
+	ftl_print(value == NULL ? "null" : "<file>");
+}
+
+void ftl_dump_opt_int(int64_t value) {
+	if (value == INT64_MIN) {
+		ftl_print("null");
+		return;
+	}
+	ftl_dump_int(value);
+}
+
+void ftl_dump_opt_float(double value) {
+	if (isnan(value)) {
+		ftl_print("null");
+		return;
+	}
+	ftl_dump_float(value);
+}
+
+void ftl_dump_ptr_int(int64_t* value) {
+	if (value == NULL) {
+		ftl_print("null");
+		return;
+	}
+	ftl_dump_int(*value);
+}
+
+void ftl_dump_ptr_float(double* value) {
+	if (value == NULL) {
+		ftl_print("null");
+		return;
+	}
+	ftl_dump_float(*value);
+}
+
+void ftl_dump_ptr_str(char** value) {
+	if (value == NULL) {
+		ftl_print("null");
+		return;
+	}
+	ftl_dump_str(*value);
+}
+
+void ftl_dump_ptr_File(FILE** value) {
+	if (value == NULL) {
+		ftl_print("null");
+		return;
+	}
+	ftl_dump_File(*value);
+}
@@ -0,0 +1,95 @@
+/* Declarations of the FTL runtime library, see `ftl_runtime.c`. */
+#ifndef FTL_RUNTIME_H
+#define FTL_RUNTIME_H
+
+#include <inttypes.h>
+#include <limits.h>
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/* Opaque handle of an open file, NULL if opening failed. */
+typedef FILE* File;
+
+int64_t ftl_print(char* string);
+int64_t ftl_print_int(int64_t value);
+int64_t ftl_print_float(double value);
+char* ftl_read_line(void);
+char* ftl_str_concat(char* lhs, char* rhs);
+int64_t str_len(char* string);
+int64_t str_compare(char* lhs, char* rhs);
+char* substring(char* string, int64_t start, int64_t length);
+double ftl_pow(double base, double exponent);
+int64_t random_int(int64_t lo, int64_t hi);
+double random_float(void);
+int64_t now_millis(void);
+char* ftl_getenv(char* name);
+File ftl_open(char* path, char* mode);
+char* ftl_file_read_line(File file);
+int64_t ftl_write(File file, char* text);
+int64_t ftl_close(File file);
+
+/* Writers of the `dump` intrinsic. Those of structs are generated next to the structs. */
+void ftl_dump_int(int64_t value);
+void ftl_dump_float(double value);
+void ftl_dump_str(char* value);
+void ftl_dump_File(File value);
+void ftl_dump_opt_int(int64_t value);
+void ftl_dump_opt_float(double value);
+void ftl_dump_ptr_int(int64_t* value);
+void ftl_dump_ptr_float(double* value);
+void ftl_dump_ptr_str(char** value);
+void ftl_dump_ptr_File(File* value);
+
+/* Integer overloads of the intrinsics. */
+static inline int64_t ftl_isqrt(int64_t x) {
+	return (int64_t)sqrt((double)x);
+}
+
+static inline int64_t ftl_iabs(int64_t x) {
+	return x < 0 ? -x : x;
+}
+
+static inline int64_t ftl_imin(int64_t lhs, int64_t rhs) {
+	return lhs < rhs ? lhs : rhs;
+}
+
+static inline int64_t ftl_imax(int64_t lhs, int64_t rhs) {
+	return lhs > rhs ? lhs : rhs;
+}
+
+/* `mod` of FTL, whose result is never negative, unlike that of `%`. */
+static inline int64_t ftl_mod(int64_t lhs, int64_t rhs) {
+	int64_t remainder = lhs % rhs;
+	if (remainder < 0) {
+		return rhs < 0 ? remainder - rhs : remainder + rhs;
+	}
+	return remainder;
+}
+
+static inline int64_t ftl_ipow(int64_t base, int64_t exponent) {
+	if (exponent < 0) {
+		/* 1 / base^-exponent, truncated towards zero */
+		if (base == 1) return 1;
+		if (base == -1) return exponent % 2 == 0 ? 1 : -1;
+		return 0;
+	}
+	int64_t result = 1;
+	for (; exponent > 0; exponent--) {
+		result *= base;
+	}
+	return result;
+}
+
+/* Intrinsics, selecting the overload by the type of the first argument. Small integer literals are `int`s in C. */
+#define ftl_intrinsic_sqrt(x) _Generic((x), int: ftl_isqrt, int64_t: ftl_isqrt, default: sqrt)(x)
+#define ftl_intrinsic_abs(x) _Generic((x), int: ftl_iabs, int64_t: ftl_iabs, default: fabs)(x)
+#define ftl_intrinsic_min(lhs, rhs) _Generic((lhs), int: ftl_imin, int64_t: ftl_imin, default: fmin)(lhs, rhs)
+#define ftl_intrinsic_max(lhs, rhs) _Generic((lhs), int: ftl_imax, int64_t: ftl_imax, default: fmax)(lhs, rhs)
+#define ftl_intrinsic_pow(base, exponent) _Generic((base), int: ftl_ipow, int64_t: ftl_ipow, default: pow)(base, exponent)
+
+/* Prints a value of a call of the `print` intrinsic, selecting the print function by its type. */
+#define ftl_print_value(x) _Generic((x), int: ftl_print_int, int64_t: ftl_print_int, double: ftl_print_float, default: ftl_print)(x)
+
+#endif
@@ -0,0 +1,188 @@
+//! The runtime library with IO, math and string helpers, which is linked to every compiled FTL program.
+//!
+//! The [declarations] of the runtime functions and structs are registered in the
+//! [symbol table](crate::semantic_analyzer::SymbolTable), so programs can use them without `extern` declarations.
+//! The [interpreter](crate::interpreter) and the Python emitter provide their own implementations.
+
+use std::{
+	path::{Path, PathBuf},
+	process,
+	sync::Arc,
+};
+
+use crate::{ast, build, lexer::Lexer, parser::Parser, source::Source, CompileError};
+
+/// C header declaring the runtime functions. It also includes the C standard library headers the generated code needs.
+pub const C_HEADER: &str = include_str!("ftl_runtime.h");
+
+/// C implementation of the runtime functions.
+pub const C_SOURCE: &str = include_str!("ftl_runtime.c");
+
+/// Arguments for the C compiler to link the libraries the runtime depends on.
+pub const LINK_ARGS: &[&str] = &["-lm"];
+
+/// FTL declarations of the runtime functions and structs.
+const DECLARATIONS: &str = include_str!("declarations.ftl");
+
+/// Runtime functions whose C implementation has a different name, since the C standard library already defines a
+/// function with the FTL name, but with different semantics.
+const C_NAMES: &[(&str, &str)] = &[
+	("getenv", "ftl_getenv"),
+	("open", "ftl_open"),
+	("read_line", "ftl_file_read_line"),
+	("write", "ftl_write"),
+	("close", "ftl_close"),
+];
+
+/// Returns the declarations of all runtime functions and structs as [function prototypes](ast::Node::FunctionPrototype)
+/// and [structs](ast::Node::Struct).
+///
+/// Runtime structs are opaque, i.e. have no fields.
+pub fn declarations() -> Vec<ast::Node> {
+	let source = Arc::new(Source::new("<runtime>".to_owned(), DECLARATIONS.to_owned()));
+	let tokens = Lexer::new(source.iter()).collect::<Result<Vec<_>, _>>().expect("runtime declarations are lexable");
+	let ast_nodes: Vec<ast::Node> =
+		Parser::new(tokens.into_iter()).collect::<Result<_, _>>().expect("runtime declarations are parsable");
+	ast_nodes.into_iter().filter(|node| !matches!(node, ast::Node::Comment(_))).collect()
+}
+
+/// Returns the name of the C function implementing the function `name`.
+///
+/// Calls of functions which are not part of the runtime keep their name.
+pub fn c_name(name: &str) -> &str {
+	C_NAMES.iter().find(|(ftl_name, _)| *ftl_name == name).map_or(name, |(_, c_name)| c_name)
+}
+
+/// Writes the runtime into `directory` and compiles it to an object file, unless it is already up to date.
+///
+/// Returns the path of the object file.
+pub fn compile(directory: &Path) -> Result<PathBuf, CompileError> {
+	let c_path = directory.join("ftl_runtime.c");
+	let object_path = directory.join("ftl_runtime.o");
+	if build::write_if_changed(&c_path, C_SOURCE)? || !object_path.exists() {
+		tracing::info!(path = ?c_path, "compiling runtime");
+		build::run_c_compiler(process::Command::new("cc").arg("-c").arg(&c_path).arg("-o").arg(&object_path))?;
+	}
+	Ok(object_path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		differential::{assert_consistent, Outcome},
+		test_support::TempDir,
+	};
+
+	#[test]
+	fn test_declarations() {
+		let names: Vec<_> = declarations()
+			.into_iter()
+			.map(|node| match node {
+				ast::Node::FunctionPrototype(prototype) => prototype.name.value,
+				ast::Node::Struct(struct_) => struct_.name.value,
+				ast::Node::Function(_) | ast::Node::Comment(_) | ast::Node::Invalid(_) => {
+					unreachable!("runtime declarations contain no function definitions and comments")
+				},
+			})
+			.collect();
+		assert_eq!(
+			names,
+			[
+				"File",
+				"ftl_print",
+				"ftl_print_int",
+				"ftl_print_float",
+				"ftl_read_line",
+				"ftl_str_concat",
+				"str_len",
+				"str_compare",
+				"substring",
+				"ftl_pow",
+				"random_int",
+				"random_float",
+				"now_millis",
+				"getenv",
+				"exit",
+				"open",
+				"read_line",
+				"write",
+				"close"
+			]
+		);
+	}
+
+	#[test]
+	fn test_runtime() {
+		let outcome = assert_consistent(
+			"def main(): int {
+				var greeting: str = \"Hello, World!\\n\"
+				ftl_print(greeting)
+				return ftl_print(\"\\\"quoted\\\"\")
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: 8, stdout: "Hello, World!\n\"quoted\"".to_owned() });
+	}
+
+	#[test]
+	fn test_random_and_time() {
+		let outcome = assert_consistent(
+			"def main(): int {
+				var start: int = now_millis()
+				if random_float() < 1.0 {
+					return now_millis() - start < 1000
+				}
+				return 0
+			}",
+		);
+		assert_eq!(outcome.exit_code, 1);
+	}
+
+	#[test]
+	fn test_process_builtins() {
+		let outcome = assert_consistent(
+			"def main(): int {
+				ftl_print(getenv(\"HOME\"))
+				ftl_print(getenv(\"FTL_UNSET_VARIABLE\"))
+				exit(3)
+				return 0
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: 3, stdout: std::env::var("HOME").unwrap_or_default() });
+	}
+
+	#[test]
+	fn test_file_io() {
+		let directory = TempDir::new().unwrap();
+		let path = directory.path().join("file.txt");
+		let outcome = assert_consistent(&format!(
+			"def main(): int {{
+				var file: File = open(\"{path}\", \"w\")
+				write(file, \"first\\nsecond\\n\")
+				close(file)
+				file = open(\"{path}\", \"r\")
+				var first: str = read_line(file)
+				ftl_print(read_line(file))
+				close(file)
+				return close(open(\"{path}/missing\", \"r\"))
+			}}",
+			path = path.display()
+		));
+		assert_eq!(outcome, Outcome { exit_code: 255, stdout: "second".to_owned() });
+	}
+
+	#[test]
+	fn test_strings() {
+		let outcome = assert_consistent(
+			"def main(): int {
+				var greeting: str = (\"Hello\" + \", \" + \"World\")
+				ftl_print((greeting + \"\\n\"))
+				ftl_print((substring(greeting, 7, 10) + substring(greeting, (0 - 3), 5) + \"\\n\"))
+				ftl_print((substring(greeting, 7, 9223372036854775807) + substring(greeting, (0 - 7), (0 - 9223372036854775807)) + \"\\n\"))
+				var order: int = (str_compare(\"b\", \"a\") * 100 + (greeting = \"Hello, World\") * 10 + (\"b\" < \"a\"))
+				return str_len(greeting) + order
+			}",
+		);
+		assert_eq!(outcome, Outcome { exit_code: 122, stdout: "Hello, World\nWorldHe\nWorld\n".to_owned() });
+	}
+}
@@ -0,0 +1,117 @@
+//! Runtime checks of compiled programs by the sanitizers of the C compiler.
+//!
+//! Programs compiled with sanitizers report memory errors and undefined behavior, e.g. an overflowing `int`, when
+//! they occur. The generated C code then contains [`#line` directives](crate::emitter::C::line_directives), so that
+//! the reports refer to the lines of the FTL source:
+//!
+//! ```text
+//! overflow.ftl:3:4: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'
+//! ```
+//!
+//! Columns in the reports are those of the generated C code.
+
+use std::{fmt, str::FromStr};
+
+/// A sanitizer of the C compiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sanitizer {
+	/// AddressSanitizer, detecting out-of-bounds accesses, use after free and leaks.
+	Address,
+	/// UndefinedBehaviorSanitizer, detecting e.g. signed integer overflow and division by zero.
+	Undefined,
+}
+
+impl FromStr for Sanitizer {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"address" => Ok(Sanitizer::Address),
+			"undefined" => Ok(Sanitizer::Undefined),
+			other => Err(format!("unknown sanitizer `{}`, expected one of address, undefined", other)),
+		}
+	}
+}
+
+impl fmt::Display for Sanitizer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Sanitizer::Address => write!(f, "address"),
+			Sanitizer::Undefined => write!(f, "undefined"),
+		}
+	}
+}
+
+/// Arguments of the C compiler enabling the `sanitizers` when compiling and linking. Empty if no sanitizer is
+/// selected.
+///
+/// Debug information and frame pointers are included for readable stack traces. Undefined behavior aborts the
+/// program instead of being reported only, so that it is noticed in tests.
+pub fn compiler_args(sanitizers: &[Sanitizer]) -> Vec<String> {
+	if sanitizers.is_empty() {
+		return Vec::new();
+	}
+	let names: Vec<_> = sanitizers.iter().map(Sanitizer::to_string).collect();
+	let mut args =
+		vec!["-g".to_owned(), "-fno-omit-frame-pointer".to_owned(), format!("-fsanitize={}", names.join(","))];
+	if sanitizers.contains(&Sanitizer::Undefined) {
+		args.push("-fno-sanitize-recover=undefined".to_owned());
+	}
+	args
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, process::Command, sync::Arc};
+
+	use super::*;
+	use crate::{
+		dump::Dumper,
+		emitter::{self, Emitter},
+		runtime,
+		semantic_analyzer::CheckConfig,
+		source::Source,
+		test_support::TempDir,
+	};
+
+	#[test]
+	fn test_compiler_args() {
+		assert!(compiler_args(&[]).is_empty());
+		let sanitizers: Vec<Sanitizer> = "address,undefined".split(',').map(|name| name.parse().unwrap()).collect();
+		assert_eq!(
+			compiler_args(&sanitizers),
+			["-g", "-fno-omit-frame-pointer", "-fsanitize=address,undefined", "-fno-sanitize-recover=undefined"]
+		);
+		assert!("thread".parse::<Sanitizer>().is_err());
+	}
+
+	#[test]
+	fn test_report_refers_to_ftl() {
+		let source_code = "def main(): int {\n\tvar x: int = 9223372036854775807\n\tx = x + 1\n\treturn 0\n}\n";
+		let source = Arc::new(Source::new("overflow.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes =
+			crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+		let directory = TempDir::new().unwrap();
+		let c_path = directory.path().join("overflow.c");
+		let emitter = emitter::C { line_directives: true };
+		emitter.codegen(&mut ast_nodes.into_iter(), Box::new(fs::File::create(&c_path).unwrap())).unwrap();
+
+		let executable = directory.path().join("overflow");
+		let compile = Command::new("cc")
+			.args(compiler_args(&[Sanitizer::Undefined]))
+			.arg("-o")
+			.arg(&executable)
+			.arg(&c_path)
+			.arg(runtime::compile(directory.path()).unwrap())
+			.args(runtime::LINK_ARGS)
+			.output()
+			.unwrap();
+		assert!(compile.status.success(), "{}", String::from_utf8_lossy(&compile.stderr));
+
+		let output = Command::new(&executable).output().unwrap();
+		assert!(!output.status.success());
+		let report = String::from_utf8_lossy(&output.stderr);
+		assert!(report.starts_with("overflow.ftl:3:"), "{}", report);
+		assert!(report.contains("signed integer overflow"), "{}", report);
+	}
+}
@@ -0,0 +1,21 @@
+/// Selects the optional checks of the [semantic analysis](super::analyze).
+///
+/// Values are never converted implicitly between types, so explicit casts are required regardless of the
+/// configuration, and variables can never shadow other variables.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CheckConfig {
+	/// Report the warnings of the [lints](super::lint) as errors.
+	pub deny_warnings: bool,
+	/// Require conditions of if-else and while loops to be comparisons, instead of treating every value that is not
+	/// zero as true.
+	pub require_bool_conditions: bool,
+	/// Forbid variables and arguments named like a function, which they would hide in the generated code.
+	pub forbid_shadowing: bool,
+}
+
+impl CheckConfig {
+	/// All checks enabled, as selected by `--strict`.
+	pub fn strict() -> Self {
+		Self { deny_warnings: true, require_bool_conditions: true, forbid_shadowing: true }
+	}
+}
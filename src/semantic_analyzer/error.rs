@@ -1,9 +1,13 @@
 use std::{ops::Deref, sync::Arc};
 
 use crate::{
-	ast::{expression::FunctionCall, statement::DataType},
-	semantic_analyzer::variable::Variable,
-	source::{PositionContainer, SourcePositionRange},
+	ast::{
+		expression::{BinaryOperator, FunctionCall},
+		statement::DataType,
+	},
+	diagnostic::{Diagnostic, Severity},
+	semantic_analyzer::{variable::Variable, Lint},
+	source::{SourcePositionRange, Spanned},
 };
 
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
@@ -12,7 +16,7 @@ pub enum Error {
 	Redeclaration { previous_declaration: Arc<Variable>, new_declaration: Arc<Variable> },
 
 	#[error("{}: UndeclaredVariable: Variable `{name}` is not declared.", name.position)]
-	UndeclaredVariable { name: PositionContainer<String> },
+	UndeclaredVariable { name: Spanned<String> },
 
 	#[error("{}: TypeMismatch: expected {}, got {}", position, expected, actual)]
 	TypeMismatch { expected: DataType, position: SourcePositionRange, actual: DataType },
@@ -22,4 +26,352 @@ pub enum Error {
 
 	#[error("{}: ArgumentCountMismatch: Function `{}(...)` expects {expected} arguments but {actual} parameters provided", function_call.name.position, function_call.name.value)]
 	ArgumentCountMismatch { expected: usize, actual: usize, function_call: FunctionCall },
+
+	#[error("{}: VoidValue: Function `{}(...)` has no return value, so it can't be used in an expression", function_call.name.position, function_call.name.value)]
+	VoidValue { function_call: FunctionCall },
+
+	#[error("{}: NoMatchingOverload: Intrinsic `{}(...)` is not defined for parameters of type ({})", function_call.name.position, function_call.name.value, join_types(param_types))]
+	NoMatchingOverload { function_call: FunctionCall, param_types: Box<[DataType]> },
+
+	#[error("{}: IntrinsicRedefinition: `{}` is the name of an intrinsic and can't be used for a function", name.position, name.value)]
+	IntrinsicRedefinition { name: Spanned<String> },
+
+	#[error("{}: UnknownStruct: Struct `{}` is not defined", name.position, name.value)]
+	UnknownStruct { name: Spanned<String>, known_structs: Vec<String> },
+
+	#[error("{}: Private: `{}` is not marked with `export`, so it can only be used in `{}`", name.position, name.value, definition.source.name)]
+	Private { name: Spanned<String>, definition: SourcePositionRange },
+
+	#[error("{}: DuplicateDefinition: `{}` is also defined in `{}`", name.position, name.value, previous.source.name)]
+	DuplicateDefinition { name: Spanned<String>, previous: SourcePositionRange },
+
+	#[error("{}: UnknownField: Struct `{}` has no field `{}`", field.position, struct_name.value, field.value)]
+	UnknownField { struct_name: Spanned<String>, field: Spanned<String> },
+
+	#[error("{position}: NotAStruct: Field `{}` accessed on value of type {data_type}, which is no struct", field.value, position = field.position)]
+	NotAStruct { data_type: DataType, field: Spanned<String> },
+
+	#[error("{}: NonBoolCondition: Condition is no comparison", position)]
+	NonBoolCondition { position: SourcePositionRange },
+
+	#[error("{}: Shadowing: Variable `{}` has the name of a function", name.position, name.value)]
+	Shadowing { name: Spanned<String> },
+
+	#[error("{}: RecursiveStruct: Struct `{}` contains itself through field `{}`", field.position, struct_name.value, field.value)]
+	RecursiveStruct { struct_name: Spanned<String>, field: Spanned<String> },
+
+	#[error("{}: NonConstantDefault: Default value of field `{}` calls function `{}(...)`", call.position, field.value, call.value)]
+	NonConstantDefault { field: Spanned<String>, call: Spanned<String> },
+
+	#[error("DeniedWarning: {}", Diagnostic::from(lint).message)]
+	DeniedWarning { lint: Lint },
+
+	#[error("{}: InvalidAnnotation: `@{}`: {reason}", annotation.position, annotation.value)]
+	InvalidAnnotation { annotation: Spanned<String>, reason: &'static str },
+
+	#[error("{}: UnexpectedNull: `null` used where no optional type is expected", position)]
+	UnexpectedNull { position: SourcePositionRange, expected: Option<DataType> },
+
+	#[error("{}: OptionalOperand: Operator `{}` applied to value of optional type {data_type}", operator.position, operator.position.code())]
+	OptionalOperand { operator: Spanned<BinaryOperator>, data_type: DataType },
+
+	#[error(
+		"{}: TemporaryReference: Value of type {data_type} passed to `ptr` argument is not stored in a variable",
+		position
+	)]
+	TemporaryReference { position: SourcePositionRange, data_type: DataType },
+
+	#[error("{}: NonNumericOperand: Operator `{}` applied to values of type {data_type}, which are no numbers", operator.position, operator.position.code())]
+	NonNumericOperand { operator: Spanned<BinaryOperator>, data_type: DataType },
+
+	#[error("{}: UnorderedOperand: Operator `{}` applied to values of type {data_type}, which have no order", operator.position, operator.position.code())]
+	UnorderedOperand { operator: Spanned<BinaryOperator>, data_type: DataType },
+
+	#[error("{}: IncomparableOperand: Operator `{}` applied to values of type {data_type}, which can't be compared", operator.position, operator.position.code())]
+	IncomparableOperand { operator: Spanned<BinaryOperator>, data_type: DataType },
+
+	#[error("{position}: NonScalarCondition: Condition of type {data_type}, which is no number, string or pointer")]
+	NonScalarCondition { position: SourcePositionRange, data_type: DataType },
+
+	#[error("{}: NonIntOperand: Operator `{}` applied to values of type {data_type}, which are no ints", operator.position, operator.position.code())]
+	NonIntOperand { operator: Spanned<BinaryOperator>, data_type: DataType },
+
+	#[error("{divisor}: DivisionByZero: Division by constant zero")]
+	DivisionByZero { divisor: SourcePositionRange },
+
+	#[error("{}: UnsupportedOptional: Type {} can't be optional", data_type.position, data_type.value)]
+	UnsupportedOptional { data_type: Spanned<DataType> },
+
+	#[error("{}: MisplacedFallible: Type {} can't be used here", data_type.position, data_type.value)]
+	MisplacedFallible { data_type: Spanned<DataType> },
+
+	#[error("{}: UnhandledFailure: Call of `{}(...)` may fail, but its failure is not handled", function_call.name.position, function_call.name.value)]
+	UnhandledFailure { function_call: FunctionCall },
+
+	#[error("{}: NotFallible: `try` applied to a value of type {data_type}, which can't fail", position)]
+	NotFallible { position: SourcePositionRange, data_type: DataType },
+
+	#[error("{}: UnexpectedError: `error` used outside of a function that can fail", position)]
+	UnexpectedError { position: SourcePositionRange },
+
+	#[error("{}: InvalidFormatString: {reason}", position)]
+	InvalidFormatString { position: SourcePositionRange, reason: String },
+
+	#[error("{}: FormatArgumentCount: Format string has {placeholders} placeholders, but {arguments} arguments are provided", position)]
+	FormatArgumentCount { position: SourcePositionRange, placeholders: usize, arguments: usize },
+
+	#[error("{}: UnprintableArgument: Value of type {data_type} can't be printed", position)]
+	UnprintableArgument { position: SourcePositionRange, data_type: DataType },
+
+	#[error("{}: InvalidCode: Code that could not be parsed is used as value", position)]
+	InvalidCode { position: SourcePositionRange },
+}
+
+/// Formats data types as comma separated list.
+fn join_types(types: &[DataType]) -> String {
+	types.iter().map(DataType::to_string).collect::<Vec<_>>().join(", ")
+}
+
+impl From<&Error> for Diagnostic {
+	fn from(err: &Error) -> Self {
+		match err {
+			Error::Redeclaration { previous_declaration, new_declaration } => Diagnostic::error(
+				"Redeclaration",
+				format!("Variable `{}` is already declared", new_declaration.name.deref()),
+			)
+			.with_primary_label(new_declaration.name.position.clone(), format!("redeclared as `{}`", new_declaration))
+			.with_secondary_label(
+				previous_declaration.name.position.clone(),
+				format!("previously declared as `{}`", previous_declaration),
+			),
+			Error::UndeclaredVariable { name } => {
+				Diagnostic::error("UndeclaredVariable", format!("Variable `{}` is not declared", name.deref()))
+					.with_primary_label(name.position.clone(), "used here")
+			},
+			Error::TypeMismatch { expected, position, actual } => {
+				Diagnostic::error("TypeMismatch", format!("Expected {}, got {}", expected, actual))
+					.with_primary_label(position.clone(), format!("expected {}", expected))
+			},
+			Error::UndefinedFunctionCall { function_call } => Diagnostic::error(
+				"UndefinedFunctionCall",
+				format!("Call of function `{}(...)`, but no such function is defined", function_call.name.deref()),
+			)
+			.with_primary_label(function_call.name.position.clone(), "called here")
+			.with_help("declare external functions with `extern`"),
+			Error::ArgumentCountMismatch { expected, actual, function_call } => {
+				let diagnostic = Diagnostic::error(
+					"ArgumentCountMismatch",
+					format!(
+						"Function `{}(...)` expects {} arguments but {} parameters provided",
+						function_call.name.deref(),
+						expected,
+						actual
+					),
+				);
+				match (function_call.params.first(), function_call.params.last()) {
+					(Some(first), Some(last)) => {
+						let mut params_position = first.source_position();
+						params_position.position.end = last.source_position().position.end;
+						diagnostic
+							.with_primary_label(params_position, format!("{} parameters provided", actual))
+							.with_secondary_label(function_call.name.position.clone(), "function called here")
+					},
+					_ => {
+						diagnostic.with_primary_label(function_call.name.position.clone(), "called without parameters")
+					},
+				}
+			},
+			Error::VoidValue { function_call } => Diagnostic::error(
+				"VoidValue",
+				format!("Function `{}(...)` has no return value", function_call.name.deref()),
+			)
+			.with_primary_label(function_call.name.position.clone(), "value used here")
+			.with_help("call the function as a separate instruction or declare a return type"),
+			Error::NoMatchingOverload { function_call, param_types } => Diagnostic::error(
+				"NoMatchingOverload",
+				format!(
+					"Intrinsic `{}(...)` is not defined for parameters of type ({})",
+					function_call.name.deref(),
+					join_types(param_types)
+				),
+			)
+			.with_primary_label(function_call.name.position.clone(), "called here")
+			.with_help("intrinsics take either only int or only float parameters"),
+			Error::IntrinsicRedefinition { name } => Diagnostic::error(
+				"IntrinsicRedefinition",
+				format!("`{}` is the name of an intrinsic and can't be used for a function", name.deref()),
+			)
+			.with_primary_label(name.position.clone(), "defined here")
+			.with_help("rename the function"),
+			Error::UnknownStruct { name, known_structs } => {
+				Diagnostic::error("UnknownStruct", format!("Struct `{}` is not defined", name.deref()))
+					.with_primary_label(name.position.clone(), "used here")
+					.with_help(format!("known structs are {}", known_structs.join(", ")))
+			},
+			Error::Private { name, definition } => Diagnostic::error(
+				"Private",
+				format!("`{}` can only be used in `{}`", name.deref(), definition.source.name),
+			)
+			.with_primary_label(name.position.clone(), "used in another file")
+			.with_secondary_label(definition.clone(), "defined here without `export`")
+			.with_help(format!("mark `{}` with `export` to use it in other files", name.deref())),
+			Error::DuplicateDefinition { name, previous } => Diagnostic::error(
+				"DuplicateDefinition",
+				format!("`{}` is also defined in `{}`", name.deref(), previous.source.name),
+			)
+			.with_primary_label(name.position.clone(), "defined again here")
+			.with_secondary_label(previous.clone(), "first defined here")
+			.with_help("functions and structs of different files need different names, even if they are not exported"),
+			Error::UnknownField { struct_name, field } => Diagnostic::error(
+				"UnknownField",
+				format!("Struct `{}` has no field `{}`", struct_name.deref(), field.deref()),
+			)
+			.with_primary_label(field.position.clone(), "accessed here")
+			.with_secondary_label(struct_name.position.clone(), "struct defined here"),
+			Error::NotAStruct { data_type, field } => Diagnostic::error(
+				"NotAStruct",
+				format!("Field `{}` accessed on value of type {}, which is no struct", field.deref(), data_type),
+			)
+			.with_primary_label(field.position.clone(), "accessed here"),
+			Error::NonBoolCondition { position } => Diagnostic::error("NonBoolCondition", "Condition is no comparison")
+				.with_primary_label(position.clone(), "used as condition")
+				.with_help("compare the value explicitly, e.g. `x =/= 0`"),
+			Error::RecursiveStruct { struct_name, field } => Diagnostic::error(
+				"RecursiveStruct",
+				format!("Struct `{}` contains itself, so it would be infinitely large", struct_name.deref()),
+			)
+			.with_primary_label(field.position.clone(), "recursive field")
+			.with_secondary_label(struct_name.position.clone(), "struct defined here")
+			.with_help("use a pointer, e.g. `ptr Node`"),
+			Error::NonConstantDefault { field, call } => Diagnostic::error(
+				"NonConstantDefault",
+				format!("Default value of field `{}` is not constant", field.deref()),
+			)
+			.with_primary_label(call.position.clone(), "function call")
+			.with_secondary_label(field.position.clone(), "field defined here")
+			.with_help("use literals as default values"),
+			Error::Shadowing { name } => {
+				Diagnostic::error("Shadowing", format!("Variable `{}` has the name of a function", name.deref()))
+					.with_primary_label(name.position.clone(), "declared here")
+					.with_help("rename the variable")
+			},
+			Error::DeniedWarning { lint } => {
+				let warning = Diagnostic::from(lint);
+				Diagnostic { severity: Severity::Error, ..warning }.with_note("warnings are denied by strict mode")
+			},
+			Error::InvalidAnnotation { annotation, reason } => {
+				Diagnostic::error("InvalidAnnotation", format!("Invalid annotation `@{}`", annotation.deref()))
+					.with_primary_label(annotation.position.clone(), *reason)
+					.with_help("the only supported annotation is `@bench` on functions without arguments")
+			},
+			Error::UnexpectedNull { position, expected } => {
+				let diagnostic = Diagnostic::error("UnexpectedNull", "`null` used where no optional type is expected");
+				match expected {
+					Some(expected) => diagnostic
+						.with_primary_label(position.clone(), format!("expected {}", expected))
+						.with_help(format!("declare the type as `opt {}`", expected)),
+					None => diagnostic
+						.with_primary_label(position.clone(), "type of `null` unknown")
+						.with_help("compare `null` with a value of an optional type"),
+				}
+			},
+			Error::OptionalOperand { operator, data_type } => Diagnostic::error(
+				"OptionalOperand",
+				format!("Operator `{}` applied to value of optional type {}", operator.position.code(), data_type),
+			)
+			.with_primary_label(operator.position.clone(), "applied here")
+			.with_help("compare the value with `null` first, e.g. `if x =/= null { ... }`"),
+			Error::TemporaryReference { position, data_type } => Diagnostic::error(
+				"TemporaryReference",
+				format!("Value of type {} passed to `ptr` argument is not stored in a variable", data_type),
+			)
+			.with_primary_label(position.clone(), "passed by reference here")
+			.with_note("structs are passed to `ptr` arguments by reference, which requires a variable or field to refer to")
+			.with_help("store the value in a variable first"),
+			Error::NonNumericOperand { operator, data_type } => Diagnostic::error(
+				"NonNumericOperand",
+				format!("Operator `{}` applied to values of type {}, which are no numbers", operator.position.code(), data_type),
+			)
+			.with_primary_label(operator.position.clone(), "applied here")
+			.with_note("arithmetic operators are only defined for `int` and `float`, and `+` concatenates strings"),
+			Error::UnorderedOperand { operator, data_type } => Diagnostic::error(
+				"UnorderedOperand",
+				format!("Operator `{}` applied to values of type {}, which have no order", operator.position.code(), data_type),
+			)
+			.with_primary_label(operator.position.clone(), "applied here")
+			.with_note("only numbers and strings can be compared with `<` and `>`")
+			.with_help("compare numeric fields of the values instead, e.g. `a.x < b.x`"),
+			Error::IncomparableOperand { operator, data_type } => Diagnostic::error(
+				"IncomparableOperand",
+				format!("Operator `{}` applied to values of type {}, which can't be compared", operator.position.code(), data_type),
+			)
+			.with_primary_label(operator.position.clone(), "applied here")
+			.with_note("structs are not compared field by field")
+			.with_help("compare the fields of the values instead, e.g. `a.x = b.x`"),
+			Error::NonScalarCondition { position, data_type } => Diagnostic::error(
+				"NonScalarCondition",
+				format!("Condition of type {}, which is no number, string or pointer", data_type),
+			)
+			.with_primary_label(position.clone(), "used as condition here")
+			.with_note("a condition is true if it is not zero, which only numbers, strings and pointers can be")
+			.with_help("check a field of the struct instead, e.g. `if p.x =/= 0`"),
+			Error::NonIntOperand { operator, data_type } => Diagnostic::error(
+				"NonIntOperand",
+				format!("Operator `{}` applied to values of type {}, which are no ints", operator.position.code(), data_type),
+			)
+			.with_primary_label(operator.position.clone(), "applied here")
+			.with_note("`mod` is only defined for `int`"),
+			Error::DivisionByZero { divisor } => Diagnostic::error("DivisionByZero", "Division by constant zero")
+				.with_primary_label(divisor.clone(), "divisor is zero")
+				.with_note("dividing an `int` by zero fails at runtime"),
+			Error::UnsupportedOptional { data_type } => {
+				Diagnostic::error("UnsupportedOptional", format!("Type {} can't be optional", data_type.value))
+					.with_primary_label(data_type.position.clone(), "optional type")
+					.with_help(
+						"only int, float, str and pointers can be optional, use e.g. `opt ptr Point` for structs",
+					)
+			},
+			Error::MisplacedFallible { data_type } => Diagnostic::error(
+				"MisplacedFallible",
+				format!("Type {} can't be used here", data_type.value),
+			)
+			.with_primary_label(data_type.position.clone(), "used here")
+			.with_help("only the return types of functions other than `main` can be `... or error`"),
+			Error::UnhandledFailure { function_call } => Diagnostic::error(
+				"UnhandledFailure",
+				format!("Call of `{}(...)` may fail, but its failure is not handled", function_call.name.deref()),
+			)
+			.with_primary_label(function_call.name.position.clone(), "may fail")
+			.with_help(format!("provide a fallback value with `try {}(...) else ...`", function_call.name.deref())),
+			Error::NotFallible { position, data_type } => {
+				Diagnostic::error("NotFallible", format!("`try` applied to a value of type {}, which can't fail", data_type))
+					.with_primary_label(position.clone(), "can't fail")
+					.with_help("use the value without `try`")
+			},
+			Error::UnexpectedError { position } => {
+				Diagnostic::error("UnexpectedError", "`error` used outside of a function that can fail")
+					.with_primary_label(position.clone(), "used here")
+					.with_help("`error` can only be returned, directly or as fallback of `try`, by functions declared like `def read(): int or error`")
+			},
+			Error::InvalidFormatString { position, reason } => {
+				Diagnostic::error("InvalidFormatString", format!("Invalid format string: {}", reason))
+					.with_primary_label(position.clone(), "format string")
+					.with_help("`{}` is replaced by the next argument, write `{{` and `}}` for literal braces")
+			},
+			Error::FormatArgumentCount { position, placeholders, arguments } => Diagnostic::error(
+				"FormatArgumentCount",
+				format!("Format string has {} placeholders, but {} arguments are provided", placeholders, arguments),
+			)
+			.with_primary_label(position.clone(), format!("expects {} arguments", placeholders)),
+			Error::UnprintableArgument { position, data_type } => {
+				Diagnostic::error("UnprintableArgument", format!("Value of type {} can't be printed", data_type))
+					.with_primary_label(position.clone(), "printed here")
+					.with_note("only `int`, `float` and `str` values can be printed, `dump(value)` also prints structs")
+			},
+			Error::InvalidCode { position } => {
+				Diagnostic::error("InvalidCode", "Code that could not be parsed is used as value")
+					.with_primary_label(position.clone(), "used here")
+			},
+		}
+	}
 }
@@ -0,0 +1,405 @@
+//! Optional checks for code that compiles, but probably doesn't do what was intended.
+
+use std::{
+	collections::{HashMap, HashSet},
+	ops::Deref,
+};
+
+use crate::{
+	ast::{self, statement::DataType, Expression, FunctionDefinition},
+	diagnostic::Diagnostic,
+	semantic_analyzer::TypedProgram,
+	source::{SourcePositionRange, Spanned},
+};
+
+/// Warning found by [`lint`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+	/// The `call` is executed on every call of `function` and leads back to `function`, so the recursion never ends.
+	UnconditionalRecursion { function: Spanned<String>, call: Spanned<String> },
+	/// A pointer to the `local` variable is returned or stored in a struct of the caller at `escape`, so it outlives
+	/// the variable.
+	EscapingReference { local: Spanned<String>, escape: SourcePositionRange },
+}
+
+/// Runs all lints on the program, which may consist of multiple files and has been type checked to the `program`.
+pub fn lint<'a>(ast_nodes: impl Iterator<Item = &'a ast::Node>, program: &TypedProgram) -> Vec<Lint> {
+	let mut functions: Vec<&FunctionDefinition> = Vec::new();
+	let mut pointer_functions = HashSet::new();
+	for node in ast_nodes {
+		let prototype = match node {
+			ast::Node::Function(function) => {
+				functions.push(function);
+				&function.prototype
+			},
+			ast::Node::FunctionPrototype(prototype) => prototype,
+			_ => continue,
+		};
+		if prototype.return_type.as_ref().is_some_and(|return_type| is_pointer(return_type)) {
+			pointer_functions.insert(prototype.name.as_str());
+		}
+	}
+	let mut lints = unconditional_recursion(&functions);
+	for function in functions {
+		lints.extend(escaping_references(function, &pointer_functions, program));
+	}
+	lints
+}
+
+/// Finds functions that call themselves, directly or through other functions, without any condition on the path to
+/// the recursive call.
+fn unconditional_recursion(functions: &[&FunctionDefinition]) -> Vec<Lint> {
+	let calls: HashMap<&str, Vec<&Spanned<String>>> = functions
+		.iter()
+		.map(|function| (function.prototype.name.as_str(), unconditional_calls(&function.body)))
+		.collect();
+
+	let mut lints = Vec::new();
+	for function in functions {
+		let name = function.prototype.name.as_str();
+		let recursive_call = calls[name].iter().find(|call| reaches(call, name, &calls, &mut HashSet::new()));
+		if let Some(call) = recursive_call {
+			lints.push(Lint::UnconditionalRecursion {
+				function: function.prototype.name.clone(),
+				call: (*call).clone(),
+			});
+		}
+	}
+	lints
+}
+
+/// Whether a call of the function `from` always leads to a call of the function `to`.
+fn reaches<'a>(
+	from: &'a str,
+	to: &str,
+	calls: &HashMap<&str, Vec<&'a Spanned<String>>>,
+	visited: &mut HashSet<&'a str>,
+) -> bool {
+	if from == to {
+		return true;
+	}
+	if !visited.insert(from) {
+		return false;
+	}
+	// Extern and runtime functions have no entry and call nothing
+	let Some(callees) = calls.get(from) else {
+		return false;
+	};
+	callees.iter().any(|callee| reaches(callee, to, calls, visited))
+}
+
+/// Collects the calls executed whenever the `instructions` are executed, i.e. the calls that are neither inside an
+/// if-else or loop nor after an instruction that may return.
+fn unconditional_calls(instructions: &[ast::Instruction]) -> Vec<&Spanned<String>> {
+	let mut calls = Vec::new();
+	for instruction in instructions {
+		match instruction {
+			ast::Instruction::Expression(expression) => expression_calls(expression, &mut calls),
+			ast::Instruction::Statement(ast::Statement::VariableDeclaration(declaration)) => {
+				expression_calls(&declaration.value, &mut calls)
+			},
+			ast::Instruction::Statement(ast::Statement::VariableAssignment(assignment)) => {
+				expression_calls(&assignment.value, &mut calls)
+			},
+			ast::Instruction::Statement(ast::Statement::FieldAssignment(assignment)) => {
+				expression_calls(&assignment.value, &mut calls)
+			},
+			ast::Instruction::Statement(ast::Statement::Return(value)) => {
+				if let Some(expression) = &value.value {
+					expression_calls(expression, &mut calls);
+				}
+				break;
+			},
+			// Only the condition is evaluated on every path
+			ast::Instruction::IfElse(if_else) => {
+				expression_calls(&if_else.condition, &mut calls);
+				if contains_return(&if_else.if_true) || contains_return(&if_else.if_false) {
+					break;
+				}
+			},
+			ast::Instruction::WhileLoop(while_loop) => {
+				expression_calls(&while_loop.condition, &mut calls);
+				if contains_return(&while_loop.body) {
+					break;
+				}
+			},
+			// Invalid code might return, so the following calls aren't known to be executed
+			ast::Instruction::Invalid(_) => break,
+		}
+	}
+	calls
+}
+
+/// Whether the `instructions` contain a `return`, also in nested blocks. Invalid code might contain one as well.
+fn contains_return(instructions: &[ast::Instruction]) -> bool {
+	instructions.iter().any(|instruction| match instruction {
+		ast::Instruction::Statement(ast::Statement::Return(_)) | ast::Instruction::Invalid(_) => true,
+		ast::Instruction::IfElse(if_else) => contains_return(&if_else.if_true) || contains_return(&if_else.if_false),
+		ast::Instruction::WhileLoop(while_loop) => contains_return(&while_loop.body),
+		ast::Instruction::Expression(_) | ast::Instruction::Statement(_) => false,
+	})
+}
+
+/// Collects all calls in the `expression`, which are all evaluated when the expression is evaluated.
+fn expression_calls<'a>(expression: &'a Expression, calls: &mut Vec<&'a Spanned<String>>) {
+	match expression {
+		Expression::BinaryExpression(binary_expression) => {
+			expression_calls(&binary_expression.lhs, calls);
+			expression_calls(&binary_expression.rhs, calls);
+		},
+		Expression::FieldAccess(field_access) => expression_calls(&field_access.base, calls),
+		Expression::FunctionCall(function_call) => {
+			for param in &function_call.params {
+				expression_calls(param, calls);
+			}
+			calls.push(&function_call.name);
+		},
+		// The fallback is only evaluated if the call fails
+		Expression::Try(try_expression) => expression_calls(&try_expression.value, calls),
+		Expression::Number(_)
+		| Expression::String(_)
+		| Expression::Variable(_)
+		| Expression::Null(_)
+		| Expression::Error(_)
+		| Expression::Invalid(_) => (),
+	}
+}
+
+/// Finds pointers to local variables of the `function` that are returned or stored in structs passed by reference, so
+/// that they dangle after the function returned. Pointers to locals are created by passing a local struct to a
+/// function returning a pointer, which may return the pointer to the struct.
+fn escaping_references(
+	function: &FunctionDefinition,
+	pointer_functions: &HashSet<&str>,
+	program: &TypedProgram,
+) -> Vec<Lint> {
+	let pointers = function.prototype.args.iter().filter(|arg| is_pointer(&arg.data_type)).map(|arg| arg.name.as_str());
+	let mut escapes = Escapes {
+		pointer_functions,
+		program,
+		pointers: pointers.collect(),
+		dangling: HashMap::new(),
+		lints: Vec::new(),
+	};
+	escapes.block(&function.body);
+	escapes.lints
+}
+
+/// State of [`escaping_references`] while walking through a function.
+struct Escapes<'a> {
+	/// Functions whose return type is a pointer.
+	pointer_functions: &'a HashSet<&'a str>,
+	program: &'a TypedProgram,
+	/// Arguments and variables of pointer type, which don't store a struct themselves.
+	pointers: HashSet<&'a str>,
+	/// Variables that contain a pointer to a local variable, which is the value.
+	dangling: HashMap<&'a str, &'a Spanned<String>>,
+	lints: Vec<Lint>,
+}
+
+impl<'a> Escapes<'a> {
+	fn block(&mut self, instructions: &'a [ast::Instruction]) {
+		for instruction in instructions {
+			match instruction {
+				ast::Instruction::Statement(ast::Statement::VariableDeclaration(declaration)) => {
+					if is_pointer(&declaration.data_type) {
+						self.pointers.insert(&declaration.name);
+					}
+					if let Some(local) = self.local(&declaration.value) {
+						self.dangling.insert(&declaration.name, local);
+					}
+				},
+				ast::Instruction::Statement(ast::Statement::VariableAssignment(assignment)) => {
+					if let Some(local) = self.local(&assignment.value) {
+						self.dangling.insert(&assignment.name, local);
+					}
+				},
+				ast::Instruction::Statement(ast::Statement::FieldAssignment(assignment)) => {
+					let Some(local) = self.local(&assignment.value) else {
+						continue;
+					};
+					let Some(root) = root_variable(&assignment.target.base) else {
+						continue;
+					};
+					// Structs behind pointers belong to the caller, unless the pointer is dangling itself
+					if self.pointers.contains(root.as_str()) && !self.dangling.contains_key(root.as_str()) {
+						let escape = assignment.value.source_position();
+						self.lints.push(Lint::EscapingReference { local: local.clone(), escape });
+					} else {
+						self.dangling.insert(root, local);
+					}
+				},
+				ast::Instruction::Statement(ast::Statement::Return(value)) => {
+					let Some(value) = &value.value else {
+						continue;
+					};
+					if let Some(local) = self.local(value) {
+						self.lints
+							.push(Lint::EscapingReference { local: local.clone(), escape: value.source_position() });
+					}
+				},
+				ast::Instruction::IfElse(if_else) => {
+					self.block(&if_else.if_true);
+					self.block(&if_else.if_false);
+				},
+				ast::Instruction::WhileLoop(while_loop) => self.block(&while_loop.body),
+				ast::Instruction::Expression(_) | ast::Instruction::Invalid(_) => (),
+			}
+		}
+	}
+
+	/// Returns the local variable the value of the `expression` may point to.
+	fn local(&self, expression: &'a Expression) -> Option<&'a Spanned<String>> {
+		// Only pointers and structs, whose fields may be pointers, can point to a variable
+		let data_type = self.program.type_of(expression).map(|type_| self.program.types.data_type(type_));
+		if data_type.is_some_and(|data_type| !is_pointer(&data_type) && !matches!(data_type, DataType::Struct(_))) {
+			return None;
+		}
+		match expression {
+			Expression::Variable(variable) => self.dangling.get(variable.as_str()).copied(),
+			Expression::FieldAccess(field_access) => self.local(&field_access.base),
+			Expression::Try(try_expression) => self.local(&try_expression.value),
+			Expression::FunctionCall(function_call) if self.pointer_functions.contains(function_call.name.as_str()) => {
+				function_call.params.iter().find_map(|param| {
+					let root =
+						root_variable(param).filter(|_| self.program.references.contains(&param.source_position()));
+					match root {
+						Some(root) if !self.pointers.contains(root.as_str()) => Some(root),
+						_ => self.local(param),
+					}
+				})
+			},
+			_ => None,
+		}
+	}
+}
+
+/// Returns the variable at the root of a chain of field accesses like `a.b.c`.
+fn root_variable(expression: &Expression) -> Option<&Spanned<String>> {
+	match expression {
+		Expression::Variable(variable) => Some(variable),
+		Expression::FieldAccess(field_access) => root_variable(&field_access.base),
+		_ => None,
+	}
+}
+
+/// Whether values of the `data_type` are pointers, which may be `null` or fail.
+fn is_pointer(data_type: &DataType) -> bool {
+	match data_type {
+		DataType::Pointer(_) => true,
+		DataType::Optional(inner) | DataType::Fallible(inner) => is_pointer(inner),
+		DataType::Basic(_) | DataType::Struct(_) | DataType::Void => false,
+	}
+}
+
+impl From<&Lint> for Diagnostic {
+	fn from(lint: &Lint) -> Self {
+		match lint {
+			Lint::UnconditionalRecursion { function, call } => Diagnostic::warning(
+				"UnconditionalRecursion",
+				format!("Function `{}` calls itself on every path, so it never returns", function.deref()),
+			)
+			.with_primary_label(call.position.clone(), "recursive call")
+			.with_secondary_label(function.position.clone(), "function defined here")
+			.with_help("end the recursion with an if-else"),
+			Lint::EscapingReference { local, escape } => Diagnostic::warning(
+				"EscapingReference",
+				format!("Pointer to local variable `{}` outlives the variable", local.deref()),
+			)
+			.with_primary_label(escape.clone(), "pointer escapes here")
+			.with_secondary_label(local.position.clone(), "pointer to this variable")
+			.with_note("local variables are freed when the function returns, so the pointer would dangle in C")
+			.with_help("let the caller pass the struct by `ptr` instead"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		dump::Dumper,
+		semantic_analyzer::{self, CheckConfig},
+		source::Source,
+	};
+
+	fn lints(source_code: &str) -> Vec<Lint> {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes =
+			crate::compile_source(source, &CheckConfig::default(), &Dumper::default(), &mut Vec::new()).unwrap();
+		let program = semantic_analyzer::analyze(ast_nodes.iter(), &CheckConfig::default()).unwrap();
+		lint(ast_nodes.iter(), &program)
+	}
+
+	/// Lints the source code and returns the names of the functions with unconditional recursion.
+	fn recursive_functions(source_code: &str) -> Vec<String> {
+		lints(source_code)
+			.into_iter()
+			.filter_map(|lint| match lint {
+				Lint::UnconditionalRecursion { function, .. } => Some(function.value),
+				Lint::EscapingReference { .. } => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_unconditional_recursion() {
+		assert_eq!(recursive_functions("def f(n: int): int {\n\treturn f(n) + 1\n}"), ["f"]);
+		assert_eq!(
+			recursive_functions(
+				"def ping(n: int): int {\n\tvar x: int = pong(n)\n\treturn x\n}\ndef pong(n: int): int {\n\treturn \
+				 ping(n)\n}"
+			),
+			["ping", "pong"]
+		);
+		let in_condition = "def f(n: int): int {\n\twhile f(n) < 1 {\n\t\tn = n + 1\n\t}\n\treturn n\n}";
+		assert_eq!(recursive_functions(in_condition), ["f"]);
+	}
+
+	#[test]
+	fn test_conditional_recursion() {
+		let fib = "def fib(n: int): int {\n\tif n < 2 {\n\t\treturn n\n\t}\n\treturn fib((n - 1))\n}";
+		assert!(recursive_functions(fib).is_empty());
+		let after_return = "def f(n: int): int {\n\treturn n\n\tf(n)\n}";
+		assert!(recursive_functions(after_return).is_empty());
+		let mutual = "def is_even(n: int): int {\n\tif n < 1 {\n\t\treturn 1\n\t}\n\treturn is_odd((n - 1))\n}\ndef \
+		              is_odd(n: int): int {\n\treturn is_even(n)\n}";
+		assert!(recursive_functions(mutual).is_empty());
+	}
+
+	/// Lints the source code and returns the local variables whose pointers escape, with the escaping code.
+	fn escaping_locals(source_code: &str) -> Vec<(String, String)> {
+		lints(source_code)
+			.into_iter()
+			.filter_map(|lint| match lint {
+				Lint::EscapingReference { local, escape } => Some((local.value, escape.code().to_owned())),
+				Lint::UnconditionalRecursion { .. } => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_escaping_references() {
+		let structs = "struct P { x: int }\nstruct H { p: ptr P }\ndef id(p: ptr P): ptr P {\n\treturn p\n}\n";
+		let returned = "def f(): ptr P {\n\tvar local: P = P()\n\treturn id(local)\n}";
+		assert_eq!(escaping_locals(&format!("{}{}", structs, returned)), [("local".to_owned(), "id".to_owned())]);
+		let through_variable = "def f(): ptr P {\n\tvar local: P = P()\n\tvar q: ptr P = id(local)\n\treturn q\n}";
+		assert_eq!(escaping_locals(&format!("{}{}", structs, through_variable)).len(), 1);
+		let stored = "def f(h: ptr H) {\n\tvar local: P = P()\n\th.p = id(local)\n}";
+		assert_eq!(escaping_locals(&format!("{}{}", structs, stored)), [("local".to_owned(), "id".to_owned())]);
+		let in_struct = "def f(): H {\n\tvar local: P = P()\n\tvar h: H = H()\n\th.p = id(local)\n\treturn h\n}";
+		assert_eq!(escaping_locals(&format!("{}{}", structs, in_struct)).len(), 1);
+	}
+
+	#[test]
+	fn test_non_escaping_references() {
+		let structs = "struct P { x: int }\nstruct H { p: ptr P }\ndef id(p: ptr P): ptr P {\n\treturn p\n}\n";
+		let callers =
+			"def f(p: ptr P): ptr P {\n\treturn id(p)\n}\ndef g(h: ptr H): int {\n\tvar local: P = P()\n\tvar \
+		               q: ptr P = id(local)\n\treturn q.x\n}\ndef x(p: ptr P): int {\n\treturn p.x\n}\ndef count(): int \
+		               {\n\tvar local: P = P()\n\treturn x(local)\n}";
+		assert_eq!(escaping_locals(&format!("{}{}", structs, callers)), []);
+	}
+}
@@ -1,11 +1,238 @@
 //! Creation of a [`SymbolTable`] and [type checking](TypeChecker).
+//!
+//! [`analyze`] runs both phases and is the entry point for analyzing a whole program. The resulting [`TypedProgram`]
+//! can be queried for everything the analysis found out about the program, e.g. the types of expressions. Editors
+//! can check just the function being edited with [`analyze_function_at`].
 
+mod check_config;
 mod error;
+mod lint;
+mod scope;
 mod symbol_table;
 mod type_check;
 mod variable;
 
+use std::{
+	collections::{HashMap, HashSet},
+	iter,
+};
+
+pub use check_config::CheckConfig;
 pub use error::Error;
-pub use symbol_table::SymbolTable;
+pub use lint::{lint, Lint};
+pub use scope::{Declaration, DeclarationId, Scope, ScopeId, ScopeTree};
+pub use symbol_table::{SymbolTable, Visibility};
 pub use type_check::TypeChecker;
 pub use variable::Variable;
+
+use crate::{
+	ast::{self, Expression},
+	diagnostic::Diagnostic,
+	source::{Position, SourcePositionRange},
+	types::{TypeId, TypeTable},
+};
+
+/// Program that passed the semantic analysis.
+#[derive(Debug, Clone)]
+pub struct TypedProgram {
+	/// Globally defined structs and functions of the program.
+	pub symbol_table: SymbolTable,
+	/// All types of the program, which the [`TypeId`]s refer to.
+	pub types: TypeTable,
+	/// Inferred types of the expressions, by their [position](Expression::source_position).
+	pub expression_types: HashMap<SourcePositionRange, TypeId>,
+	/// Structs that are passed by reference to `ptr` arguments instead of being copied, by their position.
+	pub references: HashSet<SourcePositionRange>,
+	/// Calls of a function by itself whose value the function returns right away, by their position. They can jump
+	/// to the start of the function instead of growing the stack.
+	pub tail_calls: HashSet<SourcePositionRange>,
+	/// Variables declared in the functions and where they are used.
+	pub scopes: ScopeTree,
+	/// Warnings of the [lints](lint), unless they are [denied](CheckConfig::deny_warnings).
+	pub diagnostics: Vec<Diagnostic>,
+}
+
+impl TypedProgram {
+	/// The inferred type of the `expression`, which has to be part of the program.
+	///
+	/// Functions without return value are called as [`TypeId::VOID`]. Expressions of [synthetic
+	/// positions](SourcePositionRange::synthetic) share the type of the last of them.
+	pub fn type_of(&self, expression: &Expression) -> Option<TypeId> {
+		self.expression_types.get(&expression.source_position()).copied()
+	}
+}
+
+/// Analyzes the program with a [global symbol scan](SymbolTable::global_symbol_scan) followed by
+/// [type checking](TypeChecker::type_check) and the [lints](lint), whose warnings are errors if denied by the
+/// `config`.
+pub fn analyze<'a>(
+	ast_nodes: impl Iterator<Item = &'a ast::Node> + Clone,
+	config: &CheckConfig,
+) -> Result<TypedProgram, Error> {
+	let Ok(symbol_table) = SymbolTable::global_symbol_scan(ast_nodes.clone());
+	let mut program = TypeChecker::type_check(symbol_table, ast_nodes.clone(), config)?;
+	let lints = lint(ast_nodes, &program);
+	if config.deny_warnings {
+		if let Some(lint) = lints.into_iter().next() {
+			return Err(Error::DeniedWarning { lint });
+		}
+		return Ok(program);
+	}
+	program.diagnostics = lints.iter().map(Diagnostic::from).collect();
+	Ok(program)
+}
+
+/// Type checks only the function containing the `position` in the file `source_name`, e.g. on every keystroke in an
+/// editor. The `symbol_table` of a previous [global symbol scan](SymbolTable::global_symbol_scan) is reused, which
+/// only has to be repeated when a prototype or struct changes.
+///
+/// The returned program only knows about the checked function, and the lints are not run, since they need the whole
+/// program. Returns [`None`] if the position is not in a function.
+pub fn analyze_function_at<'a>(
+	symbol_table: &SymbolTable,
+	ast_nodes: impl IntoIterator<Item = &'a ast::Node>,
+	source_name: &str,
+	position: Position,
+	config: &CheckConfig,
+) -> Option<Result<TypedProgram, Error>> {
+	let function = function_at(ast_nodes, source_name, position)?;
+	Some(TypeChecker::type_check(symbol_table.clone(), iter::once(function), config))
+}
+
+/// The function whose code, from its doc comment or annotations to the start of the next node, contains the
+/// `position` in the file `source_name`.
+fn function_at<'a>(
+	ast_nodes: impl IntoIterator<Item = &'a ast::Node>,
+	source_name: &str,
+	position: Position,
+) -> Option<&'a ast::Node> {
+	let start = |ast_node: &'a ast::Node| -> &'a SourcePositionRange {
+		match ast_node {
+			ast::Node::Function(function) => ast_node
+				.doc()
+				.or(function.annotations.first())
+				.map_or(&function.prototype.name.position, |start| &start.position),
+			ast::Node::FunctionPrototype(prototype) => &prototype.name.position,
+			ast::Node::Struct(struct_) => &struct_.name.position,
+			ast::Node::Comment(comment) => &comment.position,
+			ast::Node::Invalid(position) => position,
+		}
+	};
+	let before = |start: &SourcePositionRange| {
+		(start.position.start.line, start.position.start.column) <= (position.line, position.column)
+	};
+	ast_nodes
+		.into_iter()
+		.filter(|ast_node| start(ast_node).source.name == source_name)
+		.take_while(|ast_node| before(start(ast_node)))
+		.last()
+		.filter(|ast_node| matches!(ast_node, ast::Node::Function(_)))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{ast::Instruction, dump::Dumper, source::Source};
+
+	const SOURCE_CODE: &str = "struct Point { x: int, y: float }
+def f(n: int): float {
+	var p: Point = Point()
+	if n < 1 {
+		var half: float = (p.y / 2.0)
+		return half
+	}
+	return f((n - 1))
+}";
+
+	/// Parses and analyzes the source code.
+	fn analyze_source(source_code: &str) -> (Vec<ast::Node>, TypedProgram) {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let program = analyze(ast_nodes.iter(), &CheckConfig::default()).unwrap();
+		(ast_nodes, program)
+	}
+
+	#[test]
+	fn test_typed_program() {
+		let (ast_nodes, program) = analyze_source(SOURCE_CODE);
+
+		let ast::Node::Function(function) = &ast_nodes[1] else { panic!("expected function") };
+		let Instruction::IfElse(if_else) = &function.body[1] else { panic!("expected if-else") };
+		let Expression::BinaryExpression(condition) = &if_else.condition else { panic!("expected comparison") };
+		assert_eq!(program.type_of(&if_else.condition), Some(TypeId::INT));
+		assert_eq!(program.type_of(&condition.lhs), Some(TypeId::INT));
+		let type_of_code = |code: &str| {
+			program.expression_types.iter().find(|(position, _)| position.code() == code).map(|(_, type_)| *type_)
+		};
+		assert_eq!(type_of_code("p.y"), Some(TypeId::FLOAT));
+		assert_eq!(type_of_code("p.y / 2.0"), Some(TypeId::FLOAT));
+		let point = type_of_code("Point").unwrap();
+		assert_eq!(program.types.display(point).to_string(), "Point");
+		assert_eq!(program.types.fields(point).map(<[_]>::len), Some(2));
+
+		// The recursion without condition is a warning
+		assert!(program.diagnostics.is_empty());
+		assert_eq!(analyze_source("def f(): int {\n\treturn f()\n}").1.diagnostics.len(), 1);
+	}
+
+	#[test]
+	fn test_analyze_function_at() {
+		let source_code = format!("{}\n## Broken\ndef broken(): int {{\n\treturn f(undeclared)\n}}", SOURCE_CODE);
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let Ok(symbol_table) = SymbolTable::global_symbol_scan(ast_nodes.iter());
+		let analyze_at = |source_name: &str, line: u32| {
+			let position = Position { line, column: 2, offset: 0 };
+			analyze_function_at(&symbol_table, &ast_nodes, source_name, position, &CheckConfig::default())
+		};
+
+		// Only `f` is checked, so the error in `broken` goes unnoticed
+		let program = analyze_at("test.ftl", 5).unwrap().unwrap();
+		assert!(program.expression_types.keys().any(|position| position.code() == "p.y / 2.0"));
+		assert!(program.expression_types.keys().all(|position| position.position.start.line < 10));
+		assert_eq!(program.scopes.declarations().count(), 3);
+
+		assert!(matches!(analyze_at("test.ftl", 10), Some(Err(Error::UndeclaredVariable { .. }))));
+		assert!(analyze_at("test.ftl", 1).is_none());
+		assert!(analyze_at("other.ftl", 5).is_none());
+	}
+
+	#[test]
+	fn test_scopes() {
+		let (_, program) = analyze_source(SOURCE_CODE);
+		let scopes = &program.scopes;
+		let names = |id: ScopeId| {
+			scopes
+				.get(id)
+				.declarations
+				.iter()
+				.map(|declaration| declaration.variable.name.value.clone())
+				.collect::<Vec<_>>()
+		};
+
+		let function_scope = scopes.get(ScopeTree::ROOT).children[0];
+		let if_scope = scopes.get(function_scope).children[0];
+		assert_eq!(names(function_scope), ["n", "p"]);
+		assert_eq!(names(if_scope), ["half"]);
+		assert_eq!(scopes.ancestors(if_scope).collect::<Vec<_>>(), [if_scope, function_scope, ScopeTree::ROOT]);
+		assert_eq!(scopes.declarations().count(), 3);
+
+		// Liveness of `n` from the condition to the recursive call
+		let n = scopes.lookup(if_scope, "n").unwrap();
+		let lines = |declaration: &Declaration| {
+			declaration.uses.iter().map(|position| position.position.start.line).collect::<Vec<_>>()
+		};
+		assert_eq!(lines(n), [4, 8]);
+		assert_eq!(n.first_use().unwrap().position.start.line, 4);
+		assert_eq!(n.last_use().unwrap().position.start.line, 8);
+		assert_eq!(lines(scopes.lookup(if_scope, "p").unwrap()), [5]);
+		assert_eq!(lines(scopes.lookup(if_scope, "half").unwrap()), [6]);
+		assert!(scopes.lookup(function_scope, "half").is_none());
+
+		// Renaming finds the declaration from any use
+		let declaration = scopes.declaration_at(n.last_use().unwrap()).unwrap();
+		assert_eq!(declaration.variable.name.position, n.variable.name.position);
+	}
+}
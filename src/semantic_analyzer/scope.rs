@@ -0,0 +1,130 @@
+use std::{collections::HashSet, sync::Arc};
+
+use super::Variable;
+use crate::source::SourcePositionRange;
+
+/// Identifies a [`Scope`] in a [`ScopeTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScopeId(usize);
+
+/// Identifies a [`Declaration`] in a [`ScopeTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeclarationId {
+	/// The scope the variable is declared in.
+	pub scope: ScopeId,
+	index: usize,
+}
+
+/// Declaration of a variable and the places it is used.
+#[derive(Debug, Clone)]
+pub struct Declaration {
+	pub variable: Arc<Variable>,
+	/// Positions where the variable is read or assigned, in the order of the source code.
+	pub uses: Vec<SourcePositionRange>,
+}
+
+impl Declaration {
+	/// Where the variable is used first, i.e. from where on it is live.
+	pub fn first_use(&self) -> Option<&SourcePositionRange> {
+		self.uses.first()
+	}
+
+	/// Where the variable is used last, i.e. after which it is dead.
+	pub fn last_use(&self) -> Option<&SourcePositionRange> {
+		self.uses.last()
+	}
+}
+
+/// Variables declared in a block, e.g. a function body or a branch of an if-else.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+	/// The scope of the enclosing block, or [`None`] for the [root](ScopeTree::ROOT).
+	pub parent: Option<ScopeId>,
+	/// Variables declared directly in this block, in the order of declaration.
+	pub declarations: Vec<Declaration>,
+	/// Scopes of the nested blocks, in the order of appearance.
+	pub children: Vec<ScopeId>,
+}
+
+/// The scopes of a program, built during [type checking](super::TypeChecker).
+///
+/// The [root scope](Self::ROOT) contains no variables, but one child for each function. The arguments of a
+/// function are declared in its scope.
+#[derive(Debug, Clone)]
+pub struct ScopeTree {
+	scopes: Vec<Scope>,
+	/// The [uses](Declaration::uses) of all declarations, to find those already recorded without searching them.
+	uses: HashSet<(DeclarationId, SourcePositionRange)>,
+}
+
+impl Default for ScopeTree {
+	fn default() -> Self {
+		Self { scopes: vec![Scope::default()], uses: HashSet::new() }
+	}
+}
+
+impl ScopeTree {
+	/// The outermost scope, enclosing all functions.
+	pub const ROOT: ScopeId = ScopeId(0);
+
+	/// The scope with the `id`.
+	pub fn get(&self, id: ScopeId) -> &Scope {
+		&self.scopes[id.0]
+	}
+
+	/// All scopes, parents before their children.
+	pub fn iter(&self) -> impl Iterator<Item = (ScopeId, &Scope)> {
+		self.scopes.iter().enumerate().map(|(index, scope)| (ScopeId(index), scope))
+	}
+
+	/// The scope with the `id` followed by the scopes enclosing it, up to the root.
+	pub fn ancestors(&self, id: ScopeId) -> impl Iterator<Item = ScopeId> + '_ {
+		std::iter::successors(Some(id), |id| self.get(*id).parent)
+	}
+
+	/// The declaration with the `id`.
+	pub fn declaration(&self, id: DeclarationId) -> &Declaration {
+		&self.get(id.scope).declarations[id.index]
+	}
+
+	/// All declarations of all scopes.
+	pub fn declarations(&self) -> impl Iterator<Item = &Declaration> {
+		self.scopes.iter().flat_map(|scope| &scope.declarations)
+	}
+
+	/// The declaration of the variable `name` visible in the scope `id`, i.e. the innermost one.
+	pub fn lookup(&self, id: ScopeId, name: &str) -> Option<&Declaration> {
+		self.ancestors(id).find_map(|id| {
+			self.get(id).declarations.iter().rev().find(|declaration| declaration.variable.name.value == name)
+		})
+	}
+
+	/// The declaration of the variable that is declared or used at the `position`, e.g. for renaming it.
+	pub fn declaration_at(&self, position: &SourcePositionRange) -> Option<&Declaration> {
+		self.declarations()
+			.find(|declaration| &declaration.variable.name.position == position || declaration.uses.contains(position))
+	}
+
+	/// Adds an empty scope nested in the scope `parent`.
+	pub(super) fn add_scope(&mut self, parent: ScopeId) -> ScopeId {
+		let id = ScopeId(self.scopes.len());
+		self.scopes.push(Scope { parent: Some(parent), ..Scope::default() });
+		self.scopes[parent.0].children.push(id);
+		id
+	}
+
+	/// Declares the `variable` in the scope `id`.
+	pub(super) fn declare(&mut self, id: ScopeId, variable: Arc<Variable>) -> DeclarationId {
+		let declarations = &mut self.scopes[id.0].declarations;
+		declarations.push(Declaration { variable, uses: Vec::new() });
+		DeclarationId { scope: id, index: declarations.len() - 1 }
+	}
+
+	/// Records that the variable of the declaration `id` is used at the `position`, unless that use was already
+	/// recorded, since some expressions are checked more than once.
+	pub(super) fn add_use(&mut self, id: DeclarationId, position: SourcePositionRange) {
+		if self.uses.insert((id, position.clone())) {
+			self.scopes[id.scope.0].declarations[id.index].uses.push(position);
+		}
+	}
+}
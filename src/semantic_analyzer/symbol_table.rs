@@ -3,8 +3,37 @@ use std::{collections::HashMap, convert::Infallible, ops::Deref};
 use crate::{
 	ast,
 	ast::{FunctionPrototype, Struct},
+	runtime,
+	source::SourcePositionRange,
 };
 
+/// Where a function or struct can be used. Each source file is a module of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Visibility {
+	/// In every module: marked with `export`, an `extern` function, or part of the runtime.
+	Public,
+	/// Only in the module of the source file with this name.
+	Private { module: String },
+}
+
+impl Visibility {
+	/// Visibility of a function or struct defined at `position`, which is public if it is `exported`.
+	fn of(exported: bool, position: &SourcePositionRange) -> Self {
+		match exported {
+			true => Visibility::Public,
+			false => Visibility::Private { module: position.source.name.clone() },
+		}
+	}
+
+	/// Whether a function or struct of this visibility can be used at `position`.
+	pub fn allows(&self, position: &SourcePositionRange) -> bool {
+		match self {
+			Visibility::Public => true,
+			Visibility::Private { module } => position.is_synthetic() || *module == position.source.name,
+		}
+	}
+}
+
 /// Contains all globally declared [functions](Self::functions) and [structs](Self::structs).
 #[derive(Debug, Default, Clone)]
 pub struct SymbolTable {
@@ -12,38 +41,64 @@ pub struct SymbolTable {
 	pub functions: HashMap<String, FunctionPrototype>,
 	/// All declared structs in the program, as discovered by the [global symbol scan](Self::global_symbol_scan).
 	pub structs: HashMap<String, Struct>,
+	/// Visibility of the [functions](Self::functions).
+	pub function_visibility: HashMap<String, Visibility>,
+	/// Visibility of the [structs](Self::structs).
+	pub struct_visibility: HashMap<String, Visibility>,
 }
 
 impl SymbolTable {
 	/// Generates a [`SymbolTable`] by scanning the program for global symbols like [struct](crate::ast::struct_) and [function definitions](crate::ast::FunctionDefinition).
+	///
+	/// The functions and structs of the [runtime](crate::runtime) are always present, but may be shadowed by the program.
 	#[tracing::instrument(skip_all)]
 	pub fn global_symbol_scan<'a>(ast_nodes: impl Iterator<Item = &'a ast::Node>) -> Result<Self, Infallible> {
 		let mut symbol_table = SymbolTable::default();
+		for runtime_node in runtime::declarations() {
+			symbol_table.ast_node(&runtime_node)?;
+		}
+		// The structs of the runtime are used by every module
+		for visibility in symbol_table.struct_visibility.values_mut() {
+			*visibility = Visibility::Public;
+		}
 		for ast_node in ast_nodes {
 			symbol_table.ast_node(ast_node)?;
 		}
 		Ok(symbol_table)
 	}
 
+	/// Adds the function or struct of the `ast_node`, replacing a previous definition with the same name.
+	///
+	/// This allows redefinitions in the [REPL](crate::repl).
+	pub fn replace(&mut self, ast_node: &ast::Node) {
+		let Ok(()) = self.ast_node(ast_node);
+	}
+
 	/// Scans one AST node for global symbols, i.e. functions and structs.
 	fn ast_node(&mut self, node: &ast::Node) -> Result<(), Infallible> {
 		match node {
-			ast::Node::Function(function) => self.function(&function.prototype),
+			ast::Node::Function(function) => {
+				let prototype = &function.prototype;
+				self.function(prototype, Visibility::of(prototype.exported, &prototype.name.position))
+			},
 			ast::Node::Struct(struct_) => self.struct_(struct_),
-			ast::Node::FunctionPrototype(function_prototype) => self.function(function_prototype),
-			_ => todo!(),
+			ast::Node::FunctionPrototype(function_prototype) => self.function(function_prototype, Visibility::Public),
+			ast::Node::Comment(_) | ast::Node::Invalid(_) => Ok(()),
 		}
 	}
 
 	/// Adds a function to the [functions symbol table](Self::functions).
-	fn function(&mut self, function_prototype: &FunctionPrototype) -> Result<(), Infallible> {
+	fn function(&mut self, function_prototype: &FunctionPrototype, visibility: Visibility) -> Result<(), Infallible> {
 		self.functions.insert(function_prototype.name.deref().clone(), function_prototype.clone());
+		self.function_visibility.insert(function_prototype.name.deref().clone(), visibility);
 		Ok(())
 	}
 
 	/// Adds a struct to the [structs symbol table](Self::structs).
 	fn struct_(&mut self, struct_: &Struct) -> Result<(), Infallible> {
 		self.structs.insert(struct_.name.deref().clone(), struct_.clone());
+		self.struct_visibility
+			.insert(struct_.name.deref().clone(), Visibility::of(struct_.exported, &struct_.name.position));
 		Ok(())
 	}
 }
@@ -1,68 +1,276 @@
 use std::{
+	cell::RefCell,
 	collections::{HashMap, HashSet},
-	iter,
+	iter, mem,
 	ops::Deref,
 	sync::Arc,
 };
 
-use super::{Error, SymbolTable, Variable};
+use super::{CheckConfig, DeclarationId, Error, ScopeId, ScopeTree, SymbolTable, TypedProgram, Variable, Visibility};
 use crate::{
 	ast::{
 		self,
-		expression::{BinaryExpression, FunctionCall, Number, NumberKind},
-		statement::{BasicDataType, DataType},
+		expression::{BinaryExpression, BinaryOperator, FieldAccess, FunctionCall, Number, NumberKind, TryExpression},
+		statement::DataType,
 		Expression, FunctionDefinition,
 	},
-	source::PositionContainer,
+	format_string,
+	intrinsic::Intrinsic,
+	source::{SourcePositionRange, Spanned},
+	types::{Type, TypeId, TypeTable},
 };
 
-/// Stores all variables declared in this call stack frame.
-type CallStackFrame = HashSet<Arc<Variable>>;
-
 /// Verifies that all types in the program match the expected types (e.g. in function calls and expressions) and that variables are declared before usage.
 #[derive(Debug, Clone)]
 pub struct TypeChecker {
 	/// Globally defined [structs](SymbolTable::structs) and [functions](SymbolTable::functions).
 	symbol_table: SymbolTable,
 	/// Currently declared in-scope variables.
-	pub variables: HashMap<String, Arc<Variable>>,
-	/// List of stack frames, each containing the variables declared in that scope.
-	pub call_stack: Vec<CallStackFrame>,
+	variables: HashMap<String, DeclarationId>,
+	/// Scopes of the blocks checked so far, with the declared variables and their uses.
+	scopes: RefCell<ScopeTree>,
+	/// Scope of the block currently being checked.
+	scope: ScopeId,
+	/// The types of the program, interned as they are encountered.
+	types: RefCell<TypeTable>,
+	/// Name of the function currently being checked.
+	function: String,
+	/// Return type of the function currently being checked.
+	return_type: TypeId,
+	/// Optional checks to perform.
+	config: CheckConfig,
+	/// Inferred types of the expressions checked so far, by their position.
+	expression_types: RefCell<HashMap<SourcePositionRange, TypeId>>,
+	/// Structs passed by reference to `ptr` arguments, by their position.
+	references: RefCell<HashSet<SourcePositionRange>>,
+	/// Self-recursive calls in tail position, by their position.
+	tail_calls: HashSet<SourcePositionRange>,
+	/// Variables of optional types that are known not to be `null`, with their inner type.
+	narrowed: HashMap<DeclarationId, TypeId>,
 }
 
 impl TypeChecker {
-	/// Checks that all types in statements and expressions match.
+	/// Checks that all types in statements and expressions match and returns the types and scopes found on the way.
+	///
+	/// The [diagnostics](TypedProgram::diagnostics) of the returned program are empty, since they are up to the lints.
 	#[tracing::instrument(skip_all)]
 	pub fn type_check<'a>(
 		symbol_table: SymbolTable,
 		ast_nodes: impl Iterator<Item = &'a ast::Node>,
-	) -> Result<(), Error> {
-		let mut type_check = Self { symbol_table, variables: HashMap::new(), call_stack: Vec::new() };
-
-		type_check.call_stack.push(CallStackFrame::new());
+		config: &CheckConfig,
+	) -> Result<TypedProgram, Error> {
+		let types = RefCell::new(TypeTable::new(&symbol_table));
+		let mut type_check = Self {
+			symbol_table,
+			variables: HashMap::new(),
+			scopes: RefCell::default(),
+			scope: ScopeTree::ROOT,
+			types,
+			function: String::new(),
+			return_type: TypeId::VOID,
+			config: *config,
+			expression_types: RefCell::default(),
+			references: RefCell::default(),
+			tail_calls: HashSet::new(),
+			narrowed: HashMap::new(),
+		};
 
+		let ast_nodes: Vec<_> = ast_nodes.collect();
+		Self::check_unique_definitions(&ast_nodes)?;
 		for ast_node in ast_nodes {
 			type_check.ast_node(ast_node)?;
 		}
-		Ok(())
+		Ok(TypedProgram {
+			symbol_table: type_check.symbol_table,
+			types: type_check.types.into_inner(),
+			expression_types: type_check.expression_types.into_inner(),
+			references: type_check.references.into_inner(),
+			tail_calls: type_check.tail_calls,
+			scopes: type_check.scopes.into_inner(),
+			diagnostics: Vec::new(),
+		})
 	}
 
 	/// Type checks an AST node by calling the appropriate method for the node type.
 	fn ast_node(&mut self, node: &ast::Node) -> Result<(), Error> {
 		match node {
-			ast::Node::Function(function) => self.function(function),
-			ast::Node::Struct(struct_) => Ok(()),
-			ast::Node::FunctionPrototype(_) => Ok(()),
-			_ => todo!(),
+			ast::Node::Function(function) => {
+				Self::check_not_intrinsic(&function.prototype.name)?;
+				Self::check_annotations(function)?;
+				self.prototype_data_types(&function.prototype)?;
+				self.function(function)
+			},
+			ast::Node::Struct(struct_) => {
+				struct_.fields.iter().try_for_each(|field| self.field(field))?;
+				self.check_not_recursive(struct_)
+			},
+			ast::Node::FunctionPrototype(prototype) => {
+				Self::check_not_intrinsic(&prototype.name)?;
+				self.prototype_data_types(prototype)
+			},
+			ast::Node::Comment(_) | ast::Node::Invalid(_) => Ok(()),
+		}
+	}
+
+	/// Checks that the type of the field exists and that its default value is a constant of that type.
+	fn field(&self, field: &ast::struct_::Field) -> Result<(), Error> {
+		self.resolve_data_type(&field.data_type)?;
+		let Some(default) = &field.default else {
+			return Ok(());
+		};
+		if let Some(function_call) = Self::first_function_call(default) {
+			return Err(Error::NonConstantDefault { field: field.name.clone(), call: function_call.name.clone() });
+		}
+		// Struct definitions are checked outside of functions, so variables in the default value are undeclared
+		self.check_expression(default, self.intern(&field.data_type), default.source_position()).map(|_type| ())
+	}
+
+	/// Checks that the struct does not contain itself, directly or through other structs. Pointers to itself are fine.
+	fn check_not_recursive(&self, struct_: &ast::Struct) -> Result<(), Error> {
+		for field in &struct_.fields {
+			if self.contains_struct(&field.data_type, &struct_.name, &mut HashSet::new()) {
+				return Err(Error::RecursiveStruct { struct_name: struct_.name.clone(), field: field.name.clone() });
+			}
+		}
+		Ok(())
+	}
+
+	/// Whether a value of the `data_type` contains the struct `name` without indirection through a pointer.
+	fn contains_struct<'a>(&'a self, data_type: &'a DataType, name: &str, visited: &mut HashSet<&'a str>) -> bool {
+		let DataType::Struct(struct_name) = data_type else {
+			return false;
+		};
+		if struct_name == name {
+			return true;
+		}
+		if !visited.insert(struct_name) {
+			return false;
+		}
+		let Some(struct_) = self.symbol_table.structs.get(struct_name) else {
+			return false;
+		};
+		struct_.fields.iter().any(|field| self.contains_struct(&field.data_type, name, visited))
+	}
+
+	/// Finds the first function call in the `expression`, if any.
+	fn first_function_call(expression: &Expression) -> Option<&FunctionCall> {
+		match expression {
+			Expression::BinaryExpression(binary_expression) => Self::first_function_call(&binary_expression.lhs)
+				.or_else(|| Self::first_function_call(&binary_expression.rhs)),
+			Expression::FieldAccess(field_access) => Self::first_function_call(&field_access.base),
+			Expression::FunctionCall(function_call) => Some(function_call),
+			Expression::Try(try_expression) => Self::first_function_call(&try_expression.value)
+				.or_else(|| Self::first_function_call(&try_expression.fallback)),
+			Expression::Number(_)
+			| Expression::String(_)
+			| Expression::Variable(_)
+			| Expression::Null(_)
+			| Expression::Error(_)
+			| Expression::Invalid(_) => None,
+		}
+	}
+
+	/// Checks that the types of the arguments and the return type of the function exist. Only the return type may be
+	/// fallible, except for `main`, whose return value is the exit code.
+	fn prototype_data_types(&self, prototype: &ast::FunctionPrototype) -> Result<(), Error> {
+		for arg in &prototype.args {
+			self.resolve_data_type(&arg.data_type)?;
+		}
+		prototype.return_type.iter().try_for_each(|return_type| match &return_type.value {
+			DataType::Fallible(inner) if prototype.name.as_str() != "main" => self.resolve_data_type(inner),
+			_ => self.resolve_data_type(return_type),
+		})
+	}
+
+	/// Checks that all structs used in the data type are defined in the [symbol table](Self::symbol_table).
+	fn resolve_data_type(&self, data_type: &Spanned<DataType>) -> Result<(), Error> {
+		match &data_type.value {
+			DataType::Struct(name) => match self.symbol_table.structs.get(name) {
+				Some(struct_) => {
+					self.check_visibility(&self.symbol_table.struct_visibility, &struct_.name, &data_type.position)
+				},
+				None => {
+					let mut known_structs: Vec<String> = self.symbol_table.structs.keys().cloned().collect();
+					known_structs.sort();
+					Err(Error::UnknownStruct { name: data_type.as_ref().map(|_| name.clone()), known_structs })
+				},
+			},
+			DataType::Pointer(pointee) => self.resolve_data_type(pointee),
+			// `null` is represented by a sentinel value, which only exists for these types
+			DataType::Optional(inner) => match &inner.value {
+				DataType::Basic(_) | DataType::Pointer(_) => self.resolve_data_type(inner),
+				DataType::Struct(_) | DataType::Optional(_) | DataType::Fallible(_) | DataType::Void => {
+					Err(Error::UnsupportedOptional { data_type: data_type.clone() })
+				},
+			},
+			DataType::Fallible(_) => Err(Error::MisplacedFallible { data_type: data_type.clone() }),
+			DataType::Basic(_) | DataType::Void => Ok(()),
+		}
+	}
+
+	/// Checks that no function or struct is defined in more than one file. The [symbol table](SymbolTable) knows each
+	/// name only once, so private functions and structs of different files can't share a name either.
+	fn check_unique_definitions(ast_nodes: &[&ast::Node]) -> Result<(), Error> {
+		// Functions and structs have separate namespaces
+		let mut definitions: HashMap<(bool, &str), &SourcePositionRange> = HashMap::new();
+		for ast_node in ast_nodes {
+			let (is_struct, name) = match ast_node {
+				ast::Node::Function(function) => (false, &function.prototype.name),
+				ast::Node::Struct(struct_) => (true, &struct_.name),
+				ast::Node::FunctionPrototype(_) | ast::Node::Comment(_) | ast::Node::Invalid(_) => continue,
+			};
+			match definitions.insert((is_struct, name), &name.position) {
+				Some(previous) if previous.source.name != name.position.source.name => {
+					return Err(Error::DuplicateDefinition { name: name.clone(), previous: previous.clone() })
+				},
+				_ => (),
+			}
+		}
+		Ok(())
+	}
+
+	/// Checks that a function definition or declaration does not use the reserved name of an [`Intrinsic`].
+	fn check_not_intrinsic(name: &Spanned<String>) -> Result<(), Error> {
+		match Intrinsic::from_name(name) {
+			Some(_) => Err(Error::IntrinsicRedefinition { name: name.clone() }),
+			None => Ok(()),
+		}
+	}
+
+	/// Checks that the function only has known annotations and fulfills their requirements.
+	fn check_annotations(function: &FunctionDefinition) -> Result<(), Error> {
+		for annotation in &function.annotations {
+			match annotation.as_str() {
+				"bench" if !function.prototype.args.is_empty() => {
+					return Err(Error::InvalidAnnotation {
+						annotation: annotation.clone(),
+						reason: "benchmarked functions can't have arguments",
+					})
+				},
+				"bench" | "no_mangle" => (),
+				_ => {
+					return Err(Error::InvalidAnnotation {
+						annotation: annotation.clone(),
+						reason: "unknown annotation",
+					})
+				},
+			}
 		}
+		Ok(())
 	}
 
 	/// Type checks each instruction in the given function.
 	#[tracing::instrument(skip_all, fields(name = function.prototype.name.deref()))]
 	fn function(&mut self, function: &FunctionDefinition) -> Result<(), Error> {
+		self.function = function.prototype.name.value.clone();
+		self.return_type =
+			function.prototype.return_type.as_ref().map_or(TypeId::VOID, |return_type| self.intern(return_type));
+
 		// Add the function's arguments to the symbol table
-		self.call_stack.push(CallStackFrame::new());
+		self.narrowed.clear();
+		self.enter_scope();
 		for arg in &function.prototype.args {
+			self.check_shadowing(&arg.name)?;
 			self.add_variable(Arc::new(Variable { name: arg.name.clone(), type_: arg.data_type.value.clone() }))?;
 		}
 
@@ -71,7 +279,7 @@ impl TypeChecker {
 			self.instruction(instruction)?;
 		}
 
-		self.drop_call_stack_frame();
+		self.leave_scope();
 		Ok(())
 	}
 
@@ -82,20 +290,36 @@ impl TypeChecker {
 			ast::Instruction::Statement(statement) => self.statement(statement),
 			ast::Instruction::IfElse(if_else) => self.if_else(if_else),
 			ast::Instruction::WhileLoop(while_loop) => self.while_loop(while_loop),
+			// The parser already reported why the code is invalid
+			ast::Instruction::Invalid(_) => Ok(()),
 		}
 	}
 
 	/// Type checks an expression by calling the appropriate method for the expression type.
 	fn expression(&mut self, expression: &ast::Expression) -> Result<(), Error> {
 		match expression {
-			ast::Expression::BinaryExpression(binary_expression) => {
-				self.infer_binary_expression_type(binary_expression).map(|_expression_type| ())
-			},
 			ast::Expression::FunctionCall(function_call) => {
-				self.infer_function_call_return_type(function_call).map(|return_type| ())
+				// Unlike in other expressions, functions without return value may be called here
+				let return_type = self.check_function_call(function_call)?;
+				self.check_failure_handled(function_call, return_type)?;
+				self.record_type(expression, return_type);
+				Ok(())
 			},
-			ast::Expression::Number(_) => Ok(()),
-			ast::Expression::Variable(_) => Ok(()),
+			ast::Expression::Variable(variable) => {
+				// Variables are not required to be declared here
+				if let Ok(variable_type) = self.infer_variable_type(variable) {
+					self.record_type(expression, variable_type);
+				}
+				Ok(())
+			},
+			ast::Expression::BinaryExpression(_)
+			| ast::Expression::FieldAccess(_)
+			| ast::Expression::Number(_)
+			| ast::Expression::String(_)
+			| ast::Expression::Null(_)
+			| ast::Expression::Try(_)
+			| ast::Expression::Error(_) => self.infer_expression_type(expression).map(|_expression_type| ()),
+			ast::Expression::Invalid(_) => Ok(()),
 		}
 	}
 
@@ -106,7 +330,8 @@ impl TypeChecker {
 				self.variable_declaration(variable_declaration)
 			},
 			ast::statement::Statement::VariableAssignment(assignment) => self.variable_assignment(assignment),
-			ast::Statement::Return(expression) => self.return_(expression),
+			ast::Statement::FieldAssignment(assignment) => self.field_assignment(assignment),
+			ast::Statement::Return(value) => self.return_(value),
 		}
 	}
 
@@ -115,6 +340,8 @@ impl TypeChecker {
 		&mut self,
 		variable_declaration: &ast::statement::VariableDeclaration,
 	) -> Result<(), Error> {
+		self.resolve_data_type(&variable_declaration.data_type)?;
+		self.check_shadowing(&variable_declaration.name)?;
 		let variable = Arc::new(Variable {
 			name: variable_declaration.name.clone(),
 			type_: variable_declaration.data_type.deref().clone(),
@@ -125,160 +352,539 @@ impl TypeChecker {
 			"variable declaration"
 		);
 
-		let inferred_type = self.infer_expression_type(&variable_declaration.value)?;
-		if inferred_type != variable.type_ {
-			return Err(Error::TypeMismatch {
-				expected: variable.type_.clone(),
-				position: variable.name.position.clone(),
-				actual: inferred_type,
-			});
-		}
+		let declared_type = self.intern(&variable.type_);
+		self.check_expression(&variable_declaration.value, declared_type, variable.name.position.clone())?;
 
 		// If there is a previous declaration of this variable, there is a name conflict.
-		let previous_declaration = self.variables.get(&variable.name.value);
-		if let Some(previous_declaration) = previous_declaration {
-			return Err(Error::Redeclaration {
-				previous_declaration: Arc::clone(previous_declaration),
-				new_declaration: Arc::clone(&variable),
-			});
+		if let Some((_, previous_declaration)) = self.variable(&variable.name.value) {
+			return Err(Error::Redeclaration { previous_declaration, new_declaration: Arc::clone(&variable) });
 		}
 
-		self.add_variable(variable)?;
-		// Type check the expression itself
-		// TODO: Should already be covered by the type inference of the expression, i.e. by calling `self.infer_expression_type`
-		self.expression(&variable_declaration.value)?;
+		self.add_variable(variable)
+	}
+
+	/// Checks that the variable does not hide a function, if forbidden by the [config](CheckConfig::forbid_shadowing).
+	fn check_shadowing(&self, name: &Spanned<String>) -> Result<(), Error> {
+		let is_function =
+			self.symbol_table.functions.contains_key(name.as_str()) || Intrinsic::from_name(name).is_some();
+		if self.config.forbid_shadowing && is_function {
+			return Err(Error::Shadowing { name: name.clone() });
+		}
 		Ok(())
 	}
 
-	/// Adds a variable to [`Self::variables`] and [`Self::call_stack`].
+	/// Checks that the condition is a comparison or connects conditions with `and` or `or`, if required by the
+	/// [config](CheckConfig::require_bool_conditions). Like in C, a condition can't be a struct value.
+	fn condition(&mut self, condition: &Expression) -> Result<(), Error> {
+		let is_comparison = matches!(
+			condition,
+			Expression::BinaryExpression(binary_expression)
+				if binary_expression.operator.is_comparison() || binary_expression.operator.is_logical()
+		) || matches!(condition, Expression::Invalid(_));
+		if self.config.require_bool_conditions && !is_comparison {
+			return Err(Error::NonBoolCondition { position: condition.source_position() });
+		}
+		self.expression(condition)?;
+		let type_ = self.expression_types.borrow().get(&condition.source_position()).copied();
+		match type_ {
+			Some(type_) if matches!(self.types.borrow().get(type_), Type::Struct(_) | Type::Void) => {
+				let data_type = self.types.borrow().data_type(type_);
+				Err(Error::NonScalarCondition { position: condition.source_position(), data_type })
+			},
+			_ => Ok(()),
+		}
+	}
+
+	/// Declares a variable in the current scope and adds it to [`Self::variables`].
 	fn add_variable(&mut self, var: Arc<Variable>) -> Result<(), Error> {
-		self.variables.insert(var.name.value.clone(), Arc::clone(&var));
-		self.call_stack.last_mut().unwrap().insert(var);
+		let name = var.name.value.clone();
+		let declaration = self.scopes.get_mut().declare(self.scope, var);
+		self.variables.insert(name, declaration);
 		Ok(())
 	}
 
-	/// Removes one frame from the call stack and deletes all of its variables from the symbol table ([`Self::variables`]).
-	fn drop_call_stack_frame(&mut self) {
-		let frame = self.call_stack.pop().unwrap();
-		for variable in frame {
-			self.variables.remove(&variable.name.value);
+	/// Looks up the declaration of the in-scope variable `name`.
+	fn variable(&self, name: &str) -> Option<(DeclarationId, Arc<Variable>)> {
+		let declaration = *self.variables.get(name)?;
+		Some((declaration, Arc::clone(&self.scopes.borrow().declaration(declaration).variable)))
+	}
+
+	/// Enters the scope of a block that is about to be checked.
+	fn enter_scope(&mut self) {
+		self.scope = self.scopes.get_mut().add_scope(self.scope);
+	}
+
+	/// Leaves the scope of the current block and deletes all of its variables from [`Self::variables`].
+	fn leave_scope(&mut self) {
+		let scopes = self.scopes.get_mut();
+		for declaration in &scopes.get(self.scope).declarations {
+			self.variables.remove(&declaration.variable.name.value);
 		}
+		self.scope = scopes.get(self.scope).parent.expect("the root scope is never left");
+	}
+
+	/// Remembers the type of the `expression` for the [`TypedProgram`].
+	fn record_type(&self, expression: &Expression, type_: TypeId) {
+		self.expression_types.borrow_mut().insert(expression.source_position(), type_);
+	}
+
+	/// Checks that the `expression` can be used where the type `expected` is expected, e.g. as the value of a
+	/// variable, and returns its type. Type mismatches are reported at the `position`.
+	///
+	/// Unlike other expressions, `null` has no type of its own, but takes the expected type, which has to be optional.
+	/// Invalid code takes the expected type as well, so that it causes no further errors.
+	fn check_expression(
+		&self,
+		expression: &Expression,
+		expected: TypeId,
+		position: SourcePositionRange,
+	) -> Result<TypeId, Error> {
+		match expression {
+			Expression::Null(null_position) => {
+				self.check_null(null_position, expected)?;
+				return Ok(expected);
+			},
+			Expression::Invalid(_) => return Ok(expected),
+			_ => (),
+		}
+		let actual = self.infer_expression_type(expression)?;
+		self.check_subtype(actual, expected, position)?;
+		Ok(actual)
+	}
+
+	/// Checks that `null` at the `position` can be a value of the `expected` type and records that type for it.
+	fn check_null(&self, position: &SourcePositionRange, expected: TypeId) -> Result<(), Error> {
+		let types = self.types.borrow();
+		if types.optional_inner(expected).is_none() {
+			return Err(Error::UnexpectedNull {
+				position: position.clone(),
+				expected: Some(types.data_type(expected)),
+			});
+		}
+		self.expression_types.borrow_mut().insert(position.clone(), expected);
+		Ok(())
+	}
+
+	/// Interns the `data_type` in the [type table](Self::types).
+	fn intern(&self, data_type: &DataType) -> TypeId {
+		self.types.borrow_mut().intern(data_type)
+	}
+
+	/// Checks that a value of the type `actual` at the `position` can be used where the type `expected` is expected.
+	fn check_subtype(&self, actual: TypeId, expected: TypeId, position: SourcePositionRange) -> Result<(), Error> {
+		let types = self.types.borrow();
+		if types.is_subtype(actual, expected) {
+			return Ok(());
+		}
+		Err(Error::TypeMismatch { expected: types.data_type(expected), position, actual: types.data_type(actual) })
 	}
 
 	/// Checks that the type of the expression matches that of the variable.
 	fn variable_assignment(&mut self, variable_assignment: &ast::statement::VariableAssignment) -> Result<(), Error> {
-		// Infer the type of the expression on the right-hand side of the assignment
-		let expression_type = self.infer_expression_type(&variable_assignment.value)?;
-		let var = Arc::new(Variable { name: variable_assignment.name.clone(), type_: expression_type.clone() });
-		tracing::debug!(var = var.to_string(), position = var.name.position.to_string(), "variable assignment");
+		let name = &variable_assignment.name;
+		tracing::debug!(var = name.value, position = name.position.to_string(), "variable assignment");
 
 		// Look up the type of the variable in the symbol table
-		let variable_type =
-			self.variables.get(&var.name.value).ok_or(Error::UndeclaredVariable { name: var.name.clone() })?;
-
-		if expression_type != variable_type.type_ {
-			// Cannot assign an expression to a variable of different type
-			return Err(Error::TypeMismatch {
-				expected: variable_type.type_.clone(),
-				position: variable_assignment.name.position.clone(),
-				actual: expression_type.clone(),
-			});
-		}
+		let (declaration, variable) =
+			self.variable(&name.value).ok_or(Error::UndeclaredVariable { name: name.clone() })?;
+		self.scopes.get_mut().add_use(declaration, name.position.clone());
 
-		self.add_variable(var)?;
-		self.expression(&variable_assignment.value)?;
+		// Cannot assign an expression to a variable of different type
+		let declared_type = self.intern(&variable.type_);
+		let expression_type =
+			self.check_expression(&variable_assignment.value, declared_type, name.position.clone())?;
+
+		// A variable known not to be `null` may be assigned `null` or another optional value
+		if let Some(inner) = self.narrowed.get(&declaration) {
+			if !self.types.borrow().is_subtype(expression_type, *inner) {
+				self.narrowed.remove(&declaration);
+			}
+		}
+		// Assignments don't declare a new variable, so the variable stays in the scope it was declared in
 		Ok(())
 	}
 
+	/// Checks that the type of the expression matches that of the field.
+	fn field_assignment(&mut self, field_assignment: &ast::statement::FieldAssignment) -> Result<(), Error> {
+		let field_type = self.infer_field_access_type(&field_assignment.target)?;
+		let position = field_assignment.target.field.position.clone();
+		self.check_expression(&field_assignment.value, field_type, position).map(|_type| ())
+	}
+
 	/// Checks that the return type of the function matches the type of the return expression.
-	fn return_(&mut self, expression: &Expression) -> Result<(), Error> {
-		let return_type = self.infer_expression_type(expression)?;
-		// TODO: Check that the return type matches the function's return type
-		tracing::warn!("TODO: Check that the return type {:?} matches the function's return type", return_type);
-		Ok(())
+	///
+	/// Functions that can fail return values of the type before `or error`, or `error`.
+	fn return_(&mut self, value: &Spanned<Option<Expression>>) -> Result<(), Error> {
+		let fallible_inner = self.types.borrow().fallible_inner(self.return_type);
+		match &value.value {
+			Some(Expression::Error(position)) => self.check_error(position),
+			Some(expression) => {
+				let expected = fallible_inner.unwrap_or(self.return_type);
+				self.check_expression(expression, expected, expression.source_position())?;
+				if fallible_inner.is_none() {
+					self.record_tail_call(expression);
+				}
+				Ok(())
+			},
+			None => self.check_subtype(TypeId::VOID, self.return_type, value.position.clone()),
+		}
+	}
+
+	/// Records the returned `expression` if it is a call of the function currently being checked, which can therefore
+	/// reuse the stack frame of the function.
+	///
+	/// Calls passing structs by reference are excluded, since the references may point to variables of the frame.
+	fn record_tail_call(&mut self, expression: &Expression) {
+		let Expression::FunctionCall(function_call) = expression else {
+			return;
+		};
+		let references = self.references.borrow();
+		if function_call.name.value == self.function
+			&& !function_call.params.iter().any(|param| references.contains(&param.source_position()))
+		{
+			self.tail_calls.insert(expression.source_position());
+		}
+	}
+
+	/// Checks that `error` at the `position` is a result of the function currently being checked.
+	fn check_error(&self, position: &SourcePositionRange) -> Result<(), Error> {
+		match self.types.borrow().fallible_inner(self.return_type) {
+			Some(_) => Ok(()),
+			None => Err(Error::UnexpectedError { position: position.clone() }),
+		}
 	}
 
 	/// Type checks an if-else block.
 	fn if_else(&mut self, if_else: &ast::IfElse) -> Result<(), Error> {
 		// if block, always present
-		self.expression(&if_else.condition)?;
+		let null_check = self.null_check(&if_else.condition);
+		self.condition(&if_else.condition)?;
 
-		self.call_stack.push(CallStackFrame::new());
-		for instruction in &if_else.if_true {
-			self.instruction(instruction)?;
-		}
-		self.drop_call_stack_frame();
+		// The variable compared with `null` is narrowed in the branch where it is not `null`
+		let narrow =
+			|branch_is_true: bool| null_check.filter(|(_, _, not_null_if_true)| *not_null_if_true == branch_is_true);
+		self.narrowed_block(&if_else.if_true, narrow(true))?;
 
 		// else block, optional
-		if if_else.if_false.is_empty() {
-			return Ok(());
+		if !if_else.if_false.is_empty() {
+			self.narrowed_block(&if_else.if_false, narrow(false))?;
 		}
-		self.call_stack.push(CallStackFrame::new());
-		for instruction in &if_else.if_false {
-			self.instruction(instruction)?;
+
+		// If the branch where the variable is `null` returns, it is not `null` after the if-else
+		let (null_branch, not_null_branch) = match null_check {
+			Some((_, _, true)) => (&if_else.if_false, &if_else.if_true),
+			_ => (&if_else.if_true, &if_else.if_false),
+		};
+		if let Some((declaration, inner, _)) = null_check {
+			if Self::always_returns(null_branch) && !Self::assigns(not_null_branch, &self.declared_name(declaration)) {
+				self.narrowed.insert(declaration, inner);
+			}
 		}
-		self.drop_call_stack_frame();
+		Ok(())
+	}
 
+	/// Type checks the instructions of a block in a new scope, with the `narrowing` of a variable to its inner type.
+	///
+	/// Narrowings made in the block end with it, since the block may not be executed. Those of the enclosing code end
+	/// if the block assigns the variable.
+	fn narrowed_block(
+		&mut self,
+		instructions: &[ast::Instruction],
+		narrowing: Option<(DeclarationId, TypeId, bool)>,
+	) -> Result<(), Error> {
+		let outer = self.narrowed.clone();
+		if let Some((declaration, inner, _)) = narrowing {
+			self.narrowed.insert(declaration, inner);
+		}
+		self.enter_scope();
+		for instruction in instructions {
+			self.instruction(instruction)?;
+		}
+		self.leave_scope();
+		let inner = mem::take(&mut self.narrowed);
+		self.narrowed =
+			outer.into_iter().filter(|(declaration, type_)| inner.get(declaration) == Some(type_)).collect();
 		Ok(())
 	}
 
+	/// The variable of optional type that the `condition` compares with `null`, like `x =/= null` or `x = null`,
+	/// unless it is already narrowed. Returns its inner type and whether the variable is not `null` if the condition is
+	/// true.
+	fn null_check(&self, condition: &Expression) -> Option<(DeclarationId, TypeId, bool)> {
+		let Expression::BinaryExpression(binary_expression) = condition else {
+			return None;
+		};
+		let variable = match (&*binary_expression.lhs, &*binary_expression.rhs) {
+			(Expression::Variable(variable), Expression::Null(_))
+			| (Expression::Null(_), Expression::Variable(variable)) => variable,
+			_ => return None,
+		};
+		let not_null_if_true = match binary_expression.operator.value {
+			BinaryOperator::NotEqual => true,
+			BinaryOperator::Equal => false,
+			_ => return None,
+		};
+		let (declaration, declared) = self.variable(variable)?;
+		if self.narrowed.contains_key(&declaration) {
+			return None;
+		}
+		let declared_type = self.intern(&declared.type_);
+		let inner = self.types.borrow().optional_inner(declared_type)?;
+		Some((declaration, inner, not_null_if_true))
+	}
+
+	/// The name of the variable of the `declaration`.
+	fn declared_name(&self, declaration: DeclarationId) -> String {
+		self.scopes.borrow().declaration(declaration).variable.name.value.clone()
+	}
+
+	/// Whether the `instructions` end with a `return`, also in all branches of a final if-else. Invalid code is
+	/// assumed to be a `return`, so that it causes no errors about variables that might be `null`.
+	fn always_returns(instructions: &[ast::Instruction]) -> bool {
+		match instructions.last() {
+			Some(ast::Instruction::Statement(ast::Statement::Return(_)) | ast::Instruction::Invalid(_)) => true,
+			Some(ast::Instruction::IfElse(if_else)) => {
+				Self::always_returns(&if_else.if_true) && Self::always_returns(&if_else.if_false)
+			},
+			_ => false,
+		}
+	}
+
+	/// Whether the `instructions` assign a value to the variable `name`, also in nested blocks.
+	fn assigns(instructions: &[ast::Instruction], name: &str) -> bool {
+		instructions.iter().any(|instruction| match instruction {
+			ast::Instruction::Statement(ast::Statement::VariableAssignment(assignment)) => {
+				assignment.name.value == name
+			},
+			ast::Instruction::IfElse(if_else) => {
+				Self::assigns(&if_else.if_true, name) || Self::assigns(&if_else.if_false, name)
+			},
+			ast::Instruction::WhileLoop(while_loop) => Self::assigns(&while_loop.body, name),
+			ast::Instruction::Expression(_) | ast::Instruction::Statement(_) | ast::Instruction::Invalid(_) => false,
+		})
+	}
+
 	/// Type checks a while loop.
 	fn while_loop(&mut self, while_loop: &ast::WhileLoop) -> Result<(), Error> {
-		self.expression(&while_loop.condition)?;
-
-		self.call_stack.push(CallStackFrame::new());
-		for instruction in &while_loop.body {
-			self.instruction(instruction)?;
+		// Values assigned in one iteration are seen by the next one, so these variables may be `null` again
+		let narrowed: Vec<DeclarationId> = self.narrowed.keys().copied().collect();
+		for declaration in narrowed {
+			if Self::assigns(&while_loop.body, &self.declared_name(declaration)) {
+				self.narrowed.remove(&declaration);
+			}
 		}
-		self.drop_call_stack_frame();
 
-		Ok(())
+		let null_check = self.null_check(&while_loop.condition);
+		self.condition(&while_loop.condition)?;
+		self.narrowed_block(&while_loop.body, null_check.filter(|(_, _, not_null_if_true)| *not_null_if_true))
 	}
 
 	/// Infers the type of an expression, which can consist of binary expressions, numbers, function calls and variables.
-	pub fn infer_expression_type(&self, expression: &Expression) -> Result<DataType, Error> {
-		match expression {
+	pub fn infer_expression_type(&self, expression: &Expression) -> Result<TypeId, Error> {
+		let type_ = match expression {
 			Expression::BinaryExpression(binary_expression) => self.infer_binary_expression_type(binary_expression),
+			Expression::FieldAccess(field_access) => self.infer_field_access_type(field_access),
 			Expression::FunctionCall(function_call) => self.infer_function_call_return_type(function_call),
 			Expression::Number(number) => Self::number_type_inference(number),
+			Expression::String(_) => Ok(TypeId::STR),
 			Expression::Variable(variable) => {
 				// Here, a variables is used inside an expression. This is not about a variable declaration.
 				self.infer_variable_type(variable)
 			},
-		}
+			Expression::Try(try_expression) => self.infer_try_type(try_expression),
+			// `error` is not a value, see `Self::return_` and `Self::infer_try_type`
+			Expression::Error(position) => Err(Error::UnexpectedError { position: position.clone() }),
+			// The type of `null` depends on where it is used, see `Self::check_expression`
+			Expression::Null(position) => Err(Error::UnexpectedNull { position: position.clone(), expected: None }),
+			// Only the parser creates invalid code, where the expected type is known, see `Self::check_expression`
+			Expression::Invalid(position) => Err(Error::InvalidCode { position: position.clone() }),
+		}?;
+		self.record_type(expression, type_);
+		Ok(type_)
 	}
 
 	/// Infers the type of the left-hand and right-hand side of a binary expression,
-	/// verifies that they are equal and returns this common type.
-	fn infer_binary_expression_type(&self, binary_expression: &BinaryExpression) -> Result<DataType, Error> {
-		let lhs = self.infer_expression_type(&binary_expression.lhs)?;
-		let rhs = self.infer_expression_type(&binary_expression.rhs)?;
-		if lhs != rhs {
-			return Err(Error::TypeMismatch {
-				expected: lhs,
-				position: binary_expression.operator.position.clone(),
-				actual: rhs,
-			});
+	/// verifies that they are compatible and returns their common type.
+	///
+	/// Values of optional types can only be compared for equality, e.g. with `null`, which results in an `int`. Struct
+	/// values can't be compared at all.
+	/// The sides of `and` and `or` are conditions of any number type each and result in an `int`. `mod` is only defined
+	/// for ints, which can't be divided by a constant zero.
+	fn infer_binary_expression_type(&self, binary_expression: &BinaryExpression) -> Result<TypeId, Error> {
+		let operator = &binary_expression.operator;
+		if operator.is_logical() {
+			for side in [&*binary_expression.lhs, &*binary_expression.rhs] {
+				let type_ = self.infer_expression_type(side)?;
+				if type_ != TypeId::INT && type_ != TypeId::FLOAT {
+					let data_type = self.types.borrow().data_type(type_);
+					return Err(Error::NonNumericOperand { operator: operator.clone(), data_type });
+				}
+			}
+			return Ok(TypeId::INT);
+		}
+		let (lhs, rhs) = match (&*binary_expression.lhs, &*binary_expression.rhs) {
+			// `null` has the type of the other side
+			(Expression::Null(position), other) | (other, Expression::Null(position))
+				if !matches!(other, Expression::Null(_)) =>
+			{
+				let mut other_type = self.infer_expression_type(other)?;
+				// Narrowed variables may still be compared with `null`
+				if let Expression::Variable(variable) = other {
+					other_type =
+						self.variable(variable).map_or(other_type, |(_, declared)| self.intern(&declared.type_));
+				}
+				self.check_null(position, other_type)?;
+				(other_type, other_type)
+			},
+			(lhs, rhs) => (self.infer_expression_type(lhs)?, self.infer_expression_type(rhs)?),
+		};
+		let common_type = self.types.borrow().common_type(lhs, rhs);
+		let common_type = match common_type {
+			Some(common_type) => common_type,
+			None => return self.check_subtype(rhs, lhs, operator.position.clone()).map(|()| lhs),
+		};
+
+		let types = self.types.borrow();
+		if types.optional_inner(common_type).is_some() {
+			return match operator.value {
+				BinaryOperator::Equal | BinaryOperator::NotEqual => Ok(TypeId::INT),
+				_ => {
+					Err(Error::OptionalOperand { operator: operator.clone(), data_type: types.data_type(common_type) })
+				},
+			};
+		}
+		let number = common_type == TypeId::INT || common_type == TypeId::FLOAT;
+		match operator.value {
+			BinaryOperator::Equal | BinaryOperator::NotEqual if matches!(types.get(common_type), Type::Struct(_)) => {
+				Err(Error::IncomparableOperand { operator: operator.clone(), data_type: types.data_type(common_type) })
+			},
+			// Strings are concatenated with `+` and compared by their bytes, which results in an `int`
+			BinaryOperator::Add if common_type == TypeId::STR => Ok(TypeId::STR),
+			_ if operator.is_comparison() && common_type == TypeId::STR => Ok(TypeId::INT),
+			BinaryOperator::Less
+			| BinaryOperator::Greater
+			| BinaryOperator::LessEqual
+			| BinaryOperator::GreaterEqual
+				if !number =>
+			{
+				Err(Error::UnorderedOperand { operator: operator.clone(), data_type: types.data_type(common_type) })
+			},
+			BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply | BinaryOperator::Divide
+				if !number =>
+			{
+				Err(Error::NonNumericOperand { operator: operator.clone(), data_type: types.data_type(common_type) })
+			},
+			BinaryOperator::Modulus if common_type != TypeId::INT => {
+				Err(Error::NonIntOperand { operator: operator.clone(), data_type: types.data_type(common_type) })
+			},
+			BinaryOperator::Divide | BinaryOperator::Modulus if common_type == TypeId::INT => {
+				match &*binary_expression.rhs {
+					Expression::Number(divisor) if divisor.value == NumberKind::Int(0) => {
+						Err(Error::DivisionByZero { divisor: divisor.position.clone() })
+					},
+					_ => Ok(common_type),
+				}
+			},
+			_ => Ok(common_type),
+		}
+	}
+
+	/// Infers the type of a field access by looking up the declared type of the field in the struct definition.
+	///
+	/// Fields of structs behind a pointer are accessed the same way, like `->` in C.
+	fn infer_field_access_type(&self, field_access: &FieldAccess) -> Result<TypeId, Error> {
+		let base_type = self.infer_expression_type(&field_access.base)?;
+		let types = self.types.borrow();
+		let base_type = types.pointee(base_type).unwrap_or(base_type);
+		let struct_ = match types.get(base_type) {
+			Type::Struct(struct_name) => self.symbol_table.structs.get(struct_name),
+			_ => None,
+		};
+		let Some(struct_) = struct_ else {
+			return Err(Error::NotAStruct { data_type: types.data_type(base_type), field: field_access.field.clone() });
+		};
+		types
+			.field(base_type, &field_access.field)
+			.map(|field| field.type_)
+			.ok_or_else(|| Error::UnknownField { struct_name: struct_.name.clone(), field: field_access.field.clone() })
+	}
+
+	/// Infers the type of a variable by looking it up in [`Self::variables`], and records the use of the variable.
+	fn infer_variable_type(&self, variable: &Spanned<String>) -> Result<TypeId, Error> {
+		let (declaration, declared) =
+			self.variable(&variable.value).ok_or(Error::UndeclaredVariable { name: variable.clone() })?;
+		self.scopes.borrow_mut().add_use(declaration, variable.position.clone());
+		match self.narrowed.get(&declaration) {
+			Some(inner) => Ok(*inner),
+			None => Ok(self.intern(&declared.type_)),
+		}
+	}
+
+	/// Like [`Self::check_function_call`], but for function calls whose return value is used.
+	fn infer_function_call_return_type(&self, function_call: &FunctionCall) -> Result<TypeId, Error> {
+		match self.check_function_call(function_call)? {
+			TypeId::VOID => Err(Error::VoidValue { function_call: function_call.clone() }),
+			return_type => self.check_failure_handled(function_call, return_type).map(|()| return_type),
+		}
+	}
+
+	/// Checks that the `function_call` with the `return_type` can't fail, since failures can only be handled by `try`.
+	fn check_failure_handled(&self, function_call: &FunctionCall, return_type: TypeId) -> Result<(), Error> {
+		match self.types.borrow().fallible_inner(return_type) {
+			Some(_) => Err(Error::UnhandledFailure { function_call: function_call.clone() }),
+			None => Ok(()),
 		}
-		Ok(lhs)
 	}
 
-	/// Infers the type of a variable by looking it up in [`Self::variables`].
-	fn infer_variable_type(&self, variable: &PositionContainer<String>) -> Result<DataType, Error> {
-		self.variables
-			.get(&variable.value)
-			.map(|v| v.type_.clone())
-			.ok_or(Error::UndeclaredVariable { name: variable.clone() })
+	/// Infers the type of `try value else fallback`, which is the type of the results of the call `value`. The
+	/// `fallback` must be a value of that type, or `error` to pass the failure on.
+	fn infer_try_type(&self, try_expression: &TryExpression) -> Result<TypeId, Error> {
+		let value = &try_expression.value;
+		let value_type = match &**value {
+			Expression::FunctionCall(function_call) => self.check_function_call(function_call)?,
+			value => self.infer_expression_type(value)?,
+		};
+		self.record_type(value, value_type);
+		let types = self.types.borrow();
+		let Some(inner) = types.fallible_inner(value_type) else {
+			return Err(Error::NotFallible {
+				position: value.source_position(),
+				data_type: types.data_type(value_type),
+			});
+		};
+		drop(types);
+
+		match &*try_expression.fallback {
+			Expression::Error(position) => self.check_error(position)?,
+			fallback => {
+				self.check_expression(fallback, inner, fallback.source_position())?;
+			},
+		}
+		Ok(inner)
 	}
 
 	/// Looks up the return type of the function and thereby checks that the types of the parameters supplied in the `function_call`
 	/// match the types of the arguments of the defined function in the [symbol table](Self::symbol_table).
-	fn infer_function_call_return_type(&self, function_call: &FunctionCall) -> Result<DataType, Error> {
+	///
+	/// Returns [`TypeId::VOID`] for functions without return value.
+	fn check_function_call(&self, function_call: &FunctionCall) -> Result<TypeId, Error> {
+		match Intrinsic::from_name(&function_call.name) {
+			Some(Intrinsic::Print) => return self.check_print(function_call),
+			Some(Intrinsic::Dump) => return self.check_dump(function_call),
+			Some(intrinsic) => return self.infer_intrinsic_return_type(intrinsic, function_call),
+			None => {},
+		}
+
 		// Get function definition
 		let function_definition = self.symbol_table.functions.get(&function_call.name.value);
 		let Some(function_definition) = function_definition else {
+			if self.symbol_table.structs.contains_key(&function_call.name.value) {
+				return self.check_struct_construction(function_call);
+			}
 			return Err(Error::UndefinedFunctionCall { function_call: function_call.clone() });
 		};
+		let visibility = &self.symbol_table.function_visibility;
+		self.check_visibility(visibility, &function_definition.name, &function_call.name.position)?;
 
 		// Check that the number of supplied parameters matches the number of expected arguments.
 		// Since the later used `iter::zip` returns None if one of the iterators is shorter than the other, we need to check the lengths first.
@@ -292,28 +898,574 @@ impl TypeChecker {
 
 		// Check that the types of supplied parameters and expected arguments match.
 		for (param, arg) in iter::zip(&function_call.params, &function_definition.args) {
-			let param_type = self.infer_expression_type(param)?;
-			if param_type != arg.data_type.value {
-				return Err(Error::TypeMismatch {
-					expected: arg.data_type.value.clone(),
-					position: param.source_position(),
-					actual: param_type,
-				});
+			self.check_argument(param, self.intern(&arg.data_type))?;
+		}
+
+		Ok(function_definition.return_type.as_ref().map_or(TypeId::VOID, |return_type| self.intern(return_type)))
+	}
+
+	/// Checks the `param` passed to an argument of the `expected` type.
+	///
+	/// Structs are copied into arguments of their type. A struct passed to a `ptr` argument is passed by reference
+	/// instead, so that the function can modify it. This requires a variable or field that the pointer refers to.
+	fn check_argument(&self, param: &Expression, expected: TypeId) -> Result<(), Error> {
+		let pointee = self.types.borrow().pointee(expected);
+		let Some(pointee) = pointee.filter(|pointee| self.types.borrow().fields(*pointee).is_some()) else {
+			return self.check_expression(param, expected, param.source_position()).map(|_type| ());
+		};
+		let actual = self.infer_expression_type(param)?;
+		if actual != pointee {
+			return self.check_subtype(actual, expected, param.source_position());
+		}
+		if !matches!(param, Expression::Variable(_) | Expression::FieldAccess(_)) {
+			let data_type = self.types.borrow().data_type(pointee);
+			return Err(Error::TemporaryReference { position: param.source_position(), data_type });
+		}
+		self.references.borrow_mut().insert(param.source_position());
+		Ok(())
+	}
+
+	/// Checks a call like `Point()`, which constructs the struct with the default values of its fields.
+	fn check_struct_construction(&self, function_call: &FunctionCall) -> Result<TypeId, Error> {
+		if let Some(struct_) = self.symbol_table.structs.get(&function_call.name.value) {
+			let visibility = &self.symbol_table.struct_visibility;
+			self.check_visibility(visibility, &struct_.name, &function_call.name.position)?;
+		}
+		if !function_call.params.is_empty() {
+			return Err(Error::ArgumentCountMismatch {
+				expected: 0,
+				actual: function_call.params.len(),
+				function_call: function_call.clone(),
+			});
+		}
+		Ok(self.types.borrow_mut().struct_(&function_call.name.value))
+	}
+
+	/// Checks that the function or struct `name` can be used at `position` according to its entry in `visibility`.
+	fn check_visibility(
+		&self,
+		visibility: &HashMap<String, Visibility>,
+		name: &Spanned<String>,
+		position: &SourcePositionRange,
+	) -> Result<(), Error> {
+		match visibility.get(&name.value) {
+			Some(visibility) if !visibility.allows(position) => Err(Error::Private {
+				name: Spanned::new(name.value.clone(), position.clone()),
+				definition: name.position.clone(),
+			}),
+			_ => Ok(()),
+		}
+	}
+
+	/// Selects the overload of the `intrinsic` matching the types of the parameters and returns its return type.
+	fn infer_intrinsic_return_type(&self, intrinsic: Intrinsic, function_call: &FunctionCall) -> Result<TypeId, Error> {
+		if function_call.params.len() != intrinsic.arity() {
+			return Err(Error::ArgumentCountMismatch {
+				expected: intrinsic.arity(),
+				actual: function_call.params.len(),
+				function_call: function_call.clone(),
+			});
+		}
+
+		let param_types = function_call
+			.params
+			.iter()
+			.map(|param| self.infer_expression_type(param))
+			.collect::<Result<Vec<_>, _>>()?;
+		let types = self.types.borrow();
+		intrinsic.return_type(&types, &param_types).ok_or_else(|| Error::NoMatchingOverload {
+			function_call: function_call.clone(),
+			param_types: param_types.iter().map(|type_| types.data_type(*type_)).collect(),
+		})
+	}
+
+	/// Checks that the first parameter of a call of `print` is a [format string](format_string) whose placeholders match
+	/// the remaining parameters, which must be printable.
+	fn check_print(&self, function_call: &FunctionCall) -> Result<TypeId, Error> {
+		let Some((format, args)) = function_call.params.split_first() else {
+			return Err(Error::ArgumentCountMismatch {
+				expected: Intrinsic::Print.arity(),
+				actual: 0,
+				function_call: function_call.clone(),
+			});
+		};
+		let Expression::String(format) = format else {
+			return Err(Error::InvalidFormatString {
+				position: format.source_position(),
+				reason: "the format string must be a string literal".to_owned(),
+			});
+		};
+		let segments = format_string::parse(format)
+			.map_err(|err| Error::InvalidFormatString { position: format.position.clone(), reason: err.to_string() })?;
+		let placeholders = format_string::placeholders(&segments);
+		if placeholders != args.len() {
+			return Err(Error::FormatArgumentCount {
+				position: format.position.clone(),
+				placeholders,
+				arguments: args.len(),
+			});
+		}
+
+		self.infer_expression_type(&function_call.params[0])?;
+		for arg in args {
+			match self.infer_expression_type(arg)? {
+				TypeId::INT | TypeId::FLOAT | TypeId::STR => {},
+				type_ => {
+					return Err(Error::UnprintableArgument {
+						position: arg.source_position(),
+						data_type: self.types.borrow().data_type(type_),
+					})
+				},
 			}
 		}
+		Ok(TypeId::VOID)
+	}
 
-		Ok(function_definition
-			.return_type
-			.as_ref()
-			.map(|return_type| return_type.value.clone())
-			.expect("Function without return value not supported yet"))
+	/// Checks that a call of `dump` has a single parameter of a type that can be dumped, see [`is_dumpable`].
+	fn check_dump(&self, function_call: &FunctionCall) -> Result<TypeId, Error> {
+		let [value] = &function_call.params[..] else {
+			return Err(Error::ArgumentCountMismatch {
+				expected: Intrinsic::Dump.arity(),
+				actual: function_call.params.len(),
+				function_call: function_call.clone(),
+			});
+		};
+		let type_ = self.infer_expression_type(value)?;
+		let types = self.types.borrow();
+		if !is_dumpable(&types, type_, &mut HashSet::new()) {
+			return Err(Error::UnprintableArgument {
+				position: value.source_position(),
+				data_type: types.data_type(type_),
+			});
+		}
+		Ok(TypeId::VOID)
 	}
 
 	/// Infers the type of a number expression.
-	fn number_type_inference(number: &Number) -> Result<DataType, Error> {
+	fn number_type_inference(number: &Number) -> Result<TypeId, Error> {
 		match number.value {
-			NumberKind::Int(_) => Ok(DataType::Basic(BasicDataType::Int)),
-			NumberKind::Float(_) => Ok(DataType::Basic(BasicDataType::Float)),
+			NumberKind::Int(_) => Ok(TypeId::INT),
+			NumberKind::Float(_) => Ok(TypeId::FLOAT),
 		}
 	}
 }
+
+/// Whether `dump` can print values of the `type_`, i.e. values, optionals and pointers to values, and structs whose
+/// fields can be dumped as well. The `visited` structs are not checked again, since structs may point to themselves.
+fn is_dumpable(types: &TypeTable, type_: TypeId, visited: &mut HashSet<TypeId>) -> bool {
+	match types.get(type_) {
+		Type::Basic(_) => true,
+		Type::Struct(_) => {
+			!visited.insert(type_)
+				|| types.fields(type_).unwrap_or_default().iter().all(|field| is_dumpable(types, field.type_, visited))
+		},
+		Type::Optional(inner) => is_dumpable(types, *inner, visited),
+		Type::Pointer(pointee) => {
+			matches!(types.get(*pointee), Type::Basic(_) | Type::Struct(_)) && is_dumpable(types, *pointee, visited)
+		},
+		Type::Fallible(_) | Type::Void => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		sync::Arc,
+		time::{Duration, Instant},
+	};
+
+	use super::*;
+	use crate::{diagnostic::Diagnostic, dump::Dumper, source::Source};
+
+	/// Type checks the source code.
+	fn type_check(source_code: &str) -> Result<(), Error> {
+		type_check_with(source_code, &CheckConfig::default())
+	}
+
+	/// Type checks the source code with the optional checks of the `config`.
+	fn type_check_with(source_code: &str, config: &CheckConfig) -> Result<(), Error> {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		crate::semantic_analyzer::analyze(ast_nodes.iter(), config).map(|_program| ())
+	}
+
+	#[test]
+	fn test_field_access() {
+		let structs = "struct Point { x: int y: float }\nstruct Line { start: Point end: Point }\n";
+		let valid = format!("{}def length(line: Line): float {{\n\treturn line.end.y - line.start.y\n}}", structs);
+		assert_eq!(type_check(&valid), Ok(()));
+
+		let mismatch = format!("{}def f(p: Point): int {{\n\tvar y: int = p.y\n\treturn y\n}}", structs);
+		assert!(matches!(type_check(&mismatch), Err(Error::TypeMismatch { .. })));
+
+		let unknown = format!("{}def f(p: Point): int {{\n\treturn p.z\n}}", structs);
+		let Err(Error::UnknownField { struct_name, field }) = type_check(&unknown) else {
+			panic!("expected UnknownField")
+		};
+		assert_eq!((struct_name.as_str(), struct_name.position.position.start.line), ("Point", 1));
+		assert_eq!((field.as_str(), field.position.position.start.line), ("z", 4));
+
+		let not_a_struct = "def f(a: int): int {\n\treturn a.x\n}";
+		assert!(matches!(type_check(not_a_struct), Err(Error::NotAStruct { .. })));
+	}
+
+	#[test]
+	fn test_unknown_struct() {
+		for source_code in [
+			"def f(p: Point) { }",
+			"def f(): ptr Point {\n\treturn 0\n}",
+			"extern f(p: ptr ptr Point)",
+			"struct Line { start: Point }",
+			"def f() {\n\tvar p: Point = 0\n}",
+		] {
+			let Err(Error::UnknownStruct { name, known_structs }) = type_check(source_code) else {
+				panic!("expected UnknownStruct for {}", source_code)
+			};
+			assert_eq!((name.as_str(), name.position.code()), ("Point", "Point"));
+			assert_eq!(known_structs[0], "File");
+		}
+		assert_eq!(type_check("struct Point { x: int }\ndef f(p: ptr Point) { }"), Ok(()));
+	}
+
+	#[test]
+	fn test_field_defaults() {
+		assert_eq!(type_check("struct Point { x: int = 0, y: float = 1.5, name: str = \"origin\" }"), Ok(()));
+		assert!(matches!(type_check("struct Point { x: int = 1.5 }"), Err(Error::TypeMismatch { .. })));
+		let Err(Error::NonConstantDefault { field, .. }) =
+			type_check("def zero(): int {\n\treturn 0\n}\nstruct Point { x: int = zero() }")
+		else {
+			panic!("expected NonConstantDefault")
+		};
+		assert_eq!(field.as_str(), "x");
+		assert!(matches!(type_check("struct Point { x: int = y }"), Err(Error::UndeclaredVariable { .. })));
+	}
+
+	#[test]
+	fn test_struct_construction() {
+		let structs = "struct Point { x: int, y: float }\nstruct Line { start: Point }\n";
+		let valid = "def f(): int {\n\tvar line: Line = Line()\n\tline.start.y = 1.5\n\treturn line.start.x\n}";
+		assert_eq!(type_check(&format!("{}{}", structs, valid)), Ok(()));
+		let with_args = "def f() {\n\tvar p: Point = Point(1)\n}";
+		assert!(matches!(
+			type_check(&format!("{}{}", structs, with_args)),
+			Err(Error::ArgumentCountMismatch { expected: 0, actual: 1, .. })
+		));
+		let wrong_type = "def f() {\n\tvar p: Point = Point()\n\tp.x = 1.5\n}";
+		let Err(Error::TypeMismatch { position, .. }) = type_check(&format!("{}{}", structs, wrong_type)) else {
+			panic!("expected TypeMismatch")
+		};
+		assert_eq!(position.code(), "x");
+		let unknown_field = "def f() {\n\tvar p: Point = Point()\n\tp.z = 1\n}";
+		assert!(matches!(type_check(&format!("{}{}", structs, unknown_field)), Err(Error::UnknownField { .. })));
+	}
+
+	#[test]
+	fn test_comparison() {
+		assert_eq!(type_check("def f(a: int): int {\n\treturn a < 1\n}"), Ok(()));
+		assert_eq!(type_check("def f(b: float): float {\n\treturn b < 2.5\n}"), Ok(()));
+		let structs = "struct P { x: int }\ndef f(a: P, b: P): int {\n\treturn a < b\n}";
+		let Err(Error::UnorderedOperand { operator, data_type }) = type_check(structs) else {
+			panic!("expected UnorderedOperand")
+		};
+		assert_eq!((operator.value, data_type.to_string()), (BinaryOperator::Less, "P".to_owned()));
+		assert_eq!(type_check("def f(a: str): int {\n\treturn a < \"b\"\n}"), Ok(()));
+		let equality = "struct P { x: int }\ndef f(a: P, b: P): int {\n\treturn a =/= b\n}";
+		let Err(Error::IncomparableOperand { operator, data_type }) = type_check(equality) else {
+			panic!("expected IncomparableOperand")
+		};
+		assert_eq!((operator.value, data_type.to_string()), (BinaryOperator::NotEqual, "P".to_owned()));
+		assert_eq!(type_check("struct P { x: int }\ndef f(a: P, b: P): int {\n\treturn a.x = b.x\n}"), Ok(()));
+	}
+
+	#[test]
+	fn test_condition() {
+		let struct_ = "struct P { x: int }\ndef f(p: P, q: ptr P): int {\n\twhile q {\n\t\tif p {\n\t\t\treturn 1\n\t\t}\n\t}\n\treturn 0\n}";
+		let Err(Error::NonScalarCondition { position, data_type }) = type_check(struct_) else {
+			panic!("expected NonScalarCondition")
+		};
+		assert_eq!((position.code(), data_type.to_string()), ("p", "P".to_owned()));
+		let void = "def g() {\n}\ndef f() {\n\twhile g() {\n\t}\n}";
+		assert!(matches!(type_check(void), Err(Error::NonScalarCondition { .. })));
+	}
+
+	#[test]
+	fn test_division() {
+		assert_eq!(type_check("def f(a: int): int {\n\treturn a / 2 + a mod (0 - 3)\n}"), Ok(()));
+		assert_eq!(type_check("def f(b: float): float {\n\treturn b / 0.0\n}"), Ok(()));
+		let Err(Error::NonIntOperand { operator, data_type }) =
+			type_check("def f(b: float): float {\n\treturn b mod 2.0\n}")
+		else {
+			panic!("expected NonIntOperand")
+		};
+		assert_eq!((operator.value, data_type.to_string()), (BinaryOperator::Modulus, "float".to_owned()));
+		for operator in ["/", "mod"] {
+			let Err(Error::DivisionByZero { divisor }) =
+				type_check(&format!("def f(a: int): int {{\n\treturn a {} 0\n}}", operator))
+			else {
+				panic!("expected DivisionByZero")
+			};
+			assert_eq!(divisor.code(), "0");
+		}
+	}
+
+	#[test]
+	fn test_logical_operators() {
+		assert_eq!(type_check("def f(a: int, b: float): int {\n\treturn a < 1 and b or a\n}"), Ok(()));
+		let Err(Error::NonNumericOperand { operator, data_type }) =
+			type_check("def f(a: str): int {\n\treturn 1 or a\n}")
+		else {
+			panic!("expected NonNumericOperand")
+		};
+		assert_eq!((operator.value, data_type.to_string()), (BinaryOperator::LogicalOr, "str".to_owned()));
+	}
+
+	#[test]
+	fn test_string_operators() {
+		assert_eq!(type_check("def f(a: str): str {\n\treturn a + \"b\"\n}"), Ok(()));
+		assert_eq!(type_check("def f(a: str): int {\n\treturn (a = \"b\") + (a < \"c\")\n}"), Ok(()));
+		let Err(Error::NonNumericOperand { operator, data_type }) =
+			type_check("def f(a: str): str {\n\treturn a - \"b\"\n}")
+		else {
+			panic!("expected NonNumericOperand")
+		};
+		assert_eq!((operator.value, data_type.to_string()), (BinaryOperator::Subtract, "str".to_owned()));
+		let structs = "struct P { x: int }\ndef f(a: P): P {\n\treturn a + a\n}";
+		assert!(matches!(type_check(structs), Err(Error::NonNumericOperand { .. })));
+	}
+
+	#[test]
+	fn test_print() {
+		assert_eq!(type_check("def f(a: str) {\n\tprint(\"{} {} {{}}\", a, 1.5)\n\tprint(\"\")\n}"), Ok(()));
+		let Err(Error::FormatArgumentCount { placeholders, arguments, .. }) =
+			type_check("def f() {\n\tprint(\"{} {}\", 1)\n}")
+		else {
+			panic!("expected FormatArgumentCount")
+		};
+		assert_eq!((placeholders, arguments), (2, 1));
+		let invalid =
+			["def f(a: str) {\n\tprint(a)\n}", "def f() {\n\tprint(\"{\")\n}", "def f() {\n\tprint(\"} {}\", 1)\n}"];
+		for source_code in invalid {
+			assert!(matches!(type_check(source_code), Err(Error::InvalidFormatString { .. })), "{}", source_code);
+		}
+		let structs = "struct P { x: int }\ndef f(p: P) {\n\tprint(\"{}\", p)\n}";
+		assert!(matches!(type_check(structs), Err(Error::UnprintableArgument { .. })));
+		assert!(matches!(type_check("def f(): int {\n\treturn print(\"\")\n}"), Err(Error::VoidValue { .. })));
+		assert!(matches!(type_check("def print() {\n}"), Err(Error::IntrinsicRedefinition { .. })));
+	}
+
+	#[test]
+	fn test_dump() {
+		let structs = "struct P { x: opt int, next: ptr P }\nstruct Q { p: ptr ptr P }\n";
+		let valid = "def f(p: P, q: ptr P, s: opt str) {\n\tdump(p)\n\tdump(q)\n\tdump(s)\n\tdump(1.5)\n}";
+		assert_eq!(type_check(&format!("{}{}", structs, valid)), Ok(()));
+		let Err(Error::UnprintableArgument { data_type, .. }) =
+			type_check(&format!("{}{}", structs, "def f(q: Q) {\n\tdump(q)\n}"))
+		else {
+			panic!("expected UnprintableArgument")
+		};
+		assert_eq!(data_type.to_string(), "Q");
+		assert!(matches!(
+			type_check("def f() {\n\tdump(1, 2)\n}"),
+			Err(Error::ArgumentCountMismatch { expected: 1, actual: 2, .. })
+		));
+	}
+
+	#[test]
+	fn test_struct_parameters() {
+		let structs =
+			"struct P { x: int }\ndef by_value(p: P) {\n}\ndef by_reference(p: ptr P) {\n\tp.x = p.x + 1\n}\n";
+		let passed = "def f(p: P, q: ptr P) {\n\tby_reference(p)\n\tby_reference(q)\n\tby_value(p)\n}";
+		assert_eq!(type_check(&format!("{}{}", structs, passed)), Ok(()));
+		let temporary = "def f() {\n\tby_reference(P())\n}";
+		let Err(Error::TemporaryReference { position, data_type }) = type_check(&format!("{}{}", structs, temporary))
+		else {
+			panic!("expected TemporaryReference")
+		};
+		assert_eq!((position.code(), data_type.to_string()), ("P", "P".to_owned()));
+		let dereferenced = "def f(q: ptr P) {\n\tby_value(q)\n}";
+		assert!(matches!(type_check(&format!("{}{}", structs, dereferenced)), Err(Error::TypeMismatch { .. })));
+	}
+
+	#[test]
+	fn test_pointer_types() {
+		// Types written at different places are the same
+		let identity = "struct Node { next: ptr Node }\ndef f(node: ptr Node): ptr Node {\n\treturn node\n}";
+		assert_eq!(type_check(identity), Ok(()));
+		let Err(Error::TypeMismatch { expected, actual, .. }) =
+			type_check("def f(p: ptr int): ptr float {\n\treturn p\n}")
+		else {
+			panic!("expected TypeMismatch")
+		};
+		assert_eq!((expected.to_string(), actual.to_string()), ("ptr float".to_owned(), "ptr int".to_owned()));
+	}
+
+	#[test]
+	fn test_optional_types() {
+		let narrowed = "def f(x: opt int): int {\n\tif x =/= null {\n\t\treturn x + 1\n\t}\n\treturn 0\n}";
+		assert_eq!(type_check(narrowed), Ok(()));
+		let early_return = "def f(x: opt int): int {\n\tif x = null {\n\t\treturn 0\n\t}\n\treturn x\n}";
+		assert_eq!(type_check(early_return), Ok(()));
+		// The early return of a nested block only narrows the variable in the enclosing block
+		let nested_return =
+			"def f(x: opt int): int {\n\tif 0 {\n\t\tif x = null {\n\t\t\treturn 0\n\t\t}\n\t}\n\treturn x + 1\n}";
+		assert!(matches!(type_check(nested_return), Err(Error::OptionalOperand { .. })));
+		let assigned_in_block =
+			"def f(x: opt int): int {\n\tif x = null {\n\t\treturn 0\n\t}\n\tif 1 {\n\t\tx = null\n\t}\n\treturn x + 1\n}";
+		assert!(matches!(type_check(assigned_in_block), Err(Error::OptionalOperand { .. })));
+		let not_narrowed = "def f(x: opt int): int {\n\treturn x + 1\n}";
+		assert!(matches!(type_check(not_narrowed), Err(Error::OptionalOperand { .. })));
+		let reassigned = "def f(x: opt int): int {\n\tif x =/= null {\n\t\tx = null\n\t\treturn x\n\t}\n\treturn 0\n}";
+		assert!(matches!(type_check(reassigned), Err(Error::TypeMismatch { .. })));
+		assert!(matches!(
+			type_check("def f(x: opt int): int {\n\treturn x < 1\n}"),
+			Err(Error::OptionalOperand { .. })
+		));
+		let in_loop =
+			"def f(x: opt int): int {\n\twhile x =/= null {\n\t\tx = g(x)\n\t}\n\treturn 0\n}\ndef g(x: int): opt \
+		               int {\n\treturn null\n}";
+		assert_eq!(type_check(in_loop), Ok(()));
+
+		let Err(Error::UnexpectedNull { expected, .. }) = type_check("def f() {\n\tvar x: int = null\n}") else {
+			panic!("expected UnexpectedNull")
+		};
+		assert_eq!(expected.map(|data_type| data_type.to_string()), Some("int".to_owned()));
+		assert_eq!(type_check("def f() {\n\tvar x: opt str = null\n\tx = \"set\"\n}"), Ok(()));
+		assert!(matches!(
+			type_check("struct Point { x: int }\ndef f(p: opt Point) { }"),
+			Err(Error::UnsupportedOptional { .. })
+		));
+	}
+
+	#[test]
+	fn test_fallible_functions() {
+		let read = "def read(n: int): int or error {\n\tif n < 0 {\n\t\treturn error\n\t}\n\treturn n\n}\n";
+		let handled = "def f(): int {\n\treturn try read(1) else 0\n}";
+		assert_eq!(type_check(&format!("{}{}", read, handled)), Ok(()));
+		let propagated = "def f(): float or error {\n\tvar n: int = try read(1) else error\n\treturn 1.5\n}";
+		assert_eq!(type_check(&format!("{}{}", read, propagated)), Ok(()));
+
+		for (source_code, expected) in [
+			("def f(): int {\n\treturn read(1)\n}", "UnhandledFailure"),
+			("def f() {\n\tread(1)\n}", "UnhandledFailure"),
+			("def f(): int {\n\treturn try read(1) else error\n}", "UnexpectedError"),
+			("def f(): int {\n\treturn error\n}", "UnexpectedError"),
+			("def f(): int {\n\treturn try f() else 0\n}", "NotFallible"),
+			("def f(): int {\n\treturn try read(1) else 1.5\n}", "TypeMismatch"),
+			("def main(): int or error {\n\treturn 0\n}", "MisplacedFallible"),
+		] {
+			let err = type_check(&format!("{}{}", read, source_code)).unwrap_err();
+			assert_eq!(Diagnostic::from(&err).code, expected, "{}", source_code);
+		}
+	}
+
+	#[test]
+	fn test_recursive_struct() {
+		assert_eq!(type_check("struct Node { value: int, next: ptr Node }"), Ok(()));
+		assert_eq!(type_check("struct A { b: ptr B }\nstruct B { a: A }"), Ok(()));
+		let Err(Error::RecursiveStruct { struct_name, field }) = type_check("struct Node { value: int, next: Node }")
+		else {
+			panic!("expected RecursiveStruct")
+		};
+		assert_eq!((struct_name.as_str(), field.as_str()), ("Node", "next"));
+		assert!(matches!(type_check("struct A { b: B }\nstruct B { a: A }"), Err(Error::RecursiveStruct { .. })));
+	}
+
+	#[test]
+	fn test_return_type() {
+		assert_eq!(type_check("def log(a: int) {\n\tftl_print(\"log\")\n\treturn;\n}"), Ok(()));
+		assert_eq!(type_check("def log(a: int) {\n\treturn\n}"), Ok(()));
+		for source_code in
+			["def f() {\n\treturn 1\n}", "def f(): int {\n\treturn\n}", "def f(): int {\n\treturn 1.5\n}"]
+		{
+			assert!(matches!(type_check(source_code), Err(Error::TypeMismatch { .. })), "{}", source_code);
+		}
+	}
+
+	#[test]
+	fn test_strict() {
+		let strict = CheckConfig::strict();
+
+		let int_condition = "def f(n: int): int {\n\tif n {\n\t\treturn 1\n\t}\n\treturn 0\n}";
+		assert_eq!(type_check(int_condition), Ok(()));
+		assert!(matches!(type_check_with(int_condition, &strict), Err(Error::NonBoolCondition { .. })));
+		let comparison = "def f(n: int): int {\n\twhile 0 < n {\n\t\tn = n - 1\n\t}\n\treturn n\n}";
+		assert_eq!(type_check_with(comparison, &strict), Ok(()));
+
+		let shadowing = "def f(): int {\n\tvar sqrt: int = 2\n\treturn sqrt\n}";
+		assert_eq!(type_check(shadowing), Ok(()));
+		assert!(matches!(type_check_with(shadowing, &strict), Err(Error::Shadowing { .. })));
+
+		let recursion = "def f(): int {\n\treturn f()\n}";
+		assert_eq!(type_check(recursion), Ok(()));
+		assert!(matches!(type_check_with(recursion, &strict), Err(Error::DeniedWarning { .. })));
+	}
+
+	#[test]
+	fn test_void_call() {
+		let void_function = "extern log(message: str)\n";
+		let as_instruction = format!("{}def main(): int {{\n\tlog(\"hi\")\n\treturn 0\n}}", void_function);
+		assert_eq!(type_check(&as_instruction), Ok(()));
+
+		for expression in ["var x: int = log(\"hi\")", "return log(\"hi\")", "ftl_print(log(\"hi\"))"] {
+			let as_value = format!("{}def main(): int {{\n\t{}\n}}", void_function, expression);
+			assert!(matches!(type_check(&as_value), Err(Error::VoidValue { .. })), "{}", expression);
+		}
+	}
+
+	#[test]
+	fn test_visibility() {
+		let type_check_files = |library: &str, main: &str| {
+			let mut ast_nodes = Vec::new();
+			for (name, source_code) in [("library.ftl", library), ("main.ftl", main)] {
+				let source = Arc::new(Source::new(name.to_owned(), source_code.to_owned()));
+				ast_nodes.extend(crate::parse_source(source, &Dumper::default()).unwrap());
+			}
+			crate::semantic_analyzer::analyze(ast_nodes.iter(), &CheckConfig::default()).map(|_program| ())
+		};
+		let main = "def main(): int {\n\tvar p: Point = Point()\n\treturn double(p.x)\n}";
+		let library =
+			"struct Point { x: int }\ndef double(a: int): int {\n\treturn helper(a) * 2\n}\ndef helper(a: int): \
+		               int {\n\treturn a\n}";
+		let Err(Error::Private { name, definition }) = type_check_files(library, main) else {
+			panic!("expected Private")
+		};
+		assert_eq!((name.value.as_str(), name.position.source.name.as_str()), ("Point", "main.ftl"));
+		assert_eq!(definition.source.name, "library.ftl");
+
+		let exported = library.replace("struct", "export struct").replace("def double", "export def double");
+		assert_eq!(type_check_files(&exported, main), Ok(()));
+		let only_struct = library.replace("struct", "export struct");
+		assert!(
+			matches!(type_check_files(&only_struct, main), Err(Error::Private { name, .. }) if name.value == "double")
+		);
+		// Runtime functions and structs can be used everywhere
+		assert_eq!(type_check_files("", "def main(): int {\n\treturn str_len(\"a\")\n}"), Ok(()));
+
+		// Private functions and structs of different files can't have the same name
+		let private = "struct S { x: int }\ndef helper(): int {\n\treturn 1\n}";
+		let Err(Error::DuplicateDefinition { name, previous }) =
+			type_check_files(private, &format!("{}\ndef main(): int {{\n\treturn helper()\n}}", private))
+		else {
+			panic!("expected DuplicateDefinition")
+		};
+		assert_eq!((name.value.as_str(), name.position.source.name.as_str()), ("S", "main.ftl"));
+		assert_eq!(previous.source.name, "library.ftl");
+		let only_functions = "def helper(): int {\n\treturn 1\n}";
+		assert!(matches!(
+			type_check_files(only_functions, &format!("{}\ndef main(): int {{\n\treturn 0\n}}", only_functions)),
+			Err(Error::DuplicateDefinition { name, .. }) if name.value == "helper"
+		));
+	}
+
+	#[test]
+	fn test_large_function() {
+		// The types and uses are recorded by position, which has to be cheap to hash and compare
+		let body = "\tx = (x + 1)\n".repeat(10_000);
+		let source_code = format!("def main(): int {{\n\tvar x: int = 0\n{}\treturn x\n}}", body);
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let start = Instant::now();
+		crate::semantic_analyzer::analyze(ast_nodes.iter(), &CheckConfig::default()).unwrap();
+		assert!(start.elapsed() < Duration::from_secs(1), "{:?}", start.elapsed());
+	}
+}
@@ -5,7 +5,7 @@ use std::{
 	ops::Deref,
 };
 
-use crate::{ast::statement::DataType, source::PositionContainer};
+use crate::{ast::statement::DataType, source::Spanned};
 
 /// Stores the name and type of a currently in-scope variable in the call stack.
 ///
@@ -14,7 +14,7 @@ use crate::{ast::statement::DataType, source::PositionContainer};
 #[derive(Debug)]
 pub struct Variable {
 	/// The name of the variable and the position of the declaration.
-	pub name: PositionContainer<String>,
+	pub name: Spanned<String>,
 	/// The type of the variable.
 	pub type_: DataType,
 }
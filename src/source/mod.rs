@@ -1,30 +1,38 @@
 //! Abstractions of source code.
 //!
 //! Every char in source code belongs to a [`Source`] (e.g. a file) and has a [`Position`] in this file.
-//! To make it more ergonomically to work with positions, the [`PositionContainer`] wraps an element
+//! To make it more ergonomically to work with positions, [`Spanned`] wraps an element
 //! with its [`Position`].
 
 mod position;
-mod position_container;
 mod position_range;
 mod source_position;
+mod spanned;
 
-use std::{fmt, sync::Arc};
+use std::{
+	fmt,
+	hash::{Hash, Hasher},
+	sync::Arc,
+};
 
 pub use position::Position;
-pub use position_container::PositionContainer;
 pub use position_range::PositionRange;
 pub use source_position::SourcePositionRange;
+pub use spanned::Spanned;
 
 /// Contains the source code of a file.
 ///
 /// Mostly used as `Arc<Source>`, since this is cheaper to clone.
-#[derive(Eq, PartialEq, Hash)]
+#[derive(Eq, PartialEq)]
 pub struct Source {
 	/// Filename.
 	pub name: String,
-	/// Content as chars.
-	pub text: Arc<[char]>,
+	/// Content of the file.
+	pub text: Arc<str>,
+	/// Whether this is the empty source of [synthetic](SourcePositionRange::synthetic) positions.
+	synthetic: bool,
+	/// For synthetic positions, the position of the code they were generated from.
+	generated_from: Option<SourcePositionRange>,
 }
 
 impl Source {
@@ -37,10 +45,15 @@ impl Source {
 	///
 	/// let source = Source::new("file.name".to_owned(), "ab\nc".to_owned());
 	/// assert_eq!(source.name, "file.name");
-	/// assert_eq!(&*source.text, &['a', 'b', '\n', 'c']);
+	/// assert_eq!(&*source.text, "ab\nc");
 	/// ```
 	pub fn new(name: String, text: String) -> Self {
-		Self { name, text: text.chars().collect() }
+		Self { name, text: Arc::from(text), synthetic: false, generated_from: None }
+	}
+
+	/// The empty source of [synthetic](SourcePositionRange::synthetic) positions.
+	fn synthetic() -> Self {
+		Self { synthetic: true, ..Self::new("<generated>".to_owned(), String::new()) }
 	}
 
 	/// Creates an iterator over the [`Symbol`]s of the source code.
@@ -50,13 +63,11 @@ impl Source {
 	/// ```
 	/// use std::sync::Arc;
 	///
-	/// use fortytwolang::source::{
-	/// 	Position, PositionContainer, PositionRange, Source, SourcePositionRange,
-	/// };
+	/// use fortytwolang::source::{Position, PositionRange, Source, SourcePositionRange, Spanned};
 	///
 	/// let source = Arc::new(Source::new("file.name".to_owned(), "text...".to_owned()));
 	/// let mut iter = Arc::clone(&source).iter();
-	/// let expected = Some(PositionContainer::new(
+	/// let expected = Some(Spanned::new(
 	/// 	't',
 	/// 	SourcePositionRange {
 	/// 		source: Arc::clone(&source),
@@ -73,6 +84,17 @@ impl Source {
 	}
 }
 
+/// Hashes the name instead of the whole text, which would make maps keyed by [positions](SourcePositionRange), like the
+/// types of the expressions, slow for large files. Positions in the same file share the source, which is compared by
+/// pointer first.
+impl Hash for Source {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.name.hash(state);
+		self.synthetic.hash(state);
+		self.generated_from.hash(state);
+	}
+}
+
 impl fmt::Debug for Source {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		f.debug_struct("Source").field("name", &self.name).finish()
@@ -80,7 +102,7 @@ impl fmt::Debug for Source {
 }
 
 /// A char with its position in the source code.
-pub type Symbol = PositionContainer<char>;
+pub type Symbol = Spanned<char>;
 
 /// Iterator over the chars of a source code.
 struct SourceIter {
@@ -92,9 +114,9 @@ impl Iterator for SourceIter {
 	type Item = Symbol;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		let char_ = *self.source.text.get(self.position.offset)?;
+		let char_ = self.source.text[self.position.offset as usize..].chars().next()?;
 
-		let item = PositionContainer::new(
+		let item = Spanned::new(
 			char_,
 			SourcePositionRange {
 				source: Arc::clone(&self.source),
@@ -102,7 +124,7 @@ impl Iterator for SourceIter {
 			},
 		);
 
-		self.position.offset += 1;
+		self.position.offset += char_.len_utf8() as u32;
 		if char_ == '\n' {
 			self.position.line += 1;
 			self.position.column = 1;
@@ -128,14 +150,14 @@ mod tests {
 
 		assert_eq!(
 			iter.next(),
-			Some(PositionContainer::new(
+			Some(Spanned::new(
 				'a',
 				SourcePositionRange { source: Arc::clone(&source), position: PositionRange::default() }
 			))
 		);
 		assert_eq!(
 			iter.next(),
-			Some(PositionContainer::new(
+			Some(Spanned::new(
 				'b',
 				SourcePositionRange {
 					source: Arc::clone(&source),
@@ -148,7 +170,7 @@ mod tests {
 		);
 		assert_eq!(
 			iter.next(),
-			Some(PositionContainer::new(
+			Some(Spanned::new(
 				'\n',
 				SourcePositionRange {
 					source: Arc::clone(&source),
@@ -161,7 +183,7 @@ mod tests {
 		);
 		assert_eq!(
 			iter.next(),
-			Some(PositionContainer::new(
+			Some(Spanned::new(
 				'c',
 				SourcePositionRange {
 					source: Arc::clone(&source),
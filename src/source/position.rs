@@ -1,14 +1,16 @@
 use std::fmt;
 
 /// Line and column in source code.
+///
+/// Uses `u32` instead of `usize` to keep tokens and AST nodes small, limiting source files to 4 GiB.
 #[derive(PartialEq, Eq, Hash, Clone, Debug, Copy)]
 pub struct Position {
 	/// Line in the source code file.
-	pub line: usize,
+	pub line: u32,
 	/// Column in the [line](Self::line) in the source code file.
-	pub column: usize,
+	pub column: u32,
 	/// Byte offset from the start of the source code file.
-	pub offset: usize,
+	pub offset: u32,
 }
 
 impl fmt::Display for Position {
@@ -1,4 +1,8 @@
-use std::{fmt, sync::Arc};
+use std::{
+	fmt,
+	ops::Range,
+	sync::{Arc, OnceLock},
+};
 
 use crate::source::{position_range::PositionRange, Source};
 
@@ -12,22 +16,89 @@ pub struct SourcePositionRange {
 }
 
 impl SourcePositionRange {
+	/// The position of code that was not parsed, but created by the compiler, e.g. when desugaring, or
+	/// [built](crate::ast::build) in Rust. `from` is the position of the code it was generated from, if any.
+	///
+	/// Synthetic positions are displayed as `generated code (from file.ftl:3:5)`, and diagnostics show the code they
+	/// were generated from.
+	pub fn synthetic(from: Option<&SourcePositionRange>) -> Self {
+		static WITHOUT_ORIGIN: OnceLock<Arc<Source>> = OnceLock::new();
+		// Code generated from generated code stems from the code the latter was generated from
+		let source = match from.map(|from| from.generated_from().unwrap_or(from)) {
+			Some(from) => Arc::new(Source { generated_from: Some(from.clone()), ..Source::synthetic() }),
+			None => Arc::clone(WITHOUT_ORIGIN.get_or_init(|| Arc::new(Source::synthetic()))),
+		};
+		Self { source, position: PositionRange::default() }
+	}
+
+	/// Whether this is a [synthetic](Self::synthetic) position.
+	pub fn is_synthetic(&self) -> bool {
+		self.source.synthetic
+	}
+
+	/// For [synthetic](Self::synthetic) positions, the position of the code they were generated from.
+	pub fn generated_from(&self) -> Option<&SourcePositionRange> {
+		self.source.generated_from.as_ref()
+	}
+
 	/// Returns the lines of the source code that this position range spans.
 	pub fn get_affected_lines(&self) -> String {
-		let source_string = self.source.text.iter().collect::<String>();
-		let lines: Vec<&str> = source_string.lines().collect();
-		lines[self.position.start.line - 1..=self.position.end.line - 1].join("\n")
+		if self.is_synthetic() {
+			return String::new();
+		}
+		let lines: Vec<&str> = self.source.text.lines().collect();
+		lines[self.position.start.line as usize - 1..=self.position.end.line as usize - 1].join("\n")
 	}
 
 	/// Returns the code that this position range spans.
 	pub fn get_affected_code(&self) -> String {
-		self.source.text[self.position.start.offset..=self.position.end.offset].iter().collect::<String>()
+		self.code().to_owned()
+	}
+
+	/// The code that this position range spans, borrowed from the source.
+	pub fn code(&self) -> &str {
+		&self.source.text[self.byte_range()]
+	}
+
+	/// Extends the range over the code before the byte `offset`, without trailing whitespace, e.g. to span code whose
+	/// last token is unknown. Ranges that already end after that code are returned as they are.
+	pub fn extend_to(&self, offset: usize) -> Self {
+		let mut end = self.position.start;
+		let code = self.source.text.get(end.offset as usize..offset).unwrap_or_default().trim_end();
+		match code.char_indices().last() {
+			Some((last, _)) if end.offset as usize + last > self.position.end.offset as usize => {
+				for char_ in code[..last].chars() {
+					end.offset += char_.len_utf8() as u32;
+					if char_ == '\n' {
+						end.line += 1;
+						end.column = 1;
+					} else {
+						end.column += 1;
+					}
+				}
+				Self { source: Arc::clone(&self.source), position: PositionRange { start: self.position.start, end } }
+			},
+			_ => self.clone(),
+		}
+	}
+
+	/// Byte range of the code that this position range spans, with exclusive end.
+	pub fn byte_range(&self) -> Range<usize> {
+		let start = self.position.start.offset as usize;
+		let end = self.position.end.offset as usize;
+		// The end position is inclusive, so the last char has to be skipped over
+		let end_char_len = self.source.text[end..].chars().next().map_or(0, char::len_utf8);
+		start..end + end_char_len
 	}
 }
 
 impl fmt::Display for SourcePositionRange {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		write!(f, "{}:{}", self.source.name, self.position.start)
+		match (self.is_synthetic(), self.generated_from()) {
+			(true, Some(from)) => write!(f, "generated code (from {})", from),
+			(true, None) => write!(f, "generated code"),
+			(false, _) => write!(f, "{}:{}", self.source.name, self.position.start),
+		}
 	}
 }
 
@@ -47,4 +118,29 @@ mod tests {
 		};
 		assert_eq!(position.to_string(), "file.name:42:5")
 	}
+
+	#[test]
+	fn test_synthetic() {
+		let source = Arc::new(Source::new("file.ftl".to_owned(), "def f() {}".to_owned()));
+		let position = SourcePositionRange { source, position: PositionRange::default() };
+		assert!(!position.is_synthetic());
+
+		let generated = SourcePositionRange::synthetic(Some(&position));
+		assert!(generated.is_synthetic());
+		assert_eq!(generated.to_string(), "generated code (from file.ftl:1:1)");
+		assert_eq!(SourcePositionRange::synthetic(Some(&generated)).generated_from(), Some(&position));
+		assert_eq!(SourcePositionRange::synthetic(None).to_string(), "generated code");
+		assert_eq!(SourcePositionRange::synthetic(None).get_affected_lines(), "");
+	}
+
+	#[test]
+	fn test_extend_to() {
+		let source = Arc::new(Source::new("file.ftl".to_owned(), "a b\n c  \nd".to_owned()));
+		let position = SourcePositionRange { source, position: PositionRange::default() };
+		let extended = position.extend_to(9);
+		assert_eq!(extended.code(), "a b\n c");
+		assert_eq!(extended.position.end, Position { line: 2, column: 2, offset: 5 });
+		// Shrinking is not possible
+		assert_eq!(extended.extend_to(1), extended);
+	}
 }
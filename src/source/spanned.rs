@@ -0,0 +1,122 @@
+use std::{
+	fmt,
+	ops::{Deref, DerefMut},
+};
+
+use crate::source::source_position::SourcePositionRange;
+
+/// Value inside source code with its position.
+///
+/// Behaves like a smart pointer: it dereferences to the wrapped [`value`](Self::value), so the position only has to
+/// be accessed explicitly where it is needed, e.g. for error messages.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Spanned<T> {
+	/// Where the [`value`](Self::value) is located in the source code.
+	pub position: SourcePositionRange,
+	/// The value that is wrapped with [position information](Self::position).
+	pub value: T,
+}
+
+impl<T> Spanned<T> {
+	pub fn new(value: T, position: SourcePositionRange) -> Self {
+		Self { value, position }
+	}
+
+	/// Transforms the inner value with `f`, keeping the position.
+	pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+		Spanned { value: f(self.value), position: self.position }
+	}
+
+	/// Borrows the inner value, cloning only the position.
+	pub fn as_ref(&self) -> Spanned<&T> {
+		Spanned { value: &self.value, position: self.position.clone() }
+	}
+
+	/// Separates the inner value from its position.
+	pub fn split(self) -> (T, SourcePositionRange) {
+		(self.value, self.position)
+	}
+}
+
+impl<T> Deref for Spanned<T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		&self.value
+	}
+}
+
+impl<T> DerefMut for Spanned<T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.value
+	}
+}
+
+impl<T> From<(T, SourcePositionRange)> for Spanned<T> {
+	fn from((value, position): (T, SourcePositionRange)) -> Self {
+		Self::new(value, position)
+	}
+}
+
+impl<T: fmt::Display> fmt::Display for Spanned<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "'{}' at {}", self.value, self.position)
+	}
+}
+
+impl<T: PartialOrd> PartialOrd for Spanned<T> {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		self.value.partial_cmp(&other.value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::source::{position_range::PositionRange, Source};
+
+	/// Tests that a [`Spanned`] can be dereferenced to its inner value.
+	///
+	/// More a compile-time check than a runtime test.
+	#[test]
+	fn test_deref() {
+		/// Potential expression struct used somewhere in the AST.
+		#[derive(PartialEq, Debug)]
+		struct Number(f64);
+
+		let expression = Spanned::new(
+			Number(1.0),
+			SourcePositionRange {
+				source: Arc::new(Source::new("file.name".to_owned(), "content".to_owned())),
+				position: PositionRange::default(),
+			},
+		);
+
+		assert_eq!(*expression, Number(1.0), "Deref to inner value failed");
+	}
+
+	/// Tests that the inner value can be modified through [`DerefMut`].
+	#[test]
+	fn test_deref_mut() {
+		let source = Arc::new(Source::new("file.name".to_owned(), "content".to_owned()));
+		let mut name: Spanned<String> =
+			("foo".to_owned(), SourcePositionRange { source, position: PositionRange::default() }).into();
+		name.push_str("bar");
+		assert_eq!(*name, "foobar");
+	}
+
+	/// Tests that [`Spanned::map`], [`as_ref`](Spanned::as_ref) and
+	/// [`split`](Spanned::split) keep the position.
+	#[test]
+	fn test_combinators() {
+		let source = Arc::new(Source::new("file.name".to_owned(), "content".to_owned()));
+		let position = SourcePositionRange { source, position: PositionRange::default() };
+		let number = Spanned::new(21, position.clone());
+
+		assert_eq!(number.as_ref(), Spanned::new(&21, position.clone()));
+		let doubled = number.map(|number| number * 2);
+		assert_eq!(doubled.split(), (42, position));
+	}
+}
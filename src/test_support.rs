@@ -0,0 +1,34 @@
+//! Helpers shared by the tests of the modules and the [differential](crate::differential) harness.
+
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+	process,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A temporary directory, which is removed when dropped.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+	pub(crate) fn new() -> io::Result<Self> {
+		static COUNTER: AtomicUsize = AtomicUsize::new(0);
+		let path = std::env::temp_dir().join(format!(
+			"ftl-test-{}-{}",
+			process::id(),
+			COUNTER.fetch_add(1, Ordering::Relaxed)
+		));
+		fs::create_dir_all(&path)?;
+		Ok(Self(path))
+	}
+
+	pub(crate) fn path(&self) -> &Path {
+		&self.0
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
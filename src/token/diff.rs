@@ -0,0 +1,183 @@
+//! Aligning and comparing two token streams regardless of the positions of the tokens, e.g. when refactoring the
+//! lexer or checking that the formatter only changed whitespace.
+//!
+//! The [`Display`](fmt::Display) of a [`TokenDiff`] lists the removed and added tokens like a unified diff, with two
+//! unchanged tokens around them as context:
+//!
+//! ```text
+//!   IDENT(x)@2:6-2:6 | IDENT(x)@2:6-2:6
+//!   EQUAL@2:8-2:8 | EQUAL@2:8-2:8
+//! - INT(1)@2:10-2:10
+//! + INT(2)@2:10-2:10
+//!   IDENT(y)@3:2-3:2 | IDENT(y)@3:2-3:2
+//! ```
+
+use std::fmt;
+
+use crate::token::{text, Token};
+
+/// Number of unchanged tokens shown before and after changed ones.
+const CONTEXT: usize = 2;
+
+/// How a token of one stream relates to the other stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change<'a> {
+	/// The token is in both streams, at the positions of the old and the new token.
+	Unchanged { old: &'a Token, new: &'a Token },
+	/// The token is only in the old stream.
+	Removed(&'a Token),
+	/// The token is only in the new stream.
+	Added(&'a Token),
+}
+
+/// The differences between two token streams, aligned so that as many tokens as possible are unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenDiff<'a> {
+	/// All tokens of both streams in order.
+	pub changes: Vec<Change<'a>>,
+}
+
+impl<'a> TokenDiff<'a> {
+	/// Aligns the `old` and the `new` tokens by their kind and value, ignoring their positions.
+	pub fn new(old: &'a [Token], new: &'a [Token]) -> Self {
+		Self { changes: align(old, new) }
+	}
+
+	/// Whether the streams consist of the same tokens.
+	pub fn is_empty(&self) -> bool {
+		self.changes.iter().all(|change| matches!(change, Change::Unchanged { .. }))
+	}
+}
+
+impl fmt::Display for TokenDiff<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let changed: Vec<usize> =
+			(0..self.changes.len()).filter(|&index| !matches!(self.changes[index], Change::Unchanged { .. })).collect();
+		let shown =
+			|index: usize| changed.iter().any(|&changed| index + CONTEXT >= changed && index <= changed + CONTEXT);
+		let mut skipped = false;
+		for (index, change) in self.changes.iter().enumerate() {
+			if !shown(index) {
+				skipped = true;
+				continue;
+			}
+			if skipped && index > 0 {
+				writeln!(f, "...")?;
+			}
+			skipped = false;
+			match change {
+				Change::Unchanged { old, new } => {
+					writeln!(f, "  {} | {}", text::serialize_token(old), text::serialize_token(new))?
+				},
+				Change::Removed(token) => writeln!(f, "- {}", text::serialize_token(token))?,
+				Change::Added(token) => writeln!(f, "+ {}", text::serialize_token(token))?,
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Computes the shortest edit script turning the `old` tokens into the `new` ones with the algorithm of Myers, which
+/// is fast for streams with few differences.
+fn align<'a>(old: &'a [Token], new: &'a [Token]) -> Vec<Change<'a>> {
+	let (n, m) = (old.len() as isize, new.len() as isize);
+	let max = n + m;
+	// Furthest x reached on each diagonal k = x - y, indexed by k + max + 1
+	let mut furthest = vec![0_isize; 2 * max as usize + 3];
+	let index = |k: isize| (k + max + 1) as usize;
+	let mut trace = Vec::new();
+	'search: for d in 0..=max {
+		trace.push(furthest.clone());
+		for k in (-d..=d).step_by(2) {
+			let mut x = if k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)]) {
+				furthest[index(k + 1)]
+			} else {
+				furthest[index(k - 1)] + 1
+			};
+			let mut y = x - k;
+			while x < n && y < m && old[x as usize].value == new[y as usize].value {
+				x += 1;
+				y += 1;
+			}
+			furthest[index(k)] = x;
+			if x >= n && y >= m {
+				break 'search;
+			}
+		}
+	}
+
+	// Walk back from the end along the furthest reaching paths
+	let mut changes = Vec::new();
+	let (mut x, mut y) = (n, m);
+	for (d, furthest) in trace.iter().enumerate().rev() {
+		let d = d as isize;
+		let k = x - y;
+		let previous_k =
+			if k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)]) { k + 1 } else { k - 1 };
+		let previous_x = furthest[index(previous_k)];
+		let previous_y = previous_x - previous_k;
+		while x > previous_x && y > previous_y {
+			x -= 1;
+			y -= 1;
+			changes.push(Change::Unchanged { old: &old[x as usize], new: &new[y as usize] });
+		}
+		if d > 0 {
+			if x == previous_x {
+				changes.push(Change::Added(&new[previous_y as usize]));
+			} else {
+				changes.push(Change::Removed(&old[previous_x as usize]));
+			}
+		}
+		(x, y) = (previous_x, previous_y);
+	}
+	changes.reverse();
+	changes
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{dump::Dumper, source::Source, token::TokenKind};
+
+	fn lex(source_code: &str) -> Vec<Token> {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		crate::lex_source(source, &Dumper::default()).unwrap()
+	}
+
+	#[test]
+	fn test_token_diff() {
+		let (old, new) = (lex("def f(): int { return 1 }"), lex("def f(): int {\n\treturn 1\n}"));
+		assert!(TokenDiff::new(&old, &new).is_empty());
+		assert_eq!(TokenDiff::new(&old, &new).to_string(), "");
+
+		let new = lex("def g(): int { var x: int = 1\n return 1 }");
+		let diff = TokenDiff::new(&old, &new);
+		assert!(!diff.is_empty());
+		let kind = |change: &Change| match change {
+			Change::Unchanged { old, .. } => ('=', old.value.clone()),
+			Change::Removed(token) => ('-', token.value.clone()),
+			Change::Added(token) => ('+', token.value.clone()),
+		};
+		let changed: Vec<_> = diff.changes.iter().map(kind).filter(|(change, _)| *change != '=').collect();
+		assert_eq!(
+			changed,
+			[
+				('-', TokenKind::Identifier("f".into())),
+				('+', TokenKind::Identifier("g".into())),
+				('+', TokenKind::Var),
+				('+', TokenKind::Identifier("x".into())),
+				('+', TokenKind::Colon),
+				('+', TokenKind::Identifier("int".into())),
+				('+', TokenKind::Equal),
+				('+', TokenKind::Int(1)),
+			]
+		);
+		assert_eq!(diff.changes.len(), old.len() + 7);
+		assert!(diff.to_string().starts_with("  DEF@1:1-1:3 | DEF@1:1-1:3\n- IDENT(f)@1:5-1:5\n+ IDENT(g)@1:5-1:5\n"));
+
+		assert!(TokenDiff::new(&[], &[]).is_empty());
+		assert_eq!(TokenDiff::new(&old, &[]).changes.len(), old.len());
+	}
+}
@@ -1,11 +1,16 @@
 //! The words produced by the [`Lexer`](crate::lexer::Lexer), which are then further processed by the [`Parser`](crate::parser::Parser).
 
+pub mod diff;
+pub mod text;
+
 use std::fmt;
 
-use crate::source::PositionContainer;
+use smol_str::SmolStr;
+
+use crate::source::Spanned;
 
 /// A [`TokenKind`] with its position in the source code.
-pub type Token = PositionContainer<TokenKind>;
+pub type Token = Spanned<TokenKind>;
 
 /// The words produced by the [`Lexer`](crate::lexer::Lexer), which are then further processed by the [`Parser`](crate::parser::Parser).
 #[derive(Debug, Clone, PartialEq)]
@@ -14,14 +19,18 @@ pub enum TokenKind {
 	Def,
 	/// Keyword: Extern function declaration.
 	Extern,
-	/// Function, variable name or data type.
-	Identifier(String),
+	/// Keyword: Makes the following function or struct visible to C code.
+	Export,
+	/// Function, variable name or data type. Short identifiers are stored inline without heap allocation.
+	Identifier(SmolStr),
 	/// Floating point number.
 	Float(f64),
 	/// Integer number.
 	Int(i64),
-	/// Comment (Possible a doc comment)
+	/// Comment starting with `#`.
 	Comment(String),
+	/// Doc comment of the following function or struct, i.e. lines starting with `##`, without the `##`.
+	DocComment(String),
 	/// String literal, enclosed by `"`.
 	StringLiteral(String),
 	/// `+`
@@ -34,6 +43,10 @@ pub enum TokenKind {
 	Less,
 	/// `>`
 	Greater,
+	/// `<=`
+	LessEqual,
+	/// `>=`
+	GreaterEqual,
 	/// `(`
 	OpeningParentheses,
 	/// `)`
@@ -62,7 +75,7 @@ pub enum TokenKind {
 	BitOr,
 	/// Bitwise AND
 	BitAnd,
-	/// Modulus %
+	/// Modulus `mod`
 	Modulus,
 	/// If
 	If,
@@ -72,10 +85,24 @@ pub enum TokenKind {
 	While,
 	/// `.`
 	Dot,
+	/// `@`, starts an annotation like `@bench`.
+	At,
 	/// End of line, i.e. `\n`.
 	EndOfLine,
 	/// `ptr`
 	Pointer,
+	/// `opt`, marks an optional type that may be `null`.
+	Optional,
+	/// `null`, the absence of a value of an optional type.
+	Null,
+	/// `try`, checks the result of a call of a function that can fail.
+	Try,
+	/// `or`, as in the return type `int or error` of a function that can fail, or the logical or of two conditions.
+	Or,
+	/// `and`, the logical and of two conditions.
+	And,
+	/// `error`, the result of a function that failed.
+	Error,
 	/// `struct`
 	Struct,
 	/// `var`
@@ -0,0 +1,296 @@
+//! A compact textual form of token streams, for comparing the output of the lexer with fixture files.
+//!
+//! Each token is written as its name, an optional value in parentheses and its position range:
+//!
+//! ```text
+//! DEF@1:1-1:3 IDENT(main)@1:5-1:8 LPAREN@1:9-1:9 RPAREN@1:10-1:10
+//! STR("hello \"world\"")@2:2-2:18
+//! ```
+//!
+//! [`serialize`] starts a new line for each line of the source code. [`deserialize`] doesn't care about whitespace
+//! between the tokens, so fixtures can be laid out freely.
+
+use std::{iter::Peekable, str::Chars, sync::Arc};
+
+use crate::{
+	source::{Position, PositionRange, Source, SourcePositionRange},
+	token::{Token, TokenKind},
+};
+
+/// Serializes the `tokens`, one line of text per line of source code.
+pub fn serialize(tokens: &[Token]) -> String {
+	let mut text = String::new();
+	let mut line = None;
+	for token in tokens {
+		let start = token.position.position.start;
+		match line {
+			None => (),
+			Some(line) if line == start.line => text.push(' '),
+			Some(_) => text.push('\n'),
+		}
+		line = Some(start.line);
+		text.push_str(&serialize_token(token));
+	}
+	if line.is_some() {
+		text.push('\n');
+	}
+	text
+}
+
+/// Serializes a single token, e.g. `IDENT(main)@1:5-1:8`.
+pub fn serialize_token(token: &Token) -> String {
+	let PositionRange { start, end } = token.position.position;
+	format!("{}@{}-{}", serialize_kind(&token.value), start, end)
+}
+
+/// The name of the token kind with its value, e.g. `IDENT(main)`.
+fn serialize_kind(kind: &TokenKind) -> String {
+	match kind {
+		TokenKind::Identifier(name) => format!("IDENT({})", name),
+		TokenKind::Float(float) => format!("FLOAT({:?})", float),
+		TokenKind::Int(int) => format!("INT({})", int),
+		TokenKind::Comment(comment) => format!("COMMENT({:?})", comment),
+		TokenKind::DocComment(doc) => format!("DOC_COMMENT({:?})", doc),
+		TokenKind::StringLiteral(string) => format!("STR({:?})", string),
+		kind => name(kind).to_owned(),
+	}
+}
+
+/// Name of a token kind without value.
+fn name(kind: &TokenKind) -> &'static str {
+	match kind {
+		TokenKind::Def => "DEF",
+		TokenKind::Extern => "EXTERN",
+		TokenKind::Export => "EXPORT",
+		TokenKind::Identifier(_) => "IDENT",
+		TokenKind::Float(_) => "FLOAT",
+		TokenKind::Int(_) => "INT",
+		TokenKind::Comment(_) => "COMMENT",
+		TokenKind::DocComment(_) => "DOC_COMMENT",
+		TokenKind::StringLiteral(_) => "STR",
+		TokenKind::Plus => "PLUS",
+		TokenKind::Star => "STAR",
+		TokenKind::Minus => "MINUS",
+		TokenKind::Less => "LESS",
+		TokenKind::Greater => "GREATER",
+		TokenKind::LessEqual => "LESS_EQUAL",
+		TokenKind::GreaterEqual => "GREATER_EQUAL",
+		TokenKind::OpeningParentheses => "LPAREN",
+		TokenKind::ClosingParentheses => "RPAREN",
+		TokenKind::OpeningCurlyBraces => "LBRACE",
+		TokenKind::ClosingCurlyBraces => "RBRACE",
+		TokenKind::OpeningSquareBrackets => "LBRACKET",
+		TokenKind::ClosingSquareBrackets => "RBRACKET",
+		TokenKind::Comma => "COMMA",
+		TokenKind::Semicolon => "SEMICOLON",
+		TokenKind::Colon => "COLON",
+		TokenKind::Slash => "SLASH",
+		TokenKind::Equal => "EQUAL",
+		TokenKind::NotEqual => "NOT_EQUAL",
+		TokenKind::BitOr => "BIT_OR",
+		TokenKind::BitAnd => "BIT_AND",
+		TokenKind::Modulus => "MODULUS",
+		TokenKind::If => "IF",
+		TokenKind::Else => "ELSE",
+		TokenKind::While => "WHILE",
+		TokenKind::Dot => "DOT",
+		TokenKind::At => "AT",
+		TokenKind::EndOfLine => "EOL",
+		TokenKind::Pointer => "PTR",
+		TokenKind::Optional => "OPT",
+		TokenKind::Null => "NULL",
+		TokenKind::Try => "TRY",
+		TokenKind::Or => "OR",
+		TokenKind::And => "AND",
+		TokenKind::Error => "ERROR",
+		TokenKind::Struct => "STRUCT",
+		TokenKind::Var => "VAR",
+		TokenKind::Return => "RETURN",
+	}
+}
+
+/// The token kind without value called `name`.
+fn parse_name(name: &str) -> Option<TokenKind> {
+	let kind = match name {
+		"DEF" => TokenKind::Def,
+		"EXTERN" => TokenKind::Extern,
+		"EXPORT" => TokenKind::Export,
+		"PLUS" => TokenKind::Plus,
+		"STAR" => TokenKind::Star,
+		"MINUS" => TokenKind::Minus,
+		"LESS" => TokenKind::Less,
+		"GREATER" => TokenKind::Greater,
+		"LESS_EQUAL" => TokenKind::LessEqual,
+		"GREATER_EQUAL" => TokenKind::GreaterEqual,
+		"LPAREN" => TokenKind::OpeningParentheses,
+		"RPAREN" => TokenKind::ClosingParentheses,
+		"LBRACE" => TokenKind::OpeningCurlyBraces,
+		"RBRACE" => TokenKind::ClosingCurlyBraces,
+		"LBRACKET" => TokenKind::OpeningSquareBrackets,
+		"RBRACKET" => TokenKind::ClosingSquareBrackets,
+		"COMMA" => TokenKind::Comma,
+		"SEMICOLON" => TokenKind::Semicolon,
+		"COLON" => TokenKind::Colon,
+		"SLASH" => TokenKind::Slash,
+		"EQUAL" => TokenKind::Equal,
+		"NOT_EQUAL" => TokenKind::NotEqual,
+		"BIT_OR" => TokenKind::BitOr,
+		"BIT_AND" => TokenKind::BitAnd,
+		"MODULUS" => TokenKind::Modulus,
+		"IF" => TokenKind::If,
+		"ELSE" => TokenKind::Else,
+		"WHILE" => TokenKind::While,
+		"DOT" => TokenKind::Dot,
+		"AT" => TokenKind::At,
+		"EOL" => TokenKind::EndOfLine,
+		"PTR" => TokenKind::Pointer,
+		"OPT" => TokenKind::Optional,
+		"NULL" => TokenKind::Null,
+		"TRY" => TokenKind::Try,
+		"OR" => TokenKind::Or,
+		"AND" => TokenKind::And,
+		"ERROR" => TokenKind::Error,
+		"STRUCT" => TokenKind::Struct,
+		"VAR" => TokenKind::Var,
+		"RETURN" => TokenKind::Return,
+		_ => return None,
+	};
+	Some(kind)
+}
+
+/// Parses tokens written by [`serialize`], which refer to the `source`. Whitespace between tokens is ignored.
+pub fn deserialize(text: &str, source: &Arc<Source>) -> Result<Vec<Token>, String> {
+	let line_starts: Vec<usize> =
+		std::iter::once(0).chain(source.text.match_indices('\n').map(|(offset, _)| offset + 1)).collect();
+	let mut chars = text.chars().peekable();
+	let mut tokens = Vec::new();
+	loop {
+		while chars.next_if(|char_| char_.is_whitespace()).is_some() {}
+		if chars.peek().is_none() {
+			return Ok(tokens);
+		}
+		let token = parse_token(&mut chars, &line_starts, source)
+			.map_err(|err| format!("token {}: {}", tokens.len() + 1, err))?;
+		tokens.push(token);
+	}
+}
+
+/// Parses a token like `IDENT(main)@1:5-1:8`.
+fn parse_token(chars: &mut Peekable<Chars>, line_starts: &[usize], source: &Arc<Source>) -> Result<Token, String> {
+	let kind = parse_kind(chars)?;
+	expect(chars, '@')?;
+	let start = parse_position(chars, line_starts, source)?;
+	expect(chars, '-')?;
+	let end = parse_position(chars, line_starts, source)?;
+	let position = SourcePositionRange { source: Arc::clone(source), position: PositionRange { start, end } };
+	Ok(Token::new(kind, position))
+}
+
+/// Parses a token kind with its value in parentheses, if any.
+fn parse_kind(chars: &mut Peekable<Chars>) -> Result<TokenKind, String> {
+	let name = read_while(chars, |char_| char_.is_ascii_uppercase() || char_ == '_');
+	if chars.next_if_eq(&'(').is_none() {
+		return parse_name(&name).ok_or_else(|| format!("unknown token `{}`", name));
+	}
+	let kind = match name.as_str() {
+		"IDENT" => TokenKind::Identifier(read_while(chars, |char_| char_ != ')').into()),
+		"FLOAT" => TokenKind::Float(read_while(chars, |char_| char_ != ')').parse().map_err(|err| format!("{}", err))?),
+		"INT" => TokenKind::Int(read_while(chars, |char_| char_ != ')').parse().map_err(|err| format!("{}", err))?),
+		"COMMENT" => TokenKind::Comment(parse_quoted(chars)?),
+		"DOC_COMMENT" => TokenKind::DocComment(parse_quoted(chars)?),
+		"STR" => TokenKind::StringLiteral(parse_quoted(chars)?),
+		name => return Err(format!("token `{}` has no value", name)),
+	};
+	expect(chars, ')')?;
+	Ok(kind)
+}
+
+/// Parses a string in quotes with escapes like `\"` and `\n`, as written by [`Debug`](std::fmt::Debug).
+fn parse_quoted(chars: &mut Peekable<Chars>) -> Result<String, String> {
+	expect(chars, '"')?;
+	let mut string = String::new();
+	loop {
+		match chars.next().ok_or("unterminated string")? {
+			'"' => return Ok(string),
+			'\\' => string.push(match chars.next().ok_or("unterminated string")? {
+				'n' => '\n',
+				'r' => '\r',
+				't' => '\t',
+				'0' => '\0',
+				'u' => {
+					expect(chars, '{')?;
+					let hex = read_while(chars, |char_| char_ != '}');
+					expect(chars, '}')?;
+					u32::from_str_radix(&hex, 16)
+						.ok()
+						.and_then(char::from_u32)
+						.ok_or_else(|| format!("invalid unicode escape `{}`", hex))?
+				},
+				escaped => escaped,
+			}),
+			char_ => string.push(char_),
+		}
+	}
+}
+
+/// Parses a position like `3:14` and resolves its byte offset in the `source`.
+fn parse_position(chars: &mut Peekable<Chars>, line_starts: &[usize], source: &Source) -> Result<Position, String> {
+	let line: u32 = read_while(chars, |char_| char_.is_ascii_digit()).parse().map_err(|_| "expected line")?;
+	expect(chars, ':')?;
+	let column: u32 = read_while(chars, |char_| char_.is_ascii_digit()).parse().map_err(|_| "expected column")?;
+	let line_start = *line_starts
+		.get((line as usize).wrapping_sub(1))
+		.ok_or_else(|| format!("line {} is not in the source", line))?;
+	let (offset, _) = source.text[line_start..]
+		.char_indices()
+		.nth((column as usize).wrapping_sub(1))
+		.ok_or_else(|| format!("column {}:{} is not in the source", line, column))?;
+	Ok(Position { line, column, offset: (line_start + offset) as u32 })
+}
+
+/// Reads chars as long as they fulfill the `predicate`.
+fn read_while(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) -> String {
+	let mut string = String::new();
+	while let Some(char_) = chars.next_if(|char_| predicate(*char_)) {
+		string.push(char_);
+	}
+	string
+}
+
+/// Consumes the `expected` char.
+fn expect(chars: &mut Peekable<Chars>, expected: char) -> Result<(), String> {
+	match chars.next() {
+		Some(char_) if char_ == expected => Ok(()),
+		Some(char_) => Err(format!("expected `{}`, found `{}`", expected, char_)),
+		None => Err(format!("expected `{}`, found end", expected)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::lexer::Lexer;
+
+	#[test]
+	fn test_round_trip() {
+		let source_code = "def größe(): str {\n\treturn \"tab\\there\\n\" # Done\n}\nvar x: float = 1.0";
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let tokens: Vec<Token> = Lexer::new(Arc::clone(&source).iter()).map(Result::unwrap).collect();
+		let text = serialize(&tokens);
+		assert!(text.starts_with("DEF@1:1-1:3 IDENT(größe)@1:5-1:9 LPAREN@1:10-1:10"), "{}", text);
+		assert_eq!(text.lines().count(), 4);
+		assert_eq!(deserialize(&text, &source), Ok(tokens));
+	}
+
+	#[test]
+	fn test_deserialize() {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), "x\n  \"a b\"".to_owned()));
+		let tokens = deserialize("\n  IDENT(x)@1:1-1:1\tSTR(\"a b\")@2:3-2:7  ", &source).unwrap();
+		assert_eq!(tokens[1].value, TokenKind::StringLiteral("a b".to_owned()));
+		assert_eq!(tokens[1].position.code(), "\"a b\"");
+
+		assert_eq!(deserialize("IDENT(x)@1:1-1:1 PLUS", &source), Err("token 2: expected `@`, found end".to_owned()));
+		assert_eq!(deserialize("FOO@1:1-1:1", &source), Err("token 1: unknown token `FOO`".to_owned()));
+		assert_eq!(deserialize("DEF@3:1-3:1", &source), Err("token 1: line 3 is not in the source".to_owned()));
+	}
+}
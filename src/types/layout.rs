@@ -0,0 +1,61 @@
+use crate::ast::statement::BasicDataType;
+
+/// Size and alignment of values of a type, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+	pub size: usize,
+	/// Values are stored at addresses that are a multiple of this.
+	pub align: usize,
+}
+
+/// Layout of a struct, whose fields are stored in the order of declaration with padding for their alignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout {
+	/// Layout of the whole struct, including the padding after the last field.
+	pub layout: Layout,
+	/// Offsets of the fields from the start of the struct, in the order of declaration.
+	pub offsets: Vec<usize>,
+}
+
+impl StructLayout {
+	/// Lays out fields with the `field_layouts` like a C compiler does.
+	pub fn new(field_layouts: &[Layout]) -> Self {
+		let mut offsets = Vec::with_capacity(field_layouts.len());
+		let mut size = 0_usize;
+		let mut align = 1;
+		for field_layout in field_layouts {
+			size = size.next_multiple_of(field_layout.align);
+			offsets.push(size);
+			size += field_layout.size;
+			align = align.max(field_layout.align);
+		}
+		Self { layout: Layout { size: size.next_multiple_of(align), align }, offsets }
+	}
+}
+
+/// The sizes of the scalar types of a compilation target, from which the layouts of structs are computed.
+pub trait DataLayout {
+	/// The layout of a value of the `basic_data_type`.
+	fn basic(&self, basic_data_type: &BasicDataType) -> Layout;
+
+	/// The layout of a pointer, regardless of the type it points to.
+	fn pointer(&self) -> Layout;
+}
+
+/// The layout of the C backend on 64 bit platforms, where `int` and `float` are 4 bytes and
+/// pointers, including strings, are 8 bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CLayout;
+
+impl DataLayout for CLayout {
+	fn basic(&self, basic_data_type: &BasicDataType) -> Layout {
+		match basic_data_type {
+			BasicDataType::Int | BasicDataType::Float => Layout { size: 4, align: 4 },
+			BasicDataType::Str => self.pointer(),
+		}
+	}
+
+	fn pointer(&self) -> Layout {
+		Layout { size: 8, align: 8 }
+	}
+}
@@ -0,0 +1,364 @@
+//! Interned types of a program, shared by the [semantic analysis](crate::semantic_analyzer) and its users.
+//!
+//! The AST spells out a [`DataType`] at each place a type is written, with the positions of its parts. A
+//! [`TypeTable`] stores each distinct type once and hands out [`TypeId`]s instead, which are cheap to copy and compare
+//! regardless of where the types were written. It also answers questions about types, like whether a value of one type
+//! may be used as another or how a struct is laid out in memory.
+
+mod layout;
+
+use std::{collections::HashMap, fmt};
+
+pub use layout::{CLayout, DataLayout, Layout, StructLayout};
+
+use crate::{
+	ast::statement::{BasicDataType, DataType},
+	semantic_analyzer::SymbolTable,
+	source::{SourcePositionRange, Spanned},
+};
+
+/// Identifies a [`Type`] in a [`TypeTable`]. Two ids of the same table are equal if and only if the types are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TypeId(u32);
+
+impl TypeId {
+	/// `int`, present in every table.
+	pub const INT: TypeId = TypeId(0);
+	/// `float`, present in every table.
+	pub const FLOAT: TypeId = TypeId(1);
+	/// `str`, present in every table.
+	pub const STR: TypeId = TypeId(2);
+	/// `void`, present in every table.
+	pub const VOID: TypeId = TypeId(3);
+}
+
+/// A type without position information, whose parts are referenced by their [`TypeId`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+	/// A basic data type like int and float.
+	Basic(BasicDataType),
+	/// A user defined struct, whose fields are looked up with [`TypeTable::fields`].
+	Struct(String),
+	/// A Pointer to a type.
+	Pointer(TypeId),
+	/// Either a value of the type or `null`.
+	Optional(TypeId),
+	/// Either a value of the type or `error`, the return type of a function that can fail.
+	Fallible(TypeId),
+	/// No value, the type of calls to functions without return type.
+	Void,
+}
+
+/// A field of a struct in a [`TypeTable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+	pub name: String,
+	pub type_: TypeId,
+}
+
+/// Stores each type of a program once.
+#[derive(Debug, Clone)]
+pub struct TypeTable {
+	/// The types, indexed by their id.
+	types: Vec<Type>,
+	/// The ids of the types, to find already interned types.
+	ids: HashMap<Type, TypeId>,
+	/// The fields of the structs, by the name of the struct.
+	structs: HashMap<String, Vec<Field>>,
+}
+
+impl Default for TypeTable {
+	fn default() -> Self {
+		let mut table = Self { types: Vec::new(), ids: HashMap::new(), structs: HashMap::new() };
+		for type_ in [
+			Type::Basic(BasicDataType::Int),
+			Type::Basic(BasicDataType::Float),
+			Type::Basic(BasicDataType::Str),
+			Type::Void,
+		] {
+			table.intern_type(type_);
+		}
+		table
+	}
+}
+
+impl TypeTable {
+	/// Creates a table containing the structs of the `symbol_table` and the types of their fields.
+	pub fn new(symbol_table: &SymbolTable) -> Self {
+		let mut table = Self::default();
+		for (name, struct_) in &symbol_table.structs {
+			let fields = struct_
+				.fields
+				.iter()
+				.map(|field| Field { name: field.name.value.clone(), type_: table.intern(&field.data_type) })
+				.collect();
+			table.structs.insert(name.clone(), fields);
+		}
+		table
+	}
+
+	/// Returns the id of the `data_type`, adding it to the table if it is new.
+	pub fn intern(&mut self, data_type: &DataType) -> TypeId {
+		let type_ = match data_type {
+			DataType::Basic(basic_data_type) => Type::Basic(basic_data_type.clone()),
+			DataType::Struct(name) => Type::Struct(name.clone()),
+			DataType::Pointer(pointee) => Type::Pointer(self.intern(pointee)),
+			DataType::Optional(inner) => Type::Optional(self.intern(inner)),
+			DataType::Fallible(inner) => Type::Fallible(self.intern(inner)),
+			DataType::Void => Type::Void,
+		};
+		self.intern_type(type_)
+	}
+
+	/// Returns the id of the `data_type` if it is in the table, without adding it.
+	pub fn lookup(&self, data_type: &DataType) -> Option<TypeId> {
+		let type_ = match data_type {
+			DataType::Basic(basic_data_type) => Type::Basic(basic_data_type.clone()),
+			DataType::Struct(name) => Type::Struct(name.clone()),
+			DataType::Pointer(pointee) => Type::Pointer(self.lookup(pointee)?),
+			DataType::Optional(inner) => Type::Optional(self.lookup(inner)?),
+			DataType::Fallible(inner) => Type::Fallible(self.lookup(inner)?),
+			DataType::Void => Type::Void,
+		};
+		self.ids.get(&type_).copied()
+	}
+
+	/// Returns the id of the struct `name`, adding it to the table if it is new.
+	pub fn struct_(&mut self, name: &str) -> TypeId {
+		self.intern_type(Type::Struct(name.to_owned()))
+	}
+
+	/// Returns the id of a pointer to the `pointee`, adding it to the table if it is new.
+	pub fn pointer(&mut self, pointee: TypeId) -> TypeId {
+		self.intern_type(Type::Pointer(pointee))
+	}
+
+	/// Returns the id of the optional `inner` type, adding it to the table if it is new.
+	pub fn optional(&mut self, inner: TypeId) -> TypeId {
+		self.intern_type(Type::Optional(inner))
+	}
+
+	/// The type with the `id`.
+	pub fn get(&self, id: TypeId) -> &Type {
+		&self.types[id.0 as usize]
+	}
+
+	/// The fields of the struct with the `id`, or [`None`] if it is no struct or an unknown one.
+	pub fn fields(&self, id: TypeId) -> Option<&[Field]> {
+		match self.get(id) {
+			Type::Struct(name) => self.structs.get(name).map(Vec::as_slice),
+			Type::Basic(_) | Type::Pointer(_) | Type::Optional(_) | Type::Fallible(_) | Type::Void => None,
+		}
+	}
+
+	/// The type of the values of the optional type with the `id`, or [`None`] if the type is not optional.
+	pub fn optional_inner(&self, id: TypeId) -> Option<TypeId> {
+		match self.get(id) {
+			Type::Optional(inner) => Some(*inner),
+			Type::Basic(_) | Type::Struct(_) | Type::Pointer(_) | Type::Fallible(_) | Type::Void => None,
+		}
+	}
+
+	/// The type of the results of the fallible type with the `id`, or [`None`] if the type can't be `error`.
+	pub fn fallible_inner(&self, id: TypeId) -> Option<TypeId> {
+		match self.get(id) {
+			Type::Fallible(inner) => Some(*inner),
+			Type::Basic(_) | Type::Struct(_) | Type::Pointer(_) | Type::Optional(_) | Type::Void => None,
+		}
+	}
+
+	/// The type pointed to by the pointer type with the `id`, or [`None`] if the type is no pointer.
+	pub fn pointee(&self, id: TypeId) -> Option<TypeId> {
+		match self.get(id) {
+			Type::Pointer(pointee) => Some(*pointee),
+			Type::Basic(_) | Type::Struct(_) | Type::Optional(_) | Type::Fallible(_) | Type::Void => None,
+		}
+	}
+
+	/// The field `name` of the struct with the `id`.
+	pub fn field(&self, id: TypeId, name: &str) -> Option<&Field> {
+		self.fields(id)?.iter().find(|field| field.name == name)
+	}
+
+	/// Converts the type with the `id` back to a [`DataType`], e.g. for error messages. The positions of its parts
+	/// are [synthetic](SourcePositionRange::synthetic).
+	pub fn data_type(&self, id: TypeId) -> DataType {
+		match self.get(id) {
+			Type::Basic(basic_data_type) => DataType::Basic(basic_data_type.clone()),
+			Type::Struct(name) => DataType::Struct(name.clone()),
+			Type::Pointer(pointee) => DataType::Pointer(Box::new(self.spanned_data_type(*pointee))),
+			Type::Optional(inner) => DataType::Optional(Box::new(self.spanned_data_type(*inner))),
+			Type::Fallible(inner) => DataType::Fallible(Box::new(self.spanned_data_type(*inner))),
+			Type::Void => DataType::Void,
+		}
+	}
+
+	/// Like [`Self::data_type`], with a synthetic position.
+	fn spanned_data_type(&self, id: TypeId) -> Spanned<DataType> {
+		Spanned::new(self.data_type(id), SourcePositionRange::synthetic(None))
+	}
+
+	/// Formats the type with the `id` like it is written in FTL code, e.g. `ptr Point`.
+	pub fn display(&self, id: TypeId) -> impl fmt::Display + '_ {
+		TypeDisplay { table: self, id }
+	}
+
+	/// Whether the type with the `id` is `int` or `float`.
+	pub fn is_numeric(&self, id: TypeId) -> bool {
+		id == TypeId::INT || id == TypeId::FLOAT
+	}
+
+	/// Whether a value of the type `sub` can be used where a value of the type `sup` is expected, e.g. when
+	/// assigning it to a variable or passing it to a function.
+	///
+	/// Besides the same type, this is the case for optional types, which accept values of their inner type.
+	pub fn is_subtype(&self, sub: TypeId, sup: TypeId) -> bool {
+		sub == sup || self.optional_inner(sup).is_some_and(|inner| self.is_subtype(sub, inner))
+	}
+
+	/// The type both operands of a binary expression are converted to before the operation, or [`None`] if the
+	/// types are incompatible.
+	pub fn common_type(&self, lhs: TypeId, rhs: TypeId) -> Option<TypeId> {
+		if self.is_subtype(rhs, lhs) {
+			Some(lhs)
+		} else if self.is_subtype(lhs, rhs) {
+			Some(rhs)
+		} else {
+			None
+		}
+	}
+
+	/// The size and alignment of a value of the type with the `id` on the `target`, or [`None`] for `void`, fallible
+	/// types, which are never stored, unknown structs and structs containing themselves. Optional types are laid out like their inner type, since `null`
+	/// is represented by a sentinel value.
+	pub fn layout(&self, id: TypeId, target: &impl DataLayout) -> Option<Layout> {
+		match self.get(id) {
+			Type::Basic(basic_data_type) => Some(target.basic(basic_data_type)),
+			Type::Struct(_) => self.struct_layout(id, target).map(|struct_layout| struct_layout.layout),
+			Type::Pointer(_) => Some(target.pointer()),
+			Type::Optional(inner) => self.layout(*inner, target),
+			Type::Fallible(_) | Type::Void => None,
+		}
+	}
+
+	/// The layout of the struct with the `id` on the `target`, with the offsets of its fields.
+	pub fn struct_layout(&self, id: TypeId, target: &impl DataLayout) -> Option<StructLayout> {
+		self.struct_layout_visiting(id, target, &mut Vec::new())
+	}
+
+	/// Like [`Self::struct_layout`], but fails for the structs currently `visiting`, which contain themselves.
+	fn struct_layout_visiting(
+		&self,
+		id: TypeId,
+		target: &impl DataLayout,
+		visiting: &mut Vec<TypeId>,
+	) -> Option<StructLayout> {
+		if visiting.contains(&id) {
+			return None;
+		}
+		visiting.push(id);
+		let field_layouts = self
+			.fields(id)?
+			.iter()
+			.map(|field| match self.get(field.type_) {
+				Type::Struct(_) => {
+					self.struct_layout_visiting(field.type_, target, visiting).map(|layout| layout.layout)
+				},
+				_ => self.layout(field.type_, target),
+			})
+			.collect::<Option<Vec<_>>>()?;
+		visiting.pop();
+		Some(StructLayout::new(&field_layouts))
+	}
+
+	/// Returns the id of the `type_`, adding it to the table if it is new.
+	fn intern_type(&mut self, type_: Type) -> TypeId {
+		if let Some(id) = self.ids.get(&type_) {
+			return *id;
+		}
+		let id = TypeId(self.types.len() as u32);
+		self.types.push(type_.clone());
+		self.ids.insert(type_, id);
+		id
+	}
+}
+
+/// Formats a type of a [`TypeTable`], see [`TypeTable::display`].
+struct TypeDisplay<'a> {
+	table: &'a TypeTable,
+	id: TypeId,
+}
+
+impl fmt::Display for TypeDisplay<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.table.get(self.id) {
+			Type::Basic(basic_data_type) => write!(f, "{}", basic_data_type),
+			Type::Struct(name) => write!(f, "{}", name),
+			Type::Pointer(pointee) => write!(f, "ptr {}", self.table.display(*pointee)),
+			Type::Optional(inner) => write!(f, "opt {}", self.table.display(*inner)),
+			Type::Fallible(inner) => write!(f, "{} or error", self.table.display(*inner)),
+			Type::Void => write!(f, "void"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{dump::Dumper, source::Source};
+
+	/// Creates the table of the structs in the source code.
+	fn type_table(source_code: &str) -> TypeTable {
+		let source = Arc::new(Source::new("test.ftl".to_owned(), source_code.to_owned()));
+		let ast_nodes = crate::parse_source(source, &Dumper::default()).unwrap();
+		let Ok(symbol_table) = SymbolTable::global_symbol_scan(ast_nodes.iter());
+		TypeTable::new(&symbol_table)
+	}
+
+	#[test]
+	fn test_interning() {
+		let mut table = type_table("struct Node { value: int, next: ptr Node }");
+		let node = table.struct_("Node");
+		let pointer = table.pointer(node);
+		assert_eq!(table.field(node, "next").map(|field| field.type_), Some(pointer));
+		assert_eq!(table.field(node, "value").map(|field| field.type_), Some(TypeId::INT));
+		assert_eq!(table.fields(pointer), None);
+
+		// Positions don't matter
+		let data_type = table.data_type(pointer);
+		assert_eq!(table.intern(&data_type), pointer);
+		assert_eq!(table.lookup(&DataType::Struct("Unknown".to_owned())), None);
+		let pointer_pointer = table.pointer(pointer);
+		assert_eq!(table.display(pointer_pointer).to_string(), "ptr ptr Node");
+		assert_eq!(data_type.to_string(), "ptr Node");
+
+		assert!(table.is_subtype(pointer, pointer));
+		assert!(!table.is_subtype(TypeId::INT, TypeId::FLOAT));
+		let optional = table.optional(TypeId::INT);
+		assert!(table.is_subtype(TypeId::INT, optional));
+		assert!(!table.is_subtype(optional, TypeId::INT));
+		assert_eq!(table.common_type(TypeId::INT, optional), Some(optional));
+		assert_eq!(table.display(optional).to_string(), "opt int");
+		assert_eq!(table.common_type(TypeId::FLOAT, TypeId::FLOAT), Some(TypeId::FLOAT));
+		assert_eq!(table.common_type(TypeId::INT, TypeId::STR), None);
+	}
+
+	#[test]
+	fn test_layout() {
+		let mut table = type_table(
+			"struct Point { x: int, y: float }\nstruct Node { value: int, next: ptr Node, point: Point }\nstruct A { b: \
+			 B }\nstruct B { a: A }",
+		);
+		let [point, node, a, unknown] = ["Point", "Node", "A", "Unknown"].map(|name| table.struct_(name));
+		let point = table.struct_layout(point, &CLayout).unwrap();
+		assert_eq!(point, StructLayout { layout: Layout { size: 8, align: 4 }, offsets: vec![0, 4] });
+		let node = table.struct_layout(node, &CLayout).unwrap();
+		assert_eq!(node, StructLayout { layout: Layout { size: 24, align: 8 }, offsets: vec![0, 8, 16] });
+
+		assert_eq!(table.layout(TypeId::VOID, &CLayout), None);
+		assert_eq!(table.layout(a, &CLayout), None);
+		assert_eq!(table.layout(unknown, &CLayout), None);
+	}
+}